@@ -0,0 +1,219 @@
+//! # App Data Bundle Module
+//!
+//! This module packs the user's configuration into a single ZIP archive and restores it,
+//! for moving to a new machine or keeping a backup.
+//!
+//! ## Contents
+//! - `settings.json`: Backend settings
+//! - `.app-state.dat`: The frontend store (preferences, global variables, custom themes)
+//! - `user-dictionary.json`: Words added to the spell checker
+//! - `remote-endpoints.json`: Remote file endpoints (without their passwords)
+//! - `snippets.json`: User snippets
+//! - `dictionaries/`: Hunspell dictionaries the user installed
+//! - `templates/`: Templates of the template gallery
+//! - `manifest.json`: Format version, app version, creation time and the file list
+//!
+//! Secrets stay in the OS keychain and are not exported. Machine-specific state (session,
+//! recent files, window positions, sync state, search index, autosaves) is left out, as
+//! its paths rarely exist on another machine.
+//!
+//! ## Import
+//! Every entry is validated and read before anything is written, so a damaged archive
+//! changes nothing. Entries this version does not know (from a newer version) are skipped.
+//! Backend settings and the user dictionary are reloaded right away; the frontend store
+//! is read again when the app restarts.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use tracing::{info, warn};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::path_scope;
+use crate::storage;
+
+// Version of the archive layout
+pub const BUNDLE_FORMAT: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+// Files of the app data directory that are bundled
+const BUNDLED_FILES: &[&str] = &[
+    "settings.json",
+    ".app-state.dat",
+    "user-dictionary.json",
+    "remote-endpoints.json",
+    "snippets.json",
+];
+// Folders of the app data directory that are bundled with all their files
+const BUNDLED_DIRS: &[&str] = &["dictionaries", "templates"];
+// Upper bound of the unpacked size of an archive
+const MAX_BUNDLE_SIZE: u64 = 256 * 1024 * 1024;
+
+// Description of an archive, stored as `manifest.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format: u32,
+    pub app_version: String,
+    // RFC 3339
+    pub created_at: String,
+    // Paths relative to the app data directory, forward-slashed
+    pub files: Vec<String>,
+}
+
+// Whether an archive entry is one of the bundled files (and stays inside its folder)
+fn is_bundled(name: &str) -> bool {
+    if BUNDLED_FILES.contains(&name) {
+        return true;
+    }
+    let path = Path::new(name);
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+        && path.components().count() > 1
+        && BUNDLED_DIRS.iter().any(|dir| path.starts_with(dir))
+}
+
+fn collect_dir(data_dir: &Path, relative: &str, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(data_dir.join(relative)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = format!("{}/{}", relative, entry.file_name().to_string_lossy());
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => collect_dir(data_dir, &name, files),
+            Ok(file_type) if file_type.is_file() => files.push(name),
+            _ => {}
+        }
+    }
+}
+
+// Bundled files that exist in `data_dir`
+pub fn bundle_files(data_dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = BUNDLED_FILES
+        .iter()
+        .filter(|name| data_dir.join(name).is_file())
+        .map(|name| name.to_string())
+        .collect();
+    for dir in BUNDLED_DIRS {
+        collect_dir(data_dir, dir, &mut files);
+    }
+    files.sort();
+    files
+}
+
+// Pack the bundled files of `data_dir` into an archive at `archive`
+pub fn write_bundle(data_dir: &Path, archive: &Path) -> Result<BundleManifest, String> {
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        files: bundle_files(data_dir),
+    };
+    let zip_error = |e: zip::result::ZipError| format!("Failed to write archive: {}", e);
+    let io_error = |e: std::io::Error| format!("Failed to write archive: {}", e);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer.start_file(MANIFEST_NAME, options).map_err(zip_error)?;
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Failed to serialize: {}", e))?;
+    writer.write_all(&json).map_err(io_error)?;
+    for name in &manifest.files {
+        let bytes = fs::read(data_dir.join(name)).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        writer.start_file(name.as_str(), options).map_err(zip_error)?;
+        writer.write_all(&bytes).map_err(io_error)?;
+    }
+    let bytes = writer.finish().map_err(zip_error)?.into_inner();
+    storage::write_atomic(archive, &bytes)?;
+    Ok(manifest)
+}
+
+// Restore the files of an archive into `data_dir`. Returns the manifest with the files
+// that were restored.
+pub fn read_bundle(archive: &Path, data_dir: &Path) -> Result<BundleManifest, String> {
+    let file = fs::File::open(archive).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Not a Bokuchi data archive: {}", e))?;
+
+    let mut manifest: BundleManifest = {
+        let mut entry = zip
+            .by_name(MANIFEST_NAME)
+            .map_err(|_| "Not a Bokuchi data archive: manifest.json is missing".to_string())?;
+        let mut json = String::new();
+        entry
+            .by_ref()
+            .take(1024 * 1024)
+            .read_to_string(&mut json)
+            .map_err(|e| format!("Failed to read manifest: {}", e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid manifest: {}", e))?
+    };
+    if manifest.format == 0 || manifest.format > BUNDLE_FORMAT {
+        return Err(format!(
+            "The archive was created by a newer version of Bokuchi ({}); please update first",
+            manifest.app_version
+        ));
+    }
+
+    let mut restored: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut total: u64 = 0;
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index).map_err(|e| format!("Failed to read archive: {}", e))?;
+        let name = entry.name().to_string();
+        if entry.is_dir() || name == MANIFEST_NAME {
+            continue;
+        }
+        if !is_bundled(&name) {
+            warn!("Skipping unknown archive entry {}", name);
+            continue;
+        }
+        total += entry.size();
+        if total > MAX_BUNDLE_SIZE {
+            return Err("The archive is too large".to_string());
+        }
+        let mut bytes = Vec::new();
+        entry
+            .by_ref()
+            .take(MAX_BUNDLE_SIZE)
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        restored.push((name, bytes));
+    }
+
+    for (name, bytes) in &restored {
+        storage::write_atomic(&data_dir.join(name), bytes)?;
+    }
+    manifest.files = restored.into_iter().map(|(name, _)| name).collect();
+    Ok(manifest)
+}
+
+fn data_dir() -> Result<PathBuf, String> {
+    storage::app_data_dir().ok_or_else(|| "App data directory is not available".to_string())
+}
+
+// Tauri command: Export settings, variables, themes and dictionaries into an archive
+#[tauri::command]
+pub async fn export_app_data(path: String) -> Result<BundleManifest, String> {
+    path_scope::check_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let manifest = write_bundle(&data_dir()?, Path::new(&path))?;
+        info!("Exported {} app data files to {}", manifest.files.len(), path);
+        Ok(manifest)
+    })
+    .await
+    .map_err(|e| format!("Failed to export app data: {}", e))?
+}
+
+// Tauri command: Import an archive created by `export_app_data`, replacing the current
+// files. The frontend should restart afterwards to load its restored store.
+#[tauri::command]
+pub async fn import_app_data(path: String) -> Result<BundleManifest, String> {
+    path_scope::check_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let manifest = read_bundle(Path::new(&path), &data_dir()?)?;
+        crate::settings::load_settings();
+        crate::spellcheck::reload_dictionaries();
+        info!("Imported {} app data files from {}", manifest.files.len(), path);
+        Ok(manifest)
+    })
+    .await
+    .map_err(|e| format!("Failed to import app data: {}", e))?
+}
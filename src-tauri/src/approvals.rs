@@ -0,0 +1,141 @@
+//! # Approvals Module
+//!
+//! This module asks the user in a native dialog before the app starts a program on
+//! behalf of a definition the WebView can change (external tools, process plugins), and
+//! remembers the answer.
+//!
+//! ## Storage
+//! An approval is keyed by a fingerprint of what would run (kind and definition), so any
+//! change to the program or its arguments asks again. The app data directory is
+//! reachable by other local software, so approvals are not stored as plain fingerprints:
+//! `approvals.json` holds HMAC-SHA256 tags of them, keyed by a random secret kept in the
+//! OS keychain (`approvals:secret`, see `credentials`). A tag cannot be made without the
+//! secret, and the frontend's credential commands only reach `integration:` keys.
+//!
+//! Without a usable keychain nothing is remembered and every run asks again.
+
+use hmac::{Hmac, Mac};
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::Sha256;
+use std::sync::Mutex;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tracing::{info, warn};
+
+use crate::credentials;
+use crate::error::AppError;
+use crate::locale;
+use crate::storage;
+
+const APPROVALS_FILE: &str = "approvals.json";
+const SECRET_KEY: &str = "approvals:secret";
+const SECRET_LEN: usize = 32;
+
+// Secret read from (or created in) the keychain, once per session
+static SECRET: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+
+// Tag stored for an approval
+pub fn approval_tag(secret: &[u8], kind: &str, fingerprint: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(kind.as_bytes());
+    mac.update(b"\0");
+    mac.update(fingerprint.as_bytes());
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+fn secret() -> Result<Vec<u8>, String> {
+    let mut cached = SECRET.lock().map_err(|_| "Failed to lock approval secret".to_string())?;
+    if let Some(secret) = cached.as_ref() {
+        return Ok(secret.clone());
+    }
+    let secret = match credentials::get_secret(SECRET_KEY)?.and_then(|hex| hex_decode(&hex)) {
+        Some(secret) if secret.len() == SECRET_LEN => secret,
+        _ => {
+            let mut secret = vec![0u8; SECRET_LEN];
+            SystemRandom::new()
+                .fill(&mut secret)
+                .map_err(|_| "No random numbers available".to_string())?;
+            let hex: String = secret.iter().map(|b| format!("{:02x}", b)).collect();
+            credentials::set_secret(SECRET_KEY, &hex)?;
+            secret
+        }
+    };
+    *cached = Some(secret.clone());
+    Ok(secret)
+}
+
+fn is_approved(kind: &str, fingerprint: &str) -> bool {
+    let Ok(secret) = secret() else {
+        return false;
+    };
+    let tags: Vec<String> = storage::load_json(APPROVALS_FILE);
+    tags.contains(&approval_tag(&secret, kind, fingerprint))
+}
+
+fn remember_approval(kind: &str, fingerprint: &str) {
+    let secret = match secret() {
+        Ok(secret) => secret,
+        Err(e) => {
+            warn!("Not remembering approval, keychain unavailable: {}", e);
+            return;
+        }
+    };
+    let mut tags: Vec<String> = storage::load_json(APPROVALS_FILE);
+    let tag = approval_tag(&secret, kind, fingerprint);
+    if !tags.contains(&tag) {
+        tags.push(tag);
+    }
+    if let Err(e) = storage::save_json(APPROVALS_FILE, &tags) {
+        warn!("Failed to remember approval: {}", e);
+    }
+}
+
+// Command line as shown in a confirmation dialog (arguments with spaces are quoted)
+pub fn command_line(command: &str, args: &[String]) -> String {
+    std::iter::once(command)
+        .chain(args.iter().map(String::as_str))
+        .map(|part| {
+            if part.is_empty() || part.contains(char::is_whitespace) {
+                format!("\"{}\"", part)
+            } else {
+                part.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Ask the user to confirm `fingerprint` unless it was approved before. `message` is
+// shown in a warning dialog with `confirm_label` and Cancel buttons. Blocks on the
+// dialog, so it must not be called on the main thread.
+pub fn confirm_once(
+    app_handle: &tauri::AppHandle,
+    kind: &str,
+    fingerprint: &str,
+    title: String,
+    message: String,
+    confirm_label: String,
+) -> Result<(), AppError> {
+    if is_approved(kind, fingerprint) {
+        return Ok(());
+    }
+    let confirmed = app_handle
+        .dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(confirm_label, locale::tr("dialog.cancel")))
+        .blocking_show();
+    if !confirmed {
+        info!("{} {} declined", kind, fingerprint);
+        return Err(AppError::Cancelled);
+    }
+    remember_approval(kind, fingerprint);
+    Ok(())
+}
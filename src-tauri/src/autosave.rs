@@ -0,0 +1,177 @@
+//! # Autosave Module
+//!
+//! This module stores crash-recovery snapshots of unsaved tab content in the app data
+//! directory (`autosave/<id>.json`).
+//!
+//! ## Flow
+//! - The frontend sends snapshots with `write_autosave_snapshot` as the user types; they
+//!   are buffered in memory so typing never waits on disk
+//! - Pending snapshots are written by the periodic flush and by the shutdown hook
+//!   (see `shutdown`)
+//! - Tabs of encrypted documents (`.enc`) get no snapshots, so their plain text never
+//!   reaches the disk
+//! - A separate process (`--new-instance`) puts its process ID in front of the file
+//!   names, since its tab IDs may be the same as those of the main process
+//! - `discard_autosave_snapshot` removes a snapshot once its tab was saved or closed.
+//!   It waits for a running flush, so a snapshot taken from the buffer before the discard
+//!   cannot be written back after it
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use crate::document_window;
+use crate::encryption;
+use crate::storage;
+
+const AUTOSAVE_DIR: &str = "autosave";
+
+// Autosave snapshot of one tab
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutosaveSnapshot {
+    // Frontend tab ID
+    pub id: String,
+    // None for untitled tabs
+    pub file_path: Option<String>,
+    pub title: String,
+    pub content: String,
+    // Milliseconds since the Unix epoch
+    pub saved_at: u64,
+}
+
+static PENDING_SNAPSHOTS: OnceLock<Mutex<HashMap<String, AutosaveSnapshot>>> = OnceLock::new();
+
+fn pending_cell() -> &'static Mutex<HashMap<String, AutosaveSnapshot>> {
+    PENDING_SNAPSHOTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Held while a flush writes snapshots and while a snapshot is discarded
+static FLUSH_LOCK: Mutex<()> = Mutex::new(());
+
+// Directory holding autosave snapshots
+pub fn autosave_dir() -> Option<PathBuf> {
+    storage::app_data_path(AUTOSAVE_DIR)
+}
+
+// File name for a snapshot ID. Tab IDs come from the frontend, so anything outside
+// [A-Za-z0-9_-] is replaced to keep the name inside the autosave directory.
+pub fn snapshot_file_name(id: &str) -> String {
+    let safe: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}.json", safe)
+}
+
+// Path of the snapshot file of a tab of this process
+fn snapshot_path(dir: &Path, id: &str) -> PathBuf {
+    let name = snapshot_file_name(id);
+    if document_window::is_secondary_instance() {
+        dir.join(format!("{}-{}", std::process::id(), name))
+    } else {
+        dir.join(name)
+    }
+}
+
+// Write all pending snapshots to disk. Returns how many were written.
+pub fn flush_pending_snapshots() -> Result<usize, String> {
+    let _flushing = FLUSH_LOCK
+        .lock()
+        .map_err(|_| "Failed to lock autosave flush".to_string())?;
+    let pending: Vec<AutosaveSnapshot> = {
+        let mut map = pending_cell()
+            .lock()
+            .map_err(|_| "Failed to lock autosave buffer".to_string())?;
+        map.drain().map(|(_, snapshot)| snapshot).collect()
+    };
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let Some(dir) = autosave_dir() else {
+        requeue_snapshots(pending);
+        return Err("App data directory is not available".to_string());
+    };
+    let mut error = None;
+    let written = pending
+        .iter()
+        .take_while(|snapshot| match storage::write_json_file(&snapshot_path(&dir, &snapshot.id), snapshot) {
+            Ok(()) => true,
+            Err(e) => {
+                error = Some(e);
+                false
+            }
+        })
+        .count();
+    if let Some(e) = error {
+        // Keep the snapshots that were not written for the next flush
+        requeue_snapshots(pending.into_iter().skip(written));
+        return Err(e);
+    }
+    Ok(written)
+}
+
+// Put snapshots back into the buffer, unless a newer snapshot of the tab arrived meanwhile
+fn requeue_snapshots(snapshots: impl IntoIterator<Item = AutosaveSnapshot>) {
+    if let Ok(mut map) = pending_cell().lock() {
+        for snapshot in snapshots {
+            map.entry(snapshot.id.clone()).or_insert(snapshot);
+        }
+    }
+}
+
+// Tauri command: Buffer an autosave snapshot of a tab (ignored for encrypted documents)
+#[tauri::command]
+pub fn write_autosave_snapshot(
+    id: String,
+    file_path: Option<String>,
+    title: String,
+    content: String,
+) -> Result<(), String> {
+    if file_path.as_deref().is_some_and(encryption::is_encrypted_path) {
+        return Ok(());
+    }
+    let saved_at = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    if let Some(path) = &file_path {
+        crate::writing_stats::record_revision(path, &content);
+    }
+    let mut map = pending_cell()
+        .lock()
+        .map_err(|_| "Failed to lock autosave buffer".to_string())?;
+    map.insert(
+        id.clone(),
+        AutosaveSnapshot {
+            id,
+            file_path,
+            title,
+            content,
+            saved_at,
+        },
+    );
+    Ok(())
+}
+
+// Tauri command: Drop the snapshot of a tab that was saved or closed
+#[tauri::command]
+pub fn discard_autosave_snapshot(id: String) -> Result<(), String> {
+    let _flushing = FLUSH_LOCK
+        .lock()
+        .map_err(|_| "Failed to lock autosave flush".to_string())?;
+    if let Ok(mut map) = pending_cell().lock() {
+        map.remove(&id);
+    }
+    if let Some(dir) = autosave_dir() {
+        let path = snapshot_path(&dir, &id);
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("Failed to remove snapshot: {}", e))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,162 @@
+//! # Bookmarks Module
+//!
+//! This module keeps bookmarks on lines of documents for the bookmarks sidebar. They are
+//! stored in `bookmarks.json` in the app data directory, so they survive restarts and
+//! are listed across documents.
+//!
+//! ## Paths
+//! Bookmarks keep the path they were added with; paths are compared canonically, so a
+//! bookmark is found through any path of its file (e.g. a symlinked folder).
+//!
+//! ## Edits
+//! Lines move as a document is edited. The frontend reports its edits as line ranges
+//! (`adjust_bookmarks`): bookmarks below an edit move by the number of lines it added or
+//! removed, and bookmarks on removed lines move to the start of the edit.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::storage;
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+// A bookmarked line
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bookmark {
+    pub id: String,
+    pub path: String,
+    // 1-based
+    pub line: usize,
+    pub label: String,
+    // Milliseconds since the Unix epoch
+    pub created: u64,
+}
+
+// An edit of a document: `removed` lines starting at `start_line` (1-based) were
+// replaced by `inserted` lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineEdit {
+    pub start_line: usize,
+    pub removed: usize,
+    pub inserted: usize,
+}
+
+static BOOKMARKS: OnceLock<Mutex<Vec<Bookmark>>> = OnceLock::new();
+
+fn bookmarks_cell() -> &'static Mutex<Vec<Bookmark>> {
+    BOOKMARKS.get_or_init(|| Mutex::new(storage::load_json(BOOKMARKS_FILE)))
+}
+
+// Canonical form of a path for comparisons (the path itself if it does not exist)
+fn path_key(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+// Line of a bookmark at `line` after an edit
+pub fn shift_line(line: usize, edit: &LineEdit) -> usize {
+    if line < edit.start_line {
+        line
+    } else if line >= edit.start_line + edit.removed {
+        (line + edit.inserted - edit.removed).max(1)
+    } else {
+        edit.start_line
+    }
+}
+
+// Move the bookmarks of the file `key` along a series of edits
+pub fn apply_edits(bookmarks: &mut [Bookmark], key: &Path, edits: &[LineEdit]) {
+    for bookmark in bookmarks.iter_mut().filter(|b| path_key(&b.path) == key) {
+        for edit in edits {
+            bookmark.line = shift_line(bookmark.line, edit);
+        }
+    }
+}
+
+// Bookmarks of a file, or of all files below a folder, by path and line
+pub fn filter_bookmarks(bookmarks: &[Bookmark], path: Option<&str>, workspace: Option<&str>) -> Vec<Bookmark> {
+    let file = path.map(path_key);
+    let folder = workspace.map(path_key);
+    let mut found: Vec<Bookmark> = bookmarks
+        .iter()
+        .filter(|bookmark| {
+            let key = path_key(&bookmark.path);
+            file.as_ref().is_none_or(|file| key == *file) && folder.as_ref().is_none_or(|folder| key.starts_with(folder))
+        })
+        .cloned()
+        .collect();
+    found.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    found
+}
+
+// Apply a change to the bookmarks and persist them
+fn update_bookmarks<T>(f: impl FnOnce(&mut Vec<Bookmark>) -> T) -> Result<T, String> {
+    let mut bookmarks = bookmarks_cell()
+        .lock()
+        .map_err(|_| "Failed to lock bookmarks".to_string())?;
+    let result = f(&mut bookmarks);
+    storage::save_json(BOOKMARKS_FILE, &*bookmarks)?;
+    Ok(result)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Tauri command: Bookmark a line of a document
+#[tauri::command]
+pub fn add_bookmark(path: String, line: usize, label: Option<String>) -> Result<Bookmark, String> {
+    if line == 0 {
+        return Err("Lines start at 1".to_string());
+    }
+    update_bookmarks(|bookmarks| {
+        let created = now_millis();
+        let mut id = created;
+        while bookmarks.iter().any(|b| b.id == format!("{:x}", id)) {
+            id += 1;
+        }
+        let bookmark = Bookmark {
+            id: format!("{:x}", id),
+            path,
+            line,
+            label: label.unwrap_or_default().trim().to_string(),
+            created,
+        };
+        bookmarks.push(bookmark.clone());
+        bookmark
+    })
+}
+
+// Tauri command: List the bookmarks of a document (`path`), of the documents below a
+// folder (`workspace`), or all of them
+#[tauri::command]
+pub fn list_bookmarks(path: Option<String>, workspace: Option<String>) -> Result<Vec<Bookmark>, String> {
+    let bookmarks = bookmarks_cell()
+        .lock()
+        .map_err(|_| "Failed to lock bookmarks".to_string())?;
+    Ok(filter_bookmarks(&bookmarks, path.as_deref(), workspace.as_deref()))
+}
+
+// Tauri command: Remove a bookmark
+#[tauri::command]
+pub fn remove_bookmark(id: String) -> Result<(), String> {
+    update_bookmarks(|bookmarks| bookmarks.retain(|bookmark| bookmark.id != id))
+}
+
+// Tauri command: Move the bookmarks of a document along edits made in the editor, in the
+// order they were made. Returns the document's bookmarks.
+#[tauri::command]
+pub fn adjust_bookmarks(path: String, edits: Vec<LineEdit>) -> Result<Vec<Bookmark>, String> {
+    let key = path_key(&path);
+    update_bookmarks(|bookmarks| {
+        apply_edits(bookmarks, &key, &edits);
+        filter_bookmarks(bookmarks, Some(&path), None)
+    })
+}
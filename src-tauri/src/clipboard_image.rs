@@ -0,0 +1,128 @@
+//! # Clipboard Image Module
+//!
+//! This module saves the image on the system clipboard (e.g. a screenshot) as a file next
+//! to a document, so pasting it inserts a working image link. The clipboard is read in
+//! Rust through the clipboard plugin, so the bitmap never passes through the webview.
+//!
+//! ## Formats
+//! - `png`: Lossless, keeps transparency (the default for screenshots)
+//! - `jpeg`: Smaller for photos; transparent pixels are flattened onto white
+//!
+//! Files are named `pasted-<date>-<time>.<ext>`; an existing file with the same name gets
+//! a numeric suffix, or is reused if it holds the same image.
+
+use chrono::Local;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tracing::info;
+
+use crate::commands::write_image_dedup;
+use crate::path_scope;
+
+const JPEG_QUALITY: u8 = 90;
+
+// Format of a saved image
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    #[serde(alias = "jpg")]
+    Jpeg,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+// Encode an RGBA bitmap (4 bytes per pixel, row by row) as PNG or JPEG
+pub fn encode_rgba(rgba: &[u8], width: u32, height: u32, format: ImageFormat) -> Result<Vec<u8>, String> {
+    if rgba.len() != width as usize * height as usize * 4 || rgba.is_empty() {
+        return Err(format!("Invalid image data ({}x{}, {} bytes)", width, height, rgba.len()));
+    }
+    let mut encoded = Vec::new();
+    match format {
+        ImageFormat::Png => PngEncoder::new(&mut encoded).write_image(rgba, width, height, ExtendedColorType::Rgba8),
+        ImageFormat::Jpeg => {
+            // Flatten onto white: JPEG has no alpha channel
+            let rgb: Vec<u8> = rgba
+                .chunks_exact(4)
+                .flat_map(|pixel| {
+                    let alpha = pixel[3] as u32;
+                    let blend = move |channel: u8| ((channel as u32 * alpha + 255 * (255 - alpha)) / 255) as u8;
+                    [blend(pixel[0]), blend(pixel[1]), blend(pixel[2])]
+                })
+                .collect();
+            JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY).write_image(&rgb, width, height, ExtendedColorType::Rgb8)
+        }
+    }
+    .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(encoded)
+}
+
+// Encode a bitmap and write it into `target_dir/subdir`. Returns the path of the file
+// relative to `target_dir`, forward-slashed.
+pub fn save_rgba(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    target_dir: &str,
+    subdir: &str,
+) -> Result<String, String> {
+    path_scope::check_asset_dir(target_dir, subdir)?;
+    let encoded = encode_rgba(rgba, width, height, format)?;
+    let dir = Path::new(target_dir).join(subdir);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create image directory: {} ({:?})", e, e.kind()))?;
+    let filename = format!("pasted-{}.{}", Local::now().format("%Y%m%d-%H%M%S"), format.extension());
+    let name = write_image_dedup(&dir, &filename, &encoded)?;
+    let subdir = subdir.replace('\\', "/");
+    let subdir = subdir.trim_matches('/');
+    Ok(if subdir.is_empty() {
+        name
+    } else {
+        format!("{}/{}", subdir, name)
+    })
+}
+
+// Tauri command: Save the image on the clipboard into `target_dir` (or its `subdir`, e.g.
+// "images"). Returns the path relative to `target_dir` for the Markdown link.
+#[tauri::command]
+pub async fn save_clipboard_image(
+    app_handle: tauri::AppHandle,
+    target_dir: String,
+    format: Option<ImageFormat>,
+    subdir: Option<String>,
+) -> Result<String, String> {
+    let format = format.unwrap_or_default();
+    // The clipboard must not be read on the main thread (it can deadlock on Linux)
+    tauri::async_runtime::spawn_blocking(move || {
+        let image = app_handle
+            .clipboard()
+            .read_image()
+            .map_err(|e| format!("No image on the clipboard: {}", e))?;
+        let path = save_rgba(
+            image.rgba(),
+            image.width(),
+            image.height(),
+            format,
+            &target_dir,
+            subdir.as_deref().unwrap_or(""),
+        )?;
+        info!("Saved clipboard image as {}", path);
+        Ok(path)
+    })
+    .await
+    .map_err(|e| format!("Failed to save clipboard image: {}", e))?
+}
@@ -0,0 +1,103 @@
+//! # Clipboard Text Module
+//!
+//! This module copies the final text of a document to the system clipboard, for pasting
+//! it into systems that only accept plain text (forms, chat, ticket trackers).
+//!
+//! ## Processing
+//! - Variables are expanded the same way as the preview (`get_expanded_markdown`); the
+//!   `<!-- @var -->` definitions are consumed by the expansion
+//! - Frontmatter is removed (optional)
+//! - Remaining HTML comments are removed (optional); a line holding only a comment is
+//!   removed with it. Comments inside fenced code blocks are kept.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tracing::info;
+
+use crate::commands;
+use crate::frontmatter;
+use crate::markdown::lines_outside_code;
+
+lazy_static! {
+    // A comment on lines of its own, with the line break after it
+    static ref COMMENT_LINES: Regex = Regex::new(r"(?ms)^[ \t]*<!--.*?-->[ \t]*(?:\r?\n|\z)").unwrap();
+    static ref COMMENT: Regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+}
+
+// Options of `copy_processed_text`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CopyTextOptions {
+    pub strip_frontmatter: bool,
+    pub strip_comments: bool,
+}
+
+impl Default for CopyTextOptions {
+    fn default() -> Self {
+        CopyTextOptions {
+            strip_frontmatter: true,
+            strip_comments: true,
+        }
+    }
+}
+
+// Remove the HTML comments outside fenced code blocks
+pub fn strip_comments(content: &str) -> String {
+    let prose: Vec<usize> = lines_outside_code(content).into_iter().map(|(index, _)| index).collect();
+    let mut output = String::with_capacity(content.len());
+    // Consecutive lines outside code, processed together so comments may span lines
+    let mut chunk = String::new();
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        if prose.binary_search(&index).is_ok() {
+            chunk.push_str(line);
+            continue;
+        }
+        output.push_str(&strip_chunk_comments(&chunk));
+        chunk.clear();
+        output.push_str(line);
+    }
+    output.push_str(&strip_chunk_comments(&chunk));
+    output
+}
+
+fn strip_chunk_comments(chunk: &str) -> String {
+    let without_lines = COMMENT_LINES.replace_all(chunk, "");
+    COMMENT.replace_all(&without_lines, "").into_owned()
+}
+
+// The text `copy_processed_text` puts on the clipboard, from expanded content
+pub fn finish_text(expanded: &str, options: &CopyTextOptions) -> String {
+    let body = if options.strip_frontmatter {
+        frontmatter::split_frontmatter(expanded).body
+    } else {
+        expanded
+    };
+    let text = if options.strip_comments {
+        strip_comments(body)
+    } else {
+        body.to_string()
+    };
+    text.trim_start_matches(['\r', '\n']).to_string()
+}
+
+// Tauri command: Copy a document with its variables expanded to the clipboard as plain
+// text. Returns the copied text.
+#[tauri::command]
+pub async fn copy_processed_text(
+    app_handle: tauri::AppHandle,
+    content: String,
+    options: Option<CopyTextOptions>,
+) -> Result<String, String> {
+    let expanded = commands::get_expanded_markdown(content, HashMap::new(), None, None).await?;
+    let text = finish_text(&expanded, &options.unwrap_or_default());
+    app_handle
+        .clipboard()
+        .write_text(text.clone())
+        .map_err(|e| format!("Failed to copy to the clipboard: {}", e))?;
+    info!("Copied {} bytes of processed text", text.len());
+    Ok(text)
+}
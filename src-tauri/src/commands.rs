@@ -10,41 +10,73 @@
 //! - `load_variables_from_yaml`: Import variables from YAML content
 //! - `export_variables_to_yaml`: Export current variables to YAML format
 //!
+//! Overwritten values of global variables are kept in `variable_history`.
+//!
 //! ### Markdown Processing
 //! - `process_markdown`: Process Markdown content with variable substitution
 //! - `get_expanded_markdown`: Get expanded Markdown with variables resolved
+//! - `process_markdown_with_map`: Process Markdown and report the position of each
+//!   substitution, for highlighting substituted text in the preview
+//! - `get_unmet_requirements`: Variables a document requires (`<!-- @require -->`) that
+//!   have no value yet
+//!
+//! Both resolve the `<!-- @include -->` lines of the document at `path` first (see
+//! `includes`), run the enabled `pre-variable` and `post-variable` plugins around the
+//! expansion, apply smart typography when it is enabled (see `typography`) and are served
+//! from `markdown_cache` when the content and the variables are unchanged.
 //!
 //! ### File Operations
 //! - `read_file`: Read file content with validation (10MB limit, .md/.txt only)
-//! - `save_file`: Save content to file with validation
+//! - `save_file`: Save content to file with validation and pre-save checks (read-only,
+//!   permissions, free disk space)
 //! - `get_file_hash`: Calculate file hash for change detection
+//! - `get_file_hashes`: Hashes of many files at once (e.g. all open tabs), computed on the
+//!   worker pool; files that fail are reported next to the results
 //!
 //! ### File Association
 //! - `get_pending_file_paths_command`: Retrieve buffered file paths from file association
 //! - `set_frontend_ready_command`: Notify that frontend is ready to receive events
 //!
 //! ### Utility
-//! - `log_from_frontend`: Log messages from frontend to Rust console
+//! - `log_from_frontend`: Log messages from frontend into the backend log
+//!
+//! ## Path Scope
+//! File commands only accept paths inside the folders and files the user opened
+//! (`path_scope`); other paths are rejected with `AppError::OutsideScope`.
+//!
+//! ## Blocking IO
+//! The file commands are async and run their `std::fs` work on the blocking thread pool
+//! (`run_blocking`), so a slow disk or network drive never blocks the async runtime that
+//! handles the other commands.
 //!
 //! ## Error Handling
 //! All commands return `Result<T, String>` for proper error handling and user feedback.
 
 use std::collections::HashMap;
 use std::fs;
-use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::path::Path;
-
-use tauri::Emitter;
-
-use crate::variable_processor::VARIABLE_PROCESSOR;
-use crate::file_operations::calculate_file_hash;
+use std::path::{Path, PathBuf};
+
+use tracing::{info, warn};
+
+use crate::crash;
+use crate::error::AppError;
+use crate::includes;
+use crate::markdown_cache;
+use crate::path_scope;
+use crate::plugins::{self, PluginStage};
+use crate::settings;
+use crate::typography;
+use crate::variable_history;
+use crate::variable_processor::{ProcessOptions, ProcessedContent, VARIABLE_PROCESSOR};
+use crate::file_operations::{calculate_file_hash, check_writable, write_error};
 use crate::file_association::{get_pending_file_paths, set_frontend_ready};
-use crate::types::FileHashInfo;
+use crate::types::{emit_event, FileHashInfo, OpenFileEvent};
+use crate::worker_pool::{self, BatchResult};
 
 // Tauri command: Set global variable
 #[tauri::command]
 pub fn set_global_variable(name: String, value: String) -> Result<(), String> {
-    VARIABLE_PROCESSOR.set_global_variable(name, value);
+    variable_history::set_global_variable(name, value);
     Ok(())
 }
 
@@ -74,46 +106,118 @@ pub fn export_variables_to_yaml() -> Result<String, String> {
 // Both commands expand variables identically; they remain separate IPC entry
 // points because the frontend calls them in different contexts (live preview
 // vs. "save with variables applied"). `command_name` is only used in the
-// panic error/log messages.
+// panic error/log messages. Results are cached by content, variable set and
+// options (see `markdown_cache`).
 //
-// Wrapped in catch_unwind because this is invoked on every keystroke in the
+// Wrapped in crash::catch_panic because this is invoked on every keystroke in the
 // editor — a panic here previously killed the whole Tauri main process. We
 // would rather surface the panic as a command error and keep the editor alive
 // than have the app exit in the middle of someone's edit.
-fn expand_markdown_guarded(
+//
+// Plugins may take seconds (see `plugins::plugin_timeout`), so the commands run this on
+// a blocking thread rather than on the main thread.
+pub fn expand_markdown_guarded(
     command_name: &str,
     content: String,
     global_variables: HashMap<String, String>,
+    options: ProcessOptions,
+    path: Option<String>,
 ) -> Result<String, String> {
-    catch_unwind(AssertUnwindSafe(|| {
+    crash::catch_panic(|| {
         for (name, value) in global_variables {
             VARIABLE_PROCESSOR.set_global_variable(name, value);
         }
-        VARIABLE_PROCESSOR.process_variables(&content)
-    }))
+        let content = match &path {
+            Some(path) => includes::resolve_includes(&content, Path::new(path)),
+            None => content,
+        };
+        let variables = VARIABLE_PROCESSOR.get_all_variables();
+        let key = markdown_cache::cache_key(&content, &variables, &options);
+        if let Some(expanded) = key.as_ref().and_then(markdown_cache::get) {
+            return expanded;
+        }
+        let content = plugins::apply_stage(PluginStage::PreVariable, content);
+        let expanded = VARIABLE_PROCESSOR.process_variables_with_options(&content, &HashMap::new(), &options);
+        let expanded = plugins::apply_stage(PluginStage::PostVariable, expanded);
+        let expanded = if typography::is_enabled(&expanded, settings::current_settings().smart_typography) {
+            typography::smarten_markdown(&expanded)
+        } else {
+            expanded
+        };
+        if let Some(key) = key {
+            markdown_cache::insert(key, expanded.clone());
+        }
+        expanded
+    })
     .map_err(|panic_payload| {
         let msg = panic_message(&panic_payload);
-        eprintln!("[{}] panic caught: {}", command_name, msg);
+        warn!("[{}] panic caught: {}", command_name, msg);
         format!("{} panicked: {}", command_name, msg)
     })
 }
 
-// Tauri command: Process Markdown (variable expansion)
+// Tauri command: Process Markdown (variable expansion) of the document at `path`
 #[tauri::command]
-pub fn process_markdown(
+pub async fn process_markdown(
     content: String,
     global_variables: HashMap<String, String>,
+    options: Option<ProcessOptions>,
+    path: Option<String>,
 ) -> Result<String, String> {
-    expand_markdown_guarded("process_markdown", content, global_variables)
+    tauri::async_runtime::spawn_blocking(move || {
+        expand_markdown_guarded("process_markdown", content, global_variables, options.unwrap_or_default(), path)
+    })
+    .await
+    .map_err(|e| format!("Failed to process Markdown: {}", e))?
 }
 
-// Tauri command: Get expanded Markdown content
+// Tauri command: Get expanded Markdown content of the document at `path`
 #[tauri::command]
-pub fn get_expanded_markdown(
+pub async fn get_expanded_markdown(
     content: String,
     global_variables: HashMap<String, String>,
+    options: Option<ProcessOptions>,
+    path: Option<String>,
 ) -> Result<String, String> {
-    expand_markdown_guarded("get_expanded_markdown", content, global_variables)
+    tauri::async_runtime::spawn_blocking(move || {
+        expand_markdown_guarded("get_expanded_markdown", content, global_variables, options.unwrap_or_default(), path)
+    })
+    .await
+    .map_err(|e| format!("Failed to expand Markdown: {}", e))?
+}
+
+// Tauri command: Process Markdown and report where each substitution was made. Plugins
+// are not run and the result is not cached, since plugins would move the ranges.
+#[tauri::command]
+pub fn process_markdown_with_map(
+    content: String,
+    global_variables: HashMap<String, String>,
+    options: Option<ProcessOptions>,
+) -> Result<ProcessedContent, String> {
+    crash::catch_panic(|| {
+        for (name, value) in global_variables {
+            VARIABLE_PROCESSOR.set_global_variable(name, value);
+        }
+        VARIABLE_PROCESSOR.process_variables_with_map(&content, &HashMap::new(), &options.unwrap_or_default())
+    })
+    .map_err(|panic_payload| {
+        let msg = panic_message(&panic_payload);
+        warn!("[process_markdown_with_map] panic caught: {}", msg);
+        format!("process_markdown_with_map panicked: {}", msg)
+    })
+}
+
+// Tauri command: Get the variables a document requires that have no value yet, so the
+// frontend can ask for them before rendering the preview
+#[tauri::command]
+pub fn get_unmet_requirements(
+    content: String,
+    global_variables: HashMap<String, String>,
+) -> Result<Vec<String>, String> {
+    for (name, value) in global_variables {
+        VARIABLE_PROCESSOR.set_global_variable(name, value);
+    }
+    Ok(VARIABLE_PROCESSOR.unmet_requirements(&content, &HashMap::new()))
 }
 
 // Extract a printable message from a panic payload. Panics carry their payload
@@ -129,70 +233,86 @@ fn panic_message(payload: &Box<dyn std::any::Any + Send>) -> String {
     }
 }
 
+// Run blocking file IO on the blocking thread pool. The file commands are async, but
+// `std::fs` calls made directly in them would block a runtime worker; on a slow network
+// drive that stalls the IPC handling of every other command.
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("File operation failed: {}", e))?
+}
+
 // Tauri command: Read file
 #[tauri::command]
 pub async fn read_file(path: String) -> Result<String, String> {
+    run_blocking(move || read_text_file(&path)).await
+}
+
+// Read a document for `read_file`
+pub fn read_text_file(path: &str) -> Result<String, String> {
+    // Only files inside the opened folders/files may be read
+    path_scope::check_path(path)?;
+
     // File size check (10MB limit)
-    let metadata = fs::metadata(&path).map_err(|_| "File not found".to_string())?;
+    let metadata = fs::metadata(path).map_err(|_| AppError::NotFound(PathBuf::from(path)))?;
     if metadata.len() > 10 * 1024 * 1024 {
-        return Err("File too large (max 10MB)".to_string());
+        return Err(AppError::FileTooLarge(PathBuf::from(path)).into());
     }
 
     // File extension check
-    if let Some(ext) = Path::new(&path).extension() {
+    if let Some(ext) = Path::new(path).extension() {
         let ext_str = ext.to_string_lossy().to_lowercase();
         if ext_str != "md" && ext_str != "txt" {
-            return Err("Unsupported file type. Only .md and .txt files are supported".to_string());
+            return Err(AppError::UnsupportedFileType(PathBuf::from(path)).into());
         }
     }
 
     // Read file
-    fs::read_to_string(&path).map_err(|_| "Failed to read file".to_string())
+    fs::read_to_string(path).map_err(|e| AppError::ReadFailed(e.to_string()).into())
 }
 
 // Tauri command: Save file
 #[tauri::command]
 pub async fn save_file(path: String, content: String) -> Result<(), String> {
-    // File extension check. Files with an extension must be .md/.txt.
-    // Extension-less files are a legitimate case — the folder tree's
-    // "all files" mode opens them via `read_file` (which deliberately allows
-    // a missing extension, see R-CMD-07), and Ctrl+S on such a tab calls this
-    // command — so they stay writable. Hidden dotfiles are the exception:
-    // `Path::extension()` returns None for names like ".bashrc", which
-    // previously bypassed the allowlist and let IPC calls write shell/config
-    // files. Those are rejected. (`read_directory` never lists hidden files,
-    // so no in-app flow opens them.)
-    let path_ref = Path::new(&path);
-    match path_ref.extension() {
-        Some(ext) => {
-            let ext_str = ext.to_string_lossy().to_lowercase();
-            if ext_str != "md" && ext_str != "txt" {
-                return Err(
-                    "Unsupported file type. Only .md and .txt files are supported".to_string(),
-                );
-            }
-        }
-        None => {
-            let is_hidden = path_ref
-                .file_name()
-                .map(|n| n.to_string_lossy().starts_with('.'))
-                .unwrap_or(true);
-            if is_hidden {
-                return Err("Unsupported file type. Hidden files cannot be saved".to_string());
-            }
-        }
-    }
+    let (path, content) = run_blocking(move || {
+        write_text_file(&path, &content)?;
+        // The file watcher must not report the save back as an external change
+        crate::file_watch::note_saved(&path);
+        Ok((path, content))
+    })
+    .await?;
+
+    // Push the saved document when workspace sync is enabled
+    crate::sync::spawn_push(&path);
+    // Commit the saved document when Git auto-commit is enabled
+    crate::git::spawn_auto_commit(path, content);
+    Ok(())
+}
+
+// Validate and write a document for `save_file`
+fn write_text_file(path: &str, content: &str) -> Result<(), String> {
+    // Only .md/.txt files inside the opened folders/files may be written
+    path_scope::check_document_write(path)?;
+    let path_ref = Path::new(path);
+
+    // Read-only file, missing permission or full disk: fail before touching the file
+    check_writable(path_ref, content.len() as u64)?;
 
     // Create directory
-    if let Some(parent) = Path::new(&path).parent() {
-        fs::create_dir_all(parent).map_err(|_| "Failed to create directory".to_string())?;
+    if let Some(parent) = path_ref.parent() {
+        fs::create_dir_all(parent).map_err(|e| write_error(parent, &e))?;
     }
 
-    // Save file. Surface the OS-level error kind so the user sees the
-    // underlying cause (PermissionDenied, sharing violation from a syncing
-    // cloud drive, etc.) rather than a generic "Failed to save file".
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to save file: {} ({:?})", e, e.kind()))
+    // Count the words written, while the file still holds the previous version
+    crate::writing_stats::record_revision(path, content);
+
+    // Save file. Errors the checks could not foresee are still mapped to a
+    // specific cause where possible; anything else keeps the OS-level error
+    // kind (e.g. a sharing violation from a syncing cloud drive).
+    fs::write(path, content).map_err(|e| write_error(path_ref, &e))?;
+    Ok(())
 }
 
 // Tauri command: Save raw image bytes into a document-relative asset folder.
@@ -208,11 +328,15 @@ pub async fn save_image_bytes(
     filename: String,
     bytes: Vec<u8>,
 ) -> Result<String, String> {
-    let dir = Path::new(&dest_dir).join(&subdir);
-    fs::create_dir_all(&dir)
-        .map_err(|e| format!("Failed to create image directory: {} ({:?})", e, e.kind()))?;
-    let name = write_image_dedup(&dir, &filename, &bytes)?;
-    Ok(format!("{}/{}", subdir.replace('\\', "/"), name))
+    run_blocking(move || {
+        path_scope::check_asset_dir(&dest_dir, &subdir)?;
+        let dir = Path::new(&dest_dir).join(&subdir);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create image directory: {} ({:?})", e, e.kind()))?;
+        let name = write_image_dedup(&dir, &filename, &bytes)?;
+        Ok(format!("{}/{}", subdir.replace('\\', "/"), name))
+    })
+    .await
 }
 
 // Tauri command: Copy an existing image file into a document-relative asset
@@ -226,17 +350,22 @@ pub async fn copy_image_asset(
     dest_dir: String,
     subdir: String,
 ) -> Result<String, String> {
-    let bytes = fs::read(&src_path)
-        .map_err(|e| format!("Failed to read image: {} ({:?})", e, e.kind()))?;
-    let filename = Path::new(&src_path)
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .ok_or_else(|| "Invalid source image path".to_string())?;
-    let dir = Path::new(&dest_dir).join(&subdir);
-    fs::create_dir_all(&dir)
-        .map_err(|e| format!("Failed to create image directory: {} ({:?})", e, e.kind()))?;
-    let name = write_image_dedup(&dir, &filename, &bytes)?;
-    Ok(format!("{}/{}", subdir.replace('\\', "/"), name))
+    run_blocking(move || {
+        path_scope::check_path(&src_path)?;
+        path_scope::check_asset_dir(&dest_dir, &subdir)?;
+        let bytes = fs::read(&src_path)
+            .map_err(|e| format!("Failed to read image: {} ({:?})", e, e.kind()))?;
+        let filename = Path::new(&src_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| "Invalid source image path".to_string())?;
+        let dir = Path::new(&dest_dir).join(&subdir);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create image directory: {} ({:?})", e, e.kind()))?;
+        let name = write_image_dedup(&dir, &filename, &bytes)?;
+        Ok(format!("{}/{}", subdir.replace('\\', "/"), name))
+    })
+    .await
 }
 
 // Write `bytes` into `dir` under `filename`, avoiding collisions. Only the file
@@ -283,7 +412,23 @@ pub(crate) fn write_image_dedup(dir: &Path, filename: &str, bytes: &[u8]) -> Res
 // Tauri command: Get file hash
 #[tauri::command]
 pub async fn get_file_hash(path: String) -> Result<FileHashInfo, String> {
-    calculate_file_hash(&path)
+    run_blocking(move || {
+        path_scope::check_path(&path)?;
+        calculate_file_hash(&path)
+    })
+    .await
+}
+
+// Tauri command: Get the hashes of many files in parallel
+#[tauri::command]
+pub async fn get_file_hashes(paths: Vec<String>) -> Result<BatchResult<FileHashInfo>, String> {
+    run_blocking(move || {
+        Ok(worker_pool::map_paths(paths, |path| {
+            path_scope::check_path(path)?;
+            calculate_file_hash(path)
+        }))
+    })
+    .await
 }
 
 // Tauri command: Get pending file paths
@@ -292,26 +437,24 @@ pub fn get_pending_file_paths_command() -> Vec<String> {
     get_pending_file_paths()
 }
 
-// Log message from frontend to Rust console
+// Log message from frontend into the backend log (target `frontend`).
+// `level` is one of trace/debug/info/warn/error; defaults to info. Messages are
+// truncated, stripped of control characters and rate-limited (see `logging`).
 #[tauri::command]
-pub fn log_from_frontend(message: String) {
-    println!("[FRONTEND] {}", message);
+pub fn log_from_frontend(message: String, level: Option<String>) {
+    crate::logging::log_frontend_message(&message, level.as_deref());
 }
 
 // Tauri command: Set frontend ready and emit any buffered file paths
 #[tauri::command]
 pub fn set_frontend_ready_command(app_handle: tauri::AppHandle) {
-    set_frontend_ready();
-
-    // Emit any buffered pending file paths immediately
-    let pending = get_pending_file_paths();
+    // Emit any buffered pending file paths immediately (taken together with the
+    // flag update, so no path arriving meanwhile is lost)
+    let pending = set_frontend_ready();
     if !pending.is_empty() {
-        println!("Emitting {} buffered file paths after frontend ready", pending.len());
+        info!("Emitting {} buffered file paths after frontend ready", pending.len());
         for file_path in pending {
-            let _ = app_handle.emit(
-                "open-file",
-                crate::types::OpenFileEvent { file_path },
-            );
+            let _ = emit_event(&app_handle, None, &OpenFileEvent { file_path });
         }
     }
 }
@@ -319,7 +462,13 @@ pub fn set_frontend_ready_command(app_handle: tauri::AppHandle) {
 // Tauri command: Read directory entries (for folder tree)
 #[tauri::command]
 pub async fn read_directory(path: String, show_all_files: bool) -> Result<Vec<crate::types::DirEntry>, String> {
-    let dir_path = Path::new(&path);
+    run_blocking(move || list_directory(&path, show_all_files)).await
+}
+
+// List a folder for `read_directory`
+fn list_directory(path: &str, show_all_files: bool) -> Result<Vec<crate::types::DirEntry>, String> {
+    path_scope::check_path(path)?;
+    let dir_path = Path::new(path);
     if !dir_path.is_dir() {
         return Err("Path is not a directory".to_string());
     }
@@ -379,11 +528,12 @@ pub async fn read_directory(path: String, show_all_files: bool) -> Result<Vec<cr
     Ok(dirs)
 }
 
-// Tauri command: Rename file
-#[tauri::command]
-pub async fn rename_file(old_path: String, new_path: String) -> Result<(), String> {
-    let old = Path::new(&old_path);
-    let new = Path::new(&new_path);
+// Rename or move a file, refusing to replace an existing one
+pub fn rename_path(old_path: &str, new_path: &str) -> Result<(), String> {
+    path_scope::check_path(old_path)?;
+    path_scope::check_path(new_path)?;
+    let old = Path::new(old_path);
+    let new = Path::new(new_path);
 
     if !old.exists() {
         return Err("Source file not found".to_string());
@@ -395,3 +545,9 @@ pub async fn rename_file(old_path: String, new_path: String) -> Result<(), Strin
 
     fs::rename(old, new).map_err(|e| format!("Failed to rename file: {}", e))
 }
+
+// Tauri command: Rename file
+#[tauri::command]
+pub async fn rename_file(old_path: String, new_path: String) -> Result<(), String> {
+    run_blocking(move || rename_path(&old_path, &new_path)).await
+}
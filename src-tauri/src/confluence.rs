@@ -0,0 +1,395 @@
+//! # Confluence Module
+//!
+//! This module publishes a document to Confluence: the processed Markdown is converted to
+//! Confluence's storage format (XHTML with `ac:` macros) and a page is created or updated
+//! through the REST API.
+//!
+//! ## Configuration
+//! The site, account, space and parent page are in the settings (`confluence`). The API
+//! token is kept in the keychain under the integration key `confluence` (see
+//! `credentials`). With a username, requests use basic authentication with the token
+//! (Confluence Cloud: email and API token); without one, the token is sent as a bearer
+//! token (personal access tokens of Confluence Server and Data Center).
+//!
+//! ## Pages
+//! A page is updated when its ID is given, or when the space already has a page with the
+//! same title; otherwise it is created below the parent page. Updates bump the page
+//! version, so Confluence keeps the page history.
+//!
+//! ## Conversion
+//! Paragraphs, headings, lists, block quotes, tables, links, emphasis and code are
+//! converted to their XHTML counterparts; fenced code blocks become `code` macros with
+//! their language. Task list items get a ☐/☑ mark. Images with an `http(s)` URL are
+//! embedded; local images are not uploaded and are replaced by their alt text. Raw HTML
+//! is kept as text, except line breaks (`<br>`) and comments, which are dropped.
+//! The Markdown extensions follow the settings (see `markdown_dialect`).
+
+use lazy_static::lazy_static;
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd, TextMergeStream};
+use quick_xml::escape::escape;
+use regex::Regex;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Method, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::Url;
+
+use tracing::info;
+
+use crate::credentials;
+use crate::http;
+use crate::markdown_dialect;
+use crate::settings::{self, ConfluenceSettings, MarkdownExtensions};
+
+lazy_static! {
+    static ref LINE_BREAK: Regex = Regex::new(r"(?i)^<br\s*/?>$").unwrap();
+}
+
+const TOKEN_KEY: &str = "confluence";
+
+// Result of `publish_to_confluence`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublishedPage {
+    pub id: String,
+    pub url: String,
+    pub version: u64,
+    // False when an existing page was updated
+    pub created: bool,
+}
+
+// A code block being collected
+struct CodeBlock {
+    language: Option<String>,
+    code: String,
+}
+
+fn code_macro(block: &CodeBlock) -> String {
+    let mut output = String::from("<ac:structured-macro ac:name=\"code\">");
+    if let Some(language) = &block.language {
+        output.push_str(&format!(
+            "<ac:parameter ac:name=\"language\">{}</ac:parameter>",
+            escape(language.as_str())
+        ));
+    }
+    // "]]>" cannot appear inside CDATA: end the section and start a new one
+    let code = block.code.trim_end_matches('\n').replace("]]>", "]]]]><![CDATA[>");
+    output.push_str(&format!(
+        "<ac:plain-text-body><![CDATA[{}]]></ac:plain-text-body></ac:structured-macro>",
+        code
+    ));
+    output
+}
+
+// Convert raw HTML: line breaks are kept, comments dropped, anything else kept as text
+fn convert_html(html: &str) -> String {
+    let trimmed = html.trim();
+    if LINE_BREAK.is_match(trimmed) {
+        "<br />".to_string()
+    } else if trimmed.starts_with("<!--") {
+        String::new()
+    } else {
+        escape(html).into_owned()
+    }
+}
+
+// Convert Markdown to Confluence storage format
+pub fn to_storage_format(markdown: &str) -> String {
+    to_storage_format_with(markdown, &markdown_dialect::current_extensions())
+}
+
+// Convert Markdown to Confluence storage format with the given Markdown extensions
+pub fn to_storage_format_with(markdown: &str, extensions: &MarkdownExtensions) -> String {
+    let events = TextMergeStream::new(Parser::new_ext(markdown, markdown_dialect::parser_options(extensions)));
+    let events = if extensions.autolinks {
+        markdown_dialect::link_bare_urls(events)
+    } else {
+        events.collect()
+    };
+    let mut output = String::new();
+    let mut code: Option<CodeBlock> = None;
+    // Alt text of the image being converted, with its URL (None for local images)
+    let mut image: Option<(String, Option<String>)> = None;
+    let mut in_metadata = false;
+    let mut in_table_head = false;
+
+    for event in events {
+        if let Some(block) = code.as_mut() {
+            match event {
+                Event::Text(text) => block.code.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => {
+                    output.push_str(&code_macro(block));
+                    code = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if let Some((alt, url)) = image.as_mut() {
+            match event {
+                Event::Text(text) | Event::Code(text) => alt.push_str(&text),
+                Event::End(TagEnd::Image) => {
+                    match url {
+                        Some(url) => output.push_str(&format!(
+                            "<ac:image ac:alt=\"{}\"><ri:url ri:value=\"{}\" /></ac:image>",
+                            escape(alt.as_str()),
+                            escape(url.as_str())
+                        )),
+                        None => output.push_str(&escape(alt.as_str())),
+                    }
+                    image = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Paragraph => output.push_str("<p>"),
+                Tag::Heading { level, .. } => output.push_str(&format!("<{}>", level)),
+                Tag::BlockQuote(_) => output.push_str("<blockquote>"),
+                Tag::CodeBlock(kind) => {
+                    let language = match kind {
+                        CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(str::to_string),
+                        CodeBlockKind::Indented => None,
+                    };
+                    code = Some(CodeBlock {
+                        language,
+                        code: String::new(),
+                    });
+                }
+                Tag::List(Some(start)) if start != 1 => output.push_str(&format!("<ol start=\"{}\">", start)),
+                Tag::List(Some(_)) => output.push_str("<ol>"),
+                Tag::List(None) => output.push_str("<ul>"),
+                Tag::Item => output.push_str("<li>"),
+                Tag::FootnoteDefinition(label) => {
+                    output.push_str(&format!("<div><sup>{}</sup> ", escape(label.as_ref())))
+                }
+                Tag::Table(_) => output.push_str("<table><tbody>"),
+                Tag::TableHead => {
+                    in_table_head = true;
+                    output.push_str("<tr>");
+                }
+                Tag::TableRow => output.push_str("<tr>"),
+                Tag::TableCell => output.push_str(if in_table_head { "<th>" } else { "<td>" }),
+                Tag::Emphasis => output.push_str("<em>"),
+                Tag::Strong => output.push_str("<strong>"),
+                Tag::Strikethrough => output.push_str("<del>"),
+                Tag::Link { dest_url, .. } => output.push_str(&format!("<a href=\"{}\">", escape(dest_url.as_ref()))),
+                Tag::Image { dest_url, .. } => {
+                    let is_web = dest_url.starts_with("http://") || dest_url.starts_with("https://");
+                    image = Some((String::new(), is_web.then(|| dest_url.to_string())));
+                }
+                Tag::MetadataBlock(_) => in_metadata = true,
+                _ => {}
+            },
+            Event::End(end) => match end {
+                TagEnd::Paragraph => output.push_str("</p>"),
+                TagEnd::Heading(level) => output.push_str(&format!("</{}>", level)),
+                TagEnd::BlockQuote(_) => output.push_str("</blockquote>"),
+                TagEnd::List(true) => output.push_str("</ol>"),
+                TagEnd::List(false) => output.push_str("</ul>"),
+                TagEnd::Item => output.push_str("</li>"),
+                TagEnd::FootnoteDefinition => output.push_str("</div>"),
+                TagEnd::Table => output.push_str("</tbody></table>"),
+                TagEnd::TableHead => {
+                    in_table_head = false;
+                    output.push_str("</tr>");
+                }
+                TagEnd::TableRow => output.push_str("</tr>"),
+                TagEnd::TableCell => output.push_str(if in_table_head { "</th>" } else { "</td>" }),
+                TagEnd::Emphasis => output.push_str("</em>"),
+                TagEnd::Strong => output.push_str("</strong>"),
+                TagEnd::Strikethrough => output.push_str("</del>"),
+                TagEnd::Link => output.push_str("</a>"),
+                TagEnd::MetadataBlock(_) => in_metadata = false,
+                _ => {}
+            },
+            Event::Text(text) if !in_metadata => output.push_str(&escape(text.as_ref())),
+            Event::Code(text) => output.push_str(&format!("<code>{}</code>", escape(text.as_ref()))),
+            Event::Html(html) | Event::InlineHtml(html) => output.push_str(&convert_html(&html)),
+            Event::FootnoteReference(label) => output.push_str(&format!("<sup>{}</sup>", escape(label.as_ref()))),
+            Event::SoftBreak => output.push('\n'),
+            Event::HardBreak => output.push_str("<br />"),
+            Event::Rule => output.push_str("<hr />"),
+            Event::TaskListMarker(done) => output.push_str(if done { "☑ " } else { "☐ " }),
+            _ => {}
+        }
+    }
+    output
+}
+
+// Request body creating a page, or updating one to `version`
+pub fn page_body(
+    settings: &ConfluenceSettings,
+    title: &str,
+    storage: &str,
+    update: Option<(&str, u64)>,
+) -> Value {
+    let mut body = json!({
+        "type": "page",
+        "title": title,
+        "space": { "key": settings.space_key.trim() },
+        "body": { "storage": { "value": storage, "representation": "storage" } },
+    });
+    match update {
+        Some((id, version)) => {
+            body["id"] = json!(id);
+            body["version"] = json!({ "number": version });
+        }
+        None => {
+            if let Some(parent) = settings.parent_page_id.as_deref().filter(|id| !id.is_empty()) {
+                body["ancestors"] = json!([{ "id": parent }]);
+            }
+        }
+    }
+    body
+}
+
+// Browser URL of a page in an API response
+pub fn page_url(page: &Value, base_url: &str) -> Option<String> {
+    let webui = page["_links"]["webui"].as_str()?;
+    let base = page["_links"]["base"]
+        .as_str()
+        .unwrap_or(base_url)
+        .trim_end_matches('/');
+    Some(format!("{}{}", base, webui))
+}
+
+// Client of the configured Confluence site
+struct ConfluenceClient {
+    settings: ConfluenceSettings,
+    token: String,
+}
+
+impl ConfluenceClient {
+    fn from_settings() -> Result<Self, String> {
+        let settings = settings::current_settings().confluence;
+        if settings.base_url.trim().is_empty() || settings.space_key.trim().is_empty() {
+            return Err("Confluence is not configured".to_string());
+        }
+        let token = credentials::get_secret(&credentials::integration_key(TOKEN_KEY)?)?
+            .ok_or_else(|| "No Confluence API token is stored".to_string())?;
+        Ok(Self { settings, token })
+    }
+
+    // URL of the content API, or of one page (with query parameters)
+    fn content_url(&self, id: Option<&str>, query: &[(&str, &str)]) -> Result<Url, String> {
+        let base = self.settings.base_url.trim().trim_end_matches('/');
+        let mut url = Url::parse(&format!("{}/rest/api/content", base))
+            .map_err(|e| format!("Invalid Confluence URL: {}", e))?;
+        if let Some(id) = id
+            && let Ok(mut segments) = url.path_segments_mut()
+        {
+            segments.push(id);
+        }
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        Ok(url)
+    }
+
+    fn request(&self, method: Method, url: Url) -> Result<RequestBuilder, String> {
+        let request = http::client()?.request(method, url);
+        Ok(if self.settings.username.is_empty() {
+            request.bearer_auth(&self.token)
+        } else {
+            request.basic_auth(&self.settings.username, Some(&self.token))
+        })
+    }
+
+    async fn send(request: RequestBuilder, action: &str) -> Result<Value, String> {
+        let response: Response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to {}: {}", action, e))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to {}: {}", action, e))?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<Value>(&text)
+                .ok()
+                .and_then(|body| body["message"].as_str().map(str::to_string))
+                .unwrap_or_else(|| text.trim().to_string());
+            return Err(format!("Failed to {}: Confluence returned {} {}", action, status, message));
+        }
+        serde_json::from_str(&text).map_err(|e| format!("Failed to {}: invalid response: {}", action, e))
+    }
+
+    // ID and version of a page, by ID or by its title in the space
+    async fn find_page(&self, page_id: Option<&str>, title: &str) -> Result<Option<(String, u64)>, String> {
+        let page = match page_id {
+            Some(id) => {
+                let url = self.content_url(Some(id), &[("expand", "version")])?;
+                Self::send(self.request(Method::GET, url)?, "find page").await?
+            }
+            None => {
+                let url = self.content_url(
+                    None,
+                    &[
+                        ("spaceKey", self.settings.space_key.trim()),
+                        ("title", title),
+                        ("expand", "version"),
+                    ],
+                )?;
+                let found = Self::send(self.request(Method::GET, url)?, "find page").await?;
+                match found["results"].get(0) {
+                    Some(page) => page.clone(),
+                    None => return Ok(None),
+                }
+            }
+        };
+        let id = page["id"].as_str().ok_or("Failed to find page: no page ID")?;
+        Ok(Some((id.to_string(), page["version"]["number"].as_u64().unwrap_or(0))))
+    }
+
+    async fn publish(&self, title: &str, storage: &str, page_id: Option<&str>) -> Result<PublishedPage, String> {
+        let existing = self.find_page(page_id, title).await?;
+        let (method, url, body, action) = match &existing {
+            Some((id, version)) => (
+                Method::PUT,
+                self.content_url(Some(id), &[])?,
+                page_body(&self.settings, title, storage, Some((id, version + 1))),
+                "update page",
+            ),
+            None => (
+                Method::POST,
+                self.content_url(None, &[])?,
+                page_body(&self.settings, title, storage, None),
+                "create page",
+            ),
+        };
+        let request = self
+            .request(method, url)?
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        let page = Self::send(request, action).await?;
+        Ok(PublishedPage {
+            id: page["id"].as_str().unwrap_or_default().to_string(),
+            url: page_url(&page, &self.settings.base_url).unwrap_or_default(),
+            version: page["version"]["number"].as_u64().unwrap_or(1),
+            created: existing.is_none(),
+        })
+    }
+}
+
+// Tauri command: Publish a processed document to the configured Confluence space, as a
+// new page or an update of `page_id` (or of the page with the same title)
+#[tauri::command]
+pub async fn publish_to_confluence(
+    title: String,
+    content: String,
+    page_id: Option<String>,
+) -> Result<PublishedPage, String> {
+    let title = title.trim().to_string();
+    if title.is_empty() {
+        return Err("A page title is required".to_string());
+    }
+    let client = ConfluenceClient::from_settings()?;
+    let storage = to_storage_format(&content);
+    let page = client.publish(&title, &storage, page_id.as_deref()).await?;
+    info!("Published {:?} to Confluence: {}", title, page.url);
+    Ok(page)
+}
@@ -0,0 +1,134 @@
+//! # Editor Context Menu Module
+//!
+//! This module shows a native right-click menu for the editor area.
+//!
+//! The frontend intercepts `contextmenu` in the editor and calls `show_editor_context_menu`
+//! with the click position and what is under the cursor. Choosing an item emits an
+//! `editor-context-action` event (payload `{ action }`) to the window that opened the menu;
+//! the editor performs the action itself, so undo history stays intact.
+//!
+//! ## Actions
+//! - `cut`, `copy`: Need a selection
+//! - `paste`
+//! - `insert_link`, `insert_table`
+//! - `toggle_checkbox`: Only on a task list item
+//! - `copy_as_html`: Copies the selection (or the whole document) as rendered HTML
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use tauri::menu::{IsMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::{LogicalPosition, Manager};
+use tracing::{debug, warn};
+
+use crate::locale::tr;
+use crate::types::{emit_event, EditorContextActionEvent};
+
+// Menu item IDs are `ctx_<action>`
+const CONTEXT_ITEM_PREFIX: &str = "ctx_";
+
+// Context menu layout: action and label key, `None` for a separator
+const CONTEXT_MENU_LAYOUT: &[Option<(&str, &str)>] = &[
+    Some(("cut", "menu.cut")),
+    Some(("copy", "menu.copy")),
+    Some(("paste", "menu.paste")),
+    None,
+    Some(("insert_link", "context.insert_link")),
+    Some(("insert_table", "context.insert_table")),
+    Some(("toggle_checkbox", "context.toggle_checkbox")),
+    None,
+    Some(("copy_as_html", "context.copy_as_html")),
+];
+
+// Window that opened the last context menu (actions are sent back to it)
+static CONTEXT_MENU_WINDOW: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+
+fn context_menu_window() -> &'static Mutex<Option<String>> {
+    CONTEXT_MENU_WINDOW.get_or_init(|| Mutex::new(None))
+}
+
+// Click position in CSS pixels, relative to the window
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ContextMenuPosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+// What is under the cursor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorContext {
+    pub has_selection: bool,
+    pub in_task_item: bool,
+}
+
+// Whether an action is available in the given context
+pub fn is_action_enabled(action: &str, context: &EditorContext) -> bool {
+    match action {
+        "cut" | "copy" => context.has_selection,
+        "toggle_checkbox" => context.in_task_item,
+        _ => true,
+    }
+}
+
+// Parse the action out of a context menu item ID
+pub fn context_action(id: &str) -> Option<&str> {
+    id.strip_prefix(CONTEXT_ITEM_PREFIX)
+}
+
+// Forward a chosen context menu item to the window that opened the menu
+pub fn handle_context_action(app: &tauri::AppHandle, action: &str) {
+    let label = context_menu_window()
+        .lock()
+        .ok()
+        .and_then(|mut label| label.take())
+        .unwrap_or_else(|| "main".to_string());
+    let event = EditorContextActionEvent {
+        action: action.to_string(),
+    };
+    let result = emit_event(app, Some(label.as_str()), &event);
+    debug!("Emitted editor-context-action {} to {}: {:?}", action, label, result);
+}
+
+// Tauri command: Show the editor context menu at `position`
+#[tauri::command]
+pub fn show_editor_context_menu(
+    window: tauri::Window,
+    position: ContextMenuPosition,
+    context: EditorContext,
+) -> Result<(), String> {
+    let app = window.app_handle();
+    let mut items: Vec<Box<dyn IsMenuItem<tauri::Wry>>> = Vec::new();
+    for entry in CONTEXT_MENU_LAYOUT {
+        let item: Box<dyn IsMenuItem<tauri::Wry>> = match entry {
+            Some((action, label_key)) => Box::new(
+                MenuItem::with_id(
+                    app,
+                    format!("{}{}", CONTEXT_ITEM_PREFIX, action),
+                    tr(label_key),
+                    is_action_enabled(action, &context),
+                    None::<&str>,
+                )
+                .map_err(|e| format!("Failed to build context menu: {}", e))?,
+            ),
+            None => Box::new(
+                PredefinedMenuItem::separator(app)
+                    .map_err(|e| format!("Failed to build context menu: {}", e))?,
+            ),
+        };
+        items.push(item);
+    }
+    let refs: Vec<&dyn IsMenuItem<tauri::Wry>> = items.iter().map(|i| i.as_ref()).collect();
+    let menu = Menu::with_items(app, &refs).map_err(|e| format!("Failed to build context menu: {}", e))?;
+
+    if let Ok(mut label) = context_menu_window().lock() {
+        *label = Some(window.label().to_string());
+    }
+    window
+        .popup_menu_at(&menu, LogicalPosition::new(position.x, position.y))
+        .map_err(|e| {
+            warn!("Failed to show context menu: {}", e);
+            format!("Failed to show context menu: {}", e)
+        })
+}
@@ -0,0 +1,156 @@
+//! # Crash Report Module
+//!
+//! This module installs a panic hook that writes a crash report before the process goes
+//! down, so an abnormal exit can be reported with useful details.
+//!
+//! ## Report Contents
+//! - Panic message and source location
+//! - Thread name
+//! - App version, OS and architecture
+//! - Backtrace
+//!
+//! Panics that do not bring the app down are not reported: panics inside `catch_panic`
+//! (the Markdown expansion run on every keystroke) and panics in tasks of the async
+//! runtime, which the runtime turns into an error of the task. They are only logged.
+//!
+//! The report is written to `crash/last-crash.log` in the app data directory (or the
+//! system temp directory if the panic happens before the app data directory is known).
+//! On the next start the frontend calls `get_last_crash_report` to offer the report for an
+//! issue, and `dismiss_crash_report` once the user has seen it.
+
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+
+use crate::storage;
+
+const CRASH_DIR: &str = "crash";
+const CRASH_FILE: &str = "last-crash.log";
+
+thread_local! {
+    // Depth of `catch_panic` calls running on this thread
+    static CATCHING: Cell<usize> = const { Cell::new(0) };
+}
+
+// Crash report returned to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub path: String,
+    pub contents: String,
+}
+
+// Location of the crash log
+pub fn crash_log_path() -> PathBuf {
+    storage::app_data_path(CRASH_DIR)
+        .unwrap_or_else(|| std::env::temp_dir().join("bokuchi-crash"))
+        .join(CRASH_FILE)
+}
+
+// Build the crash report text
+pub fn format_crash_report(message: &str, location: &str, thread: &str, backtrace: &str, timestamp: &str) -> String {
+    format!(
+        "Bokuchi crash report\n\
+         ====================\n\
+         Time: {}\n\
+         Version: {}\n\
+         OS: {} ({})\n\
+         Thread: {}\n\
+         Location: {}\n\
+         \n\
+         Message:\n{}\n\
+         \n\
+         Backtrace:\n{}\n",
+        timestamp,
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        thread,
+        location,
+        message,
+        backtrace,
+    )
+}
+
+// Run `f`, turning a panic into an error. The panic is not reported as a crash.
+pub fn catch_panic<R>(f: impl FnOnce() -> R) -> std::thread::Result<R> {
+    CATCHING.with(|depth| depth.set(depth.get() + 1));
+    let result = catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|depth| depth.set(depth.get() - 1));
+    result
+}
+
+// Whether a panic on the current thread will be caught: inside `catch_panic`, or on a
+// thread of the async runtime (whose tasks catch panics)
+pub fn panic_is_caught() -> bool {
+    CATCHING.with(|depth| depth.get() > 0) || tokio::runtime::Handle::try_current().is_ok()
+}
+
+// Install the panic hook. The previous hook still runs afterwards so the panic is also
+// printed to stderr.
+pub fn install_panic_hook() {
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "Unknown panic payload".to_string()
+        };
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+            .unwrap_or_else(|| "unknown".to_string());
+        let thread = std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string();
+        if panic_is_caught() {
+            tracing::warn!(target: "panic", "{} at {} (thread: {}, caught)", message, location, thread);
+            previous_hook(info);
+            return;
+        }
+        tracing::error!(target: "panic", "{} at {} (thread: {})", message, location, thread);
+
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+        let timestamp = chrono::Local::now().to_rfc3339();
+
+        let report = format_crash_report(&message, &location, &thread, &backtrace, &timestamp);
+        let path = crash_log_path();
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        match fs::write(&path, report) {
+            Ok(()) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+
+        previous_hook(info);
+    }));
+}
+
+// Tauri command: Get the report of the last crash, if any
+#[tauri::command]
+pub fn get_last_crash_report() -> Result<Option<CrashReport>, String> {
+    let path = crash_log_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&path).map_err(|e| format!("Failed to read crash report: {}", e))?;
+    Ok(Some(CrashReport {
+        path: path.to_string_lossy().to_string(),
+        contents,
+    }))
+}
+
+// Tauri command: Delete the last crash report once the user has dealt with it
+#[tauri::command]
+pub fn dismiss_crash_report() -> Result<(), String> {
+    let path = crash_log_path();
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove crash report: {}", e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! # Credentials Module
+//!
+//! This module keeps secrets (passwords, tokens) in the OS keychain: the macOS Keychain,
+//! the Windows Credential Manager, or the Secret Service / kernel keyring on Linux.
+//! Secrets are never written to the JSON files in the app data directory.
+//!
+//! ## Keys
+//! Every secret is stored under the `Bokuchi` service with a key naming its owner, e.g.
+//! `remote:<endpoint id>` for the password of a remote-files endpoint.
+//!
+//! ## Integration Tokens
+//! Integrations (git remotes, WebDAV, publishing APIs) store their tokens through the
+//! `set_credential` / `get_credential` / `delete_credential` commands instead of the
+//! store JSON. Their keys are put under `integration:`, so the frontend can only reach
+//! its own tokens, not the secrets the backend keeps for remotes and sync.
+
+use keyring::Entry;
+
+const SERVICE: &str = "Bokuchi";
+// Namespace of the keys used through the commands
+const INTEGRATION_PREFIX: &str = "integration:";
+const MAX_KEY_LEN: usize = 128;
+const MAX_SECRET_LEN: usize = 16 * 1024;
+
+fn entry(key: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, key).map_err(|e| format!("Failed to access the keychain: {}", e))
+}
+
+// Store a secret, replacing any previous one
+pub fn set_secret(key: &str, secret: &str) -> Result<(), String> {
+    entry(key)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to store credential: {}", e))
+}
+
+// Read a secret (None if nothing is stored under `key`)
+pub fn get_secret(key: &str) -> Result<Option<String>, String> {
+    match entry(key)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read credential: {}", e)),
+    }
+}
+
+// Delete a secret (deleting a missing one is not an error)
+pub fn delete_secret(key: &str) -> Result<(), String> {
+    match entry(key)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete credential: {}", e)),
+    }
+}
+
+// Keychain key of an integration token (e.g. `github` -> `integration:github`)
+pub fn integration_key(key: &str) -> Result<String, String> {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':' | '@' | '/');
+    if key.is_empty() || key.len() > MAX_KEY_LEN || !key.chars().all(valid_char) {
+        return Err(format!("Invalid credential key: {:?}", key));
+    }
+    Ok(format!("{}{}", INTEGRATION_PREFIX, key))
+}
+
+// Tauri command: Store the token of an integration in the keychain
+#[tauri::command]
+pub fn set_credential(key: String, secret: String) -> Result<(), String> {
+    if secret.is_empty() || secret.len() > MAX_SECRET_LEN {
+        return Err("Credential must be between 1 byte and 16KB".to_string());
+    }
+    set_secret(&integration_key(&key)?, &secret)
+}
+
+// Tauri command: Read the token of an integration (None if none is stored)
+#[tauri::command]
+pub fn get_credential(key: String) -> Result<Option<String>, String> {
+    get_secret(&integration_key(&key)?)
+}
+
+// Tauri command: Delete the token of an integration
+#[tauri::command]
+pub fn delete_credential(key: String) -> Result<(), String> {
+    delete_secret(&integration_key(&key)?)
+}
@@ -0,0 +1,275 @@
+//! # Diagnostics Module
+//!
+//! This module gathers what is needed to look into a bug report into one ZIP archive the
+//! user can attach to an issue, and describes the environment for the About dialog
+//! (`get_environment_info`).
+//!
+//! ## Contents
+//! - `system.json`: App, Tauri and WebView versions, OS, kernel, session type (Wayland/X11),
+//!   locale and the GPUs with their drivers
+//! - `environment.txt`: Environment variables that affect rendering (Wayland/X11,
+//!   WebKitGTK, EGL/GL drivers, the graphics fallback), see `DIAGNOSTIC_ENV_PREFIXES`
+//! - `logs.txt`: The most recent log lines
+//! - `settings.json`: Backend settings, with anything that looks like a secret redacted
+//! - `last-crash.log`: The report of the last crash, if there is one
+//!
+//! Passwords and tokens live in the OS keychain and are never read here.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use tauri::Manager;
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::crash;
+use crate::locale;
+use crate::logging;
+use crate::path_scope;
+use crate::settings;
+use crate::storage;
+
+// Environment variables whose name starts with one of these are included
+pub const DIAGNOSTIC_ENV_PREFIXES: &[&str] = &[
+    "WAYLAND_",
+    "DISPLAY",
+    "XDG_SESSION_",
+    "XDG_CURRENT_DESKTOP",
+    "GDK_",
+    "GTK_",
+    "WEBKIT_",
+    "EGL_",
+    "__EGL_",
+    "LIBGL_",
+    "MESA_",
+    "__GLX_",
+    "__NV_",
+    "APPIMAGE",
+    "BOKUCHI_",
+];
+// Parts of setting names whose values are redacted
+const SECRET_KEY_PARTS: &[&str] = &["password", "secret", "token", "api_key", "access_key", "credential"];
+const REDACTED: &str = "[redacted]";
+// Log lines included in the archive
+const DIAGNOSTIC_LOG_LINES: usize = 2000;
+
+// The system the app runs on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemInfo {
+    pub app_version: String,
+    pub tauri_version: String,
+    pub os: String,
+    pub arch: String,
+    // Distribution name on Linux
+    pub os_version: Option<String>,
+    pub kernel: Option<String>,
+    pub webview_version: Option<String>,
+    // "wayland" or "x11" on Linux
+    pub session_type: Option<String>,
+    // Locale of the app's own strings
+    pub locale: String,
+    // "<vendor> (<driver>)" of each GPU; only known on Linux
+    pub gpus: Vec<String>,
+}
+
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let release = fs::read_to_string("/etc/os-release").ok()?;
+    release
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME=").map(|name| name.trim_matches('"').to_string()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn os_version() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn kernel() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|release| release.trim().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kernel() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn session_type() -> Option<String> {
+    std::env::var("XDG_SESSION_TYPE")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .or_else(|| std::env::var_os("WAYLAND_DISPLAY").map(|_| "wayland".to_string()))
+        .or_else(|| std::env::var_os("DISPLAY").map(|_| "x11".to_string()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn session_type() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn gpus() -> Vec<String> {
+    let Ok(entries) = fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    let mut gpus: Vec<String> = entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // card0, card1, ... (not their connectors, e.g. card0-HDMI-A-1)
+            name.strip_prefix("card").is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .filter_map(|entry| {
+            let device = entry.path().join("device");
+            let vendor = fs::read_to_string(device.join("vendor")).ok()?;
+            let vendor = match vendor.trim() {
+                "0x10de" => "NVIDIA".to_string(),
+                "0x1002" => "AMD".to_string(),
+                "0x8086" => "Intel".to_string(),
+                other => other.to_string(),
+            };
+            let driver = fs::read_link(device.join("driver"))
+                .ok()
+                .and_then(|link| link.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_else(|| "no driver".to_string());
+            Some(format!("{} ({})", vendor, driver))
+        })
+        .collect();
+    if let Ok(version) = fs::read_to_string("/proc/driver/nvidia/version")
+        && let Some(first) = version.lines().next()
+    {
+        gpus.push(first.trim().to_string());
+    }
+    gpus
+}
+
+#[cfg(not(target_os = "linux"))]
+fn gpus() -> Vec<String> {
+    Vec::new()
+}
+
+// Describe the system the app runs on
+pub fn system_info() -> SystemInfo {
+    SystemInfo {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        tauri_version: tauri::VERSION.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        os_version: os_version(),
+        kernel: kernel(),
+        webview_version: tauri::webview_version().ok(),
+        session_type: session_type(),
+        locale: locale::current_locale().to_string(),
+        gpus: gpus(),
+    }
+}
+
+// Whether an environment variable is included in the archive
+pub fn is_diagnostic_env(name: &str) -> bool {
+    DIAGNOSTIC_ENV_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+// The environment variables included in the archive, sorted by name
+pub fn diagnostic_env(vars: impl Iterator<Item = (String, String)>) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = vars.filter(|(name, _)| is_diagnostic_env(name)).collect();
+    vars.sort();
+    vars
+}
+
+// Replace the values of secret-looking keys (at any depth) with a placeholder
+pub fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SECRET_KEY_PARTS.iter().any(|part| key.contains(part)) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+// Pack the files of a diagnostics archive into `archive`
+pub fn write_diagnostics_bundle(archive: &Path, files: &[(&str, Vec<u8>)]) -> Result<(), String> {
+    let zip_error = |e: zip::result::ZipError| format!("Failed to write archive: {}", e);
+    let io_error = |e: std::io::Error| format!("Failed to write archive: {}", e);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, bytes) in files {
+        writer.start_file(*name, options).map_err(zip_error)?;
+        writer.write_all(bytes).map_err(io_error)?;
+    }
+    let bytes = writer.finish().map_err(zip_error)?.into_inner();
+    storage::write_atomic(archive, &bytes)
+}
+
+// Gather the files of the archive
+fn diagnostics_files() -> Result<Vec<(&'static str, Vec<u8>)>, String> {
+    let json = |value: &Value| serde_json::to_vec_pretty(value).map_err(|e| format!("Failed to serialize: {}", e));
+
+    let system = serde_json::to_value(system_info()).map_err(|e| format!("Failed to serialize: {}", e))?;
+    let environment: String = diagnostic_env(std::env::vars())
+        .into_iter()
+        .map(|(name, value)| format!("{}={}\n", name, value))
+        .collect();
+    let logs = logging::get_recent_logs(None, Some(DIAGNOSTIC_LOG_LINES))?.join("\n");
+    let mut settings =
+        serde_json::to_value(settings::current_settings()).map_err(|e| format!("Failed to serialize: {}", e))?;
+    redact_secrets(&mut settings);
+
+    let mut files = vec![
+        ("system.json", json(&system)?),
+        ("environment.txt", environment.into_bytes()),
+        ("logs.txt", logs.into_bytes()),
+        ("settings.json", json(&settings)?),
+    ];
+    if let Ok(crash_report) = fs::read(crash::crash_log_path()) {
+        files.push(("last-crash.log", crash_report));
+    }
+    Ok(files)
+}
+
+// Tauri command: Describe the app's environment (versions, session type, GPUs, locale)
+// for the About dialog
+#[tauri::command]
+pub fn get_environment_info() -> SystemInfo {
+    system_info()
+}
+
+// Tauri command: Write a diagnostics archive for a bug report to `path`, or to the
+// Downloads folder. Returns the path of the archive.
+#[tauri::command]
+pub async fn generate_diagnostics_bundle(app_handle: tauri::AppHandle, path: Option<String>) -> Result<String, String> {
+    let archive = match path {
+        Some(path) => {
+            path_scope::check_path(&path)?;
+            PathBuf::from(path)
+        }
+        None => {
+            let dir = app_handle.path().download_dir().unwrap_or_else(|_| std::env::temp_dir());
+            dir.join(format!("bokuchi-diagnostics-{}.zip", Local::now().format("%Y%m%d-%H%M%S")))
+        }
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        write_diagnostics_bundle(&archive, &diagnostics_files()?)?;
+        info!("Wrote diagnostics archive to {:?}", archive);
+        Ok(archive.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| format!("Failed to write diagnostics: {}", e))?
+}
@@ -0,0 +1,252 @@
+//! # Diff Module
+//!
+//! This module compares the editor buffer with the file on disk, so the "file changed
+//! externally" dialog can show exactly what differs instead of only asking whether to
+//! reload or overwrite. It also compares two documents (or a document and the clipboard)
+//! for a side-by-side diff view.
+//!
+//! ## Output
+//! Hunks have the same shape as `git_diff_file` (see `git::DiffHunk`): the file on disk is
+//! the old side, the editor content the new side. Identical texts produce no hunks.
+//! Line endings are compared as well, so a file rewritten with CRLF line breaks differs
+//! on every line.
+//!
+//! ## Word Highlights
+//! Comparisons (`compare_files`, `compare_with_clipboard`) also mark the changed words of
+//! changed lines: within a hunk, each removed line is paired with the added line at the
+//! same position of the following added lines, and the words not common to both are
+//! highlighted. Highlights are UTF-16 ranges within the line. Words are runs of letters
+//! and digits; CJK characters and punctuation count as one word each, so changes in
+//! Japanese text are not highlighted as whole sentences.
+
+use git2::{DiffOptions, Patch};
+use serde::{Deserialize, Serialize};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::commands;
+use crate::git::{self, DiffHunk, DiffLine, DiffLineKind};
+
+const CONTEXT_LINES: u32 = 3;
+// Longer lines are highlighted as a whole
+const MAX_LINE_WORDS: usize = 500;
+
+// A highlighted range of a line, in UTF-16 code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+// A diff line with its changed words
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComparedLine {
+    #[serde(flatten)]
+    pub line: DiffLine,
+    // Empty for context lines and changed lines without a counterpart
+    pub highlights: Vec<WordSpan>,
+}
+
+// A hunk of a comparison
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComparedHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub header: String,
+    pub lines: Vec<ComparedLine>,
+}
+
+// Result of `compare_files` and `compare_with_clipboard`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Comparison {
+    pub hunks: Vec<ComparedHunk>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+// Diff two texts as unified hunks
+pub fn diff_texts(old: &str, new: &str) -> Result<Vec<DiffHunk>, String> {
+    diff_texts_with_context(old, new, CONTEXT_LINES)
+}
+
+fn diff_texts_with_context(old: &str, new: &str, context: u32) -> Result<Vec<DiffHunk>, String> {
+    let mut options = DiffOptions::new();
+    options.context_lines(context).force_text(true);
+    let patch = Patch::from_buffers(old.as_bytes(), None, new.as_bytes(), None, Some(&mut options))
+        .map_err(|e| format!("Failed to diff content: {}", e))?;
+    git::patch_hunks(&patch)
+}
+
+// Words of a line with their UTF-16 ranges
+fn words(line: &str) -> Vec<(&str, WordSpan)> {
+    let is_word_char = |c: char| (c.is_alphanumeric() && c < '\u{2E80}') || c == '_';
+    let mut words: Vec<(&str, WordSpan)> = Vec::new();
+    let mut utf16 = 0;
+    let mut start: Option<(usize, usize)> = None;
+    for (offset, c) in line.char_indices() {
+        let joins = match (start, line[..offset].chars().next_back()) {
+            (Some(_), Some(previous)) => {
+                (is_word_char(previous) && is_word_char(c)) || (previous.is_whitespace() && c.is_whitespace())
+            }
+            _ => false,
+        };
+        if !joins {
+            if let Some((byte_start, utf16_start)) = start {
+                words.push((&line[byte_start..offset], WordSpan { start: utf16_start, end: utf16 }));
+            }
+            start = Some((offset, utf16));
+        }
+        utf16 += c.len_utf16();
+    }
+    if let Some((byte_start, utf16_start)) = start {
+        words.push((&line[byte_start..], WordSpan { start: utf16_start, end: utf16 }));
+    }
+    words
+}
+
+// Ranges of the words of `line` that are not in the longest common subsequence with
+// `other`, merged where adjacent
+fn changed_words(line: &str, other: &str) -> Vec<WordSpan> {
+    let a = words(line);
+    let b = words(other);
+    let mut changed = vec![true; a.len()];
+    if a.len() <= MAX_LINE_WORDS && b.len() <= MAX_LINE_WORDS {
+        // lengths[i][j]: LCS length of a[i..] and b[j..]
+        let mut lengths = vec![vec![0u16; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lengths[i][j] = if a[i].0 == b[j].0 {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i].0 == b[j].0 {
+                changed[i] = false;
+                i += 1;
+                j += 1;
+            } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+
+    let mut spans: Vec<WordSpan> = Vec::new();
+    for ((_, span), changed) in a.iter().zip(changed) {
+        if !changed {
+            continue;
+        }
+        match spans.last_mut() {
+            Some(last) if last.end == span.start => last.end = span.end,
+            _ => spans.push(*span),
+        }
+    }
+    spans
+}
+
+// Add word highlights to the lines of a hunk
+fn compare_hunk(hunk: DiffHunk) -> ComparedHunk {
+    let mut highlights: Vec<Vec<WordSpan>> = vec![Vec::new(); hunk.lines.len()];
+    let mut index = 0;
+    while index < hunk.lines.len() {
+        let removed_start = index;
+        while index < hunk.lines.len() && hunk.lines[index].kind == DiffLineKind::Removed {
+            index += 1;
+        }
+        let added_start = index;
+        while index < hunk.lines.len() && hunk.lines[index].kind == DiffLineKind::Added {
+            index += 1;
+        }
+        let pairs = (added_start - removed_start).min(index - added_start);
+        for pair in 0..pairs {
+            let (old, new) = (removed_start + pair, added_start + pair);
+            highlights[old] = changed_words(&hunk.lines[old].content, &hunk.lines[new].content);
+            highlights[new] = changed_words(&hunk.lines[new].content, &hunk.lines[old].content);
+        }
+        if index == removed_start {
+            index += 1;
+        }
+    }
+    ComparedHunk {
+        old_start: hunk.old_start,
+        old_lines: hunk.old_lines,
+        new_start: hunk.new_start,
+        new_lines: hunk.new_lines,
+        header: hunk.header,
+        lines: hunk
+            .lines
+            .into_iter()
+            .zip(highlights)
+            .map(|(line, highlights)| ComparedLine { line, highlights })
+            .collect(),
+    }
+}
+
+// Compare two texts with `context` lines around changes and word highlights
+pub fn compare_texts(old: &str, new: &str, context: u32) -> Result<Comparison, String> {
+    let hunks: Vec<ComparedHunk> = diff_texts_with_context(old, new, context)?
+        .into_iter()
+        .map(compare_hunk)
+        .collect();
+    let count = |kind| {
+        hunks
+            .iter()
+            .flat_map(|hunk| &hunk.lines)
+            .filter(|line| line.line.kind == kind)
+            .count()
+    };
+    Ok(Comparison {
+        added: count(DiffLineKind::Added),
+        removed: count(DiffLineKind::Removed),
+        hunks,
+    })
+}
+
+// Tauri command: Diff the editor content against the file on disk
+#[tauri::command]
+pub async fn diff_content(path: String, editor_content: String) -> Result<Vec<DiffHunk>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let disk_content = commands::read_text_file(&path)?;
+        diff_texts(&disk_content, &editor_content)
+    })
+    .await
+    .map_err(|e| format!("Failed to diff content: {}", e))?
+}
+
+// Tauri command: Compare two documents (`path_a` is the old side). `context` is the
+// number of unchanged lines around changes (3 by default); a large value gives the whole
+// documents for a side-by-side view.
+#[tauri::command]
+pub async fn compare_files(path_a: String, path_b: String, context: Option<u32>) -> Result<Comparison, String> {
+    let old = commands::read_file(path_a).await?;
+    let new = commands::read_file(path_b).await?;
+    tauri::async_runtime::spawn_blocking(move || compare_texts(&old, &new, context.unwrap_or(CONTEXT_LINES)))
+        .await
+        .map_err(|e| format!("Failed to compare documents: {}", e))?
+}
+
+// Tauri command: Compare a document (the old side) with the text on the clipboard
+#[tauri::command]
+pub async fn compare_with_clipboard(
+    app_handle: tauri::AppHandle,
+    path: String,
+    context: Option<u32>,
+) -> Result<Comparison, String> {
+    let old = commands::read_file(path).await?;
+    // The clipboard must not be read on the main thread (it can deadlock on Linux)
+    tauri::async_runtime::spawn_blocking(move || {
+        let new = app_handle
+            .clipboard()
+            .read_text()
+            .map_err(|e| format!("No text on the clipboard: {}", e))?;
+        compare_texts(&old, &new, context.unwrap_or(CONTEXT_LINES))
+    })
+    .await
+    .map_err(|e| format!("Failed to compare with clipboard: {}", e))?
+}
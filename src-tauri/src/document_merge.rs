@@ -0,0 +1,290 @@
+//! # Document Merge Module
+//!
+//! This module merges several documents into one, in a given order, for building a book
+//! or a single specification out of chapter files.
+//!
+//! ## Per File
+//! Each file is processed on its own before it is appended:
+//! - Variables are expanded with the file's own definitions (optional)
+//! - Frontmatter is removed
+//! - Headings are demoted by `demote_headings` levels, up to level 6; setext headings
+//!   become ATX headings
+//! - Relative links and images are rewritten to work from the output folder (optional)
+//! - A level-1 heading with the file's title is inserted (optional). A file that already
+//!   starts with a level-1 heading of that title keeps it as its title heading, and that
+//!   heading is not demoted.
+//!
+//! Files are separated by a blank line. Headings inside lists and block quotes, and
+//! anything inside code, are left alone.
+//!
+//! ## Links
+//! Link targets that are URLs, anchors (`#intro`) or absolute paths are kept. Links
+//! between the merged files still point at the files.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use tracing::info;
+
+use crate::commands;
+use crate::document_structure::{parse_structure, BlockKind};
+use crate::frontmatter;
+use crate::links;
+use crate::markdown::{self, lines_outside_code};
+use crate::notifications;
+use crate::variable_processor::VARIABLE_PROCESSOR;
+
+lazy_static! {
+    static ref LINK_TARGET: Regex = Regex::new(r"(!?\[[^\]]*\]\(\s*)(<[^>]*>|[^)\s]+)").unwrap();
+    static ref REFERENCE_TARGET: Regex = Regex::new(r"^( {0,3}\[[^\]]+\]:\s*)(<[^>]*>|\S+)").unwrap();
+    static ref ATX_PREFIX: Regex = Regex::new(r"^ {0,3}(#{1,6})(?:[ \t]+|$)").unwrap();
+}
+
+// Options of `merge_documents`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MergeOptions {
+    // Levels added to the headings of each file
+    pub demote_headings: u8,
+    // Insert the title of each file (see `markdown::document_title`) as a level-1 heading
+    pub title_headings: bool,
+    pub rewrite_links: bool,
+    pub process_variables: bool,
+    // File the merged document is saved to; None only returns it. Links are rewritten
+    // relative to its folder, or to the folder of the first file without one.
+    pub output_path: Option<String>,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            demote_headings: 0,
+            title_headings: false,
+            rewrite_links: true,
+            process_variables: true,
+            output_path: None,
+        }
+    }
+}
+
+// Result of `merge_documents`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergedDocument {
+    pub content: String,
+    // Where it was saved
+    pub path: Option<String>,
+}
+
+// `target` relative to the folder `base`; both are absolute and normalized
+fn relative_to(target: &Path, base: &Path) -> PathBuf {
+    let target_components: Vec<Component> = target.components().collect();
+    let base_components: Vec<Component> = base.components().collect();
+    let common = target_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    // Different roots (e.g. drives on Windows) cannot be related
+    if common == 0 {
+        return target.to_path_buf();
+    }
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+    for component in &target_components[common..] {
+        relative.push(component.as_os_str());
+    }
+    relative
+}
+
+// A link target of a file in `source_dir`, rewritten for a document in `output_dir`.
+// None when it stays as it is.
+fn rewrite_target(raw: &str, source_dir: &Path, output_dir: &Path) -> Option<String> {
+    let target = raw.strip_prefix('<').and_then(|t| t.strip_suffix('>')).unwrap_or(raw);
+    let is_external = target.contains("://")
+        || ["mailto:", "tel:", "data:", "#", "/"]
+            .iter()
+            .any(|prefix| target.starts_with(prefix));
+    if is_external {
+        return None;
+    }
+    let split = target.find(['#', '?']).unwrap_or(target.len());
+    let (path, suffix) = target.split_at(split);
+    let decoded = links::percent_decode(path);
+    if path.is_empty() || Path::new(&decoded).is_absolute() {
+        return None;
+    }
+    let absolute = links::normalize_path(&source_dir.join(&decoded));
+    let relative = relative_to(&absolute, &links::normalize_path(output_dir))
+        .to_string_lossy()
+        .replace('\\', "/");
+    if relative == decoded {
+        return None;
+    }
+    let rewritten = format!("{}{}", relative, suffix);
+    if rewritten.contains([' ', '(', ')']) {
+        Some(format!("<{}>", rewritten))
+    } else {
+        Some(rewritten)
+    }
+}
+
+// Rewrite the relative links, images and reference definitions of a document
+pub fn rewrite_links(body: &str, source_dir: &Path, output_dir: &Path) -> String {
+    let code_free: Vec<usize> = lines_outside_code(body).into_iter().map(|(index, _)| index).collect();
+    body.split_inclusive('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            if code_free.binary_search(&index).is_err() {
+                return line.to_string();
+            }
+            let masked = markdown::mask_inline_code(line);
+            let mut output = String::with_capacity(line.len());
+            let mut last = 0;
+            let mut targets: Vec<_> = LINK_TARGET
+                .captures_iter(&masked)
+                .chain(REFERENCE_TARGET.captures_iter(&masked))
+                .filter_map(|caps| caps.get(2))
+                .map(|target| target.range())
+                .collect();
+            targets.sort_by_key(|range| range.start);
+            for range in targets {
+                if range.start < last {
+                    continue;
+                }
+                if let Some(rewritten) = rewrite_target(&line[range.clone()], source_dir, output_dir) {
+                    output.push_str(&line[last..range.start]);
+                    output.push_str(&rewritten);
+                    last = range.end;
+                }
+            }
+            output.push_str(&line[last..]);
+            output
+        })
+        .collect()
+}
+
+// Demote the top-level headings of a document by `levels` (up to level 6), except the
+// heading at `keep_line` (1-based)
+pub fn demote_headings(body: &str, levels: u8, keep_line: Option<usize>) -> String {
+    if levels == 0 {
+        return body.to_string();
+    }
+    let lines: Vec<&str> = body.split_inclusive('\n').collect();
+    // Replacement of each changed line; None removes it (setext underlines)
+    let mut replaced: HashMap<usize, Option<String>> = HashMap::new();
+    for block in parse_structure(body) {
+        let BlockKind::Heading { level, text, .. } = &block.kind else {
+            continue;
+        };
+        if Some(block.start_line) == keep_line {
+            continue;
+        }
+        let index = block.start_line - 1;
+        let Some(line) = lines.get(index) else {
+            continue;
+        };
+        let hashes = "#".repeat(level.saturating_add(levels).min(6) as usize);
+        match ATX_PREFIX.captures(line) {
+            Some(caps) => {
+                let marker = caps.get(1).map_or(0..0, |m| m.range());
+                let demoted = format!("{}{}{}", &line[..marker.start], hashes, &line[marker.end..]);
+                replaced.insert(index, Some(demoted));
+            }
+            None => {
+                let ending = if line.ends_with("\r\n") { "\r\n" } else { "\n" };
+                replaced.insert(index, Some(format!("{} {}{}", hashes, text, ending)));
+                for removed in block.start_line..block.end_line {
+                    replaced.insert(removed, None);
+                }
+            }
+        }
+    }
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| match replaced.get(&index) {
+            Some(replacement) => replacement.clone(),
+            None => Some(line.to_string()),
+        })
+        .collect()
+}
+
+// Process one file of the merge
+pub fn merge_part(path: &Path, content: &str, options: &MergeOptions, output_dir: &Path) -> String {
+    let processed = if options.process_variables {
+        VARIABLE_PROCESSOR.process_variables(content)
+    } else {
+        content.to_string()
+    };
+    let title = markdown::document_title(path, &processed);
+    let body = frontmatter::split_frontmatter(&processed).body.trim_start_matches(['\r', '\n']);
+
+    // A first heading that already is the title
+    let title_line = parse_structure(body).into_iter().find_map(|block| match block.kind {
+        BlockKind::Heading { level, text, .. } => Some((level == 1 && text == title).then_some(block.start_line)),
+        _ => None,
+    });
+    let title_line = title_line.flatten().filter(|_| options.title_headings);
+
+    let mut body = demote_headings(body, options.demote_headings, title_line);
+    if options.rewrite_links {
+        let source_dir = path.parent().unwrap_or(Path::new(""));
+        body = rewrite_links(&body, source_dir, output_dir);
+    }
+    let body = body.trim_end();
+    if options.title_headings && title_line.is_none() && !title.is_empty() {
+        format!("# {}\n\n{}\n", title, body)
+    } else {
+        format!("{}\n", body)
+    }
+}
+
+// Merge documents (path and content) in order
+pub fn merge_contents(files: &[(PathBuf, String)], options: &MergeOptions) -> String {
+    let output_dir = match &options.output_path {
+        Some(output) => Path::new(output).parent().map(Path::to_path_buf),
+        None => files.first().and_then(|(path, _)| path.parent().map(Path::to_path_buf)),
+    }
+    .unwrap_or_default();
+    files
+        .iter()
+        .map(|(path, content)| merge_part(path, content, options, &output_dir))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Tauri command: Merge documents into one, in the order given (a "book build")
+#[tauri::command]
+pub async fn merge_documents(paths: Vec<String>, options: Option<MergeOptions>) -> Result<MergedDocument, String> {
+    if paths.is_empty() {
+        return Err("No documents to merge".to_string());
+    }
+    let options = options.unwrap_or_default();
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        let content = commands::read_file(path.clone()).await?;
+        files.push((PathBuf::from(path), content));
+    }
+    let count = files.len();
+    let (content, options) = tauri::async_runtime::spawn_blocking(move || (merge_contents(&files, &options), options))
+        .await
+        .map_err(|e| format!("Failed to merge documents: {}", e))?;
+    if let Some(output) = &options.output_path {
+        commands::save_file(output.clone(), content.clone()).await?;
+        info!("Merged {} documents into {:?}", count, output);
+        let name = Path::new(output)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| output.clone());
+        notifications::notify("notification.export_finished", &name, Some(output));
+    }
+    Ok(MergedDocument {
+        content,
+        path: options.output_path,
+    })
+}
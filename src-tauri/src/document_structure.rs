@@ -0,0 +1,273 @@
+//! # Document Structure Module
+//!
+//! This module parses a Markdown document (CommonMark with the GitHub extensions) into a
+//! simplified block tree, so folding, block selection and structural navigation in the
+//! frontend do not need a second Markdown parser.
+//!
+//! ## Blocks
+//! Only block-level structure is reported: frontmatter, headings, paragraphs, lists and
+//! their items, block quotes, code blocks, tables, HTML blocks, footnote definitions and
+//! thematic breaks. Inline formatting is not.
+//!
+//! List items and block quotes contain their blocks as `children`; everything else is a
+//! leaf.
+//!
+//! ## Dialect
+//! The Markdown extensions follow the settings (see `markdown_dialect`). With heading
+//! attributes enabled, a heading's `{#id}` is its anchor.
+//!
+//! ## Ranges
+//! Every block has its 1-based first and last line, and `start`/`end` as UTF-16 offsets
+//! into the document (JavaScript string offsets, as used by the editor). The end excludes
+//! the block's trailing line break.
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::markdown;
+use crate::markdown_dialect;
+use crate::settings::MarkdownExtensions;
+
+// Kind of a block, with its details
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockKind {
+    Frontmatter,
+    Heading {
+        level: u8,
+        text: String,
+        // GitHub-style, made unique within the document ("intro", "intro-1")
+        anchor: String,
+    },
+    Paragraph,
+    List {
+        ordered: bool,
+        // Number of the first item of an ordered list
+        start: Option<u64>,
+    },
+    ListItem {
+        // Some for task list items
+        checked: Option<bool>,
+    },
+    BlockQuote,
+    CodeBlock {
+        // Info string of a fenced block ("rust"); None for indented blocks
+        language: Option<String>,
+        fenced: bool,
+    },
+    Table {
+        columns: usize,
+        // Header row included
+        rows: usize,
+    },
+    Html,
+    FootnoteDefinition {
+        label: String,
+    },
+    ThematicBreak,
+}
+
+// A block of the document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Block {
+    #[serde(flatten)]
+    pub kind: BlockKind,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub children: Vec<Block>,
+}
+
+// Line and UTF-16 positions of byte offsets in a document
+struct SourceIndex<'a> {
+    content: &'a str,
+    // Byte offset and UTF-16 offset of each line start
+    lines: Vec<(usize, usize)>,
+}
+
+impl<'a> SourceIndex<'a> {
+    fn new(content: &'a str) -> Self {
+        let mut lines = vec![(0, 0)];
+        let mut utf16 = 0;
+        for (offset, c) in content.char_indices() {
+            utf16 += c.len_utf16();
+            if c == '\n' {
+                lines.push((offset + 1, utf16));
+            }
+        }
+        SourceIndex { content, lines }
+    }
+
+    // 1-based line and UTF-16 offset of a byte offset
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.lines.partition_point(|(start, _)| *start <= offset) - 1;
+        let (line_start, line_utf16) = self.lines[line];
+        (line + 1, line_utf16 + self.content[line_start..offset].encode_utf16().count())
+    }
+
+    fn block(&self, kind: BlockKind, range: Range<usize>) -> Block {
+        let text = &self.content[range.clone()];
+        let end = range.start + text.trim_end_matches(['\n', '\r']).len();
+        let (start_line, start) = self.position(range.start);
+        let (end_line, end) = self.position(end);
+        Block {
+            kind,
+            start_line,
+            end_line,
+            start,
+            end,
+            children: Vec::new(),
+        }
+    }
+}
+
+// The block a start tag opens, None for inline tags and parts of tables
+fn block_kind(tag: &Tag) -> Option<BlockKind> {
+    Some(match tag {
+        Tag::Paragraph => BlockKind::Paragraph,
+        Tag::Heading { level, id, .. } => BlockKind::Heading {
+            level: *level as u8,
+            text: String::new(),
+            // The `{#id}` attribute, if any
+            anchor: id.as_deref().unwrap_or_default().to_string(),
+        },
+        Tag::BlockQuote(_) => BlockKind::BlockQuote,
+        Tag::CodeBlock(CodeBlockKind::Fenced(info)) => BlockKind::CodeBlock {
+            language: info.split_whitespace().next().map(str::to_string),
+            fenced: true,
+        },
+        Tag::CodeBlock(CodeBlockKind::Indented) => BlockKind::CodeBlock {
+            language: None,
+            fenced: false,
+        },
+        Tag::HtmlBlock => BlockKind::Html,
+        Tag::List(start) => BlockKind::List {
+            ordered: start.is_some(),
+            start: *start,
+        },
+        Tag::Item => BlockKind::ListItem { checked: None },
+        Tag::FootnoteDefinition(label) => BlockKind::FootnoteDefinition {
+            label: label.to_string(),
+        },
+        Tag::Table(alignments) => BlockKind::Table {
+            columns: alignments.len(),
+            rows: 0,
+        },
+        Tag::MetadataBlock(_) => BlockKind::Frontmatter,
+        _ => return None,
+    })
+}
+
+fn is_block_end(end: &TagEnd) -> bool {
+    matches!(
+        end,
+        TagEnd::Paragraph
+            | TagEnd::Heading(_)
+            | TagEnd::BlockQuote(_)
+            | TagEnd::CodeBlock
+            | TagEnd::HtmlBlock
+            | TagEnd::List(_)
+            | TagEnd::Item
+            | TagEnd::FootnoteDefinition
+            | TagEnd::Table
+            | TagEnd::MetadataBlock(_)
+    )
+}
+
+// Parse a document into its block tree, with the Markdown extensions of the settings
+pub fn parse_structure(content: &str) -> Vec<Block> {
+    parse_structure_with(content, &markdown_dialect::current_extensions())
+}
+
+// Parse a document into its block tree with the given Markdown extensions
+pub fn parse_structure_with(content: &str, extensions: &MarkdownExtensions) -> Vec<Block> {
+    let options = markdown_dialect::parser_options(extensions);
+    let index = SourceIndex::new(content);
+    let mut anchors: HashMap<String, usize> = HashMap::new();
+    let mut roots: Vec<Block> = Vec::new();
+    // Open blocks, innermost last
+    let mut stack: Vec<Block> = Vec::new();
+
+    for (event, range) in Parser::new_ext(content, options).into_offset_iter() {
+        match event {
+            Event::Start(tag) => {
+                if let Some(kind) = block_kind(&tag) {
+                    stack.push(index.block(kind, range));
+                } else if matches!(tag, Tag::TableHead | Tag::TableRow)
+                    && let Some(BlockKind::Table { rows, .. }) = stack.last_mut().map(|block| &mut block.kind)
+                {
+                    *rows += 1;
+                }
+            }
+            Event::End(end) if is_block_end(&end) => {
+                let Some(mut block) = stack.pop() else {
+                    continue;
+                };
+                if let BlockKind::Heading { text, anchor, .. } = &mut block.kind {
+                    *text = text.trim().to_string();
+                    if anchor.is_empty() {
+                        *anchor = markdown::unique_anchor(markdown::slugify_heading(text), &mut anchors);
+                    } else {
+                        anchors.entry(anchor.clone()).or_insert(0);
+                    }
+                }
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(block),
+                    None => roots.push(block),
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(BlockKind::Heading { text: heading, .. }) = stack.last_mut().map(|block| &mut block.kind) {
+                    heading.push_str(&text);
+                }
+            }
+            // Inside the item, or inside its first paragraph in a loose list
+            Event::TaskListMarker(done) => {
+                if let Some(checked) = stack.iter_mut().rev().find_map(|block| match &mut block.kind {
+                    BlockKind::ListItem { checked } => Some(checked),
+                    _ => None,
+                }) {
+                    *checked = Some(done);
+                }
+            }
+            Event::Rule => {
+                let block = index.block(BlockKind::ThematicBreak, range);
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(block),
+                    None => roots.push(block),
+                }
+            }
+            _ => {}
+        }
+    }
+    roots
+}
+
+// Tauri command: Parse a document into its block structure (headings, paragraphs, lists,
+// tables, code blocks, ... with their ranges)
+#[tauri::command]
+pub async fn parse_document_structure(content: String) -> Result<Vec<Block>, String> {
+    tauri::async_runtime::spawn_blocking(move || parse_structure(&content))
+        .await
+        .map_err(|e| format!("Failed to parse document: {}", e))
+}
+
+// Tauri command: GitHub-compatible slug of a heading text, as used for anchors
+#[tauri::command]
+pub fn slugify_heading(text: String) -> String {
+    markdown::slugify_heading(&text)
+}
+
+// Tauri command: Anchors of a document's headings, in document order, made unique the
+// way the outline and exports do
+#[tauri::command]
+pub fn heading_anchors(texts: Vec<String>) -> Vec<String> {
+    let mut occurrences = HashMap::new();
+    texts
+        .iter()
+        .map(|text| markdown::unique_anchor(markdown::slugify_heading(text), &mut occurrences))
+        .collect()
+}
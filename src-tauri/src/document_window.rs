@@ -0,0 +1,140 @@
+//! # Document Window Module
+//!
+//! This module opens additional editor windows, one document each, for users who prefer
+//! windows over in-app tabs.
+//!
+//! ## macOS Native Tabs
+//! All editor windows (including `main`, see `tauri.conf.json`) share the tabbing
+//! identifier `DOCUMENT_TABBING_ID`, so macOS can group them in the system tab bar.
+//! AppKit adds "Show Tab Bar", "Merge All Windows" and "Move Tab to New Window" to the
+//! Window menu by itself, because that menu is registered by ID (see `menu`).
+//!
+//! ## Event Routing
+//! With several editor windows, native menu commands must reach only the window the user
+//! is working in. `focused_window_label` picks that window; `menu::handle_menu_event`
+//! emits `menu-*` events to it instead of broadcasting them.
+//!
+//! ## Separate Processes
+//! Launching Bokuchi again normally hands the arguments to the running process (the
+//! single-instance plugin). Started with `--new-instance`, it runs as an independent
+//! process instead, e.g. one per project; `open_new_instance` ("New Window in Separate
+//! Process") starts one. Such a process starts with an empty session and does not save
+//! it, so it never overwrites the tabs of the main process. It has no tray icon and does
+//! not register the global shortcut, which belong to the main process. Its autosave
+//! snapshots carry its process ID, and the other files both processes change are
+//! updated under a lock (see `storage`).
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use tracing::info;
+
+use crate::path_scope;
+
+// Argument that starts an independent process instead of handing over to the running one
+pub const NEW_INSTANCE_ARG: &str = "--new-instance";
+
+// Tabbing identifier shared by all editor windows (macOS)
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub const DOCUMENT_TABBING_ID: &str = "bokuchi-documents";
+// Label prefix of document windows ("document-<n>")
+pub const DOCUMENT_WINDOW_PREFIX: &str = "document-";
+
+static NEXT_DOCUMENT_WINDOW: AtomicUsize = AtomicUsize::new(1);
+// Set in a process started with `NEW_INSTANCE_ARG`
+static SECONDARY_INSTANCE: AtomicBool = AtomicBool::new(false);
+
+// Percent-encode a query parameter value
+pub fn encode_query_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// Frontend URL of a document window
+pub fn document_window_url(file_path: Option<&str>) -> String {
+    match file_path {
+        Some(path) => format!("index.html?view=document&file={}", encode_query_value(path)),
+        None => "index.html?view=document".to_string(),
+    }
+}
+
+// Whether the command line (executable first) asks for an independent process
+pub fn wants_new_instance(args: &[String]) -> bool {
+    args.iter().skip(1).any(|arg| arg == NEW_INSTANCE_ARG)
+}
+
+// Mark this process as one started with `NEW_INSTANCE_ARG` (called once during setup)
+pub fn set_secondary_instance() {
+    SECONDARY_INSTANCE.store(true, Ordering::SeqCst);
+}
+
+// Whether this process was started with `NEW_INSTANCE_ARG`
+pub fn is_secondary_instance() -> bool {
+    SECONDARY_INSTANCE.load(Ordering::SeqCst)
+}
+
+// Label of the editor window that should receive menu commands: the focused one, or
+// `main` when no window has focus (e.g. the tray menu is open)
+pub fn focused_window_label(app: &tauri::AppHandle) -> String {
+    app.webview_windows()
+        .into_iter()
+        .find(|(label, window)| {
+            (label == "main" || label.starts_with(DOCUMENT_WINDOW_PREFIX)) && window.is_focused().unwrap_or(false)
+        })
+        .map(|(label, _)| label)
+        .unwrap_or_else(|| "main".to_string())
+}
+
+// Tauri command: Open a document in a new editor window (empty if `file_path` is None).
+// Returns the window label.
+#[tauri::command]
+pub async fn open_document_window(app_handle: tauri::AppHandle, file_path: Option<String>) -> Result<String, String> {
+    let label = format!(
+        "{}{}",
+        DOCUMENT_WINDOW_PREFIX,
+        NEXT_DOCUMENT_WINDOW.fetch_add(1, Ordering::SeqCst)
+    );
+    let url = document_window_url(file_path.as_deref());
+
+    let builder = WebviewWindowBuilder::new(&app_handle, &label, WebviewUrl::App(url.into()))
+        .title("Bokuchi")
+        .inner_size(1000.0, 700.0)
+        .min_inner_size(800.0, 600.0);
+    #[cfg(target_os = "macos")]
+    let builder = builder.tabbing_identifier(DOCUMENT_TABBING_ID);
+
+    let window = builder
+        .build()
+        .map_err(|e| format!("Failed to open document window: {}", e))?;
+    let _ = window.set_focus();
+    info!("Opened document window {} for {:?}", label, file_path);
+    Ok(label)
+}
+
+// Tauri command: Start Bokuchi as a separate process with `NEW_INSTANCE_ARG`, opening
+// `file_path` in it if given
+#[tauri::command]
+pub fn open_new_instance(file_path: Option<String>) -> Result<(), String> {
+    // The new instance puts the file into its scope, so it must be in this one
+    if let Some(path) = &file_path {
+        path_scope::check_path(path)?;
+    }
+    // AppImages must be started through the AppImage, not the mounted binary
+    let exe = std::env::var_os("APPIMAGE")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::current_exe().ok())
+        .ok_or_else(|| "Failed to find the Bokuchi executable".to_string())?;
+    std::process::Command::new(exe)
+        .arg(NEW_INSTANCE_ARG)
+        .args(&file_path)
+        .spawn()
+        .map_err(|e| format!("Failed to start a new instance: {}", e))?;
+    info!("Started a new instance for {:?}", file_path);
+    Ok(())
+}
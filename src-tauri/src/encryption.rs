@@ -0,0 +1,342 @@
+//! # Encryption Module
+//!
+//! This module reads and writes password-protected documents (e.g. a journal saved as
+//! `journal.md.enc`). The file holds only the encrypted Markdown; it is decrypted in
+//! the backend and the plain text never touches the disk: autosave keeps no snapshots of
+//! `.enc` tabs (see `is_encrypted_path`).
+//!
+//! ## Container Format
+//! ```text
+//! "BKENC" | version (1) | KDF id (1) | KDF parameters | salt (16) | nonce (12)
+//! | ciphertext with the 16-byte authentication tag
+//! ```
+//! The content is encrypted with AES-256-GCM. The key is derived from the passphrase with
+//! Argon2id (`DEFAULT_KDF`: 19 MiB, 2 passes, 1 lane, the OWASP minimum; random salt per
+//! passphrase). The header is authenticated as associated data, so any change to the file
+//! (or a wrong passphrase) makes decryption fail.
+//!
+//! | Version | KDF id | KDF parameters (u32, big-endian)          |
+//! |---------|--------|-------------------------------------------|
+//! | 2       | 2      | memory (KiB), iterations, parallelism     |
+//! | 1       | 1      | PBKDF2-HMAC-SHA256 iterations             |
+//!
+//! Version 1 files (PBKDF2) are still opened. They are saved in version 1 as long as the
+//! cached key is used, and move to Argon2id when a passphrase is given on save. The KDF
+//! parameters read from a file are bounded, so a crafted header cannot make opening it
+//! allocate gigabytes.
+//!
+//! ## Key Cache
+//! After a document was opened or saved with its passphrase, the derived key is kept in
+//! memory for the rest of the session, so saving again (and reopening) does not ask for
+//! the passphrase. `forget_encryption_keys` wipes the cache (e.g. when the user locks the
+//! journal); keys are zeroed when they are dropped.
+
+use std::collections::HashMap;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use tracing::info;
+use zeroize::Zeroizing;
+
+use crate::error::AppError;
+use crate::file_operations::check_writable;
+use crate::path_scope;
+use crate::storage;
+
+const MAGIC: &[u8] = b"BKENC";
+const FORMAT_VERSION_PBKDF2: u8 = 1;
+const FORMAT_VERSION_ARGON2: u8 = 2;
+const KDF_PBKDF2_SHA256: u8 = 1;
+const KDF_ARGON2ID: u8 = 2;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+// Largest Argon2id parameters accepted from a file header
+const MAX_ARGON2_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ARGON2_ITERATIONS: u32 = 64;
+const MAX_ARGON2_PARALLELISM: u32 = 16;
+// OWASP minimum for Argon2id
+pub const DEFAULT_KDF: KdfParams = KdfParams::Argon2id {
+    memory_kib: 19 * 1024,
+    iterations: 2,
+    parallelism: 1,
+};
+// File extension of encrypted documents
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+// Same limit as `read_file`
+const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+// Key derivation function and its parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdfParams {
+    Argon2id { memory_kib: u32, iterations: u32, parallelism: u32 },
+    // Format version 1
+    Pbkdf2Sha256 { iterations: u32 },
+}
+
+impl KdfParams {
+    // Format version and KDF id written for these parameters
+    fn ids(self) -> (u8, u8) {
+        match self {
+            KdfParams::Argon2id { .. } => (FORMAT_VERSION_ARGON2, KDF_ARGON2ID),
+            KdfParams::Pbkdf2Sha256 { .. } => (FORMAT_VERSION_PBKDF2, KDF_PBKDF2_SHA256),
+        }
+    }
+
+    fn values(self) -> Vec<u32> {
+        match self {
+            KdfParams::Argon2id { memory_kib, iterations, parallelism } => vec![memory_kib, iterations, parallelism],
+            KdfParams::Pbkdf2Sha256 { iterations } => vec![iterations],
+        }
+    }
+
+    fn header_len(self) -> usize {
+        MAGIC.len() + 2 + 4 * self.values().len() + SALT_LEN + NONCE_LEN
+    }
+}
+
+// Key derived from a passphrase, with the parameters it was derived with
+#[derive(Clone)]
+struct DerivedKey {
+    salt: [u8; SALT_LEN],
+    kdf: KdfParams,
+    key: Zeroizing<[u8; KEY_LEN]>,
+}
+
+// Parsed container header
+struct Header {
+    kdf: KdfParams,
+    salt: [u8; SALT_LEN],
+    nonce: [u8; NONCE_LEN],
+}
+
+static KEY_CACHE: OnceLock<Mutex<HashMap<PathBuf, DerivedKey>>> = OnceLock::new();
+
+fn key_cache() -> &'static Mutex<HashMap<PathBuf, DerivedKey>> {
+    KEY_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Whether the bytes are an encrypted document
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], AppError> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| AppError::EncryptionFailed("no random numbers available".to_string()))?;
+    Ok(bytes)
+}
+
+fn derive_key(passphrase: &str, salt: [u8; SALT_LEN], kdf: KdfParams) -> Result<DerivedKey, AppError> {
+    if passphrase.is_empty() {
+        return Err(AppError::PassphraseRequired);
+    }
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    match kdf {
+        KdfParams::Argon2id { memory_kib, iterations, parallelism } => {
+            // Out of range parameters can only come from a damaged or crafted header
+            if memory_kib > MAX_ARGON2_MEMORY_KIB
+                || iterations > MAX_ARGON2_ITERATIONS
+                || parallelism > MAX_ARGON2_PARALLELISM
+            {
+                return Err(AppError::WrongPassphrase);
+            }
+            let params =
+                Params::new(memory_kib, iterations, parallelism, Some(KEY_LEN)).map_err(|_| AppError::WrongPassphrase)?;
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+                .map_err(|e| AppError::EncryptionFailed(e.to_string()))?;
+        }
+        KdfParams::Pbkdf2Sha256 { iterations } => {
+            let rounds = NonZeroU32::new(iterations).ok_or(AppError::WrongPassphrase)?;
+            pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, rounds, &salt, passphrase.as_bytes(), key.as_mut());
+        }
+    }
+    Ok(DerivedKey { salt, kdf, key })
+}
+
+fn parse_header(bytes: &[u8]) -> Option<Header> {
+    if !is_encrypted(bytes) || bytes.len() < MAGIC.len() + 2 {
+        return None;
+    }
+    let rest = &bytes[MAGIC.len()..];
+    let count = match (rest[0], rest[1]) {
+        (FORMAT_VERSION_ARGON2, KDF_ARGON2ID) => 3,
+        (FORMAT_VERSION_PBKDF2, KDF_PBKDF2_SHA256) => 1,
+        _ => return None,
+    };
+    let values_end = 2 + 4 * count;
+    if rest.len() < values_end + SALT_LEN + NONCE_LEN {
+        return None;
+    }
+    let values: Vec<u32> = rest[2..values_end]
+        .chunks_exact(4)
+        .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    let kdf = match values[..] {
+        [memory_kib, iterations, parallelism] => KdfParams::Argon2id { memory_kib, iterations, parallelism },
+        [iterations] => KdfParams::Pbkdf2Sha256 { iterations },
+        _ => return None,
+    };
+    Some(Header {
+        kdf,
+        salt: rest[values_end..values_end + SALT_LEN].try_into().ok()?,
+        nonce: rest[values_end + SALT_LEN..values_end + SALT_LEN + NONCE_LEN].try_into().ok()?,
+    })
+}
+
+fn aead_key(key: &DerivedKey) -> Result<LessSafeKey, AppError> {
+    UnboundKey::new(&AES_256_GCM, key.key.as_ref())
+        .map(LessSafeKey::new)
+        .map_err(|_| AppError::EncryptionFailed("invalid key".to_string()))
+}
+
+// Encrypt content with a derived key (a fresh nonce every time)
+fn seal(content: &str, key: &DerivedKey) -> Result<Vec<u8>, AppError> {
+    let nonce = random_bytes::<NONCE_LEN>()?;
+    let mut output = Vec::with_capacity(key.kdf.header_len() + content.len() + AES_256_GCM.tag_len());
+    let (version, kdf_id) = key.kdf.ids();
+    output.extend_from_slice(MAGIC);
+    output.push(version);
+    output.push(kdf_id);
+    for value in key.kdf.values() {
+        output.extend_from_slice(&value.to_be_bytes());
+    }
+    output.extend_from_slice(&key.salt);
+    output.extend_from_slice(&nonce);
+
+    let mut in_out = content.as_bytes().to_vec();
+    aead_key(key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(&output[..]), &mut in_out)
+        .map_err(|_| AppError::EncryptionFailed("encryption failed".to_string()))?;
+    output.extend_from_slice(&in_out);
+    Ok(output)
+}
+
+// Decrypt a container with a derived key
+fn open(bytes: &[u8], header: &Header, key: &DerivedKey) -> Result<String, AppError> {
+    let header_len = header.kdf.header_len();
+    let mut in_out = bytes[header_len..].to_vec();
+    let plain = aead_key(key)?
+        .open_in_place(
+            Nonce::assume_unique_for_key(header.nonce),
+            Aad::from(&bytes[..header_len]),
+            &mut in_out,
+        )
+        .map_err(|_| AppError::WrongPassphrase)?;
+    let content = String::from_utf8(plain.to_vec()).map_err(|_| AppError::WrongPassphrase);
+    in_out.iter_mut().for_each(|b| *b = 0);
+    content
+}
+
+fn cache_key(path: &str) -> Result<PathBuf, AppError> {
+    path_scope::resolve_path(Path::new(path))
+}
+
+// The cached key of a file (a copy, so a failed use leaves it cached)
+fn cached_key(cache_path: &Path) -> Option<DerivedKey> {
+    key_cache().lock().ok()?.get(cache_path).cloned()
+}
+
+fn cache_key_of(cache_path: PathBuf, key: DerivedKey) {
+    if let Ok(mut cache) = key_cache().lock() {
+        cache.insert(cache_path, key);
+    }
+}
+
+// Decrypt an encrypted document, with its passphrase or the cached key
+pub fn open_document(path: &str, passphrase: Option<&str>) -> Result<String, AppError> {
+    let metadata = fs::metadata(path).map_err(|_| AppError::InvalidPath(format!("{} not found", path)))?;
+    if metadata.len() > MAX_FILE_SIZE {
+        return Err(AppError::InvalidPath("file too large (max 10MB)".to_string()));
+    }
+    let bytes = fs::read(path).map_err(|e| AppError::InvalidPath(format!("cannot read {}: {}", path, e)))?;
+    let header = parse_header(&bytes).ok_or_else(|| AppError::NotEncrypted(PathBuf::from(path)))?;
+    let cache_path = cache_key(path)?;
+
+    let key = match passphrase {
+        Some(passphrase) => derive_key(passphrase, header.salt, header.kdf)?,
+        None => match cached_key(&cache_path) {
+            Some(key) if key.salt == header.salt && key.kdf == header.kdf => key,
+            _ => return Err(AppError::PassphraseRequired),
+        },
+    };
+    let content = open(&bytes, &header, &key)?;
+    cache_key_of(cache_path, key);
+    Ok(content)
+}
+
+// Whether a path names an encrypted document (by its extension)
+pub fn is_encrypted_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case(ENCRYPTED_EXTENSION))
+}
+
+// Encrypt and save a document. A passphrase derives a new key (with `kdf`, new salt);
+// without one, the cached key of the file is used.
+pub fn save_document(path: &str, content: &str, passphrase: Option<&str>, kdf: KdfParams) -> Result<(), AppError> {
+    let path_ref = Path::new(path);
+    if !is_encrypted_path(path) {
+        return Err(AppError::InvalidPath(format!(
+            "encrypted documents must end with .{}",
+            ENCRYPTED_EXTENSION
+        )));
+    }
+    let cache_path = cache_key(path)?;
+
+    let key = match passphrase {
+        Some(passphrase) => derive_key(passphrase, random_bytes::<SALT_LEN>()?, kdf)?,
+        None => cached_key(&cache_path).ok_or(AppError::PassphraseRequired)?,
+    };
+    let sealed = seal(content, &key);
+    cache_key_of(cache_path, key);
+    let sealed = sealed?;
+
+    check_writable(path_ref, sealed.len() as u64)?;
+    storage::write_atomic(path_ref, &sealed).map_err(AppError::SaveFailed)
+}
+
+// Tauri command: Open an encrypted document. Without a passphrase, the key cached for
+// this file is used.
+#[tauri::command]
+pub async fn open_encrypted(path: String, passphrase: Option<String>) -> Result<String, String> {
+    path_scope::check_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || open_document(&path, passphrase.as_deref()))
+        .await
+        .map_err(|e| format!("Failed to open encrypted document: {}", e))?
+        .map_err(String::from)
+}
+
+// Tauri command: Save a document encrypted. A passphrase sets (or changes) the
+// passphrase of the file; without one, the key cached for this file is used.
+#[tauri::command]
+pub async fn save_encrypted(path: String, content: String, passphrase: Option<String>) -> Result<(), String> {
+    path_scope::check_path(&path)?;
+    let saved_path = path.clone();
+    tauri::async_runtime::spawn_blocking(move || save_document(&path, &content, passphrase.as_deref(), DEFAULT_KDF))
+        .await
+        .map_err(|e| format!("Failed to save encrypted document: {}", e))??;
+
+    // The encrypted file is synced and committed like any saved document. The commit
+    // message gets no document variables, which would come from the plain text.
+    crate::sync::spawn_push(&saved_path);
+    crate::git::spawn_auto_commit(saved_path, String::new());
+    Ok(())
+}
+
+// Tauri command: Forget all passphrase-derived keys of this session
+#[tauri::command]
+pub fn forget_encryption_keys() {
+    if let Ok(mut cache) = key_cache().lock() {
+        info!("Forgetting {} cached encryption keys", cache.len());
+        cache.clear();
+    }
+}
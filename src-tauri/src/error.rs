@@ -0,0 +1,129 @@
+//! # Error Module
+//!
+//! This module defines the typed errors of the backend. Commands still return
+//! `Result<T, String>` to the frontend; `AppError` converts into its message, so `?` works
+//! in commands as well as in helpers that return `AppError`.
+//!
+//! ## Messages
+//! Messages come from the locale catalogs (`error.<kind>`, see `locale`), so the frontend
+//! shows them in the user's language. `Display` always uses English, for logs.
+//!
+//! ## Kinds
+//! - `InvalidPath`: The path is empty, relative or cannot be resolved
+//! - `OutsideScope`: The path is outside the folders and files the user opened
+//!   (see `path_scope`)
+//! - `ReadOnly`: The file has the read-only flag
+//! - `PermissionDenied`: The file may not be written by the current user
+//! - `FolderNotWritable`: A new file cannot be created in the folder
+//! - `DiskFull`: The volume has not enough free space (or the quota is exhausted)
+//! - `SaveFailed`: Any other error while saving, with the OS error
+//! - `PassphraseRequired`: An encrypted document needs its passphrase (see `encryption`)
+//! - `WrongPassphrase`: Decryption failed: wrong passphrase or a damaged file
+//! - `NotEncrypted`: The file is not an encrypted document
+//! - `EncryptionFailed`: Encryption itself failed
+//! - `Cancelled`: A long-running task was cancelled by the user (see `tasks`)
+//! - `NotFound`: The file does not exist
+//! - `FileTooLarge`: The file is over the 10MB limit for documents
+//! - `UnsupportedFileType`: Documents must be .md or .txt files
+//! - `HiddenFile`: Hidden files (".bashrc") are never written
+//! - `ReadFailed`: Any other error while reading, with the OS error
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::locale;
+
+// Typed backend error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    InvalidPath(String),
+    OutsideScope(PathBuf),
+    ReadOnly(PathBuf),
+    PermissionDenied(PathBuf),
+    FolderNotWritable(PathBuf),
+    DiskFull(PathBuf),
+    SaveFailed(String),
+    PassphraseRequired,
+    WrongPassphrase,
+    NotEncrypted(PathBuf),
+    EncryptionFailed(String),
+    Cancelled,
+    NotFound(PathBuf),
+    FileTooLarge(PathBuf),
+    UnsupportedFileType(PathBuf),
+    HiddenFile(PathBuf),
+    ReadFailed(String),
+}
+
+impl AppError {
+    // Name of the error kind, as used in the message catalog (`error.<kind>`)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::InvalidPath(_) => "invalid_path",
+            AppError::OutsideScope(_) => "outside_scope",
+            AppError::ReadOnly(_) => "read_only",
+            AppError::PermissionDenied(_) => "permission_denied",
+            AppError::FolderNotWritable(_) => "folder_not_writable",
+            AppError::DiskFull(_) => "disk_full",
+            AppError::SaveFailed(_) => "save_failed",
+            AppError::PassphraseRequired => "passphrase_required",
+            AppError::WrongPassphrase => "wrong_passphrase",
+            AppError::NotEncrypted(_) => "not_encrypted",
+            AppError::EncryptionFailed(_) => "encryption_failed",
+            AppError::Cancelled => "cancelled",
+            AppError::NotFound(_) => "not_found",
+            AppError::FileTooLarge(_) => "file_too_large",
+            AppError::UnsupportedFileType(_) => "unsupported_file_type",
+            AppError::HiddenFile(_) => "hidden_file",
+            AppError::ReadFailed(_) => "read_failed",
+        }
+    }
+
+    // The message in `locale`
+    pub fn message_in(&self, locale: &str) -> String {
+        let key = format!("error.{}", self.kind());
+        match self {
+            AppError::OutsideScope(path)
+            | AppError::ReadOnly(path)
+            | AppError::PermissionDenied(path)
+            | AppError::FolderNotWritable(path)
+            | AppError::DiskFull(path)
+            | AppError::NotEncrypted(path)
+            | AppError::NotFound(path)
+            | AppError::FileTooLarge(path)
+            | AppError::UnsupportedFileType(path)
+            | AppError::HiddenFile(path) => {
+                locale::tr_args_in(locale, &key, &[("path", &path.display().to_string())])
+            }
+            AppError::InvalidPath(reason)
+            | AppError::SaveFailed(reason)
+            | AppError::EncryptionFailed(reason)
+            | AppError::ReadFailed(reason) => {
+                locale::tr_args_in(locale, &key, &[("reason", reason)])
+            }
+            AppError::PassphraseRequired | AppError::WrongPassphrase | AppError::Cancelled => {
+                locale::tr_in(locale, &key)
+            }
+        }
+    }
+
+    // The message in the current locale, as shown to the user
+    pub fn localized(&self) -> String {
+        self.message_in(locale::current_locale())
+    }
+}
+
+// English, for logs
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message_in(locale::DEFAULT_LOCALE))
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.localized()
+    }
+}
@@ -0,0 +1,248 @@
+//! # External Tools Module
+//!
+//! This module runs the user-defined commands of the "Run Tool" menu (e.g. `vale`,
+//! `pandoc` or own scripts) on the current file, streaming their output to the frontend.
+//!
+//! ## Definitions
+//! Tools are part of the backend settings (`external_tools`): a program, its arguments
+//! and an optional working directory. Arguments may contain placeholders:
+//! - `$FILE`: Path of the current file
+//! - `$DIR`: Folder of the current file
+//! - `$SELECTION`: Text selected in the editor
+//!
+//! The program is started directly, not through a shell, so a file name or selection is
+//! always passed as (part of) a single argument and never interpreted as shell syntax.
+//!
+//! ## Confirmation
+//! Tool definitions can be changed through `set_backend_settings`, which any script in
+//! the WebView can call. So the first time a tool runs, and again whenever its program,
+//! arguments or working directory change, a native dialog shows the command line and
+//! asks the user to confirm it. Confirmed definitions are remembered by their
+//! fingerprint, signed so that they cannot be forged (see `approvals`).
+//!
+//! ## Runs
+//! A run is registered as a task (kind `tool`, see `tasks`), so it shows up in
+//! `list_tasks` and `cancel_task` stops it by killing the process.
+//!
+//! ## Events
+//! - `tool-output`: `{ task_id, stream, line }` for every line the tool writes to stdout
+//!   or stderr, as it is written
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use tracing::info;
+
+use crate::approvals;
+use crate::error::AppError;
+use crate::locale;
+use crate::path_scope;
+use crate::s3::sha256_hex;
+use crate::settings::{self, ExternalTool};
+use crate::tasks::{self, CancellationToken};
+use crate::types::{emit_event, ToolOutputEvent};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// Stream a line of output came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolStream {
+    Stdout,
+    Stderr,
+}
+
+// Result of a finished run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolRunResult {
+    pub task_id: String,
+    // None if the tool was ended by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+}
+
+// Values of the placeholders for one run
+#[derive(Debug, Clone, Default)]
+pub struct ToolContext {
+    pub file: Option<String>,
+    pub selection: Option<String>,
+}
+
+// Replace the placeholders of an argument. Fails if the argument needs a file and none
+// is open.
+pub fn expand_placeholders(arg: &str, context: &ToolContext) -> Result<String, String> {
+    let needs_file = arg.contains("$FILE") || arg.contains("$DIR");
+    let file = match (&context.file, needs_file) {
+        (Some(file), _) => file.as_str(),
+        (None, true) => return Err("The tool needs a saved file".to_string()),
+        (None, false) => "",
+    };
+    let dir = Path::new(file)
+        .parent()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(arg
+        .replace("$FILE", file)
+        .replace("$DIR", &dir)
+        .replace("$SELECTION", context.selection.as_deref().unwrap_or_default()))
+}
+
+// Fingerprint of what a tool runs (program, arguments, working directory). Renaming a
+// tool keeps its fingerprint; any other change needs a new confirmation.
+pub fn tool_fingerprint(tool: &ExternalTool) -> String {
+    let definition = serde_json::to_vec(&(&tool.command, &tool.args, &tool.working_dir))
+        .unwrap_or_default();
+    sha256_hex(&definition)
+}
+
+// Command line of a tool as shown in the confirmation dialog
+pub fn tool_command_line(tool: &ExternalTool) -> String {
+    approvals::command_line(&tool.command, &tool.args)
+}
+
+// Ask the user to confirm a tool the first time it (or its current definition) runs.
+// Blocks on the dialog, so it must not be called on the main thread.
+fn confirm_tool(app_handle: &tauri::AppHandle, tool: &ExternalTool) -> Result<(), AppError> {
+    let locale = locale::current_locale();
+    let command_line = tool_command_line(tool);
+    let mut message = locale::tr_args_in(
+        locale,
+        "dialog.run_tool_message",
+        &[("name", &tool.name), ("command", &command_line)],
+    );
+    if let Some(dir) = &tool.working_dir {
+        message.push_str("\n\n");
+        message.push_str(&locale::tr_args_in(locale, "dialog.run_tool_working_dir", &[("dir", dir)]));
+    }
+    approvals::confirm_once(
+        app_handle,
+        "tool",
+        &tool_fingerprint(tool),
+        locale::tr_in(locale, "dialog.run_tool_title"),
+        message,
+        locale::tr_in(locale, "dialog.run_tool_confirm"),
+    )
+}
+
+// Pass the lines of a pipe to `on_line` on a thread of its own
+fn forward_lines<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    stream: ToolStream,
+    on_line: impl Fn(ToolStream, String) + Send + 'static,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        if let Some(pipe) = pipe {
+            for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                on_line(stream, line);
+            }
+        }
+    })
+}
+
+// Run a tool until it exits or `cancel` is set. `on_line` receives every line of output.
+pub fn run_tool_process(
+    tool: &ExternalTool,
+    context: &ToolContext,
+    cancel: &CancellationToken,
+    on_line: impl Fn(ToolStream, String) + Send + Clone + 'static,
+) -> Result<Option<i32>, String> {
+    let args = tool
+        .args
+        .iter()
+        .map(|arg| expand_placeholders(arg, context))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut command = Command::new(&tool.command);
+    command
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let working_dir = match &tool.working_dir {
+        Some(dir) => Some(expand_placeholders(dir, context)?),
+        None => context
+            .file
+            .as_deref()
+            .and_then(|file| Path::new(file).parent())
+            .map(|dir| dir.to_string_lossy().to_string()),
+    };
+    if let Some(dir) = working_dir.filter(|dir| !dir.is_empty()) {
+        command.current_dir(dir);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: no console window flashing up
+        command.creation_flags(0x0800_0000);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", tool.command, e))?;
+
+    let stdout = forward_lines(child.stdout.take(), ToolStream::Stdout, on_line.clone());
+    let stderr = forward_lines(child.stderr.take(), ToolStream::Stderr, on_line);
+
+    let status = loop {
+        if cancel.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(AppError::Cancelled.into());
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(format!("Failed to wait for {}: {}", tool.command, e)),
+        }
+    };
+    let _ = stdout.join();
+    let _ = stderr.join();
+    Ok(status.code())
+}
+
+// Tauri command: Run an external tool on a file. A new or changed tool is confirmed in
+// a native dialog first. Output is streamed as `tool-output` events; the result arrives
+// when the tool exits.
+#[tauri::command]
+pub async fn run_tool(
+    app_handle: tauri::AppHandle,
+    tool_id: String,
+    file: Option<String>,
+    selection: Option<String>,
+    task_id: Option<String>,
+) -> Result<ToolRunResult, String> {
+    let tool = settings::current_settings()
+        .external_tools
+        .into_iter()
+        .find(|tool| tool.id == tool_id)
+        .ok_or_else(|| format!("Unknown tool: {}", tool_id))?;
+    if let Some(file) = &file {
+        path_scope::check_path(file)?;
+    }
+    let task = tasks::register_task(task_id, "tool")?;
+    let task_id = task.id().to_string();
+    let context = ToolContext { file, selection };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        confirm_tool(&app_handle, &tool)?;
+        info!("Running tool {} ({})", tool.name, tool.command);
+        let event_task_id = task_id.clone();
+        let exit_code = run_tool_process(&tool, &context, &task.token(), move |stream, line| {
+            let event = ToolOutputEvent {
+                task_id: event_task_id.clone(),
+                stream,
+                line,
+            };
+            let _ = emit_event(&app_handle, None, &event);
+        })?;
+        Ok(ToolRunResult {
+            task_id,
+            exit_code,
+            success: exit_code == Some(0),
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to run tool: {}", e))?
+}
@@ -0,0 +1,141 @@
+//! # Favorites Module
+//!
+//! This module keeps the files the user pinned as favorites, for the Favorites section of
+//! the sidebar and the Favorites submenu of the tray menu.
+//!
+//! ## Behavior
+//! - Favorites keep the order they were pinned in; pinning a favorite again keeps its place
+//! - Favorites whose file no longer exists (deleted, or moved outside the app) are pruned
+//!   whenever the list is read
+//! - Every change is persisted to `favorites.json` and rebuilds the tray menu
+//! - Only files inside the path scope can be pinned (`path_scope`), since opening a
+//!   favorite from the tray grants it again
+//! - Favorites are watched for changes made outside the app (`file_watch`), which raise
+//!   a notification
+//!
+//! Tauri has no API for the macOS Dock menu, so on macOS the menu bar icon's menu is where
+//! favorites are listed outside the window.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use tracing::info;
+
+use crate::error::AppError;
+use crate::file_watch;
+use crate::path_scope;
+use crate::storage;
+use crate::tray;
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+// Pinned file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FavoriteFile {
+    pub path: String,
+    pub name: String,
+    // Milliseconds since the Unix epoch
+    pub pinned_at: u64,
+}
+
+static FAVORITES: OnceLock<Mutex<Vec<FavoriteFile>>> = OnceLock::new();
+
+fn favorites_cell() -> &'static Mutex<Vec<FavoriteFile>> {
+    FAVORITES.get_or_init(|| Mutex::new(storage::load_json(FAVORITES_FILE)))
+}
+
+// Append `path` to `list` unless it is already pinned
+pub fn push_favorite(list: &mut Vec<FavoriteFile>, path: &str, now: u64) {
+    if list.iter().any(|f| f.path == path) {
+        return;
+    }
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    list.push(FavoriteFile {
+        path: path.to_string(),
+        name,
+        pinned_at: now,
+    });
+}
+
+// Remove the favorites whose file is gone. Returns whether any were removed.
+pub fn prune_missing(list: &mut Vec<FavoriteFile>) -> bool {
+    let before = list.len();
+    list.retain(|f| Path::new(&f.path).is_file());
+    list.len() != before
+}
+
+// Apply a change to the favorites, pruning missing files; persists them and refreshes
+// the tray menu when anything changed. Returns the resulting list.
+fn update_favorites<F>(app_handle: Option<&tauri::AppHandle>, change: F) -> Result<Vec<FavoriteFile>, String>
+where
+    F: FnOnce(&mut Vec<FavoriteFile>),
+{
+    let (snapshot, changed) = {
+        let mut favorites = favorites_cell()
+            .lock()
+            .map_err(|_| "Failed to lock favorites".to_string())?;
+        let before = favorites.clone();
+        change(&mut favorites);
+        if prune_missing(&mut favorites) {
+            info!("Pruned favorites whose files are gone");
+        }
+        (favorites.clone(), *favorites != before)
+    };
+    if changed {
+        storage::save_json(FAVORITES_FILE, &snapshot)?;
+        file_watch::watch_pinned(&favorite_paths(&snapshot));
+        if let Some(app_handle) = app_handle {
+            tray::refresh_tray_menu(app_handle);
+        }
+    }
+    Ok(snapshot)
+}
+
+fn favorite_paths(list: &[FavoriteFile]) -> Vec<String> {
+    list.iter().map(|f| f.path.clone()).collect()
+}
+
+// Watch the favorites for external changes (called once during setup)
+pub fn watch_favorites() {
+    file_watch::watch_pinned(&favorite_paths(&favorites()));
+}
+
+// Get the favorites in the order they were pinned
+pub fn favorites() -> Vec<FavoriteFile> {
+    update_favorites(None, |_| {}).unwrap_or_default()
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Tauri command: Pin a file as a favorite. Returns the favorites.
+#[tauri::command]
+pub fn pin_file(app_handle: tauri::AppHandle, path: String) -> Result<Vec<FavoriteFile>, String> {
+    if !Path::new(&path).is_file() {
+        return Err(AppError::NotFound(PathBuf::from(&path)).into());
+    }
+    path_scope::check_path(&path)?;
+    update_favorites(Some(&app_handle), |favorites| push_favorite(favorites, &path, now_millis()))
+}
+
+// Tauri command: Unpin a favorite. Returns the favorites.
+#[tauri::command]
+pub fn unpin_file(app_handle: tauri::AppHandle, path: String) -> Result<Vec<FavoriteFile>, String> {
+    update_favorites(Some(&app_handle), |favorites| favorites.retain(|f| f.path != path))
+}
+
+// Tauri command: Get the favorites, without the ones whose file is gone
+#[tauri::command]
+pub fn get_favorites(app_handle: tauri::AppHandle) -> Result<Vec<FavoriteFile>, String> {
+    update_favorites(Some(&app_handle), |_| {})
+}
@@ -17,21 +17,29 @@
 //! 4. If frontend is ready, emit `open-file` event immediately
 //! 5. If frontend is not ready, buffer the file path for later retrieval
 //!
+//! The ready flag and the buffer share one lock: a path is either buffered before
+//! `set_frontend_ready` drains the buffer, or emitted because the frontend is ready.
+//! The buffer keeps arrival order, ignores paths the OS delivers twice and holds at most
+//! `MAX_PENDING_FILE_PATHS` paths.
+//!
 //! ## Platform Support
 //! Currently supports macOS file association. Other platforms can be added by implementing
 //! similar event handling logic.
 
 use std::path::Path;
 use std::sync::Mutex;
-use tauri::Emitter;
+use tracing::{debug, info, warn};
+
+use crate::types::{emit_event, OpenFileEvent, PendingFiles, PENDING_FILE_PATHS};
 
-use crate::types::{OpenFileEvent, PENDING_FILE_PATHS, FRONTEND_READY};
+fn pending_files() -> &'static Mutex<PendingFiles> {
+    PENDING_FILE_PATHS.get_or_init(|| Mutex::new(PendingFiles::default()))
+}
 
 // Check if frontend is ready
 pub fn is_frontend_ready() -> bool {
-    let ready = FRONTEND_READY.get_or_init(|| Mutex::new(false));
-    if let Ok(is_ready) = ready.lock() {
-        *is_ready
+    if let Ok(pending) = pending_files().lock() {
+        pending.frontend_ready
     } else {
         false
     }
@@ -39,89 +47,114 @@ pub fn is_frontend_ready() -> bool {
 
 // Get buffered file paths (for frontend to retrieve after initialization)
 pub fn get_pending_file_paths() -> Vec<String> {
-    let pending_paths = PENDING_FILE_PATHS.get_or_init(|| Mutex::new(Vec::new()));
-    if let Ok(mut paths) = pending_paths.lock() {
-        let result = paths.clone();
-        paths.clear(); // Clear buffer after retrieving
-        println!("Retrieved {} pending file paths: {:?}", result.len(), result);
+    if let Ok(mut pending) = pending_files().lock() {
+        let result = pending.take(); // Clear buffer after retrieving
+        debug!("Retrieved {} pending file paths: {:?}", result.len(), result);
         result
     } else {
-        println!("Failed to lock pending file paths");
+        warn!("Failed to lock pending file paths");
+        Vec::new()
+    }
+}
+
+// Set frontend ready state and take the buffered file paths in the same step
+pub fn set_frontend_ready() -> Vec<String> {
+    if let Ok(mut pending) = pending_files().lock() {
+        pending.frontend_ready = true;
+        info!("Frontend is now ready");
+        pending.take()
+    } else {
+        warn!("Failed to lock pending file paths");
         Vec::new()
     }
 }
 
-// Set frontend ready state
-pub fn set_frontend_ready() {
-    let ready = FRONTEND_READY.get_or_init(|| Mutex::new(false));
-    if let Ok(mut is_ready) = ready.lock() {
-        *is_ready = true;
-        println!("Frontend is now ready");
+// Buffer a file path. With `unless_ready`, nothing is buffered once the frontend is
+// ready (returns false: emit the event instead).
+fn buffer_file_path(file_path: String, unless_ready: bool) -> bool {
+    let Ok(mut pending) = pending_files().lock() else {
+        warn!("Failed to lock pending file paths");
+        return false;
+    };
+    if unless_ready && pending.frontend_ready {
+        return false;
+    }
+    info!("Buffering file path for later retrieval: {}", file_path);
+    if !pending.push(file_path) {
+        warn!("Pending file path buffer is full, dropping path");
+    }
+    debug!("Total buffered: {}", pending.paths.len());
+    true
+}
+
+// Make a command-line file argument absolute: relative paths are relative to the folder
+// the command was run in (`cwd`)
+pub fn resolve_file_arg(arg: &str, cwd: &Path) -> String {
+    let path = Path::new(arg);
+    if path.is_absolute() {
+        arg.to_string()
+    } else {
+        cwd.join(path).to_string_lossy().to_string()
     }
 }
 
 // Handle file open events (cross-platform)
 pub fn handle_open_file_event(app_handle: &tauri::AppHandle, file_path: String) {
-    println!("Handling open file event for: {}", file_path);
+    info!("Handling open file event for: {}", file_path);
 
     // If file exists and has md or txt extension
     if Path::new(&file_path).exists() {
         if let Some(ext) = Path::new(&file_path).extension() {
             let ext_str = ext.to_string_lossy().to_lowercase();
             if ext_str == "md" || ext_str == "txt" {
-                println!("Valid file type, attempting to emit open-file event");
+                // The user opened this file explicitly: allow the file commands to use it
+                crate::path_scope::grant_path(Path::new(&file_path));
+                debug!("Valid file type, attempting to emit open-file event");
 
-                // Check if frontend is ready before emitting
-                if is_frontend_ready() {
-                    // Try to emit event to frontend immediately
-                    match app_handle.emit(
-                        "open-file",
-                        OpenFileEvent {
-                            file_path: file_path.clone(),
-                        },
-                    ) {
-                        Ok(_) => {
-                            println!("Successfully emitted open-file event (frontend ready)");
-                            return;
-                        }
-                        Err(e) => {
-                            println!("Failed to emit open-file event: {}", e);
-                        }
-                    }
-                } else {
-                    println!("Frontend not ready, will buffer file path");
+                // Buffer the path while the frontend is not ready (checked under the
+                // same lock that `set_frontend_ready` takes)
+                if buffer_file_path(file_path.clone(), true) {
+                    info!("Frontend not ready, buffered file path");
+                    return;
                 }
 
-                // If immediate emit failed, buffer the file path for later retrieval
-                println!("Buffering file path for later retrieval: {}", file_path);
-                let pending_paths = PENDING_FILE_PATHS.get_or_init(|| Mutex::new(Vec::new()));
-                if let Ok(mut paths) = pending_paths.lock() {
-                    paths.push(file_path);
-                    println!("File path added to buffer. Total buffered: {}", paths.len());
+                // Try to emit event to frontend immediately
+                let event = OpenFileEvent {
+                    file_path: file_path.clone(),
+                };
+                match emit_event(app_handle, None, &event) {
+                    Ok(_) => {
+                        info!("Successfully emitted open-file event (frontend ready)");
+                    }
+                    Err(e) => {
+                        // If immediate emit failed, buffer the file path for later retrieval
+                        warn!("Failed to emit open-file event: {}", e);
+                        buffer_file_path(file_path, false);
+                    }
                 }
             } else {
-                println!("Invalid file extension: {}", ext_str);
+                warn!("Invalid file extension: {}", ext_str);
             }
         } else {
-            println!("No file extension found");
+            warn!("No file extension found");
         }
     } else {
-        println!("File does not exist: {}", file_path);
+        warn!("File does not exist: {}", file_path);
     }
 }
 
 // Handle RunEvent::Opened for macOS
 #[cfg(target_os = "macos")]
 pub fn handle_run_event_opened(app_handle: &tauri::AppHandle, urls: Vec<url::Url>) {
-    println!("RunEvent::Opened received with {} URLs", urls.len());
+    info!("RunEvent::Opened received with {} URLs", urls.len());
     for url in urls {
-        println!("Processing URL: {}", url);
+        debug!("Processing URL: {}", url);
         if let Ok(path_buf) = url.to_file_path() {
             let file_path = path_buf.to_string_lossy().to_string();
-            println!("Converted to file path: {}", file_path);
+            debug!("Converted to file path: {}", file_path);
             handle_open_file_event(app_handle, file_path);
         } else {
-            println!("Failed to convert URL to file path: {}", url);
+            warn!("Failed to convert URL to file path: {}", url);
         }
     }
 }
\ No newline at end of file
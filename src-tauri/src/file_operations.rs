@@ -6,21 +6,43 @@
 //! - **File Hash Calculation**: Generate SHA256 hashes for file content
 //! - **Large File Handling**: Skip hash calculation for files larger than 10MB
 //! - **Metadata Extraction**: Get file modification time and size information
+//! - **Pre-save Checks**: Detect read-only files, missing permissions and a full disk
+//!   before writing, and turn write errors into the same specific errors
 //!
 //! ## Performance Considerations
 //! - Files larger than 10MB are marked with a special "large_file" hash to avoid
 //!   memory issues during hash calculation
-//! - Hash calculation is performed on the entire file content for integrity checking
+//! - Hash calculation is performed on the entire file content for integrity checking. The
+//!   raw bytes are streamed into the hasher, so files that are not UTF-8 (opened after
+//!   transcoding) are hashed too; for UTF-8 files the hash equals `content_hash`.
 
 use sha2::{Digest, Sha256};
-use std::fs;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use crate::error::AppError;
 use crate::types::FileHashInfo;
 
+// SHA256 hash of file content, as used in `FileHashInfo`
+pub fn content_hash(content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+// SHA256 hash of a file's raw bytes, read in chunks
+pub fn file_content_hash(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 // Calculate file hash
 pub fn calculate_file_hash(path: &str) -> Result<FileHashInfo, String> {
-    let metadata = fs::metadata(path).map_err(|_| "File not found".to_string())?;
+    let metadata = fs::metadata(path).map_err(|_| AppError::NotFound(PathBuf::from(path)))?;
 
     let modified_time = metadata
         .modified()
@@ -40,15 +62,72 @@ pub fn calculate_file_hash(path: &str) -> Result<FileHashInfo, String> {
         });
     }
 
-    // Read file content and calculate hash
-    let content = fs::read_to_string(path).map_err(|_| "Failed to read file".to_string())?;
-    let mut hasher = Sha256::new();
-    hasher.update(content.as_bytes());
-    let hash = format!("{:x}", hasher.finalize());
-
     Ok(FileHashInfo {
-        hash,
+        hash: file_content_hash(Path::new(path)).map_err(|e| AppError::ReadFailed(e.to_string()))?,
         modified_time,
         file_size,
     })
-}
\ No newline at end of file
+}
+
+// Whether a file changed from `previous` to `current` (None: no file): whether anything
+// changed, and whether the content changed
+pub fn compare_hash_info(previous: Option<&FileHashInfo>, current: Option<&FileHashInfo>) -> (bool, bool) {
+    match (previous, current) {
+        (Some(previous), Some(current)) => {
+            let touched = previous.modified_time != current.modified_time
+                || previous.file_size != current.file_size
+                || previous.hash != current.hash;
+            // Files over the hash limit all share one hash; treat any touch as a change
+            let content = previous.hash != current.hash || (touched && current.hash == "large_file");
+            (touched, content)
+        }
+        (None, None) => (false, false),
+        _ => (true, true),
+    }
+}
+
+// Nearest existing folder above `path` (where a new file or folder would be created)
+fn existing_parent(path: &Path) -> Option<&Path> {
+    path.ancestors().skip(1).find(|ancestor| ancestor.is_dir())
+}
+
+// Check that `content_len` bytes can be saved to `path` before anything is written
+pub fn check_writable(path: &Path, content_len: u64) -> Result<(), AppError> {
+    let needed = match fs::metadata(path) {
+        Ok(metadata) => {
+            if metadata.is_dir() {
+                return Err(AppError::InvalidPath(format!("{} is a folder", path.display())));
+            }
+            if metadata.permissions().readonly() {
+                return Err(AppError::ReadOnly(path.to_path_buf()));
+            }
+            // Opening without truncating tells whether the file may be written
+            if let Err(e) = OpenOptions::new().write(true).open(path) {
+                return Err(write_error(path, &e));
+            }
+            content_len.saturating_sub(metadata.len())
+        }
+        Err(_) => content_len,
+    };
+
+    if needed > 0
+        && let Some(parent) = existing_parent(path)
+        && fs4::available_space(parent).is_ok_and(|available| available < needed)
+    {
+        return Err(AppError::DiskFull(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+// Turn an error of creating or writing `path` into a specific error
+pub fn write_error(path: &Path, error: &io::Error) -> AppError {
+    match error.kind() {
+        ErrorKind::StorageFull | ErrorKind::QuotaExceeded => AppError::DiskFull(path.to_path_buf()),
+        ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => match fs::metadata(path) {
+            Ok(metadata) if metadata.permissions().readonly() => AppError::ReadOnly(path.to_path_buf()),
+            Ok(_) => AppError::PermissionDenied(path.to_path_buf()),
+            Err(_) => AppError::FolderNotWritable(existing_parent(path).unwrap_or(path).to_path_buf()),
+        },
+        _ => AppError::SaveFailed(format!("{} ({:?})", error, error.kind())),
+    }
+}
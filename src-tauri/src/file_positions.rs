@@ -0,0 +1,126 @@
+//! # File Positions Module
+//!
+//! This module remembers where the cursor and the scroll position of each document were
+//! when it was last left, so reopening a long document continues where it was left.
+//!
+//! ## Flow
+//! - When a tab is switched away from or closed, the frontend reports its position with
+//!   `save_file_position`
+//! - `read_file_with_position` returns a document together with its last position (the
+//!   frontend may also ask for it alone with `get_file_position`)
+//!
+//! Positions are keyed by the canonical file path and stored in `file-positions.json`.
+//! Only the `MAX_FILES` most recently left files are kept.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use crate::commands;
+use crate::storage;
+
+const POSITIONS_FILE: &str = "file-positions.json";
+
+// Maximum number of files whose position is kept
+pub const MAX_FILES: usize = 1000;
+
+// Last position in a document
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilePosition {
+    // 1-based
+    pub cursor_line: usize,
+    pub cursor_column: usize,
+    // Pixels from the top of the editor
+    pub scroll_top: f64,
+    // Pixels from the top of the preview, when it scrolls on its own
+    pub preview_scroll_top: Option<f64>,
+    // Milliseconds since the Unix epoch
+    pub last_used: u64,
+}
+
+// Result of `read_file_with_position`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionedDocument {
+    pub content: String,
+    // None when the file was not opened before
+    pub position: Option<FilePosition>,
+}
+
+static POSITIONS: OnceLock<Mutex<HashMap<String, FilePosition>>> = OnceLock::new();
+
+fn positions_cell() -> &'static Mutex<HashMap<String, FilePosition>> {
+    POSITIONS.get_or_init(|| Mutex::new(storage::load_json(POSITIONS_FILE)))
+}
+
+// Key of a file: its canonical path, so the same file opened through a symlink matches
+pub fn position_key(path: &str) -> String {
+    let path = Path::new(path);
+    path.canonicalize()
+        .unwrap_or_else(|_| path.components().collect())
+        .to_string_lossy()
+        .to_string()
+}
+
+// Insert a position and drop the least recently used files beyond `max`
+pub fn remember_position(positions: &mut HashMap<String, FilePosition>, key: String, position: FilePosition, max: usize) {
+    positions.insert(key, position);
+    while positions.len() > max {
+        let oldest = positions
+            .iter()
+            .min_by_key(|(_, p)| p.last_used)
+            .map(|(k, _)| k.clone());
+        match oldest {
+            Some(oldest) => positions.remove(&oldest),
+            None => break,
+        };
+    }
+}
+
+fn lookup_position(path: &str) -> Result<Option<FilePosition>, String> {
+    let positions = positions_cell()
+        .lock()
+        .map_err(|_| "Failed to lock file positions".to_string())?;
+    Ok(positions.get(&position_key(path)).cloned())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Tauri command: Remember the position in a document the user is leaving
+#[tauri::command]
+pub fn save_file_position(path: String, position: FilePosition) -> Result<(), String> {
+    let position = FilePosition {
+        last_used: now_millis(),
+        ..position
+    };
+    let snapshot = {
+        let mut positions = positions_cell()
+            .lock()
+            .map_err(|_| "Failed to lock file positions".to_string())?;
+        remember_position(&mut positions, position_key(&path), position, MAX_FILES);
+        positions.clone()
+    };
+    storage::save_json(POSITIONS_FILE, &snapshot)
+}
+
+// Tauri command: Last position in a document (None if it has none)
+#[tauri::command]
+pub fn get_file_position(path: String) -> Result<Option<FilePosition>, String> {
+    lookup_position(&path)
+}
+
+// Tauri command: Read a document like `read_file`, with its last position
+#[tauri::command]
+pub async fn read_file_with_position(path: String) -> Result<PositionedDocument, String> {
+    let content = commands::read_file(path.clone()).await?;
+    let position = lookup_position(&path)?;
+    Ok(PositionedDocument { content, position })
+}
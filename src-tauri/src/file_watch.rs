@@ -0,0 +1,308 @@
+//! # File Watch Module
+//!
+//! This module watches the files open in tabs, so a tab without unsaved edits follows
+//! changes made outside the app (a `git pull`, another editor, a sync client), and
+//! reports changes of subscribed files instead of the frontend polling their hashes.
+//!
+//! ## Watched Files
+//! - Open files, taken from the session the frontend reports (`update_session`)
+//! - Files subscribed with `subscribe_file_state` until `unsubscribe_file_state`
+//! - Pinned files (`favorites`)
+//!
+//! The folders containing them are watched (not the files themselves), as many tools
+//! replace a file instead of writing into it, which ends a watch on the file.
+//!
+//! ## Changes
+//! Watcher events are debounced, so a burst of writes hashes a file once. The new hash,
+//! modification time and size are compared with the last known ones (from the
+//! subscription, the last change or the app's own last save); nothing is emitted when
+//! none of them changed.
+//! - Subscribed files: A single `file-state-changed` event with the new hash and
+//!   modification time, whether the content actually differs, or that the file is gone
+//! - Open files whose content differs and whose tab is not modified: The file is read
+//!   again and a `file-reloaded` event with the new content is emitted. Tabs with unsaved
+//!   edits are left alone; the frontend's conflict handling covers them.
+//!
+//! `file-reloaded` is a proposal, not a completed reload. Whether a tab is modified is
+//! taken from the last `update_session` snapshot, which lags behind typing, so the
+//! frontend must check its own dirty flag and drop the event (leaving the change to its
+//! conflict handling) when the tab has unsaved edits.
+//! - Pinned files whose content differs: An OS notification (see `notifications`)
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use notify::RecommendedWatcher;
+use tauri::AppHandle;
+use tracing::{debug, info, warn};
+
+use crate::commands;
+use crate::error::AppError;
+use crate::file_operations;
+use crate::notifications;
+use crate::path_scope;
+use crate::session::{self, SessionTab};
+use crate::types::{emit_event, FileHashInfo, FileReloadedEvent, FileStateChangedEvent};
+use crate::workspace;
+
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+// A watched file
+#[derive(Default)]
+struct WatchedFile {
+    // As given by the frontend
+    path: String,
+    // Open in a tab
+    open: bool,
+    subscribed: bool,
+    pinned: bool,
+    // Last known state; None until known
+    state: Option<FileHashInfo>,
+}
+
+#[derive(Default)]
+struct WatchState {
+    app_handle: Option<AppHandle>,
+    // By canonical path
+    files: HashMap<PathBuf, WatchedFile>,
+    // One watcher per folder with watched files
+    watchers: HashMap<PathBuf, RecommendedWatcher>,
+}
+
+impl WatchedFile {
+    fn is_watched(&self) -> bool {
+        self.open || self.subscribed || self.pinned
+    }
+
+    // Hash a file that just became watched, so the first watcher event (even one that
+    // only touches the file) is compared with its content at that point
+    fn seed_state(&mut self) {
+        if self.state.is_none() {
+            self.state = file_operations::calculate_file_hash(&self.path).ok();
+        }
+    }
+}
+
+impl WatchState {
+    // Watch the folders of the watched files, and stop watching the others
+    fn update_watchers(&mut self) {
+        let mut dirs: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter_map(|path| path.parent().map(Path::to_path_buf))
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        self.watchers.retain(|dir, _| dirs.contains(dir));
+        for dir in dirs {
+            if self.watchers.contains_key(&dir) {
+                continue;
+            }
+            match workspace::watch_dir_debounced(&dir, WATCH_DEBOUNCE, handle_changes) {
+                Ok(watcher) => {
+                    debug!("Watching {:?}", dir);
+                    self.watchers.insert(dir, watcher);
+                }
+                Err(e) => warn!("{}", e),
+            }
+        }
+    }
+}
+
+static WATCH_STATE: OnceLock<Mutex<WatchState>> = OnceLock::new();
+
+fn watch_state_cell() -> &'static Mutex<WatchState> {
+    WATCH_STATE.get_or_init(|| Mutex::new(WatchState::default()))
+}
+
+// Keep the app handle for emitting events (called once during setup)
+pub fn init_file_watch(app_handle: AppHandle) {
+    if let Ok(mut state) = watch_state_cell().lock() {
+        state.app_handle = Some(app_handle);
+    }
+}
+
+// Follow the files of the given tabs, and stop following closed ones
+pub fn watch_open_tabs(tabs: &[SessionTab]) {
+    let Ok(mut state) = watch_state_cell().lock() else {
+        return;
+    };
+    for file in state.files.values_mut() {
+        file.open = false;
+    }
+    for path in tabs.iter().filter_map(|tab| tab.path.as_ref()) {
+        let Ok(canonical) = fs::canonicalize(path) else {
+            continue;
+        };
+        let file = state.files.entry(canonical).or_default();
+        file.path = path.clone();
+        file.open = true;
+        file.seed_state();
+    }
+    state.files.retain(|_, file| file.is_watched());
+    state.update_watchers();
+}
+
+// Follow the pinned files, and stop following unpinned ones
+pub fn watch_pinned(paths: &[String]) {
+    let Ok(mut state) = watch_state_cell().lock() else {
+        return;
+    };
+    for file in state.files.values_mut() {
+        file.pinned = false;
+    }
+    for path in paths {
+        let Ok(canonical) = fs::canonicalize(path) else {
+            continue;
+        };
+        let file = state.files.entry(canonical).or_default();
+        if file.path.is_empty() {
+            file.path = path.clone();
+        }
+        file.pinned = true;
+        file.seed_state();
+    }
+    state.files.retain(|_, file| file.is_watched());
+    state.update_watchers();
+}
+
+// Remember the state of a file the app just saved, so the save is not reported back
+pub fn note_saved(path: &str) {
+    let Ok(canonical) = fs::canonicalize(path) else {
+        return;
+    };
+    let Ok(mut state) = watch_state_cell().lock() else {
+        return;
+    };
+    if let Some(file) = state.files.get_mut(&canonical) {
+        file.state = file_operations::calculate_file_hash(path).ok();
+    }
+}
+
+// Whether the tab of a file has unsaved edits, according to the session
+fn is_modified(path: &str) -> bool {
+    session::get_session()
+        .tabs
+        .iter()
+        .any(|tab| tab.is_modified && tab.path.as_deref() == Some(path))
+}
+
+// Check the watched files among the changed paths and emit their events
+fn handle_changes(paths: Vec<PathBuf>) -> bool {
+    for changed in paths {
+        let (key, path, known) = {
+            let Ok(state) = watch_state_cell().lock() else {
+                return false;
+            };
+            let key = if state.files.contains_key(&changed) {
+                changed
+            } else {
+                match fs::canonicalize(&changed) {
+                    Ok(canonical) if state.files.contains_key(&canonical) => canonical,
+                    _ => continue,
+                }
+            };
+            let file = &state.files[&key];
+            (key.clone(), file.path.clone(), file.state.clone())
+        };
+
+        // None when the file is gone
+        let current = file_operations::calculate_file_hash(&key.to_string_lossy()).ok();
+        let (touched, content_changed) = file_operations::compare_hash_info(known.as_ref(), current.as_ref());
+        if !touched {
+            continue;
+        }
+
+        let Ok(mut state) = watch_state_cell().lock() else {
+            return false;
+        };
+        let app_handle = state.app_handle.clone();
+        let Some(file) = state.files.get_mut(&key) else {
+            continue;
+        };
+        file.state = current.clone();
+        let (open, subscribed, pinned) = (file.open, file.subscribed, file.pinned);
+        drop(state);
+
+        let Some(app_handle) = app_handle else {
+            continue;
+        };
+        if subscribed {
+            let event = FileStateChangedEvent {
+                path: path.clone(),
+                exists: current.is_some(),
+                hash: current.clone(),
+                content_changed,
+            };
+            let _ = emit_event(&app_handle, None, &event);
+        }
+        if pinned && content_changed && current.is_some() {
+            let name = Path::new(&path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone());
+            notifications::notify("notification.file_changed", &name, Some(&path));
+        }
+        if open && content_changed && current.is_some() {
+            if is_modified(&path) {
+                debug!("{} changed on disk, but its tab has unsaved edits", path);
+                continue;
+            }
+            match (commands::read_text_file(&path), current) {
+                (Ok(content), Some(hash)) => {
+                    info!("Reloading {} after an external change", path);
+                    let _ = emit_event(&app_handle, None, &FileReloadedEvent { path, content, hash });
+                }
+                (Err(e), _) => debug!("Not reloading {}: {}", path, e),
+                _ => {}
+            }
+        }
+    }
+    true
+}
+
+// Tauri command: Report changes of a file with `file-state-changed` events. Returns its
+// current state, which later changes are compared with.
+#[tauri::command]
+pub async fn subscribe_file_state(path: String) -> Result<FileHashInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        path_scope::check_path(&path)?;
+        let canonical = fs::canonicalize(&path).map_err(|_| AppError::NotFound(PathBuf::from(&path)))?;
+        let info = file_operations::calculate_file_hash(&path)?;
+        let mut state = watch_state_cell()
+            .lock()
+            .map_err(|_| "Failed to lock file watch".to_string())?;
+        let file = state.files.entry(canonical).or_default();
+        file.path = path;
+        file.subscribed = true;
+        file.state = Some(info.clone());
+        state.update_watchers();
+        Ok(info)
+    })
+    .await
+    .map_err(|e| format!("Failed to subscribe to file: {}", e))?
+}
+
+// Tauri command: Stop reporting changes of a file
+#[tauri::command]
+pub fn unsubscribe_file_state(path: String) -> Result<(), String> {
+    let mut state = watch_state_cell()
+        .lock()
+        .map_err(|_| "Failed to lock file watch".to_string())?;
+    let key = fs::canonicalize(&path).ok().or_else(|| {
+        state
+            .files
+            .iter()
+            .find(|(_, file)| file.path == path)
+            .map(|(key, _)| key.clone())
+    });
+    if let Some(file) = key.as_ref().and_then(|key| state.files.get_mut(key)) {
+        file.subscribed = false;
+    }
+    state.files.retain(|_, file| file.is_watched());
+    state.update_watchers();
+    Ok(())
+}
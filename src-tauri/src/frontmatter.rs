@@ -0,0 +1,64 @@
+//! # Frontmatter Module
+//!
+//! This module splits YAML frontmatter from Markdown documents.
+//!
+//! ## Format
+//! Frontmatter is a YAML block at the very start of a document, opened by a `---` line
+//! and closed by a `---` or `...` line:
+//!
+//! ```text
+//! ---
+//! title: Meeting notes
+//! tags: [work, meetings]
+//! ---
+//! # Body
+//! ```
+
+use serde_yaml::Value;
+
+// A document split into frontmatter and body
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitDocument<'a> {
+    // YAML between the delimiters (None if the document has no frontmatter)
+    pub frontmatter: Option<&'a str>,
+    pub body: &'a str,
+    // Number of lines before the body (delimiters included)
+    pub body_line_offset: usize,
+}
+
+// Split a document into frontmatter and body
+pub fn split_frontmatter(content: &str) -> SplitDocument<'_> {
+    let no_frontmatter = SplitDocument {
+        frontmatter: None,
+        body: content,
+        body_line_offset: 0,
+    };
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return no_frontmatter;
+    };
+
+    let mut offset = 0;
+    for (index, line) in rest.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed == "---" || trimmed == "..." {
+            return SplitDocument {
+                frontmatter: Some(&rest[..offset]),
+                body: &rest[offset + line.len()..],
+                body_line_offset: index + 2,
+            };
+        }
+        offset += line.len();
+    }
+    no_frontmatter
+}
+
+// Parse the frontmatter of a document (None if missing or not a YAML mapping)
+pub fn parse_frontmatter(content: &str) -> Option<Value> {
+    let frontmatter = split_frontmatter(content).frontmatter?;
+    serde_yaml::from_str::<Value>(frontmatter)
+        .ok()
+        .filter(Value::is_mapping)
+}
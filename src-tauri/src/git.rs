@@ -0,0 +1,777 @@
+//! # Git Module
+//!
+//! This module shows the Git state of documents: whether a file is modified, and which
+//! lines changed since the last commit, for modified-line gutters and a dirty badge per tab.
+//!
+//! ## Repository Discovery
+//! The repository is found by walking up from the file's folder, so any document inside a
+//! working tree works without configuration. Files outside a repository report no
+//! repository instead of an error.
+//!
+//! ## Diffs
+//! `git_diff_file` compares HEAD with the file on disk (staged and unstaged changes
+//! together), as unified hunks with old/new line numbers. An untracked file is one added
+//! hunk; in a repository without commits every file is new.
+//!
+//! ## Commits and History
+//! `git_commit` commits exactly the given files (like `git commit <paths>`): changes
+//! staged for other files stay staged. The author comes from the Git configuration,
+//! falling back to a generic Bokuchi identity. `git_log_file` lists the commits that
+//! changed a file (renames are not followed) and `git_show_file_at` returns its content
+//! at any revision (`HEAD~2`, a commit id, a tag, ...).
+//!
+//! ## Blame
+//! `git_blame` annotates each line of the file on disk with the commit that last changed
+//! it. Lines changed since the last commit (and all lines of an untracked file) have no
+//! commit.
+//!
+//! ## Auto-Commit
+//! With the `git_auto_commit` setting, every successful `save_file` of a document inside
+//! a repository commits that document in the background. The message is rendered from
+//! the `git_auto_commit_message` template:
+//! - `{filename}`, `{path}` (relative to the repository root)
+//! - `{timestamp}`, `{date}`, `{time}` (local time)
+//! - `{{name}}` variables, from the document's `<!-- @var -->` definitions or the global
+//!   variables
+//!
+//! Ignored files are never committed, and a save without changes creates no commit.
+//!
+//! ## Change Events
+//! `git_status` starts watching the repository it reports on. When files of the working
+//! tree, the index, HEAD or refs change, a `git-status-changed` event carrying the
+//! repository root is emitted (debounced), and the frontend re-queries its tabs.
+
+use chrono::{DateTime, Local};
+use git2::build::TreeUpdateBuilder;
+use git2::{BlameOptions, Commit, DiffOptions, FileMode, Oid, Patch, Repository, Signature, Status, StatusOptions};
+use lazy_static::lazy_static;
+use notify::RecommendedWatcher;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tracing::{debug, info, warn};
+
+use crate::path_scope;
+use crate::settings;
+use crate::variable_processor::VARIABLE_PROCESSOR;
+use crate::workspace;
+use crate::types::{emit_event, GitStatusChangedEvent};
+
+lazy_static! {
+    static ref TEMPLATE_VARIABLE: Regex = Regex::new(r"\{\{([^}]+)\}\}").unwrap();
+}
+
+// Time to collect file changes before `git-status-changed` is emitted
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+const DIFF_CONTEXT_LINES: u32 = 3;
+const DEFAULT_LOG_LIMIT: usize = 50;
+// Author used when the Git configuration has no user.name / user.email
+const FALLBACK_AUTHOR_NAME: &str = "Bokuchi";
+const FALLBACK_AUTHOR_EMAIL: &str = "bokuchi@localhost";
+pub const DEFAULT_AUTO_COMMIT_MESSAGE: &str = "Update {filename} ({timestamp})";
+
+// State of a file compared to HEAD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileState {
+    Unmodified,
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+// Git state of one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitFileStatus {
+    pub path: String,
+    pub state: FileState,
+    // Changes are staged in the index
+    pub staged: bool,
+    // The working copy differs from the index
+    pub unstaged: bool,
+}
+
+// Result of `git_status`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitStatus {
+    // Root of the working tree (None if the path is not in a repository)
+    pub repository: Option<String>,
+    // Current branch, or the short commit id when HEAD is detached
+    pub branch: Option<String>,
+    // For a file: its status. For a folder: the changed files below it.
+    pub files: Vec<GitFileStatus>,
+}
+
+// Kind of a diff line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+// A line of a diff hunk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    // 1-based line numbers (None for added lines in the old file and removed lines in the new one)
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    // Line text without the line break
+    pub content: String,
+}
+
+// A unified diff hunk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    // "@@ -1,3 +1,4 @@" line
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+// A commit, as listed in the history of a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitCommitInfo {
+    pub id: String,
+    pub short_id: String,
+    // First line of the message
+    pub summary: String,
+    pub message: String,
+    pub author: String,
+    pub email: String,
+    // Seconds since the Unix epoch
+    pub time: i64,
+}
+
+// Blame annotation of one line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlameLine {
+    // 1-based
+    pub line_number: usize,
+    // None for lines that are not committed yet
+    pub commit_id: Option<String>,
+    pub author: Option<String>,
+    // Seconds since the Unix epoch
+    pub time: Option<i64>,
+    pub summary: Option<String>,
+}
+
+// Watchers of the repositories queried so far, by working tree root
+static REPOSITORY_WATCHERS: OnceLock<Mutex<HashMap<PathBuf, RecommendedWatcher>>> = OnceLock::new();
+
+// Serializes automatic commits, so quick successive saves do not race for the index lock
+static AUTO_COMMIT_LOCK: Mutex<()> = Mutex::new(());
+
+fn repository_watchers_cell() -> &'static Mutex<HashMap<PathBuf, RecommendedWatcher>> {
+    REPOSITORY_WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Canonical form of a path that may not exist anymore (e.g. a deleted file)
+fn canonical_path(path: &Path) -> PathBuf {
+    if let Ok(canonical) = std::fs::canonicalize(path) {
+        return canonical;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => canonical_path(parent).join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+// Open the repository whose working tree contains `path` (None outside a repository)
+pub fn open_repository(path: &Path) -> Option<Repository> {
+    let start = if path.is_dir() { path } else { path.parent()? };
+    Repository::discover(start).ok().filter(|repo| !repo.is_bare())
+}
+
+// Root of the working tree of a repository
+pub fn workdir(repo: &Repository) -> Result<PathBuf, String> {
+    repo.workdir()
+        .map(canonical_path)
+        .ok_or_else(|| "Repository has no working tree".to_string())
+}
+
+// Path of `path` relative to the working tree, with '/' separators ("" for the root)
+pub fn relative_path(repo: &Repository, path: &Path) -> Result<String, String> {
+    let root = workdir(repo)?;
+    let path = canonical_path(path);
+    let relative = path
+        .strip_prefix(&root)
+        .map_err(|_| format!("{:?} is not inside the repository", path))?;
+    Ok(relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+// Current branch name, or the short commit id when HEAD is detached
+pub fn current_branch(repo: &Repository) -> Option<String> {
+    match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().map(str::to_string),
+        Ok(head) => head
+            .target()
+            .map(|oid| oid.to_string().chars().take(7).collect()),
+        // Unborn branch: HEAD points to a branch that has no commit yet
+        Err(_) => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|head| head.symbolic_target().map(str::to_string))
+            .map(|target| target.trim_start_matches("refs/heads/").to_string()),
+    }
+}
+
+fn file_state(status: Status) -> FileState {
+    if status.is_conflicted() {
+        FileState::Conflicted
+    } else if status.is_wt_new() && !status.is_index_new() {
+        FileState::Untracked
+    } else if status.is_index_new() {
+        FileState::Added
+    } else if status.intersects(Status::INDEX_DELETED | Status::WT_DELETED) {
+        FileState::Deleted
+    } else if status.intersects(Status::INDEX_RENAMED | Status::WT_RENAMED) {
+        FileState::Renamed
+    } else if status.is_empty() {
+        FileState::Unmodified
+    } else {
+        FileState::Modified
+    }
+}
+
+// Status of `path` (a file or a folder) in the repository containing it
+pub fn repository_status(path: &Path) -> Result<GitStatus, String> {
+    let Some(repo) = open_repository(path) else {
+        return Ok(GitStatus::default());
+    };
+    let root = workdir(&repo)?;
+    let relative = relative_path(&repo, path)?;
+    let is_file = !path.is_dir();
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    if !relative.is_empty() {
+        options.pathspec(&relative);
+        if is_file {
+            options.disable_pathspec_match(true);
+        }
+    }
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|e| format!("Failed to read Git status: {}", e))?;
+
+    let mut files: Vec<GitFileStatus> = statuses
+        .iter()
+        .filter(|entry| !entry.status().is_ignored())
+        .filter_map(|entry| {
+            let status = entry.status();
+            Some(GitFileStatus {
+                path: root.join(entry.path()?).to_string_lossy().to_string(),
+                state: file_state(status),
+                staged: status.intersects(
+                    Status::INDEX_NEW
+                        | Status::INDEX_MODIFIED
+                        | Status::INDEX_DELETED
+                        | Status::INDEX_RENAMED
+                        | Status::INDEX_TYPECHANGE,
+                ),
+                unstaged: status.intersects(
+                    Status::WT_NEW | Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE,
+                ),
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // A clean file has no status entry
+    if is_file && files.is_empty() {
+        files.push(GitFileStatus {
+            path: root.join(&relative).to_string_lossy().to_string(),
+            state: FileState::Unmodified,
+            staged: false,
+            unstaged: false,
+        });
+    }
+
+    Ok(GitStatus {
+        repository: Some(root.to_string_lossy().to_string()),
+        branch: current_branch(&repo),
+        files,
+    })
+}
+
+// Convert the hunks of a patch
+pub fn patch_hunks(patch: &Patch<'_>) -> Result<Vec<DiffHunk>, String> {
+    let mut hunks = Vec::with_capacity(patch.num_hunks());
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch
+            .hunk(hunk_index)
+            .map_err(|e| format!("Failed to read diff: {}", e))?;
+        let mut lines = Vec::with_capacity(line_count);
+        for line_index in 0..line_count {
+            let line = patch
+                .line_in_hunk(hunk_index, line_index)
+                .map_err(|e| format!("Failed to read diff: {}", e))?;
+            let kind = match line.origin() {
+                ' ' => DiffLineKind::Context,
+                '+' => DiffLineKind::Added,
+                '-' => DiffLineKind::Removed,
+                // "\ No newline at end of file" markers
+                _ => continue,
+            };
+            let content = String::from_utf8_lossy(line.content());
+            lines.push(DiffLine {
+                kind,
+                old_line: line.old_lineno(),
+                new_line: line.new_lineno(),
+                content: content.trim_end_matches(['\r', '\n']).to_string(),
+            });
+        }
+        hunks.push(DiffHunk {
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+            lines,
+        });
+    }
+    Ok(hunks)
+}
+
+// Diff of a file on disk against HEAD
+pub fn diff_file(path: &Path) -> Result<Vec<DiffHunk>, String> {
+    let repo = open_repository(path).ok_or_else(|| "File is not in a Git repository".to_string())?;
+    let relative = relative_path(&repo, path)?;
+    let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+
+    let mut options = DiffOptions::new();
+    options
+        .pathspec(&relative)
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .show_untracked_content(true)
+        .context_lines(DIFF_CONTEXT_LINES);
+    let diff = repo
+        .diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut options))
+        .map_err(|e| format!("Failed to diff file: {}", e))?;
+
+    let mut hunks = Vec::new();
+    for index in 0..diff.deltas().len() {
+        if let Some(patch) = Patch::from_diff(&diff, index).map_err(|e| format!("Failed to diff file: {}", e))? {
+            hunks.extend(patch_hunks(&patch)?);
+        }
+    }
+    Ok(hunks)
+}
+
+fn commit_info(commit: &Commit<'_>) -> GitCommitInfo {
+    let id = commit.id().to_string();
+    let author = commit.author();
+    GitCommitInfo {
+        short_id: id.chars().take(7).collect(),
+        id,
+        summary: commit.summary().unwrap_or("").to_string(),
+        message: commit.message().unwrap_or("").to_string(),
+        author: author.name().unwrap_or("").to_string(),
+        email: author.email().unwrap_or("").to_string(),
+        time: commit.time().seconds(),
+    }
+}
+
+// Signature from the Git configuration, or the fallback identity
+pub fn signature(repo: &Repository) -> Result<Signature<'static>, String> {
+    repo.signature()
+        .or_else(|_| Signature::now(FALLBACK_AUTHOR_NAME, FALLBACK_AUTHOR_EMAIL))
+        .map_err(|e| format!("Failed to create commit signature: {}", e))
+}
+
+// Commit the given files (all in one repository) with `message`. Only these files are
+// committed; the index is updated for them as well.
+pub fn commit_paths(paths: &[PathBuf], message: &str) -> Result<GitCommitInfo, String> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err("Commit message is empty".to_string());
+    }
+    let first = paths.first().ok_or_else(|| "No files to commit".to_string())?;
+    let repo = open_repository(first).ok_or_else(|| "File is not in a Git repository".to_string())?;
+    let git_error = |e: git2::Error| format!("Failed to commit: {}", e);
+
+    let mut index = repo.index().map_err(git_error)?;
+    let mut update = TreeUpdateBuilder::new();
+    for path in paths {
+        let relative = relative_path(&repo, path)?;
+        if relative.is_empty() {
+            return Err(format!("{:?} is not a file", path));
+        }
+        if path.is_file() {
+            index.add_path(Path::new(&relative)).map_err(git_error)?;
+            let entry = index
+                .get_path(Path::new(&relative), 0)
+                .ok_or_else(|| format!("Failed to stage {}", relative))?;
+            let mode = if entry.mode & 0o111 != 0 {
+                FileMode::BlobExecutable
+            } else {
+                FileMode::Blob
+            };
+            update.upsert(relative.as_str(), entry.id, mode);
+        } else {
+            // Deleted file (removing an untracked path is not an error)
+            let _ = index.remove_path(Path::new(&relative));
+            update.remove(relative.as_str());
+        }
+    }
+    index.write().map_err(git_error)?;
+
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let base_tree = match &parent {
+        Some(commit) => commit.tree().map_err(git_error)?,
+        None => {
+            let empty = repo.treebuilder(None).and_then(|b| b.write()).map_err(git_error)?;
+            repo.find_tree(empty).map_err(git_error)?
+        }
+    };
+    let tree_id = update.create_updated(&repo, &base_tree).map_err(git_error)?;
+    if parent.is_some() && tree_id == base_tree.id() {
+        return Err("Nothing to commit".to_string());
+    }
+    let tree = repo.find_tree(tree_id).map_err(git_error)?;
+
+    let signature = signature(&repo)?;
+    let parents: Vec<&Commit<'_>> = parent.iter().collect();
+    let commit_id = repo
+        .commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+        .map_err(git_error)?;
+    let commit = repo.find_commit(commit_id).map_err(git_error)?;
+    info!("Committed {} files as {}", paths.len(), commit_id);
+    Ok(commit_info(&commit))
+}
+
+// Blob id of `relative` in a commit (None if the file does not exist there)
+fn blob_at(commit: &Commit<'_>, relative: &str) -> Option<Oid> {
+    commit
+        .tree()
+        .ok()?
+        .get_path(Path::new(relative))
+        .ok()
+        .map(|entry| entry.id())
+}
+
+// Commits that changed a file, newest first
+pub fn file_history(path: &Path, limit: usize) -> Result<Vec<GitCommitInfo>, String> {
+    let repo = open_repository(path).ok_or_else(|| "File is not in a Git repository".to_string())?;
+    let relative = relative_path(&repo, path)?;
+    let git_error = |e: git2::Error| format!("Failed to read history: {}", e);
+    if repo.head().is_err() {
+        // No commits yet
+        return Ok(Vec::new());
+    }
+
+    let mut walk = repo.revwalk().map_err(git_error)?;
+    walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME).map_err(git_error)?;
+    walk.push_head().map_err(git_error)?;
+
+    let mut history = Vec::new();
+    for oid in walk {
+        let commit = repo.find_commit(oid.map_err(git_error)?).map_err(git_error)?;
+        let current = blob_at(&commit, &relative);
+        let changed = if commit.parent_count() == 0 {
+            current.is_some()
+        } else {
+            // A merge changed the file only if it differs from every parent
+            commit
+                .parents()
+                .all(|parent| blob_at(&parent, &relative) != current)
+        };
+        if changed {
+            history.push(commit_info(&commit));
+            if history.len() >= limit {
+                break;
+            }
+        }
+    }
+    Ok(history)
+}
+
+// Content of a file at a revision
+pub fn show_file_at(path: &Path, rev: &str) -> Result<String, String> {
+    let repo = open_repository(path).ok_or_else(|| "File is not in a Git repository".to_string())?;
+    let relative = relative_path(&repo, path)?;
+    let commit = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Unknown revision {}: {}", rev, e))?;
+    let entry = commit
+        .tree()
+        .and_then(|tree| tree.get_path(Path::new(&relative)))
+        .map_err(|_| format!("{} does not exist at {}", relative, rev))?;
+    let blob = repo
+        .find_blob(entry.id())
+        .map_err(|e| format!("Failed to read {} at {}: {}", relative, rev, e))?;
+    String::from_utf8(blob.content().to_vec()).map_err(|_| format!("{} is not a text file at {}", relative, rev))
+}
+
+// Blame the lines of a file as it is on disk
+pub fn blame_file(path: &Path) -> Result<Vec<BlameLine>, String> {
+    let repo = open_repository(path).ok_or_else(|| "File is not in a Git repository".to_string())?;
+    let relative = relative_path(&repo, path)?;
+    let content = std::fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let line_count = if content.is_empty() {
+        0
+    } else {
+        content.split(|b| *b == b'\n').count() - usize::from(content.ends_with(b"\n"))
+    };
+    let uncommitted = |line_number: usize| BlameLine {
+        line_number,
+        commit_id: None,
+        author: None,
+        time: None,
+        summary: None,
+    };
+
+    let tracked = repo
+        .head()
+        .ok()
+        .and_then(|head| head.peel_to_tree().ok())
+        .is_some_and(|tree| tree.get_path(Path::new(&relative)).is_ok());
+    if !tracked {
+        return Ok((1..=line_count).map(uncommitted).collect());
+    }
+
+    let git_error = |e: git2::Error| format!("Failed to blame file: {}", e);
+    let committed = repo
+        .blame_file(Path::new(&relative), Some(&mut BlameOptions::new()))
+        .map_err(git_error)?;
+    // Map the lines on disk onto the committed ones
+    let blame = committed.blame_buffer(&content).map_err(git_error)?;
+
+    let mut summaries: HashMap<Oid, Option<String>> = HashMap::new();
+    let mut lines = Vec::with_capacity(line_count);
+    for hunk in blame.iter() {
+        let start = hunk.final_start_line();
+        let commit_id = hunk.final_commit_id();
+        for line_number in start..start + hunk.lines_in_hunk() {
+            if commit_id.is_zero() {
+                lines.push(uncommitted(line_number));
+                continue;
+            }
+            let summary = summaries
+                .entry(commit_id)
+                .or_insert_with(|| {
+                    repo.find_commit(commit_id)
+                        .ok()
+                        .and_then(|commit| commit.summary().map(str::to_string))
+                })
+                .clone();
+            let signature = hunk.final_signature();
+            lines.push(BlameLine {
+                line_number,
+                commit_id: Some(commit_id.to_string()),
+                author: signature.name().map(str::to_string),
+                time: Some(signature.when().seconds()),
+                summary,
+            });
+        }
+    }
+    lines.sort_by_key(|line| line.line_number);
+    Ok(lines)
+}
+
+// Render an auto-commit message template for the file at `relative_path`
+pub fn render_commit_message(
+    template: &str,
+    relative_path: &str,
+    now: DateTime<Local>,
+    variables: &HashMap<String, String>,
+) -> String {
+    let filename = relative_path.rsplit('/').next().unwrap_or(relative_path);
+    let with_variables = TEMPLATE_VARIABLE.replace_all(template, |caps: &Captures<'_>| {
+        let name = caps[1].trim();
+        variables
+            .get(name)
+            .cloned()
+            .or_else(|| VARIABLE_PROCESSOR.get_variable(name))
+            .unwrap_or_else(|| caps[0].to_string())
+    });
+    with_variables
+        .replace("{filename}", filename)
+        .replace("{path}", relative_path)
+        .replace("{timestamp}", &now.format("%Y-%m-%d %H:%M:%S").to_string())
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H:%M:%S").to_string())
+}
+
+// Commit a saved document if auto-commit is enabled and it is inside a repository
+pub fn auto_commit(path: &Path, content: &str) -> Result<Option<GitCommitInfo>, String> {
+    let settings = settings::current_settings();
+    if !settings.git_auto_commit {
+        return Ok(None);
+    }
+    let Some(repo) = open_repository(path) else {
+        return Ok(None);
+    };
+    let relative = relative_path(&repo, path)?;
+    if repo.is_path_ignored(Path::new(&relative)).unwrap_or(false) {
+        return Ok(None);
+    }
+
+    let variables: HashMap<String, String> = VARIABLE_PROCESSOR
+        .parse_variables_from_markdown(content)
+        .0
+        .into_iter()
+        .map(|v| (v.name, v.value))
+        .collect();
+    let template = settings
+        .git_auto_commit_message
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_AUTO_COMMIT_MESSAGE.to_string());
+    let message = render_commit_message(&template, &relative, Local::now(), &variables);
+
+    let _guard = AUTO_COMMIT_LOCK.lock().map_err(|_| "Failed to lock auto-commit".to_string())?;
+    match commit_paths(&[path.to_path_buf()], &message) {
+        Ok(commit) => Ok(Some(commit)),
+        // Saving unchanged content is not an error
+        Err(e) if e == "Nothing to commit" => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Run `auto_commit` in the background after a save
+pub fn spawn_auto_commit(path: String, content: String) {
+    if !settings::current_settings().git_auto_commit {
+        return;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = auto_commit(Path::new(&path), &content) {
+            warn!("Auto-commit of {} failed: {}", path, e);
+        }
+    });
+}
+
+// Whether a change below the working tree can affect the status of its files. Inside
+// `.git` only the index, HEAD and refs matter (not objects, logs or lock files).
+fn affects_status(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    let mut components = relative.components().map(|c| c.as_os_str().to_string_lossy());
+    match components.next().as_deref() {
+        Some(".git") => match components.next().as_deref() {
+            Some("index") | Some("HEAD") | Some("packed-refs") => true,
+            Some("refs") => path.extension().is_none_or(|ext| ext != "lock"),
+            _ => false,
+        },
+        Some(_) => true,
+        None => false,
+    }
+}
+
+// Start watching a repository for `git-status-changed` events (once per repository)
+fn watch_repository(app_handle: &tauri::AppHandle, root: &Path) {
+    let Ok(mut watchers) = repository_watchers_cell().lock() else {
+        return;
+    };
+    if watchers.contains_key(root) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let repository = root.to_path_buf();
+    let watcher = workspace::watch_debounced(root, WATCH_DEBOUNCE, move |paths| {
+        if paths.iter().any(|path| affects_status(&repository, path)) {
+            debug!("Git status of {:?} changed", repository);
+            let event = GitStatusChangedEvent {
+                repository: repository.to_string_lossy().to_string(),
+            };
+            let _ = emit_event(&app_handle, None, &event);
+        }
+        true
+    });
+    match watcher {
+        Ok(watcher) => {
+            watchers.insert(root.to_path_buf(), watcher);
+        }
+        Err(e) => warn!("{}", e),
+    }
+}
+
+// Tauri command: Get the Git status of a file, or of the changed files below a folder
+#[tauri::command]
+pub async fn git_status(app_handle: tauri::AppHandle, path: String) -> Result<GitStatus, String> {
+    path_scope::check_path(&path)?;
+    let status = tauri::async_runtime::spawn_blocking(move || repository_status(Path::new(&path)))
+        .await
+        .map_err(|e| format!("Failed to read Git status: {}", e))??;
+    if let Some(root) = &status.repository {
+        watch_repository(&app_handle, Path::new(root));
+    }
+    Ok(status)
+}
+
+// Tauri command: Get the changes of a file compared to HEAD as unified hunks
+#[tauri::command]
+pub async fn git_diff_file(path: String) -> Result<Vec<DiffHunk>, String> {
+    path_scope::check_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || diff_file(Path::new(&path)))
+        .await
+        .map_err(|e| format!("Failed to diff file: {}", e))?
+}
+
+// Tauri command: Commit the given files
+#[tauri::command]
+pub async fn git_commit(paths: Vec<String>, message: String) -> Result<GitCommitInfo, String> {
+    for path in &paths {
+        path_scope::check_path(path)?;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        commit_paths(&paths, &message)
+    })
+    .await
+    .map_err(|e| format!("Failed to commit: {}", e))?
+}
+
+// Tauri command: List the commits that changed a file, newest first
+#[tauri::command]
+pub async fn git_log_file(path: String, limit: Option<usize>) -> Result<Vec<GitCommitInfo>, String> {
+    path_scope::check_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || file_history(Path::new(&path), limit.unwrap_or(DEFAULT_LOG_LIMIT)))
+        .await
+        .map_err(|e| format!("Failed to read history: {}", e))?
+}
+
+// Tauri command: Get the content of a file at a revision
+#[tauri::command]
+pub async fn git_show_file_at(path: String, rev: String) -> Result<String, String> {
+    path_scope::check_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || show_file_at(Path::new(&path), &rev))
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?
+}
+
+// Tauri command: Get the blame annotation of each line of a file
+#[tauri::command]
+pub async fn git_blame(path: String) -> Result<Vec<BlameLine>, String> {
+    path_scope::check_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || blame_file(Path::new(&path)))
+        .await
+        .map_err(|e| format!("Failed to blame file: {}", e))?
+}
@@ -0,0 +1,239 @@
+//! # Grammar Module
+//!
+//! This module checks the grammar and style of a document with a LanguageTool server
+//! running on the user's machine (or network), e.g. the official Docker image or
+//! `languagetool-server.jar`. Nothing is sent anywhere unless the integration is enabled
+//! in the settings, and only to the configured server.
+//!
+//! ## Request
+//! The document is sent as annotated text: prose as text, everything else (frontmatter,
+//! code, URLs, link targets, HTML, variables) as markup, so LanguageTool skips it but the
+//! offsets of the issues still refer to the whole document.
+//!
+//! ## Queue and Debounce
+//! The frontend may call `check_grammar` on every edit. A call first waits for
+//! `DEBOUNCE`, and only one request is sent to the server at a time. A call that was
+//! overtaken by a newer one while waiting returns `None` without contacting the server,
+//! so only the latest content is checked.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::frontmatter;
+use crate::http;
+use crate::markdown::lines_outside_code;
+use crate::settings;
+use crate::spellcheck::mask_non_prose;
+
+const DEFAULT_SERVER_URL: &str = "http://localhost:8081";
+const DEBOUNCE: Duration = Duration::from_millis(500);
+// Larger documents are not sent
+const MAX_CONTENT_LEN: usize = 256 * 1024;
+const MAX_REPLACEMENTS: usize = 5;
+
+// Latest call of `check_grammar`
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+// One request to the server at a time
+static REQUEST_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+// A grammar or style issue. `start` and `end` are UTF-16 offsets within the document
+// (JavaScript string offsets).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrammarIssue {
+    pub start: usize,
+    pub end: usize,
+    pub message: String,
+    pub short_message: String,
+    pub rule_id: String,
+    // LanguageTool issue type (e.g. "grammar", "misspelling", "style")
+    pub issue_type: String,
+    pub category_id: String,
+    pub category: String,
+    pub replacements: Vec<String>,
+}
+
+// Part of an annotated text
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Annotation {
+    Text(String),
+    Markup(String),
+}
+
+#[derive(Deserialize)]
+struct CheckResponse {
+    matches: Vec<ResponseMatch>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseMatch {
+    message: String,
+    #[serde(default)]
+    short_message: String,
+    offset: usize,
+    length: usize,
+    #[serde(default)]
+    replacements: Vec<ResponseReplacement>,
+    rule: ResponseRule,
+}
+
+#[derive(Deserialize)]
+struct ResponseReplacement {
+    value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseRule {
+    id: String,
+    #[serde(default)]
+    issue_type: String,
+    category: ResponseCategory,
+}
+
+#[derive(Deserialize)]
+struct ResponseCategory {
+    id: String,
+    name: String,
+}
+
+// Append a run to an annotated text, merging it with the previous run of the same kind
+fn push_run(annotations: &mut Vec<Annotation>, is_text: bool, run: &str) {
+    if run.is_empty() {
+        return;
+    }
+    match (annotations.last_mut(), is_text) {
+        (Some(Annotation::Text(last)), true) | (Some(Annotation::Markup(last)), false) => last.push_str(run),
+        _ => annotations.push(if is_text {
+            Annotation::Text(run.to_string())
+        } else {
+            Annotation::Markup(run.to_string())
+        }),
+    }
+}
+
+// Split a document into prose (text) and everything else (markup). The runs joined
+// together are the document.
+pub fn annotate(content: &str) -> Vec<Annotation> {
+    let document = frontmatter::split_frontmatter(content);
+    let mut annotations = Vec::new();
+    push_run(&mut annotations, false, &content[..content.len() - document.body.len()]);
+
+    let prose: Vec<usize> = lines_outside_code(document.body).into_iter().map(|(index, _)| index).collect();
+    for (index, line) in document.body.split_inclusive('\n').enumerate() {
+        let text = line.trim_end_matches(['\r', '\n']);
+        if prose.binary_search(&index).is_ok() {
+            // Masking keeps byte offsets, so masked bytes mark the markup
+            let masked = mask_non_prose(text);
+            let mut run_start = 0;
+            let mut run_is_text = true;
+            for (i, (original, masked)) in text.bytes().zip(masked.bytes()).enumerate() {
+                let is_text = original == masked;
+                if is_text != run_is_text {
+                    push_run(&mut annotations, run_is_text, &text[run_start..i]);
+                    run_start = i;
+                    run_is_text = is_text;
+                }
+            }
+            push_run(&mut annotations, run_is_text, &text[run_start..]);
+        } else {
+            push_run(&mut annotations, false, text);
+        }
+        push_run(&mut annotations, true, &line[text.len()..]);
+    }
+    annotations
+}
+
+// Check endpoint of a server URL ("http://host:8081", ".../v2" or ".../v2/check")
+pub fn check_endpoint(server_url: &str) -> String {
+    let url = server_url.trim().trim_end_matches('/');
+    let url = if url.is_empty() { DEFAULT_SERVER_URL } else { url };
+    let base = url
+        .strip_suffix("/v2/check")
+        .or_else(|| url.strip_suffix("/v2"))
+        .unwrap_or(url);
+    format!("{}/v2/check", base)
+}
+
+// Issues from a LanguageTool response
+pub fn parse_response(body: &str) -> Result<Vec<GrammarIssue>, String> {
+    let response: CheckResponse =
+        serde_json::from_str(body).map_err(|e| format!("Invalid response from LanguageTool: {}", e))?;
+    Ok(response
+        .matches
+        .into_iter()
+        .map(|m| GrammarIssue {
+            start: m.offset,
+            end: m.offset + m.length,
+            message: m.message,
+            short_message: m.short_message,
+            rule_id: m.rule.id,
+            issue_type: m.rule.issue_type,
+            category_id: m.rule.category.id,
+            category: m.rule.category.name,
+            replacements: m
+                .replacements
+                .into_iter()
+                .take(MAX_REPLACEMENTS)
+                .map(|r| r.value)
+                .collect(),
+        })
+        .collect())
+}
+
+async fn request_check(server_url: &str, content: &str, lang: &str) -> Result<Vec<GrammarIssue>, String> {
+    let data = serde_json::json!({ "annotation": annotate(content) }).to_string();
+    let lang = if lang.trim().is_empty() { "auto" } else { lang.trim() };
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("data", &data)
+        .append_pair("language", lang)
+        .finish();
+    let endpoint = check_endpoint(server_url);
+
+    let response = http::client()?
+        .post(&endpoint)
+        .header(reqwest::header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("LanguageTool server is not reachable at {}: {}", endpoint, e))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read LanguageTool response: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("LanguageTool returned {}: {}", status, text.trim()));
+    }
+    parse_response(&text)
+}
+
+// Tauri command: Check the grammar of a document with the configured LanguageTool server.
+// Returns None if a newer check replaced this one before it was sent.
+#[tauri::command]
+pub async fn check_grammar(content: String, lang: String) -> Result<Option<Vec<GrammarIssue>>, String> {
+    let grammar = settings::current_settings().grammar;
+    if !grammar.enabled {
+        return Err("Grammar checking is disabled".to_string());
+    }
+    if content.len() > MAX_CONTENT_LEN {
+        return Err(format!("The document is too large to check ({} bytes)", content.len()));
+    }
+
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let is_latest = || GENERATION.load(Ordering::SeqCst) == generation;
+    tokio::time::sleep(DEBOUNCE).await;
+    if !is_latest() {
+        return Ok(None);
+    }
+    let _request = REQUEST_LOCK.lock().await;
+    if !is_latest() {
+        return Ok(None);
+    }
+    debug!("Checking grammar of {} bytes ({})", content.len(), lang);
+    request_check(&grammar.server_url, &content, &lang).await.map(Some)
+}
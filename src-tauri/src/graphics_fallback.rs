@@ -0,0 +1,190 @@
+//! # Graphics Fallback Module (Linux)
+//!
+//! WebKitGTK's GPU paths (DMABUF renderer, EGL) fail on some driver/compositor
+//! combinations. Some failures are loud (`EGL_BAD_PARAMETER` aborting the process, seen
+//! on CachyOS/Wayland), some silent (garbled rendering on the Raspberry Pi). Neither can
+//! be handled once the webview exists, so `apply_graphics_fallback` runs at the very
+//! start of `run()`, before `tauri::Builder`.
+//!
+//! ## Detection
+//! - **Raspberry Pi**: Known-bad, DMABUF is disabled in-process
+//! - **Previous launch died**: The `graphics-fallback` plugin writes a `webview-starting`
+//!   marker right before the windows are created and removes it when the main page has
+//!   loaded; if it is still there, the last launch never got a working webview
+//! - **Wayland + NVIDIA proprietary driver**: Known to fail EGL initialization
+//!
+//! For the last two the app re-executes itself once with the fallback environment
+//! (`FALLBACK_ENV`) and `BOKUCHI_GRAPHICS_FALLBACK=<reason>`, so the new process starts
+//! with a clean GL stack. If that launch reaches the page load, the fallback is
+//! remembered (`graphics-fallback` file) and applied in-process on later launches.
+//!
+//! Setting `WEBKIT_DISABLE_DMABUF_RENDERER` explicitly (to any value) disables all of this.
+
+use std::fs;
+use std::path::PathBuf;
+
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::webview::PageLoadEvent;
+use tauri::Runtime;
+use tracing::{info, warn};
+
+// Set on the relaunched process; holds the reason for the fallback
+const FALLBACK_MARKER_ENV: &str = "BOKUCHI_GRAPHICS_FALLBACK";
+
+// Known-good environment: no DMABUF renderer, no accelerated compositing, software GL
+const FALLBACK_ENV: &[(&str, &str)] = &[
+    ("WEBKIT_DISABLE_DMABUF_RENDERER", "1"),
+    ("WEBKIT_DISABLE_COMPOSITING_MODE", "1"),
+    ("LIBGL_ALWAYS_SOFTWARE", "1"),
+];
+
+const STARTING_MARKER_FILE: &str = "webview-starting";
+const REMEMBERED_FALLBACK_FILE: &str = "graphics-fallback";
+
+// Directory for the marker files. The app data directory is not known before the Tauri
+// builder runs, so this uses the XDG cache directory with the bundle identifier.
+fn state_dir() -> Option<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache.join("com.pemomomo.bokuchi"))
+}
+
+fn state_file(name: &str) -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join(name))
+}
+
+// Decide whether to relaunch with the fallback environment
+pub fn fallback_reason(previous_launch_died: bool, is_wayland: bool, has_nvidia: bool) -> Option<&'static str> {
+    if previous_launch_died {
+        Some("previous launch did not reach a working webview")
+    } else if is_wayland && has_nvidia {
+        Some("Wayland session with the NVIDIA proprietary driver")
+    } else {
+        None
+    }
+}
+
+fn is_wayland_session() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland"))
+}
+
+fn is_raspberry_pi() -> bool {
+    // The Raspberry Pi exposes its model name via the device tree.
+    fs::read_to_string("/proc/device-tree/model")
+        .map(|model| model.contains("Raspberry Pi"))
+        .unwrap_or(false)
+}
+
+fn set_fallback_env() {
+    for (key, value) in FALLBACK_ENV {
+        // SAFETY: called at the very start of `run()`, before Tauri spawns any
+        // threads, so there is no concurrent access to the environment.
+        unsafe {
+            std::env::set_var(key, value);
+        }
+    }
+}
+
+fn write_starting_marker() {
+    if let Some(path) = state_file(STARTING_MARKER_FILE) {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(path, std::process::id().to_string());
+    }
+}
+
+// Re-execute the current binary with the fallback environment. Only returns on failure.
+fn relaunch_with_fallback(reason: &str) -> std::io::Error {
+    use std::os::unix::process::CommandExt;
+
+    // AppImages must be relaunched through the AppImage, not the mounted binary
+    let exe = std::env::var_os("APPIMAGE")
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_exe().ok());
+    let Some(exe) = exe else {
+        return std::io::Error::other("current executable not found");
+    };
+    let mut command = std::process::Command::new(exe);
+    command
+        .args(std::env::args_os().skip(1))
+        .envs(FALLBACK_ENV.iter().copied())
+        .env(FALLBACK_MARKER_ENV, reason);
+    command.exec()
+}
+
+// Apply the graphics fallback if needed. Must run before any webview is created.
+pub fn apply_graphics_fallback() {
+    // Respect an explicit user setting (escape hatch to force-enable on a
+    // normal desktop, or force-disable elsewhere).
+    if std::env::var_os("WEBKIT_DISABLE_DMABUF_RENDERER").is_some() {
+        if let Ok(reason) = std::env::var(FALLBACK_MARKER_ENV) {
+            info!("Relaunched with graphics fallback ({}): {:?}", reason, FALLBACK_ENV);
+        }
+        return;
+    }
+
+    // WebKitGTK >= 2.42 enables a DMABUF-based GPU renderer by default. On the
+    // Raspberry Pi (VideoCore / V3D driver) this produces garbled, scanline-like
+    // noise instead of the UI. The failure is silent, so the known-bad environment
+    // is detected up front instead.
+    if is_raspberry_pi() {
+        // SAFETY: see `set_fallback_env`
+        unsafe {
+            std::env::set_var("WEBKIT_DISABLE_DMABUF_RENDERER", "1");
+        }
+        info!("Detected Raspberry Pi: disabled WebKitGTK DMABUF renderer");
+        return;
+    }
+
+    // A previous fallback launch worked: keep using it without relaunching
+    if let Some(remembered) = state_file(REMEMBERED_FALLBACK_FILE).and_then(|p| fs::read_to_string(p).ok()) {
+        set_fallback_env();
+        info!("Using remembered graphics fallback ({}): {:?}", remembered.trim(), FALLBACK_ENV);
+        return;
+    }
+
+    let previous_launch_died = state_file(STARTING_MARKER_FILE).is_some_and(|p| p.exists());
+    let has_nvidia = std::path::Path::new("/proc/driver/nvidia/version").exists();
+    if let Some(reason) = fallback_reason(previous_launch_died, is_wayland_session(), has_nvidia) {
+        warn!("Graphics initialization likely to fail ({}); relaunching with {:?}", reason, FALLBACK_ENV);
+        let error = relaunch_with_fallback(reason);
+        // Relaunch failed: fall back in-process, which covers most cases
+        warn!("Failed to relaunch with graphics fallback: {}", error);
+        set_fallback_env();
+    }
+}
+
+// Called when the main window finished loading its page: the webview works
+fn mark_webview_started() {
+    if let Some(path) = state_file(STARTING_MARKER_FILE) {
+        let _ = fs::remove_file(path);
+    }
+    if let Ok(reason) = std::env::var(FALLBACK_MARKER_ENV)
+        && let Some(path) = state_file(REMEMBERED_FALLBACK_FILE)
+    {
+        match fs::write(&path, &reason) {
+            Ok(()) => info!("Graphics fallback worked; remembered in {:?}", path),
+            Err(e) => warn!("Failed to remember graphics fallback: {}", e),
+        }
+    }
+}
+
+// Plugin tracking whether the webview comes up. Register it after the single-instance
+// plugin, so a second instance that exits right away never leaves a marker behind.
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    Builder::new("graphics-fallback")
+        .setup(|_app, _api| {
+            write_starting_marker();
+            Ok(())
+        })
+        .on_page_load(|webview, payload| {
+            if webview.label() == "main" && payload.event() == PageLoadEvent::Finished {
+                mark_webview_started();
+            }
+        })
+        .build()
+}
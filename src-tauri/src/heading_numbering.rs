@@ -0,0 +1,211 @@
+//! # Heading Numbering Module
+//!
+//! This module numbers the headings of a document hierarchically (`1.`, `1.1`, `1.1.1`)
+//! for specifications that require numbered sections, and strips the numbers again.
+//!
+//! ## Numbers
+//! Headings from `min_level` to `max_level` are numbered; the highest level among them
+//! in the document is the top level. Numbering again replaces the existing numbers, so it also renumbers after
+//! sections were moved. A number is recognized by its dots (`1.`, `2.3`, `2.3.1.`): a
+//! heading like "2024 review" keeps its year. Top-level numbers of setext headings are
+//! written as `1\.`, as `1.` would start a list.
+//!
+//! ## Anchors
+//! Numbers change the anchors of the headings (`#intro` becomes `#1-intro`). Links to
+//! them within the document are updated, and the changes are returned, so links in other
+//! documents can be updated as well.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::document_structure::{parse_structure, Block, BlockKind};
+use crate::markdown::lines_outside_code;
+
+lazy_static! {
+    static ref NUMBER: Regex = Regex::new(r"^\d+\\?\.(?:\d+\.?)*\s+").unwrap();
+    static ref ATX_PREFIX: Regex = Regex::new(r"^ {0,3}#{1,6}(?:[ \t]+|$)").unwrap();
+    static ref ANCHOR_LINK: Regex = Regex::new(r"\]\(#([^)\s]+)\)").unwrap();
+}
+
+// What `number_headings` does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberingMode {
+    #[default]
+    Number,
+    Strip,
+}
+
+// Options of `number_headings`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NumberingOptions {
+    pub mode: NumberingMode,
+    // Highest heading level that is numbered (2 leaves a single `#` title alone)
+    pub min_level: u8,
+    pub max_level: u8,
+}
+
+impl Default for NumberingOptions {
+    fn default() -> Self {
+        NumberingOptions {
+            mode: NumberingMode::Number,
+            min_level: 1,
+            max_level: 6,
+        }
+    }
+}
+
+// An anchor that changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnchorChange {
+    pub from: String,
+    pub to: String,
+}
+
+// Result of `number_headings`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NumberedDocument {
+    pub content: String,
+    pub anchors: Vec<AnchorChange>,
+}
+
+// A heading of the document
+struct Heading {
+    line: usize,
+    level: u8,
+    anchor: String,
+    // Not inside a list or block quote
+    top_level: bool,
+}
+
+// Headings of a document, in document order
+fn headings(content: &str) -> Vec<Heading> {
+    fn collect(blocks: &[Block], top_level: bool, out: &mut Vec<Heading>) {
+        for block in blocks {
+            if let BlockKind::Heading { level, anchor, .. } = &block.kind {
+                out.push(Heading {
+                    line: block.start_line,
+                    level: *level,
+                    anchor: anchor.clone(),
+                    top_level,
+                });
+            }
+            collect(&block.children, false, out);
+        }
+    }
+    let mut out = Vec::new();
+    collect(&parse_structure(content), true, &mut out);
+    out
+}
+
+// Section number of the next heading at `depth` (0 = top level)
+fn next_number(counters: &mut Vec<u32>, depth: usize) -> String {
+    // Levels that were skipped count as 1 ("1.1.1" under "1." for a jump from # to ###)
+    while counters.len() <= depth {
+        counters.push(if counters.len() < depth { 1 } else { 0 });
+    }
+    counters.truncate(depth + 1);
+    counters[depth] += 1;
+    let number: Vec<String> = counters.iter().map(u32::to_string).collect();
+    if depth == 0 {
+        format!("{}.", number[0])
+    } else {
+        number.join(".")
+    }
+}
+
+// Replace (or remove, with `number` None) the number of a heading line
+fn renumber_line(line: &str, number: Option<&str>) -> String {
+    let text = line.trim_end_matches(['\r', '\n']);
+    let ending = &line[text.len()..];
+    // ATX headings keep their `#`s; the text line of setext headings its indentation
+    let atx = ATX_PREFIX.find(text).map(|m| m.end());
+    let prefix_len = atx.unwrap_or_else(|| text.len() - text.trim_start().len());
+    let (prefix, title) = text.split_at(prefix_len);
+    let title = NUMBER.find(title).map_or(title, |m| &title[m.end()..]);
+    match number {
+        Some(number) if atx.is_none() && number.ends_with('.') => {
+            format!("{}{}\\. {}{}", prefix, &number[..number.len() - 1], title, ending)
+        }
+        Some(number) => format!("{}{} {}{}", prefix, number, title, ending),
+        None => format!("{}{}{}", prefix, title, ending),
+    }
+}
+
+// Number or strip the headings of a document. Headings inside lists and block quotes
+// are left alone.
+pub fn number_document(content: &str, options: &NumberingOptions) -> NumberedDocument {
+    let before = headings(content);
+    let in_range = |heading: &Heading| {
+        heading.top_level && heading.level >= options.min_level && heading.level <= options.max_level
+    };
+    let top = before
+        .iter()
+        .filter(|heading| in_range(heading))
+        .map(|heading| heading.level)
+        .min()
+        .unwrap_or(options.min_level);
+    let mut numbers: HashMap<usize, Option<String>> = HashMap::new();
+    let mut counters: Vec<u32> = Vec::new();
+    for heading in &before {
+        if !in_range(heading) {
+            continue;
+        }
+        let number = match options.mode {
+            NumberingMode::Number => Some(next_number(&mut counters, (heading.level - top) as usize)),
+            NumberingMode::Strip => None,
+        };
+        numbers.insert(heading.line, number);
+    }
+
+    let numbered: String = content
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(index, line)| match numbers.get(&(index + 1)) {
+            Some(number) => renumber_line(line, number.as_deref()),
+            None => line.to_string(),
+        })
+        .collect();
+
+    let after = headings(&numbered);
+    let anchors: Vec<AnchorChange> = before
+        .iter()
+        .zip(&after)
+        .filter(|(from, to)| from.anchor != to.anchor)
+        .map(|(from, to)| AnchorChange {
+            from: from.anchor.clone(),
+            to: to.anchor.clone(),
+        })
+        .collect();
+    let renamed: HashMap<&str, &str> = anchors
+        .iter()
+        .map(|change| (change.from.as_str(), change.to.as_str()))
+        .collect();
+    let code_free: Vec<usize> = lines_outside_code(&numbered).into_iter().map(|(index, _)| index).collect();
+    let content: String = numbered
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            if renamed.is_empty() || code_free.binary_search(&index).is_err() {
+                return line.to_string();
+            }
+            ANCHOR_LINK
+                .replace_all(line, |caps: &regex::Captures| match renamed.get(&caps[1]) {
+                    Some(to) => format!("](#{})", to),
+                    None => caps[0].to_string(),
+                })
+                .into_owned()
+        })
+        .collect();
+
+    NumberedDocument { content, anchors }
+}
+
+// Tauri command: Insert or update hierarchical heading numbers, or strip them
+#[tauri::command]
+pub fn number_headings(content: String, options: Option<NumberingOptions>) -> NumberedDocument {
+    number_document(&content, &options.unwrap_or_default())
+}
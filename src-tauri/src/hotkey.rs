@@ -0,0 +1,239 @@
+//! # Hotkey Module
+//!
+//! This module registers the configurable global shortcut that summons Bokuchi from
+//! anywhere in the OS, and implements quick capture.
+//!
+//! ## Summon Actions
+//! - **Focus**: Show and focus the main window
+//! - **Quick Capture**: Open a small always-on-top `quick-capture` window; the note typed
+//!   there is appended to the configured inbox file via `append_to_inbox`
+//!
+//! ## Journal Entries
+//! `append_to_file` appends an entry to any Markdown or text file (a journal or log
+//! note) without opening it. The entry's heading is a template expanded by the variable
+//! processor, with `{{date}}`, `{{time}}` and `{{datetime}}` set to the current time.
+//! Appends are serialized within the app and written with a single append write, so
+//! concurrent captures never interleave.
+//!
+//! The shortcut is (re)registered whenever the backend settings change, so the user can
+//! rebind it without restarting. A shortcut that cannot be registered (invalid, or taken
+//! by another app) is not saved, and the previous one stays active. A separate process
+//! (`--new-instance`) never registers it: the shortcut belongs to the main process.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tracing::{info, warn};
+
+use crate::document_window;
+use crate::error::AppError;
+use crate::path_scope;
+use crate::settings::{self, BackendSettings, SummonAction};
+use crate::tray;
+use crate::variable_processor::VARIABLE_PROCESSOR;
+use crate::workspace;
+
+const QUICK_CAPTURE_LABEL: &str = "quick-capture";
+
+// Held while an entry is appended, so entries from concurrent commands never interleave
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+// Options of `append_to_file`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppendOptions {
+    // Heading line of the entry, expanded by the variable processor; empty for none
+    pub heading: String,
+}
+
+impl Default for AppendOptions {
+    fn default() -> Self {
+        AppendOptions {
+            heading: "## {{date}} {{time}}".to_string(),
+        }
+    }
+}
+
+// The summon shortcut of the given settings, None when it is off
+fn summon_shortcut(settings: &BackendSettings) -> Result<Option<Shortcut>, String> {
+    settings
+        .summon_shortcut
+        .as_deref()
+        .map(str::trim)
+        .filter(|accelerator| !accelerator.is_empty())
+        .map(|accelerator| {
+            accelerator
+                .parse::<Shortcut>()
+                .map_err(|e| format!("Invalid shortcut '{}': {}", accelerator, e))
+        })
+        .transpose()
+}
+
+// Register the summon shortcut of `settings` in place of the one of `previous` (None at
+// startup). The new shortcut is registered before the old one is released, so a shortcut
+// that is invalid or taken by another app leaves the old binding working.
+pub fn apply_summon_shortcut(
+    app: &tauri::AppHandle,
+    previous: Option<&BackendSettings>,
+    settings: &BackendSettings,
+) -> Result<(), String> {
+    if document_window::is_secondary_instance() {
+        return Ok(());
+    }
+    let shortcuts = app.global_shortcut();
+    // A previous shortcut that did not parse was never registered
+    let old = previous.and_then(|previous| summon_shortcut(previous).ok().flatten());
+    let new = summon_shortcut(settings)?;
+    if old == new {
+        return Ok(());
+    }
+
+    if let Some(shortcut) = new {
+        shortcuts
+            .on_shortcut(shortcut, |app, _shortcut, event| {
+                if event.state() == ShortcutState::Pressed {
+                    handle_summon(app);
+                }
+            })
+            .map_err(|e| format!("Failed to register shortcut '{}': {}", shortcut, e))?;
+        info!("Registered summon shortcut: {}", shortcut);
+    }
+    if let Some(shortcut) = old
+        && let Err(e) = shortcuts.unregister(shortcut)
+    {
+        warn!("Failed to unregister shortcut '{}': {}", shortcut, e);
+    }
+    Ok(())
+}
+
+// Run the configured summon action
+fn handle_summon(app: &tauri::AppHandle) {
+    match settings::current_settings().summon_action {
+        SummonAction::Focus => tray::show_main_window(app),
+        SummonAction::QuickCapture => {
+            if let Err(e) = open_quick_capture_window(app) {
+                warn!("Failed to open quick capture window: {}", e);
+            }
+        }
+    }
+}
+
+// Show the quick capture window, creating it on first use
+fn open_quick_capture_window(app: &tauri::AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(QUICK_CAPTURE_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    WebviewWindowBuilder::new(
+        app,
+        QUICK_CAPTURE_LABEL,
+        WebviewUrl::App("index.html?view=quick-capture".into()),
+    )
+    .title("Quick Capture")
+    .inner_size(480.0, 220.0)
+    .resizable(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .center()
+    .focused(true)
+    .build()
+    .map(|_| ())
+    .map_err(|e| format!("Failed to create window: {}", e))
+}
+
+// Append a timestamped entry to a Markdown file, creating it if needed
+pub fn append_capture_entry(path: &Path, content: &str, timestamp: &str) -> Result<(), String> {
+    append_entry(path, &format!("## {}", timestamp), content)
+}
+
+// Append an entry with a heading line (none if empty) to a file, creating it if needed
+pub fn append_entry(path: &Path, heading: &str, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|_| "Failed to create directory".to_string())?;
+    }
+
+    let _guard = APPEND_LOCK.lock().map_err(|_| "Failed to lock appends".to_string())?;
+
+    // Keep the previous entry's last line intact when the file lacks a trailing newline
+    let needs_newline = std::fs::read(path)
+        .map(|bytes| !bytes.is_empty() && !bytes.ends_with(b"\n"))
+        .unwrap_or(false);
+
+    let mut entry = String::new();
+    if needs_newline {
+        entry.push('\n');
+    }
+    let heading = heading.trim();
+    if heading.is_empty() {
+        entry.push_str(&format!("\n{}\n", content.trim_end()));
+    } else {
+        entry.push_str(&format!("\n{}\n\n{}\n", heading, content.trim_end()));
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open file: {} ({:?})", e, e.kind()))?;
+    file.write_all(entry.as_bytes())
+        .map_err(|e| format!("Failed to append to file: {} ({:?})", e, e.kind()))
+}
+
+// Expand an entry heading template for the time `now`
+pub fn render_heading(template: &str, now: &DateTime<Local>) -> String {
+    let mut context = HashMap::new();
+    context.insert("date".to_string(), now.format("%Y-%m-%d").to_string());
+    context.insert("time".to_string(), now.format("%H:%M").to_string());
+    context.insert("datetime".to_string(), now.format("%Y-%m-%d %H:%M").to_string());
+    VARIABLE_PROCESSOR.process_variables_with(template, &context)
+}
+
+// Tauri command: Append a quick capture note to the inbox file. Returns the inbox path.
+#[tauri::command]
+pub fn append_to_inbox(app_handle: tauri::AppHandle, content: String) -> Result<String, String> {
+    if content.trim().is_empty() {
+        return Err("Nothing to capture".to_string());
+    }
+    let inbox = settings::current_settings()
+        .inbox_file
+        .filter(|p| !p.trim().is_empty())
+        .ok_or_else(|| "No inbox file is configured".to_string())?;
+    // The inbox setting comes from the WebView: it must be a document the user picked
+    path_scope::check_document_write(&inbox)?;
+
+    let timestamp = Local::now().format("%Y-%m-%d %H:%M").to_string();
+    append_capture_entry(Path::new(&inbox), &content, &timestamp)?;
+
+    if let Some(window) = app_handle.get_webview_window(QUICK_CAPTURE_LABEL) {
+        let _ = window.hide();
+    }
+    Ok(inbox)
+}
+
+// Tauri command: Append a timestamped entry to a Markdown or text file without opening
+// it. Returns the heading that was written.
+#[tauri::command]
+pub fn append_to_file(path: String, content: String, options: Option<AppendOptions>) -> Result<String, String> {
+    if content.trim().is_empty() {
+        return Err("Nothing to append".to_string());
+    }
+    path_scope::check_path(&path)?;
+    if !workspace::is_document_path(Path::new(&path)) {
+        return Err(AppError::UnsupportedFileType(PathBuf::from(&path)).into());
+    }
+
+    let options = options.unwrap_or_default();
+    let heading = render_heading(&options.heading, &Local::now());
+    append_entry(Path::new(&path), &heading, &content)?;
+    info!("Appended {} bytes to {}", content.len(), path);
+    Ok(heading)
+}
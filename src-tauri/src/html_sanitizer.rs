@@ -0,0 +1,77 @@
+//! # HTML Sanitizer Module
+//!
+//! Markdown may embed raw HTML, and a document from an untrusted source can use it to run
+//! scripts in the preview WebView or in exported files. This module cleans HTML rendered
+//! from Markdown with an allowlist (ammonia) before it is shown or written.
+//!
+//! ## Allowlist
+//! - ammonia's defaults: formatting, lists, tables, links and images with safe URL schemes
+//! - Markdown output: `id` and `class` on every tag (heading anchors, code languages) and
+//!   the checkboxes of task lists
+//! - The `asset` URL scheme of local images in the WebView
+//! - Extra tags, attributes and URL schemes from the settings (`html_sanitizer`)
+//!
+//! `<script>` and `<style>` elements, event handler attributes (`on...`) and `javascript:`
+//! URLs are always removed, whatever the settings say. Links get
+//! `rel="noopener noreferrer"`.
+
+use ammonia::Builder;
+use std::collections::HashSet;
+
+use crate::settings::{self, HtmlSanitizerSettings};
+
+// Elements removed with their content; they can never be allowed
+const REMOVED_TAGS: [&str; 2] = ["script", "style"];
+// URL schemes that can never be allowed
+const REMOVED_SCHEMES: [&str; 2] = ["javascript", "vbscript"];
+
+// Whether an attribute from the settings can be allowed
+fn is_allowed_attribute(name: &str) -> bool {
+    // `rel` is set by the sanitizer itself
+    !name.starts_with("on") && name != "rel"
+}
+
+// Lowercased entries of a settings list, without those `keep` rejects
+fn setting_list(values: &[String], keep: impl Fn(&str) -> bool) -> HashSet<String> {
+    values
+        .iter()
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty() && keep(value))
+        .collect()
+}
+
+// Clean rendered HTML with the built-in allowlist extended by `settings`
+pub fn sanitize_with(html: &str, settings: &HtmlSanitizerSettings) -> String {
+    let tags = setting_list(&settings.allowed_tags, |tag| !REMOVED_TAGS.contains(&tag));
+    let attributes = setting_list(&settings.allowed_attributes, is_allowed_attribute);
+    let schemes = setting_list(&settings.allowed_url_schemes, |scheme| {
+        !REMOVED_SCHEMES.contains(&scheme)
+    });
+
+    let mut builder = Builder::default();
+    builder
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_generic_attributes(["id", "class"])
+        .add_url_schemes(["asset"])
+        .add_tags(&tags)
+        .add_generic_attributes(&attributes)
+        .add_url_schemes(&schemes);
+    builder.clean(html).to_string()
+}
+
+// Clean rendered HTML as the settings say (unchanged when sanitizing is turned off)
+pub fn sanitize(html: &str) -> String {
+    let settings = settings::current_settings().html_sanitizer;
+    if !settings.enabled {
+        return html.to_string();
+    }
+    sanitize_with(html, &settings)
+}
+
+// Tauri command: Sanitize HTML rendered from Markdown before it reaches the preview or an
+// export file
+#[tauri::command]
+pub fn sanitize_html(html: String) -> String {
+    sanitize(&html)
+}
@@ -0,0 +1,54 @@
+//! # HTTP Module
+//!
+//! This module provides the HTTP client shared by features that talk to servers (remote
+//! files, ...), and a second one for fetching web pages on the user's behalf (link
+//! titles), with a short timeout and few redirects.
+//!
+//! ## TLS
+//! reqwest uses rustls with the ring crypto provider, the same setup as the updater
+//! plugin. The provider is installed as the process default before the first client is
+//! built, unless another component already installed one.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const PAGE_TIMEOUT: Duration = Duration::from_secs(10);
+const PAGE_MAX_REDIRECTS: usize = 5;
+
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+static PAGE_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+fn build_client(
+    cell: &'static OnceLock<reqwest::Client>,
+    configure: impl FnOnce(reqwest::ClientBuilder) -> reqwest::ClientBuilder,
+) -> Result<reqwest::Client, String> {
+    if let Some(client) = cell.get() {
+        return Ok(client.clone());
+    }
+    if rustls::crypto::CryptoProvider::get_default().is_none() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+    }
+    let builder = reqwest::Client::builder()
+        .user_agent(concat!("Bokuchi/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(CONNECT_TIMEOUT);
+    let client = configure(builder)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    Ok(cell.get_or_init(|| client).clone())
+}
+
+// Shared HTTP client (connections are pooled across requests)
+pub fn client() -> Result<reqwest::Client, String> {
+    build_client(&CLIENT, |builder| builder.timeout(REQUEST_TIMEOUT))
+}
+
+// Client for web pages the user pasted or linked
+pub fn page_client() -> Result<reqwest::Client, String> {
+    build_client(&PAGE_CLIENT, |builder| {
+        builder
+            .timeout(PAGE_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::limited(PAGE_MAX_REDIRECTS))
+    })
+}
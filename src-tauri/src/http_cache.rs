@@ -0,0 +1,352 @@
+//! # HTTP Cache Module
+//!
+//! This module keeps downloaded files on disk so they can be revalidated cheaply and
+//! still be used offline (remote documents opened from a URL, ...).
+//!
+//! ## Layout
+//! Each feature has its own folder in `<app data>/http-cache/`. An entry is named by the
+//! SHA-256 of its URL: `<key>.json` holds the response metadata (ETag, Last-Modified,
+//! Content-Type, fetch and expiry time) and `<key>.<ext>` the content, with the extension
+//! of its type (`.png`, `.md`, ...; `.body` for unknown types) so the file can be served
+//! as is.
+//!
+//! ## Freshness
+//! The cache headers of the server are respected, as a private (browser-like) cache:
+//! - `Cache-Control: max-age=N` or `Expires`: The entry is used without any request
+//!   until it expires
+//! - `Cache-Control: no-cache` (or no lifetime): The entry is revalidated on every use
+//! - `Cache-Control: no-store`: The response is not written to disk
+//!
+//! An expired entry is requested again with `If-None-Match` / `If-Modified-Since`; a
+//! `304 Not Modified` answer serves the cached body and renews its lifetime. When the
+//! server cannot be reached, the cached body is served and marked as stale.
+//!
+//! ## Content Types
+//! Each caller passes the content types it accepts. A response of another type is
+//! rejected before its body is read, so it is never written into the cache folder (the
+//! image cache folder is served on the asset protocol). Cached entries of another type
+//! are deleted.
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use reqwest::header::{
+    HeaderMap, CACHE_CONTROL, CONTENT_TYPE, ETAG, EXPIRES, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use tracing::{debug, warn};
+
+use crate::http;
+use crate::storage;
+
+const CACHE_DIR: &str = "http-cache";
+
+// Metadata of a cached response
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheEntry {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_type: String,
+    // File name of the body, next to the metadata
+    pub body_file: String,
+    // Seconds since the Unix epoch
+    pub fetched_at: u64,
+    // Until when the entry may be used without revalidation; None to always revalidate
+    pub expires_at: Option<u64>,
+}
+
+// What the cache headers of a response allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    // Not to be written to disk
+    NoStore,
+    // Fresh until the given time (seconds since the Unix epoch); None to always revalidate
+    Store { expires_at: Option<u64> },
+}
+
+// A response, fresh from the server or from the cache
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub entry: CacheEntry,
+    pub bytes: Vec<u8>,
+    // The cached body file; None when the response may not be stored
+    pub path: Option<PathBuf>,
+    // The body is the cached one (not modified, or the server could not be reached)
+    pub from_cache: bool,
+    // The server could not be reached, so the body may be outdated
+    pub stale: bool,
+}
+
+// Name of the cache entry of a URL
+pub fn cache_key(url: &str) -> String {
+    Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Folder of a feature's entries
+pub fn cache_dir(namespace: &str) -> Result<PathBuf, String> {
+    storage::app_data_path(CACHE_DIR)
+        .map(|dir| dir.join(namespace))
+        .ok_or_else(|| "App data directory is not available".to_string())
+}
+
+// Whether an entry may be used at `now` without asking the server
+pub fn is_fresh(entry: &CacheEntry, now: u64) -> bool {
+    entry.expires_at.is_some_and(|expires_at| now < expires_at)
+}
+
+// File extension of a content type, for the body file
+pub fn file_extension(content_type: &str) -> &'static str {
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    match mime {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        "image/svg+xml" => "svg",
+        "image/bmp" => "bmp",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        "text/markdown" | "text/x-markdown" => "md",
+        "text/plain" => "txt",
+        _ => "body",
+    }
+}
+
+// The cache policy of a response received at `now`
+pub fn cache_policy(headers: &HeaderMap, now: u64) -> CachePolicy {
+    let directives: Vec<String> = headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| directive.trim().to_ascii_lowercase())
+        .collect();
+    if directives.iter().any(|directive| directive == "no-store") {
+        return CachePolicy::NoStore;
+    }
+    if directives.iter().any(|directive| directive == "no-cache") {
+        return CachePolicy::Store { expires_at: None };
+    }
+    let max_age = directives.iter().find_map(|directive| {
+        let value = directive.strip_prefix("max-age")?.trim_start().strip_prefix('=')?;
+        value.trim().trim_matches('"').parse::<u64>().ok()
+    });
+    let expires_at = match max_age {
+        Some(max_age) => Some(now.saturating_add(max_age)),
+        None => headers
+            .get(EXPIRES)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|date| DateTime::parse_from_rfc2822(date.trim()).ok())
+            .map(|date| date.timestamp().max(0) as u64),
+    };
+    CachePolicy::Store {
+        expires_at: expires_at.filter(|expires_at| *expires_at > now),
+    }
+}
+
+// Conditional request headers for a cached entry
+pub fn revalidation_headers(entry: &CacheEntry) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(value) = entry.etag.as_deref().and_then(|etag| etag.parse().ok()) {
+        headers.insert(IF_NONE_MATCH, value);
+    }
+    if let Some(value) = entry.last_modified.as_deref().and_then(|date| date.parse().ok()) {
+        headers.insert(IF_MODIFIED_SINCE, value);
+    }
+    headers
+}
+
+// Metadata of a response to `url`, fetched at `now`
+pub fn entry_from_headers(url: &Url, headers: &HeaderMap, now: u64) -> CacheEntry {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let content_type = header(CONTENT_TYPE).unwrap_or_default().to_ascii_lowercase();
+    CacheEntry {
+        url: url.to_string(),
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+        body_file: format!("{}.{}", cache_key(url.as_str()), file_extension(&content_type)),
+        content_type,
+        fetched_at: now,
+        expires_at: match cache_policy(headers, now) {
+            CachePolicy::Store { expires_at } => expires_at,
+            CachePolicy::NoStore => None,
+        },
+    }
+}
+
+// A cached entry with its body
+fn read_entry(dir: &Path, key: &str) -> Option<(CacheEntry, Vec<u8>)> {
+    let meta_path = dir.join(format!("{}.json", key));
+    if !meta_path.exists() {
+        return None;
+    }
+    let entry: CacheEntry = storage::read_json_file(&meta_path);
+    if entry.body_file.is_empty() {
+        return None;
+    }
+    let bytes = fs::read(dir.join(&entry.body_file)).ok()?;
+    Some((entry, bytes))
+}
+
+fn remove_entry(dir: &Path, key: &str, entry: &CacheEntry) {
+    let _ = fs::remove_file(dir.join(&entry.body_file));
+    let _ = fs::remove_file(dir.join(format!("{}.json", key)));
+}
+
+// The cached entry of `key` if its content type is accepted; an entry of another type is
+// deleted
+pub fn read_accepted_entry(dir: &Path, key: &str, accept: fn(&str) -> bool) -> Option<(CacheEntry, Vec<u8>)> {
+    let (entry, bytes) = read_entry(dir, key)?;
+    if !accept(&entry.content_type) {
+        remove_entry(dir, key, &entry);
+        return None;
+    }
+    Some((entry, bytes))
+}
+
+fn write_entry(dir: &Path, key: &str, entry: &CacheEntry, bytes: Option<&[u8]>) -> Result<(), String> {
+    if let Some(bytes) = bytes {
+        storage::write_atomic(&dir.join(&entry.body_file), bytes)?;
+    }
+    storage::write_json_file(&dir.join(format!("{}.json", key)), entry)
+}
+
+// Read a response body, failing when it is larger than `max_size`
+async fn read_body(url: &Url, mut response: reqwest::Response, max_size: usize) -> Result<Vec<u8>, String> {
+    if response.content_length().is_some_and(|length| length > max_size as u64) {
+        return Err(format!("{} is larger than {} bytes", url, max_size));
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", url, e))?
+    {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > max_size {
+            return Err(format!("{} is larger than {} bytes", url, max_size));
+        }
+    }
+    Ok(bytes)
+}
+
+// Fetch `url` through the cache folder of `namespace`: a fresh cached copy is used as is,
+// an expired one is revalidated. Only responses whose content type `accept`s are
+// returned and stored.
+pub async fn fetch(
+    namespace: &str,
+    url: &Url,
+    max_size: usize,
+    accept: fn(&str) -> bool,
+) -> Result<CachedResponse, String> {
+    let dir = cache_dir(namespace)?;
+    let key = cache_key(url.as_str());
+    let cached = read_accepted_entry(&dir, &key, accept);
+    if let Some((entry, bytes)) = cached.as_ref().filter(|(entry, _)| is_fresh(entry, now_secs())) {
+        return Ok(CachedResponse {
+            path: Some(dir.join(&entry.body_file)),
+            entry: entry.clone(),
+            bytes: bytes.clone(),
+            from_cache: true,
+            stale: false,
+        });
+    }
+
+    let mut request = http::page_client()?.get(url.clone());
+    if let Some((entry, _)) = &cached {
+        request = request.headers(revalidation_headers(entry));
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return match cached {
+                Some((entry, bytes)) => {
+                    warn!("Serving cached copy of {}: {}", url, e);
+                    Ok(CachedResponse {
+                        path: Some(dir.join(&entry.body_file)),
+                        entry,
+                        bytes,
+                        from_cache: true,
+                        stale: true,
+                    })
+                }
+                None => Err(format!("Failed to fetch {}: {}", url, e)),
+            };
+        }
+    };
+
+    let cached_body_file = cached.as_ref().map(|(entry, _)| entry.body_file.clone());
+    let status = response.status();
+    if let (reqwest::StatusCode::NOT_MODIFIED, Some((mut entry, bytes))) = (status, cached) {
+        debug!("{} is not modified", url);
+        let now = now_secs();
+        entry.fetched_at = now;
+        entry.expires_at = match cache_policy(response.headers(), now) {
+            CachePolicy::Store { expires_at } => expires_at,
+            CachePolicy::NoStore => None,
+        };
+        if let Err(e) = write_entry(&dir, &key, &entry, None) {
+            warn!("Failed to update cache entry of {}: {}", url, e);
+        }
+        return Ok(CachedResponse {
+            path: Some(dir.join(&entry.body_file)),
+            entry,
+            bytes,
+            from_cache: true,
+            stale: false,
+        });
+    }
+    if !status.is_success() {
+        return Err(format!("{} returned {}", url, status));
+    }
+
+    let now = now_secs();
+    let entry = entry_from_headers(url, response.headers(), now);
+    if !accept(&entry.content_type) {
+        return Err(format!("{} has an unexpected content type ({})", url, entry.content_type));
+    }
+    let storable = cache_policy(response.headers(), now) != CachePolicy::NoStore;
+    let bytes = read_body(url, response, max_size).await?;
+    debug!("Fetched {} bytes of {}", bytes.len(), url);
+    // A body of another type, or one that may no longer be stored, is replaced
+    if let Some(old) = cached_body_file.filter(|old| !storable || *old != entry.body_file) {
+        let _ = fs::remove_file(dir.join(old));
+    }
+    let mut path = None;
+    if storable {
+        match write_entry(&dir, &key, &entry, Some(&bytes)) {
+            Ok(()) => path = Some(dir.join(&entry.body_file)),
+            Err(e) => warn!("Failed to cache {}: {}", url, e),
+        }
+    } else {
+        let _ = fs::remove_file(dir.join(format!("{}.json", key)));
+    }
+    Ok(CachedResponse {
+        path,
+        entry,
+        bytes,
+        from_cache: false,
+        stale: false,
+    })
+}
@@ -0,0 +1,424 @@
+//! # Hunspell Module
+//!
+//! This module reads Hunspell dictionaries (a `.aff` affix file and a `.dic` word list, as
+//! shipped by LibreOffice, Firefox and most Linux distributions) and checks words against
+//! them, without linking the C library.
+//!
+//! ## Supported Format
+//! - `SET`: Encoding of both files (UTF-8 or any legacy encoding, e.g. ISO8859-1)
+//! - `FLAG`: Single-character (default), `long`, `num` and `UTF-8` flags; `AF` aliases
+//! - `PFX` / `SFX`: Prefix and suffix rules with strip, add and condition, including the
+//!   cross product of a prefix and a suffix
+//! - `FORBIDDENWORD`, `NEEDAFFIX`, `NOSUGGEST`
+//! - `TRY` and `REP`: Characters and replacements used for suggestions
+//!
+//! Compounding, twofold suffixes and morphological fields are ignored: words formed that
+//! way are reported as misspelled.
+//!
+//! ## Checking
+//! A word is correct if it is in the word list, or if removing an affix (and restoring
+//! what the rule strips) yields a word that carries the rule's flag and satisfies its
+//! condition. Case follows Hunspell: a lowercase dictionary word also matches its
+//! capitalized and uppercase forms, a capitalized one (a name) only its uppercase form.
+//!
+//! ## Suggestions
+//! Candidates come from the `REP` table, a swap of two neighbouring characters, a
+//! replaced, removed or inserted character (from `TRY`), and splitting into two words.
+//! Only candidates that are correct themselves are returned.
+
+use std::collections::{HashMap, HashSet};
+
+// Flag as a number: a character code, two characters packed together, or a number
+type Flag = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlagType {
+    Char,
+    Long,
+    Num,
+}
+
+// One position of an affix condition
+#[derive(Debug, Clone)]
+enum ConditionPart {
+    Any,
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl ConditionPart {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionPart::Any => true,
+            ConditionPart::Set { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+// A prefix or suffix rule
+#[derive(Debug, Clone)]
+struct Affix {
+    flag: Flag,
+    cross_product: bool,
+    strip: String,
+    condition: Vec<ConditionPart>,
+}
+
+// A loaded dictionary
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    words: HashMap<String, Vec<Flag>>,
+    // Rules by the text they add
+    prefixes: HashMap<String, Vec<Affix>>,
+    suffixes: HashMap<String, Vec<Affix>>,
+    forbidden: Option<Flag>,
+    need_affix: Option<Flag>,
+    no_suggest: Option<Flag>,
+    try_chars: Vec<char>,
+    replacements: Vec<(String, String)>,
+}
+
+// Decode a dictionary file in the encoding its `SET` line names (UTF-8 if missing)
+fn decode(bytes: &[u8], encoding: &str) -> String {
+    match encoding_rs::Encoding::for_label(encoding.trim().as_bytes()) {
+        Some(encoding) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+        None => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+// Encoding declared by the `SET` line of an affix file
+fn declared_encoding(aff: &[u8]) -> String {
+    aff.split(|b| *b == b'\n')
+        .find_map(|line| {
+            let line = String::from_utf8_lossy(line);
+            let mut tokens = line.split_whitespace();
+            (tokens.next() == Some("SET")).then(|| tokens.next().unwrap_or("UTF-8").to_string())
+        })
+        .unwrap_or_else(|| "UTF-8".to_string())
+}
+
+fn parse_flags(text: &str, flag_type: FlagType, aliases: &[Vec<Flag>]) -> Vec<Flag> {
+    if !aliases.is_empty()
+        && let Ok(index) = text.parse::<usize>()
+    {
+        return aliases.get(index.wrapping_sub(1)).cloned().unwrap_or_default();
+    }
+    match flag_type {
+        FlagType::Char => text.chars().map(|c| c as Flag).collect(),
+        FlagType::Long => {
+            let chars: Vec<char> = text.chars().collect();
+            chars
+                .chunks(2)
+                .map(|pair| pair.iter().fold(0, |flag, c| (flag << 16) | *c as Flag))
+                .collect()
+        }
+        FlagType::Num => text.split(',').filter_map(|n| n.trim().parse().ok()).collect(),
+    }
+}
+
+fn parse_condition(text: &str) -> Vec<ConditionPart> {
+    if text == "." {
+        return Vec::new();
+    }
+    let mut parts = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => parts.push(ConditionPart::Any),
+            '[' => {
+                let mut set = Vec::new();
+                let mut negated = false;
+                for c in chars.by_ref() {
+                    match c {
+                        ']' => break,
+                        '^' if set.is_empty() && !negated => negated = true,
+                        c => set.push(c),
+                    }
+                }
+                parts.push(ConditionPart::Set { chars: set, negated });
+            }
+            c => parts.push(ConditionPart::Set {
+                chars: vec![c],
+                negated: false,
+            }),
+        }
+    }
+    parts
+}
+
+// "0" stands for the empty string in affix rules
+fn affix_text(text: &str) -> String {
+    if text == "0" { String::new() } else { text.to_string() }
+}
+
+impl Dictionary {
+    // Parse a dictionary from the decoded contents of its `.aff` and `.dic` files
+    pub fn parse(aff: &str, dic: &str) -> Result<Self, String> {
+        let mut dictionary = Dictionary::default();
+        let mut flag_type = FlagType::Char;
+        let mut aliases: Vec<Vec<Flag>> = Vec::new();
+        // Cross-product setting of the affix flags, from their header lines
+        let mut headers: HashMap<(bool, String), (bool, usize)> = HashMap::new();
+        let mut raw_flags: Vec<(&str, &str)> = Vec::new();
+
+        for line in aff.lines() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let Some(&keyword) = tokens.first() else {
+                continue;
+            };
+            match (keyword, tokens.len()) {
+                ("FLAG", 2..) => {
+                    flag_type = match tokens[1] {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Num,
+                        _ => FlagType::Char,
+                    }
+                }
+                ("AF", 2..) if tokens[1].parse::<usize>().is_err() => {
+                    aliases.push(parse_flags(tokens[1], flag_type, &[]));
+                }
+                ("FORBIDDENWORD" | "NEEDAFFIX" | "NOSUGGEST", 2..) => raw_flags.push((keyword, tokens[1])),
+                ("TRY", 2..) => dictionary.try_chars = tokens[1].chars().collect(),
+                ("REP", 3..) => dictionary
+                    .replacements
+                    .push((tokens[1].replace('_', " "), tokens[2].replace('_', " "))),
+                ("PFX" | "SFX", 4..) => {
+                    let is_prefix = keyword == "PFX";
+                    let key = (is_prefix, tokens[1].to_string());
+                    match headers.get_mut(&key) {
+                        Some((cross_product, remaining)) if *remaining > 0 => {
+                            *remaining -= 1;
+                            let (add, _continuation) = tokens[3].split_once('/').unwrap_or((tokens[3], ""));
+                            let Some(&flag) = parse_flags(tokens[1], flag_type, &[]).first() else {
+                                continue;
+                            };
+                            let affix = Affix {
+                                flag,
+                                cross_product: *cross_product,
+                                strip: affix_text(tokens[2]),
+                                condition: parse_condition(tokens.get(4).copied().unwrap_or(".")),
+                            };
+                            let rules = if is_prefix {
+                                &mut dictionary.prefixes
+                            } else {
+                                &mut dictionary.suffixes
+                            };
+                            rules.entry(affix_text(add)).or_default().push(affix);
+                        }
+                        _ => {
+                            let count = tokens[3].parse().map_err(|_| format!("Invalid affix header: {}", line))?;
+                            headers.insert(key, (tokens[2] == "Y", count));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (keyword, flag) in raw_flags {
+            let flag = parse_flags(flag, flag_type, &[]).first().copied();
+            match keyword {
+                "FORBIDDENWORD" => dictionary.forbidden = flag,
+                "NEEDAFFIX" => dictionary.need_affix = flag,
+                _ => dictionary.no_suggest = flag,
+            }
+        }
+
+        for (index, line) in dic.lines().enumerate() {
+            let entry = line.split(['\t', ' ']).next().unwrap_or("").trim();
+            // The first line holds the (approximate) number of words
+            if entry.is_empty() || (index == 0 && entry.chars().all(|c| c.is_ascii_digit())) {
+                continue;
+            }
+            let (word, flags) = match entry.rfind('/') {
+                Some(slash) if slash > 0 => (&entry[..slash], parse_flags(&entry[slash + 1..], flag_type, &aliases)),
+                _ => (entry, Vec::new()),
+            };
+            dictionary.words.entry(word.to_string()).or_default().extend(flags);
+        }
+        if dictionary.words.is_empty() {
+            return Err("The dictionary has no words".to_string());
+        }
+        Ok(dictionary)
+    }
+
+    // Load a dictionary from the raw contents of its `.aff` and `.dic` files
+    pub fn from_bytes(aff: &[u8], dic: &[u8]) -> Result<Self, String> {
+        let encoding = declared_encoding(aff);
+        Self::parse(&decode(aff, &encoding), &decode(dic, &encoding))
+    }
+
+    fn has_flag(flags: &[Flag], flag: Option<Flag>) -> bool {
+        flag.is_some_and(|flag| flags.contains(&flag))
+    }
+
+    // Whether `root` is a dictionary word carrying all of `required`
+    fn root_with_flags(&self, root: &str, required: &[Flag]) -> bool {
+        self.words.get(root).is_some_and(|flags| {
+            !Self::has_flag(flags, self.forbidden) && required.iter().all(|flag| flags.contains(flag))
+        })
+    }
+
+    // Prefix conditions apply to the start of the root, suffix conditions to its end
+    fn condition_matches(condition: &[ConditionPart], root: &str, at_end: bool) -> bool {
+        let chars: Vec<char> = root.chars().collect();
+        if chars.len() < condition.len() {
+            return false;
+        }
+        let offset = if at_end { chars.len() - condition.len() } else { 0 };
+        condition
+            .iter()
+            .zip(&chars[offset..])
+            .all(|(part, c)| part.matches(*c))
+    }
+
+    // Roots of `word` with one suffix removed, with the flag of the removed suffix
+    fn suffix_roots<'a>(&'a self, word: &'a str) -> impl Iterator<Item = (String, &'a Affix)> + 'a {
+        word.char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(word.len()))
+            .skip(1)
+            .flat_map(move |i| {
+                self.suffixes
+                    .get(&word[i..])
+                    .into_iter()
+                    .flatten()
+                    .map(move |affix| (format!("{}{}", &word[..i], affix.strip), affix))
+            })
+            .filter(|(root, affix)| Self::condition_matches(&affix.condition, root, true))
+    }
+
+    fn check_affixed(&self, word: &str) -> bool {
+        if self
+            .suffix_roots(word)
+            .any(|(root, affix)| self.root_with_flags(&root, &[affix.flag]))
+        {
+            return true;
+        }
+        for (i, _) in word.char_indices().skip(1) {
+            let Some(rules) = self.prefixes.get(&word[..i]) else {
+                continue;
+            };
+            for prefix in rules {
+                let root = format!("{}{}", prefix.strip, &word[i..]);
+                if !Self::condition_matches(&prefix.condition, &root, false) {
+                    continue;
+                }
+                if self.root_with_flags(&root, &[prefix.flag]) {
+                    return true;
+                }
+                if prefix.cross_product
+                    && self.suffix_roots(&root).any(|(stem, suffix)| {
+                        suffix.cross_product && self.root_with_flags(&stem, &[prefix.flag, suffix.flag])
+                    })
+                {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn check_exact(&self, word: &str) -> bool {
+        match self.words.get(word) {
+            Some(flags) if Self::has_flag(flags, self.forbidden) => false,
+            Some(flags) if !Self::has_flag(flags, self.need_affix) => true,
+            _ => self.check_affixed(word),
+        }
+    }
+
+    // Whether a word is spelled correctly
+    pub fn check(&self, word: &str) -> bool {
+        let word = word.replace('’', "'");
+        let word = word.trim_matches('\'');
+        if word.is_empty() {
+            return false;
+        }
+        if self.check_exact(word) {
+            return true;
+        }
+        let mut chars = word.chars();
+        let first = chars.next().unwrap_or_default();
+        let rest: String = chars.collect();
+        if first.is_uppercase() && rest.chars().all(|c| !c.is_uppercase()) {
+            // "Hello" at the start of a sentence
+            return self.check_exact(&format!("{}{}", first.to_lowercase(), rest));
+        }
+        if word.chars().any(char::is_lowercase) {
+            return false;
+        }
+        // "HELLO" and "PARIS"
+        let lower = word.to_lowercase();
+        let capitalized = capitalize(&lower);
+        self.check_exact(&lower) || self.check_exact(&capitalized)
+    }
+
+    fn suggestable(&self, word: &str) -> bool {
+        self.check(word)
+            && !self
+                .words
+                .get(word)
+                .is_some_and(|flags| Self::has_flag(flags, self.no_suggest))
+    }
+
+    // Corrections for a misspelled word, best first
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut candidates: Vec<String> = Vec::new();
+
+        for (from, to) in &self.replacements {
+            for (i, _) in word.match_indices(from.as_str()) {
+                candidates.push(format!("{}{}{}", &word[..i], to, &word[i + from.len()..]));
+            }
+        }
+        candidates.push(word.to_lowercase());
+        candidates.push(capitalize(word));
+        for i in 1..chars.len() {
+            let mut swapped = chars.clone();
+            swapped.swap(i - 1, i);
+            candidates.push(swapped.into_iter().collect());
+        }
+        for i in 0..chars.len() {
+            for &c in &self.try_chars {
+                if c != chars[i] {
+                    let mut replaced = chars.clone();
+                    replaced[i] = c;
+                    candidates.push(replaced.into_iter().collect());
+                }
+            }
+        }
+        for i in 0..chars.len() {
+            let mut removed = chars.clone();
+            removed.remove(i);
+            candidates.push(removed.into_iter().collect());
+        }
+        for i in 0..=chars.len() {
+            for &c in &self.try_chars {
+                let mut inserted = chars.clone();
+                inserted.insert(i, c);
+                candidates.push(inserted.into_iter().collect());
+            }
+        }
+        for i in 1..chars.len() {
+            let (first, second): (String, String) = (chars[..i].iter().collect(), chars[i..].iter().collect());
+            if self.suggestable(&first) && self.suggestable(&second) {
+                candidates.push(format!("{} {}", first, second));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate != word && seen.insert(candidate.clone()))
+            .filter(|candidate| candidate.contains(' ') || self.suggestable(candidate))
+            .take(limit)
+            .collect()
+    }
+}
+
+// "hello" → "Hello"
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,68 @@
+//! # Image Cache Module
+//!
+//! This module downloads the web images (`http`/`https`) a preview references and keeps
+//! them on disk, so previews render offline and do not download every image again on
+//! each render.
+//!
+//! ## Serving
+//! Images are kept in the `images` folder of the HTTP cache (see `http_cache`), named
+//! with the extension of their type. That folder is added to the asset protocol scope at
+//! startup, so the preview loads a cached image with `convertFileSrc(path)`.
+//!
+//! ## Freshness
+//! The image's cache headers decide when it is checked again (see `http_cache`). Without
+//! a connection, the last downloaded copy is used. Images the server marks `no-store`
+//! are not kept; the preview loads them from their URL instead.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use tracing::{debug, warn};
+
+use crate::http_cache;
+use crate::link_metadata;
+
+const CACHE_NAMESPACE: &str = "images";
+const MAX_IMAGE_SIZE: usize = 20 * 1024 * 1024;
+
+// Result of `cache_remote_image`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedImage {
+    pub url: String,
+    // The cached file; None when the image may not be stored
+    pub path: Option<String>,
+    // The server could not be reached, so the image may be outdated
+    pub stale: bool,
+}
+
+// Allow the cache folder on the asset protocol scope
+pub fn init_image_cache(app_handle: &AppHandle) {
+    let dir = match http_cache::cache_dir(CACHE_NAMESPACE) {
+        Ok(dir) => dir,
+        Err(e) => {
+            warn!("Image cache is not available: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = app_handle.asset_protocol_scope().allow_directory(&dir, true) {
+        warn!("Failed to allow image cache on the asset protocol: {}", e);
+    }
+}
+
+// Tauri command: Download a web image of the preview into the cache (or revalidate the
+// cached copy). Returns the file to load it from.
+#[tauri::command]
+pub async fn cache_remote_image(url: String) -> Result<CachedImage, String> {
+    let parsed = link_metadata::parse_web_url(&url)?;
+    // Anything but an image must not end up in the folder served on the asset protocol
+    let response = http_cache::fetch(CACHE_NAMESPACE, &parsed, MAX_IMAGE_SIZE, |content_type| {
+        content_type.starts_with("image/")
+    })
+    .await?;
+    debug!("Image {} from cache: {}", parsed, response.from_cache);
+    Ok(CachedImage {
+        url: parsed.to_string(),
+        path: response.path.map(|path| path.to_string_lossy().to_string()),
+        stale: response.stale,
+    })
+}
@@ -0,0 +1,372 @@
+//! # Image Export Module
+//!
+//! This module saves the rendered document, or one section of it, as a PNG or JPEG image,
+//! for sharing a formatted snippet in chat apps that do not render Markdown.
+//!
+//! ## Rendering
+//! The frontend builds the same self-contained HTML as for the PDF export. It is loaded
+//! into an off-screen webview (`pdf_export::render_offscreen`) laid out at the requested
+//! width, and the whole document is captured with the snapshot API of the OS webview:
+//! - macOS: `WKWebView.takeSnapshot`, after growing the view to the document height
+//! - Windows: the DevTools `Page.captureScreenshot` method of WebView2, beyond the viewport
+//! - Linux: `webkit_web_view_get_snapshot` of the full document
+//!
+//! ## Sections
+//! With `section` set, only that heading and what follows it up to the next heading of the
+//! same or a higher level is kept. The section is matched like an include section: by the
+//! GitHub-style anchor of the heading, so the heading text or its anchor can be given. The
+//! anchors of the headings in the HTML are computed here with `markdown::unique_anchor`
+//! (an `id` attribute is used as is), and the script run in the webview only receives the
+//! position of the matching heading. If no heading matches, the whole document is
+//! exported.
+
+use image::RgbaImage;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::async_runtime::Sender;
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::clipboard_image::{encode_rgba, ImageFormat};
+use crate::file_operations::check_writable;
+use crate::link_metadata::{attributes, decode_entities};
+use crate::markdown;
+use crate::path_scope;
+use crate::pdf_export::render_offscreen;
+
+lazy_static! {
+    static ref HEADING: Regex = Regex::new(r"(?is)<h[1-6](\s[^>]*)?>(.*?)</h[1-6]\s*>").unwrap();
+    static ref TAG: Regex = Regex::new(r"(?s)<[^>]*>").unwrap();
+}
+
+// Label of the transient window the document is rendered in
+const EXPORT_WINDOW_LABEL: &str = "bokuchi-image-export";
+
+// Height of the render window before the document is measured
+const INITIAL_HEIGHT: f64 = 600.0;
+
+// Options of an image export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImageExportOptions {
+    pub format: ImageFormat,
+    // Layout width of the document in CSS pixels
+    pub width: f64,
+    // Heading text or anchor of the section to export (the whole document if unset)
+    pub section: Option<String>,
+}
+
+impl Default for ImageExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ImageFormat::Png,
+            width: 800.0,
+            section: None,
+        }
+    }
+}
+
+// Position of the heading whose anchor is the slug of `section`, among the headings of
+// `html` in document order (as `querySelectorAll` finds them)
+pub fn section_heading_index(html: &str, section: &str) -> Option<usize> {
+    let wanted = markdown::slugify_heading(section);
+    let body = html.to_ascii_lowercase().find("<body").map_or(html, |start| &html[start..]);
+    let mut occurrences = HashMap::new();
+    HEADING.captures_iter(body).position(|caps| {
+        let id = caps
+            .get(1)
+            .and_then(|attrs| attributes(attrs.as_str()).remove("id"))
+            .map(|id| decode_entities(&id));
+        let anchor = match id {
+            Some(id) => {
+                occurrences.entry(id.clone()).or_insert(0);
+                id
+            }
+            None => {
+                let text = decode_entities(&TAG.replace_all(&caps[2], ""));
+                markdown::unique_anchor(markdown::slugify_heading(&text), &mut occurrences)
+            }
+        };
+        anchor == wanted
+    })
+}
+
+// Script removing everything around the section starting at the heading at `index`: the
+// siblings of the heading before it and after the end of the section
+fn section_script(index: usize) -> String {
+    format!(
+        r#"<script>
+(function () {{
+  var level = function (element) {{
+    return /^H[1-6]$/.test(element.tagName) ? Number(element.tagName[1]) : 0;
+  }};
+  var start = document.querySelectorAll('h1, h2, h3, h4, h5, h6')[{index}];
+  if (!start) return;
+  var keep = [start];
+  for (var next = start.nextElementSibling; next; next = next.nextElementSibling) {{
+    if (level(next) && level(next) <= level(start)) break;
+    keep.push(next);
+  }}
+  Array.prototype.slice.call(start.parentElement.children).forEach(function (child) {{
+    if (keep.indexOf(child) < 0 && child.tagName !== 'STYLE' && child.tagName !== 'SCRIPT') child.remove();
+  }});
+}})();
+</script>"#
+    )
+}
+
+// `html` with only the section under the heading `section` left when it is displayed
+// (unchanged if no heading matches)
+pub fn isolate_section(html: &str, section: &str) -> String {
+    let Some(index) = section_heading_index(html, section) else {
+        return html.to_string();
+    };
+    let script = section_script(index);
+    match html.to_ascii_lowercase().rfind("</body>") {
+        Some(end) => format!("{}{}{}", &html[..end], script, &html[end..]),
+        None => format!("{}{}", html, script),
+    }
+}
+
+// Tauri command: Render `html` (a self-contained document, as for the PDF export) and save
+// it as an image at `output_path`
+#[tauri::command]
+pub async fn export_image(
+    app: AppHandle,
+    html: String,
+    output_path: String,
+    options: ImageExportOptions,
+) -> Result<(), String> {
+    path_scope::check_path(&output_path)?;
+    let html = match &options.section {
+        Some(section) => isolate_section(&html, section),
+        None => html,
+    };
+    let width = options.width.round().max(200.0);
+    let image = render_offscreen(
+        &app,
+        EXPORT_WINDOW_LABEL,
+        "Image Export",
+        html,
+        (width, INITIAL_HEIGHT),
+        snapshot_platform_webview,
+    )
+    .await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let encoded = encode_rgba(image.as_raw(), image.width(), image.height(), options.format)?;
+        check_writable(Path::new(&output_path), encoded.len() as u64)?;
+        std::fs::write(&output_path, encoded).map_err(|e| format!("Failed to write image: {}", e))?;
+        info!("Exported {}x{} image to {}", image.width(), image.height(), output_path);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Failed to export image: {}", e))?
+}
+
+// Decode the PNG a native snapshot API produced
+#[cfg(any(target_os = "macos", windows))]
+fn decode_png(png: &[u8]) -> Result<RgbaImage, String> {
+    image::load_from_memory_with_format(png, image::ImageFormat::Png)
+        .map(|image| image.to_rgba8())
+        .map_err(|e| format!("Failed to decode snapshot: {}", e))
+}
+
+// macOS: measure the document, grow the WKWebView to its height and snapshot it. The
+// snapshot is taken at the backing scale of the screen (2x on Retina displays).
+#[cfg(target_os = "macos")]
+fn snapshot_platform_webview(platform_webview: tauri::webview::PlatformWebview, done: Sender<Result<RgbaImage, String>>) {
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::MainThreadMarker;
+    use objc2_app_kit::{NSBitmapImageFileType, NSBitmapImageRep, NSImage, NSView};
+    use objc2_foundation::{NSDictionary, NSError, NSNumber, NSPoint, NSRect, NSSize, NSString};
+    use objc2_web_kit::{WKSnapshotConfiguration, WKWebView};
+
+    let wk_ptr = platform_webview.inner() as *mut WKWebView;
+    if wk_ptr.is_null() {
+        let _ = done.try_send(Err("WKWebView handle was null".to_string()));
+        return;
+    }
+    // `with_webview` runs this on the main thread
+    let Some(mtm) = MainThreadMarker::new() else {
+        let _ = done.try_send(Err("snapshot must run on the main thread".to_string()));
+        return;
+    };
+    // Safety: the pointer comes from the live webview of the export window
+    let webview: Retained<WKWebView> = match unsafe { Retained::retain(wk_ptr) } {
+        Some(webview) => webview,
+        None => {
+            let _ = done.try_send(Err("WKWebView handle was null".to_string()));
+            return;
+        }
+    };
+
+    let measured = RcBlock::new(move |result: *mut AnyObject, error: *mut NSError| {
+        let height = if error.is_null() && !result.is_null() {
+            // Safety: the script returns a number
+            unsafe { &*(result as *const NSNumber) }.doubleValue()
+        } else {
+            0.0
+        };
+        let view: &NSView = &webview;
+        let width = view.frame().size.width;
+        let height = height.max(1.0);
+        view.setFrameSize(NSSize::new(width, height));
+
+        let configuration = unsafe { WKSnapshotConfiguration::new(mtm) };
+        unsafe { configuration.setRect(NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(width, height))) };
+        let done = done.clone();
+        let captured = RcBlock::new(move |image: *mut NSImage, error: *mut NSError| {
+            let result = if image.is_null() || !error.is_null() {
+                Err("WKWebView snapshot failed".to_string())
+            } else {
+                // Safety: a non-null image handed to the completion handler
+                let image = unsafe { &*image };
+                image
+                    .TIFFRepresentation()
+                    .and_then(|tiff| NSBitmapImageRep::imageRepWithData(&tiff))
+                    .and_then(|bitmap| unsafe {
+                        bitmap.representationUsingType_properties(NSBitmapImageFileType::PNG, &NSDictionary::new())
+                    })
+                    .ok_or_else(|| "failed to convert snapshot to PNG".to_string())
+                    .and_then(|png| decode_png(&png.to_vec()))
+            };
+            let _ = done.try_send(result);
+        });
+        unsafe { webview.takeSnapshotWithConfiguration_completionHandler(Some(&configuration), &captured) };
+    });
+
+    let script = NSString::from_str("document.documentElement.scrollHeight");
+    // Safety: the webview pointer was checked above
+    unsafe { (*wk_ptr).evaluateJavaScript_completionHandler(&script, Some(&measured)) };
+}
+
+// Windows: capture the full document through the DevTools protocol, which WebView2 exposes
+// without opening the DevTools. The content size comes from `Page.getLayoutMetrics`.
+#[cfg(windows)]
+fn snapshot_platform_webview(platform_webview: tauri::webview::PlatformWebview, done: Sender<Result<RgbaImage, String>>) {
+    use base64::Engine;
+    use webview2_com::CallDevToolsProtocolMethodCompletedHandler;
+    use windows::core::{HSTRING, PCWSTR};
+
+    let result = (|| -> Result<(), String> {
+        let controller = platform_webview.controller();
+        let core = unsafe { controller.CoreWebView2() }.map_err(|e| format!("CoreWebView2: {e}"))?;
+
+        let capture_core = core.clone();
+        let done_metrics = done.clone();
+        let metrics_handler = CallDevToolsProtocolMethodCompletedHandler::create(Box::new(move |hr, json| {
+            let capture = || -> Result<(), String> {
+                hr.map_err(|e| format!("Page.getLayoutMetrics: {e}"))?;
+                let metrics: serde_json::Value =
+                    serde_json::from_str(&json).map_err(|e| format!("Page.getLayoutMetrics result: {e}"))?;
+                let size = &metrics["cssContentSize"];
+                let params = serde_json::json!({
+                    "format": "png",
+                    "captureBeyondViewport": true,
+                    "clip": {
+                        "x": 0,
+                        "y": 0,
+                        "width": size["width"].as_f64().unwrap_or(0.0).ceil(),
+                        "height": size["height"].as_f64().unwrap_or(0.0).ceil(),
+                        "scale": 1,
+                    },
+                })
+                .to_string();
+
+                let done_capture = done_metrics.clone();
+                let capture_handler = CallDevToolsProtocolMethodCompletedHandler::create(Box::new(move |hr, json| {
+                    let result = hr
+                        .map_err(|e| format!("Page.captureScreenshot: {e}"))
+                        .and_then(|_| {
+                            serde_json::from_str::<serde_json::Value>(&json)
+                                .map_err(|e| format!("Page.captureScreenshot result: {e}"))
+                        })
+                        .and_then(|screenshot| {
+                            base64::engine::general_purpose::STANDARD
+                                .decode(screenshot["data"].as_str().unwrap_or_default())
+                                .map_err(|e| format!("Page.captureScreenshot data: {e}"))
+                        })
+                        .and_then(|png| decode_png(&png));
+                    let _ = done_capture.try_send(result);
+                    Ok(())
+                }));
+                let method = HSTRING::from("Page.captureScreenshot");
+                let params = HSTRING::from(params);
+                unsafe {
+                    capture_core
+                        .CallDevToolsProtocolMethod(PCWSTR(method.as_ptr()), PCWSTR(params.as_ptr()), &capture_handler)
+                        .map_err(|e| format!("Page.captureScreenshot: {e}"))
+                }
+            };
+            if let Err(e) = capture() {
+                let _ = done_metrics.try_send(Err(e));
+            }
+            Ok(())
+        }));
+
+        let method = HSTRING::from("Page.getLayoutMetrics");
+        let params = HSTRING::from("{}");
+        unsafe {
+            core.CallDevToolsProtocolMethod(PCWSTR(method.as_ptr()), PCWSTR(params.as_ptr()), &metrics_handler)
+                .map_err(|e| format!("Page.getLayoutMetrics: {e}"))?;
+        }
+        Ok(())
+    })();
+
+    // On the success path the completion handlers send the final result; only forward
+    // early failures here.
+    if let Err(e) = result {
+        let _ = done.try_send(Err(e));
+    }
+}
+
+// Linux: snapshot the full document with WebKitGTK. Cairo hands back premultiplied
+// native-endian ARGB, which is converted to straight RGBA.
+#[cfg(target_os = "linux")]
+fn snapshot_platform_webview(platform_webview: tauri::webview::PlatformWebview, done: Sender<Result<RgbaImage, String>>) {
+    use gtk::cairo::{Format, ImageSurface};
+    use webkit2gtk::{SnapshotOptions, SnapshotRegion, WebViewExt};
+
+    let webview = platform_webview.inner();
+    webview.snapshot(
+        SnapshotRegion::FullDocument,
+        SnapshotOptions::NONE,
+        None::<&gtk::gio::Cancellable>,
+        move |result| {
+            let result = result
+                .map_err(|e| format!("WebKitGTK snapshot failed: {e}"))
+                .and_then(|surface| {
+                    ImageSurface::try_from(surface).map_err(|_| "snapshot is not an image surface".to_string())
+                })
+                .and_then(|surface| {
+                    if surface.format() != Format::ARgb32 && surface.format() != Format::Rgb24 {
+                        return Err(format!("unsupported snapshot format {:?}", surface.format()));
+                    }
+                    let (width, height, stride) = (surface.width() as u32, surface.height() as u32, surface.stride() as usize);
+                    let opaque = surface.format() == Format::Rgb24;
+                    let mut image = RgbaImage::new(width, height);
+                    surface
+                        .with_data(|data| {
+                            for (x, y, pixel) in image.enumerate_pixels_mut() {
+                                let offset = y as usize * stride + x as usize * 4;
+                                let argb = u32::from_ne_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+                                let alpha = if opaque { 255 } else { (argb >> 24) as u8 };
+                                let straight = |shift: u32| {
+                                    let channel = (argb >> shift) as u8 as u32;
+                                    if alpha == 0 { 0 } else { (channel * 255 / alpha as u32).min(255) as u8 }
+                                };
+                                *pixel = image::Rgba([straight(16), straight(8), straight(0), alpha]);
+                            }
+                        })
+                        .map_err(|e| format!("failed to read snapshot: {e}"))?;
+                    Ok(image)
+                });
+            let _ = done.try_send(result);
+        },
+    );
+}
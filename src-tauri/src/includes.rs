@@ -0,0 +1,224 @@
+//! # Includes Module
+//!
+//! This module resolves the `<!-- @include: other.md -->` lines of a document, replacing
+//! them with the content of the named file before its variables are expanded.
+//!
+//! ## Sections
+//! `<!-- @include: other.md#Heading Name -->` includes only one section: the heading and
+//! everything below it up to the next heading of the same or a higher level. The part
+//! after `#` is matched against the heading anchors of the outline (`document_structure`),
+//! so the heading text as well as its anchor (`#heading-name`, `#intro-1`) can be used.
+//!
+//! ## Resolution
+//! - Paths are relative to the including file; included files can include others
+//! - Only files inside the opened folders and files (`path_scope`) are included
+//! - Include lines inside code blocks are left alone
+//! - The frontmatter of included files is dropped
+//! - An include that cannot be resolved (missing file or section, a cycle, more than
+//!   `MAX_INCLUDE_DEPTH` levels) is kept as it is, so it is hidden by the variable
+//!   expansion like any other include line
+//! - A document resolves at most `MAX_INCLUDES` includes and reads at most
+//!   `MAX_INCLUDED_BYTES` of included files, so a file included many times at every level
+//!   cannot make the expansion grow exponentially; includes beyond that are kept as well
+//! - Line endings (including the last one) are kept
+//!
+//! ## Include Tree
+//! `get_include_tree` reports what a document is built from: every include line with the
+//! file it resolved to, its depth, the size of the text it contributed and, for includes
+//! that were not resolved, why.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+use crate::document_structure::{parse_structure, BlockKind};
+use crate::frontmatter;
+use crate::markdown::{self, lines_outside_code};
+use crate::path_scope;
+
+lazy_static! {
+    static ref INCLUDE_LINE: Regex = Regex::new(r"^\s*<!-- @include:\s*(.+?)\s*-->\s*$").unwrap();
+}
+
+// Levels of includes inside included files
+pub const MAX_INCLUDE_DEPTH: usize = 8;
+// Includes resolved for one document, at all levels
+pub const MAX_INCLUDES: usize = 1000;
+// Bytes of included files read for one document
+pub const MAX_INCLUDED_BYTES: u64 = 10 * 1024 * 1024;
+
+// What is left of the limits while resolving one document
+struct IncludeBudget {
+    includes: usize,
+    bytes: u64,
+}
+
+// An include line of a document and what it included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncludeNode {
+    // Target as written ("other.md#Heading Name")
+    pub target: String,
+    // Line of the include in the including file (1-based)
+    pub line: usize,
+    // 1 for includes of the document itself
+    pub depth: usize,
+    // File the target points to (canonical when it exists)
+    pub path: String,
+    pub section: Option<String>,
+    // Bytes of included text, nested includes resolved
+    pub size: usize,
+    // Why the include was not resolved (missing file or section, cycle, depth, scope)
+    pub error: Option<String>,
+    pub includes: Vec<IncludeNode>,
+}
+
+// File and heading of an include target ("other.md#Heading Name")
+pub fn parse_include_target(target: &str) -> (&str, Option<&str>) {
+    match target.split_once('#') {
+        Some((file, heading)) => (file.trim(), Some(heading.trim()).filter(|h| !h.is_empty())),
+        None => (target.trim(), None),
+    }
+}
+
+// The section of `content` under the heading whose anchor matches `heading`, up to the
+// next heading of the same or a higher level
+pub fn extract_section(content: &str, heading: &str) -> Option<String> {
+    let wanted = markdown::slugify_heading(heading);
+    let headings: Vec<(usize, u8, String)> = parse_structure(content)
+        .into_iter()
+        .filter_map(|block| match block.kind {
+            BlockKind::Heading { level, anchor, .. } => Some((block.start_line, level, anchor)),
+            _ => None,
+        })
+        .collect();
+    let position = headings.iter().position(|(_, _, anchor)| *anchor == wanted)?;
+    let (start_line, level, _) = &headings[position];
+    let lines: Vec<&str> = content.lines().collect();
+    let end = headings[position + 1..]
+        .iter()
+        .find(|(_, next_level, _)| next_level <= level)
+        .map_or(lines.len(), |(line, _, _)| line - 1);
+    Some(lines[start_line - 1..end].join("\n").trim_end().to_string())
+}
+
+// Replace the include lines of `content`, the content of the file at `path`, with what
+// they include
+pub fn resolve_includes(content: &str, path: &Path) -> String {
+    resolve_includes_with_tree(content, path).0
+}
+
+// `resolve_includes`, with the include tree of the document
+pub fn resolve_includes_with_tree(content: &str, path: &Path) -> (String, Vec<IncludeNode>) {
+    let mut nodes = Vec::new();
+    if !content.contains("<!-- @include:") {
+        return (content.to_string(), nodes);
+    }
+    let mut stack = vec![fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())];
+    let mut budget = IncludeBudget {
+        includes: MAX_INCLUDES,
+        bytes: MAX_INCLUDED_BYTES,
+    };
+    let resolved = resolve(content, path, &mut stack, &mut budget, &mut nodes);
+    (resolved, nodes)
+}
+
+// `stack` holds the files being included, to detect cycles; the includes of `content` are
+// added to `nodes`
+fn resolve(
+    content: &str,
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    budget: &mut IncludeBudget,
+    nodes: &mut Vec<IncludeNode>,
+) -> String {
+    let prose: HashSet<usize> = lines_outside_code(content).into_iter().map(|(index, _)| index).collect();
+    let dir = path.parent().unwrap_or(Path::new(""));
+    content
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(index, raw_line)| {
+            let line = raw_line.trim_end_matches(['\r', '\n']);
+            let ending = &raw_line[line.len()..];
+            let Some(caps) = INCLUDE_LINE.captures(line).filter(|_| prose.contains(&index)) else {
+                return raw_line.to_string();
+            };
+            let (file, section) = parse_include_target(&caps[1]);
+            let mut node = IncludeNode {
+                target: caps[1].to_string(),
+                line: index + 1,
+                depth: stack.len(),
+                path: dir.join(file).to_string_lossy().into_owned(),
+                section: section.map(str::to_string),
+                size: 0,
+                error: None,
+                includes: Vec::new(),
+            };
+            let text = match include(&mut node, dir, stack, budget) {
+                Ok(text) => {
+                    node.size = text.len();
+                    text
+                }
+                Err(e) => {
+                    warn!("Not including {} in {:?}: {}", &caps[1], path, e);
+                    node.error = Some(e);
+                    line.to_string()
+                }
+            };
+            nodes.push(node);
+            text + ending
+        })
+        .collect()
+}
+
+// Resolve the include of `node`, filling in its file and nested includes
+fn include(
+    node: &mut IncludeNode,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+    budget: &mut IncludeBudget,
+) -> Result<String, String> {
+    if stack.len() > MAX_INCLUDE_DEPTH {
+        return Err("too many nested includes".to_string());
+    }
+    if budget.includes == 0 {
+        return Err("too many includes".to_string());
+    }
+    budget.includes -= 1;
+    let target = node.target.clone();
+    let (file, heading) = parse_include_target(&target);
+    let path = fs::canonicalize(dir.join(file)).map_err(|e| format!("{}: {}", file, e))?;
+    node.path = path.to_string_lossy().into_owned();
+    path_scope::check_path(&path.to_string_lossy())?;
+    if stack.contains(&path) {
+        return Err("circular include".to_string());
+    }
+    let size = fs::metadata(&path).map_err(|e| format!("{}: {}", file, e))?.len();
+    if size > budget.bytes {
+        return Err("included files too large".to_string());
+    }
+    budget.bytes -= size;
+    let content = fs::read_to_string(&path).map_err(|e| format!("{}: {}", file, e))?;
+    let body = frontmatter::split_frontmatter(&content).body;
+    let included = match heading {
+        Some(heading) => extract_section(body, heading).ok_or_else(|| format!("no section {}", heading))?,
+        None => body.trim_end().to_string(),
+    };
+
+    stack.push(path.clone());
+    let resolved = resolve(&included, &path, stack, budget, &mut node.includes);
+    stack.pop();
+    Ok(resolved)
+}
+
+// Tauri command: Get the include tree of a document (the file at `path`, with the unsaved
+// `content`)
+#[tauri::command]
+pub fn get_include_tree(content: String, path: String) -> Result<Vec<IncludeNode>, String> {
+    path_scope::check_path(&path)?;
+    Ok(resolve_includes_with_tree(&content, Path::new(&path)).1)
+}
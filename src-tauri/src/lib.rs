@@ -9,12 +9,99 @@
 //! - `file_operations`: File-related utility functions
 //! - `file_association`: File association handling (macOS)
 //! - `commands`: Tauri commands for frontend communication
+//! - `storage`: JSON persistence in the app data directory
+//! - `settings`: Backend settings (e.g. keep running in the tray)
+//! - `menu`: Routing of native menu clicks to frontend events
+//! - `tray`: System tray icon with quick actions
+//! - `hotkey`: Global summon shortcut, quick capture and journal entries
+//! - `recent_files`: Most-recently-used file list backing the Open Recent menu
+//! - `window_close`: Close handling (hide to tray, confirm unsaved changes)
+//! - `session`: Backend copy of the editing session (open tabs, workspace)
+//! - `autosave`: Crash-recovery snapshots of unsaved tabs
+//! - `shutdown`: Flushing backend state before exit
+//! - `updater`: Background update check, manual release check and in-app installation
+//! - `crash`: Panic hook writing crash reports
+//! - `logging`: Structured logging to stdout and a rotating log file
+//! - `context_menu`: Native right-click menu for the editor
+//! - `locale`: Translations for menus and other backend strings
+//! - `document_window`: Per-document editor windows and macOS native tabs
+//! - `preview_window`: Chrome-less always-on-top preview window of a document
+//! - `workspace_state`: Window geometry, sidebar and zoom remembered per workspace
+//! - `theme`: OS light/dark appearance and change events
+//! - `workspace`: Listing the documents of a workspace folder
+//! - `search`: Full-text search across a workspace
+//! - `search_index`: Persistent search index kept up to date by a file watcher
+//! - `replace`: Find-and-replace across a workspace with preview and backups
+//! - `quick_open`: Fuzzy file switcher ranked by match quality and recency
+//! - `frontmatter`: YAML frontmatter of Markdown documents
+//! - `tags`: Tag index from frontmatter and inline `#tags`
+//! - `markdown`: Line-based helpers for scanning Markdown source
+//! - `links`: Link index between documents (backlinks)
+//! - `wikilinks`: `[[Note Title]]` resolution, note creation and export
+//! - `git`: Git status, diffs, commits, history and blame of documents
+//! - `diff`: Diff of the editor content against the file on disk, and document comparison
+//! - `http`: Shared HTTP client
+//! - `credentials`: Secrets and integration tokens kept in the OS keychain
+//! - `remote`: Remote files (endpoints, list/read/save with conflict detection)
+//! - `webdav`: WebDAV backend of the remote files
+//! - `sftp`: SFTP/SSH backend of the remote files
+//! - `s3`: Client for S3-compatible object stores
+//! - `sync`: Opt-in workspace sync to an S3-compatible bucket
+//! - `error`: Typed backend errors
+//! - `path_scope`: Folders and files the file commands may access
+//! - `encryption`: Password-protected documents (`.md.enc`)
+//! - `markdown_cache`: LRU cache of Markdown variable expansion
+//! - `worker_pool`: Shared thread pool for workspace-wide operations
+//! - `tasks`: Registry of long-running tasks with progress and cancellation
+//! - `hunspell`: Reader and checker for Hunspell dictionaries
+//! - `spellcheck`: Spell checking of documents and the user dictionary
+//! - `grammar`: Grammar checking with a local LanguageTool server
+//! - `clipboard_image`: Saving clipboard images next to documents
+//! - `screenshot`: Screenshot capture into a document's assets folder
+//! - `app_data_bundle`: Export and import of settings and user data as one archive
+//! - `plugins`: External content processors run at defined processing stages
+//! - `external_tools`: User-defined commands run on the current file
+//! - `pandoc`: Import and export through an installed pandoc, including Word and ODT import
+//! - `templates`: Template gallery with variable prompts
+//! - `snippets`: User snippets with tab stops and variable expansion
+//! - `writing_stats`: Per-day history of words written in each document
+//! - `document_structure`: Block structure of a document (headings, lists, tables, ...)
+//! - `heading_numbering`: Hierarchical numbering of headings
+//! - `link_metadata`: Titles and previews of linked web pages
+//! - `todos`: Workspace-wide task list and checkbox toggling
+//! - `zettel`: Zettelkasten note IDs and new notes named with them
+//! - `confluence`: Publishing documents to Confluence
+//! - `site_export`: Export of posts to Hugo and Jekyll sites
+//! - `http_cache`: On-disk cache of downloaded files with revalidation
+//! - `web_documents`: Read-only documents opened from a URL
+//! - `image_cache`: Offline cache of web images shown in the preview
+//! - `document_merge`: Merging of several documents into one (book build)
+//! - `file_watch`: Change events for open and subscribed files, reloading clean tabs
+//! - `bookmarks`: Bookmarked lines of documents
+//! - `file_positions`: Last cursor and scroll position of each document
+//! - `favorites`: Pinned files for the sidebar and the tray menu
+//! - `notifications`: OS notifications for background events, focusing their tab
+//! - `clipboard_text`: Copying the processed text of a document to the clipboard
+//! - `recovery`: Listing and restoring autosave snapshots and find-and-replace backups
+//! - `diagnostics`: Environment info for the About dialog and the diagnostics archive for bug reports
+//! - `workspace_variables`: Variables stored in a workspace (`.bokuchi/variables.yaml`)
+//! - `variable_history`: Previous values of global and workspace variables
+//! - `includes`: `<!-- @include -->` of other documents and of their sections
+//! - `markdown_dialect`: Markdown extensions recognized by the backend's parser
+//! - `typography`: Smart quotes, dashes and ellipses in the preview and exports
+//! - `html_sanitizer`: Allowlist cleaning of HTML rendered from Markdown
+//! - `link_check`: Broken internal links and fixes for them
+//! - `link_graph`: Documents and links of a workspace for the graph view
+//! - `image_export`: PNG/JPEG export of the rendered document or one of its sections
+//! - `graphics_fallback`: WebKitGTK graphics fallback (Linux)
 //!
 //! ## Features
 //! - **Markdown Editing**: Full-featured Markdown editor with live preview
 //! - **Variable Substitution**: Dynamic content with `{{variable}}` syntax
 //! - **File Association**: Open files by double-clicking in Finder (macOS)
 //! - **Multi-tab Interface**: Edit multiple files simultaneously
+//! - **System Tray**: Quick actions and optionally keep running when the window is closed
+//! - **Global Hotkey**: Summon the window or a quick capture note from anywhere
 //! - **Cross-platform**: Built with Tauri for native performance
 //!
 //! ## Application Lifecycle
@@ -23,9 +110,10 @@
 //! 3. Custom menu is set up with application-specific items
 //! 4. Event handlers are registered for menu actions and file associations
 //! 5. Application runs with event loop handling user interactions
+//! 6. Backend state is flushed to disk when the app exits
 
-use tauri::menu::{Menu, MenuItem, MenuItemKind};
-use tauri::{Emitter, Manager, RunEvent};
+use tauri::RunEvent;
+use tracing::{debug, info, warn};
 
 // Module declarations
 mod types;
@@ -34,6 +122,93 @@ mod file_operations;
 mod file_association;
 mod commands;
 mod pdf_export;
+mod storage;
+mod settings;
+mod menu;
+mod tray;
+mod hotkey;
+mod recent_files;
+mod window_close;
+mod session;
+mod autosave;
+mod shutdown;
+mod updater;
+mod crash;
+mod logging;
+mod context_menu;
+mod locale;
+mod document_window;
+mod preview_window;
+mod workspace_state;
+mod theme;
+mod workspace;
+mod search;
+mod search_index;
+mod replace;
+mod quick_open;
+mod frontmatter;
+mod tags;
+mod markdown;
+mod links;
+mod wikilinks;
+mod git;
+mod diff;
+mod http;
+mod credentials;
+mod remote;
+mod webdav;
+mod sftp;
+mod s3;
+mod sync;
+mod error;
+mod path_scope;
+mod encryption;
+mod markdown_cache;
+mod worker_pool;
+mod tasks;
+mod hunspell;
+mod spellcheck;
+mod grammar;
+mod clipboard_image;
+mod screenshot;
+mod app_data_bundle;
+mod plugins;
+mod approvals;
+mod external_tools;
+mod pandoc;
+mod templates;
+mod snippets;
+mod writing_stats;
+mod document_structure;
+mod heading_numbering;
+mod link_metadata;
+mod todos;
+mod zettel;
+mod confluence;
+mod site_export;
+mod http_cache;
+mod web_documents;
+mod image_cache;
+mod document_merge;
+mod file_watch;
+mod bookmarks;
+mod file_positions;
+mod favorites;
+mod notifications;
+mod clipboard_text;
+mod recovery;
+mod diagnostics;
+mod workspace_variables;
+mod variable_history;
+mod includes;
+mod markdown_dialect;
+mod typography;
+mod html_sanitizer;
+mod link_check;
+mod link_graph;
+mod image_export;
+#[cfg(target_os = "linux")]
+mod graphics_fallback;
 
 // Re-export types
 pub use types::*;
@@ -45,54 +220,38 @@ pub use file_operations::*;
 pub use file_association::*;
 // Re-export commands
 pub use commands::*;
-
-/// Work around corrupted rendering on Raspberry Pi.
-///
-/// WebKitGTK >= 2.42 enables a DMABUF-based GPU renderer by default. On the
-/// Raspberry Pi (VideoCore / V3D driver) this produces garbled, scanline-like
-/// noise instead of the UI. The failure is silent — rendering "succeeds" and
-/// only the result is broken — so it cannot be detected after the fact. We
-/// instead detect the known-bad environment (a Pi) up front and disable the
-/// DMABUF renderer before WebKitGTK is initialized.
-///
-/// Must run before any webview is created (i.e. before `tauri::Builder`).
-#[cfg(target_os = "linux")]
-fn workaround_webkit_dmabuf() {
-    // Respect an explicit user setting (escape hatch to force-enable on a
-    // normal desktop, or force-disable elsewhere).
-    if std::env::var_os("WEBKIT_DISABLE_DMABUF_RENDERER").is_some() {
-        return;
-    }
-
-    // The Raspberry Pi exposes its model name via the device tree.
-    let is_raspberry_pi = std::fs::read_to_string("/proc/device-tree/model")
-        .map(|model| model.contains("Raspberry Pi"))
-        .unwrap_or(false);
-
-    if is_raspberry_pi {
-        // SAFETY: called at the very start of `run()`, before Tauri spawns any
-        // threads, so there is no concurrent access to the environment.
-        unsafe {
-            std::env::set_var("WEBKIT_DISABLE_DMABUF_RENDERER", "1");
-        }
-        println!("Detected Raspberry Pi: disabled WebKitGTK DMABUF renderer");
-    }
-}
+// Re-export settings
+pub use settings::*;
+// Re-export recent files
+pub use recent_files::*;
+// Re-export session types
+pub use session::*;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init_logging();
+
+    // Write a crash report if anything panics from here on
+    crash::install_panic_hook();
+
+    // Pick a working WebKitGTK graphics configuration (may relaunch the process)
     #[cfg(target_os = "linux")]
-    workaround_webkit_dmabuf();
+    graphics_fallback::apply_graphics_fallback();
 
-    tauri::Builder::default()
-        .plugin(tauri_plugin_window_state::Builder::default().build())
-        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+    // `--new-instance` runs independently of an already running process
+    let new_instance = document_window::wants_new_instance(&std::env::args().collect::<Vec<_>>());
+    if new_instance {
+        info!("Starting as a separate instance");
+    }
+
+    let builder = tauri::Builder::default().plugin(tauri_plugin_window_state::Builder::default().build());
+    let builder = if new_instance {
+        builder
+    } else {
+        builder.plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
             // Focus existing window when new instance is launched
-            println!("Single instance: new instance detected with args: {:?}", args);
-            if let Some(main_window) = app.get_webview_window("main") {
-                let _ = main_window.unminimize();
-                let _ = main_window.set_focus();
-            }
+            info!("Single instance: new instance detected with args: {:?}", args);
+            tray::show_main_window(app);
 
             // Process file paths from args (args[0] is the executable, args[1..] may contain file paths)
             for arg in args.iter().skip(1) {
@@ -100,17 +259,27 @@ pub fn run() {
                 if arg.starts_with('-') {
                     continue;
                 }
-                println!("Single instance: processing file arg: {}", arg);
-                handle_open_file_event(app, arg.to_string());
+                info!("Single instance: processing file arg: {}", arg);
+                // Relative to the folder the second instance was started in
+                handle_open_file_event(app, resolve_file_arg(arg, std::path::Path::new(&cwd)));
             }
         }))
+    };
+
+    // Must come after the single-instance plugin (see `graphics_fallback::init`)
+    #[cfg(target_os = "linux")]
+    let builder = builder.plugin(graphics_fallback::init());
+
+    builder
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .invoke_handler(tauri::generate_handler![
             set_global_variable,
             get_global_variables,
@@ -118,206 +287,284 @@ pub fn run() {
             export_variables_to_yaml,
             process_markdown,
             get_expanded_markdown,
+            process_markdown_with_map,
+            get_unmet_requirements,
             read_file,
             save_file,
             save_image_bytes,
             copy_image_asset,
             get_file_hash,
+            get_file_hashes,
             get_pending_file_paths_command,
             log_from_frontend,
             set_frontend_ready_command,
             read_directory,
             rename_file,
-            pdf_export::export_pdf
+            pdf_export::export_pdf,
+            image_export::export_image,
+            get_backend_settings,
+            set_backend_settings,
+            hotkey::append_to_inbox,
+            add_recent_file,
+            get_recent_files,
+            remove_recent_file,
+            clear_recent_files,
+            menu::set_menu_state,
+            window_close::set_close_guard,
+            window_close::force_close_window,
+            update_session,
+            get_session,
+            autosave::write_autosave_snapshot,
+            autosave::discard_autosave_snapshot,
+            updater::check_for_updates,
+            updater::check_for_updates_manual,
+            updater::install_update,
+            crash::get_last_crash_report,
+            crash::dismiss_crash_report,
+            logging::get_recent_logs,
+            logging::set_log_level,
+            context_menu::show_editor_context_menu,
+            locale::get_locale,
+            locale::set_locale,
+            document_window::open_document_window,
+            document_window::open_new_instance,
+            preview_window::open_preview_window,
+            preview_window::update_preview,
+            workspace_state::save_workspace_window_state,
+            workspace_state::restore_workspace_window_state,
+            theme::get_system_theme,
+            theme::set_window_theme,
+            search::search_workspace,
+            search_index::build_index,
+            search_index::query_index,
+            search_index::index_status,
+            replace::replace_in_files,
+            replace::apply_replacements,
+            quick_open::quick_open,
+            tags::list_tags,
+            tags::files_with_tag,
+            tags::rename_tag,
+            links::get_backlinks,
+            links::find_orphans,
+            links::preview_link_updates,
+            links::rename_file_with_links,
+            link_check::check_links,
+            link_check::suggest_link_fixes,
+            link_check::apply_link_fixes,
+            link_graph::get_link_graph,
+            wikilinks::resolve_wikilink,
+            wikilinks::create_wikilink_note,
+            wikilinks::convert_wikilinks_for_export,
+            git::git_status,
+            git::git_diff_file,
+            git::git_commit,
+            git::git_log_file,
+            git::git_show_file_at,
+            git::git_blame,
+            diff::diff_content,
+            diff::compare_files,
+            diff::compare_with_clipboard,
+            remote::list_remote_endpoints,
+            remote::save_remote_endpoint,
+            remote::delete_remote_endpoint,
+            remote::remote_list,
+            remote::remote_read,
+            remote::remote_save,
+            sync::sync_now,
+            sync::get_sync_status,
+            sync::set_sync_secret_key,
+            encryption::open_encrypted,
+            encryption::save_encrypted,
+            encryption::forget_encryption_keys,
+            credentials::set_credential,
+            credentials::get_credential,
+            credentials::delete_credential,
+            markdown_cache::clear_markdown_cache,
+            tasks::cancel_task,
+            tasks::list_tasks,
+            spellcheck::check_text,
+            spellcheck::suggest,
+            spellcheck::list_spell_languages,
+            spellcheck::add_to_dictionary,
+            spellcheck::remove_from_dictionary,
+            spellcheck::get_user_dictionary,
+            grammar::check_grammar,
+            clipboard_image::save_clipboard_image,
+            screenshot::capture_screenshot,
+            app_data_bundle::export_app_data,
+            app_data_bundle::import_app_data,
+            plugins::list_plugins,
+            plugins::enable_plugin,
+            plugins::disable_plugin,
+            plugins::run_plugins,
+            external_tools::run_tool,
+            pandoc::pandoc_info,
+            pandoc::pandoc_convert,
+            pandoc::import_document,
+            templates::list_templates,
+            templates::create_from_template,
+            snippets::list_snippets,
+            snippets::save_snippet,
+            snippets::delete_snippet,
+            snippets::expand_snippet,
+            writing_stats::get_writing_stats,
+            writing_stats::get_goal_progress,
+            document_structure::parse_document_structure,
+            document_structure::slugify_heading,
+            document_structure::heading_anchors,
+            heading_numbering::number_headings,
+            link_metadata::fetch_link_title,
+            link_metadata::get_link_preview,
+            todos::collect_tasks,
+            todos::toggle_task,
+            zettel::generate_note_id,
+            zettel::new_zettel,
+            confluence::publish_to_confluence,
+            site_export::export_to_static_site,
+            web_documents::read_url,
+            image_cache::cache_remote_image,
+            document_merge::merge_documents,
+            file_watch::subscribe_file_state,
+            file_watch::unsubscribe_file_state,
+            bookmarks::add_bookmark,
+            bookmarks::list_bookmarks,
+            bookmarks::remove_bookmark,
+            bookmarks::adjust_bookmarks,
+            file_positions::save_file_position,
+            file_positions::get_file_position,
+            file_positions::read_file_with_position,
+            favorites::pin_file,
+            favorites::unpin_file,
+            favorites::get_favorites,
+            clipboard_text::copy_processed_text,
+            hotkey::append_to_file,
+            recovery::list_recovery_items,
+            recovery::restore_recovery_item,
+            diagnostics::get_environment_info,
+            diagnostics::generate_diagnostics_bundle,
+            workspace_variables::load_workspace_variables,
+            workspace_variables::get_workspace_variables,
+            workspace_variables::set_workspace_variable,
+            workspace_variables::remove_workspace_variable,
+            variable_history::get_variable_history,
+            variable_history::revert_variable,
+            html_sanitizer::sanitize_html,
+            includes::get_include_tree
         ])
-        .setup(|app| {
+        .setup(move |app| {
+            // Backend-owned persistent state
+            logging::attach_log_file(app.handle());
+            storage::init_app_data_dir(app.handle());
+            settings::load_settings();
+            locale::select_locale();
+            image_cache::init_image_cache(app.handle());
+
             // Get command line arguments
             let args: Vec<String> = std::env::args().collect();
-            println!("Command line args: {:?}", args);
+            debug!("Command line args: {:?}", args);
 
             // Debug output for environment variables (for macOS file association debugging)
             #[cfg(target_os = "macos")]
             {
-                println!("Environment variables:");
+                debug!("Environment variables:");
                 for (key, value) in std::env::vars() {
                     if key.contains("CF")
                         || key.contains("APPLE")
                         || key.contains("BUNDLE")
                         || key.contains("LAUNCH")
                     {
-                        println!("  {}: {}", key, value);
+                        debug!("  {}: {}", key, value);
                     }
                 }
-                println!("Process ID: {}", std::process::id());
-                println!("Current directory: {:?}", std::env::current_dir());
+                debug!("Process ID: {}", std::process::id());
+                debug!("Current directory: {:?}", std::env::current_dir());
             }
 
             // Process file paths from command line arguments (cross-platform)
+            let cwd = std::env::current_dir().unwrap_or_default();
             for arg in args.iter().skip(1) {
                 // Skip flags/options
                 if arg.starts_with('-') {
                     continue;
                 }
-                println!("Setup: processing file arg: {}", arg);
-                handle_open_file_event(app.handle(), arg.to_string());
+                info!("Setup: processing file arg: {}", arg);
+                handle_open_file_event(app.handle(), resolve_file_arg(arg, &cwd));
+            }
+
+            // Recent files must be loaded before the menu that lists them is built
+            recent_files::load_recent_files();
+            if new_instance {
+                document_window::set_secondary_instance();
+                session::detach_session();
+            } else {
+                session::load_session();
             }
+            path_scope::init_path_scope(app.handle());
+            tasks::init_tasks(app.handle().clone());
+            file_watch::init_file_watch(app.handle().clone());
+            favorites::watch_favorites();
+            notifications::init_notifications(app.handle().clone());
+            writing_stats::init_writing_stats(app.handle().clone());
+            shutdown::start_periodic_flush();
 
             // Custom menu setup (macOS only)
             #[cfg(target_os = "macos")]
-            {
-                println!("Setting up custom menu...");
-
-                // 1) 既定メニューを生成
-                let menu = Menu::default(&app.handle())?;
-                println!("Default menu created");
+            menu::setup_app_menu(app.handle())?;
 
-                // 2) "File" サブメニューを探して中に項目を差し込む
-                for item in menu.items()? {
-                    if let MenuItemKind::Submenu(file_sm) = item {
-                        let text = file_sm.text()?;
-                        println!("Found submenu: {}", text);
+            // クリックイベントの受け口 (application menu and tray menu)
+            app.on_menu_event(|app, ev| {
+                menu::handle_menu_event(app, ev.id().0.as_str());
+            });
 
-                        if text == "File" || text == "ファイル" {
-                            println!("Found File menu, adding custom items...");
-
-                            // デフォルトのFileメニュー項目を確認
-                            println!("Default File menu items:");
-                            for (i, item) in file_sm.items()?.iter().enumerate() {
-                                if let MenuItemKind::MenuItem(menu_item) = item {
-                                    if let Ok(item_text) = menu_item.text() {
-                                        println!("  {}: {}", i, item_text);
-                                    }
-                                }
-                            }
-
-                            // 1. New File
-                            let new_file = MenuItem::with_id(
-                                app, "new_file", "New File",
-                                true, Some("CmdOrCtrl+N")
-                            )?;
-                            file_sm.insert(&new_file, 1)?;
-                            println!("Inserted New File menu item at position 1");
-
-                            // 2. Open File
-                            let open_file = MenuItem::with_id(
-                                app, "open_file", "Open File",
-                                true, Some("CmdOrCtrl+O")
-                            )?;
-                            file_sm.insert(&open_file, 2)?;
-                            println!("Inserted Open File menu item at position 2");
-
-                            // 3. Save
-                            let save = MenuItem::with_id(
-                                app, "save", "Save",
-                                true, Some("CmdOrCtrl+S")
-                            )?;
-                            file_sm.insert(&save, 3)?;
-                            println!("Inserted Save menu item at position 3");
-
-                            // 4. Save As
-                            let save_as = MenuItem::with_id(
-                                app, "save_as", "Save As",
-                                true, Some("CmdOrCtrl+Shift+S")
-                            )?;
-                            file_sm.insert(&save_as, 4)?;
-                            println!("Inserted Save As menu item at position 4");
-
-                            // 5. Save with Variables
-                            let save_with_variables = MenuItem::with_id(
-                                app, "save_with_variables", "Save with Variables Applied",
-                                true, None::<&str>
-                            )?;
-                            file_sm.insert(&save_with_variables, 5)?;
-                            println!("Inserted Save with Variables menu item at position 5");
-                        }
-                        // Help メニューを探して項目を追加
-                        else if text == "Help" || text == "ヘルプ" {
-                            println!("Found Help menu, adding custom items...");
-
-                            // Help メニュー項目を追加
-                            let help = MenuItem::with_id(
-                                app, "help", "Help",
-                                true, Some("F1")
-                            )?;
-                            file_sm.insert(&help, 0)?; // 先頭に挿入
-                            println!("Inserted Help menu item at position 0");
-                        }
-                    }
+            // System tray and global summon shortcut belong to the main process. A missing
+            // tray host (e.g. some Linux desktops) must not prevent the app from starting,
+            // and the shortcut may already be taken by another app.
+            if !new_instance {
+                if let Err(e) = tray::setup_tray(app.handle()) {
+                    warn!("Failed to create tray icon: {}", e);
+                }
+                if let Err(e) = hotkey::apply_summon_shortcut(app.handle(), None, &settings::current_settings()) {
+                    info!("{}", e);
                 }
+            }
 
-                // 3) アプリメニューとして反映
-                app.set_menu(menu)?;
-                println!("Menu set successfully");
+            // Background update check (release builds only)
+            updater::spawn_startup_check(app.handle().clone());
 
-                // 4) クリックイベントの受け口
-                app.on_menu_event(|app, ev| {
-                    let timestamp = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis();
-                    println!("[{}] Menu event received: {} (thread: {:?})",
-                        timestamp, ev.id().0, std::thread::current().id());
-
-                    match ev.id().0.as_str() {
-                        "save" => {
-                            println!("[{}] Save menu item clicked - calling frontend function", timestamp);
-                            // フロントエンドの関数を直接呼び出し
-                            let result = app.emit("menu-save", ());
-                            println!("[{}] Emit result: {:?}", timestamp, result);
-                        }
-                        "new_file" => {
-                            println!("[{}] New File menu item clicked - calling frontend function", timestamp);
-                            let result = app.emit("menu-new-file", ());
-                            println!("[{}] Emit result: {:?}", timestamp, result);
-                        }
-                        "open_file" => {
-                            println!("[{}] Open File menu item clicked - calling frontend function", timestamp);
-                            let result = app.emit("menu-open-file", ());
-                            println!("[{}] Emit result: {:?}", timestamp, result);
-                        }
-                        "save_as" => {
-                            println!("[{}] Save As menu item clicked - calling frontend function", timestamp);
-                            let result = app.emit("menu-save-as", ());
-                            println!("[{}] Emit result: {:?}", timestamp, result);
-                        }
-                        "save_with_variables" => {
-                            println!("[{}] Save with Variables menu item clicked - calling frontend function", timestamp);
-                            let result = app.emit("menu-save-with-variables", ());
-                            println!("[{}] Emit result: {:?}", timestamp, result);
-                        }
-                        "help" => {
-                            println!("[{}] Help menu item clicked - calling frontend function", timestamp);
-                            let result = app.emit("menu-help", ());
-                            println!("[{}] Emit result: {:?}", timestamp, result);
-                        }
-                        _ => {
-                            println!("[{}] Unknown menu item clicked: {}", timestamp, ev.id().0);
-                        }
-                    }
-                });
-                println!("Menu event handler set up");
-            }
+            // Workspace sync pulls changes made elsewhere (when enabled)
+            sync::spawn_startup_sync(app.handle().clone());
 
             Ok(())
         })
-        .on_window_event(|_window, event| {
-            #[cfg(target_os = "macos")]
-            {
-                use tauri::WindowEvent;
-                match event {
-                    WindowEvent::CloseRequested { .. } => {
-                        println!("Window close requested");
-                    }
-                    _ => {}
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::CloseRequested { api, .. } => {
+                window_close::handle_close_requested(window, api);
+            }
+            tauri::WindowEvent::Focused(true) => {
+                notifications::handle_focused(window);
+            }
+            tauri::WindowEvent::ThemeChanged(theme) => {
+                theme::handle_theme_changed(window, theme);
+            }
+            tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) => {
+                for path in paths {
+                    path_scope::grant_path(path);
                 }
             }
+            _ => {}
         })
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
             match event {
                 RunEvent::Ready => {
-                    println!("Tauri app is ready");
+                    info!("Tauri app is ready");
+                }
+                RunEvent::ExitRequested { .. } => {
+                    shutdown::flush_backend_state("exit-requested");
+                }
+                RunEvent::Exit => {
+                    shutdown::flush_backend_state("exit");
                 }
                 #[cfg(target_os = "macos")]
                 RunEvent::Opened { urls } => {
@@ -0,0 +1,318 @@
+//! # Link Check Module
+//!
+//! This module finds the internal links of a workspace that lead nowhere and proposes
+//! fixes for them.
+//!
+//! ## Broken Links
+//! - A Markdown link or wiki-link whose file does not exist (targets resolve as described
+//!   in `links`; Markdown links to other existing files, such as PDFs, are fine)
+//! - A link to a heading (`other.md#heading`, `[[Other note#Heading]]`) that the linked
+//!   document does not have. Block references (`#^block`) are not checked.
+//!
+//! External URLs are not checked.
+//!
+//! ## Fixes
+//! 1. `check_links` lists the broken links of a workspace
+//! 2. `suggest_link_fixes` proposes the closest existing files (by the edit distance of
+//!    their names) or headings for each of them, best first
+//! 3. `apply_link_fixes` rewrites the accepted ones. A link that is no longer on its line
+//!    aborts the whole operation before any file is written.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use tracing::info;
+
+use crate::document_structure::{parse_structure, BlockKind};
+use crate::links::{self, DocumentInfo, LinkKind, LinkTargets};
+use crate::markdown;
+use crate::path_scope;
+use crate::storage;
+use crate::wikilinks::relative_link_path;
+
+// Fixes proposed per broken link
+pub const MAX_SUGGESTIONS: usize = 3;
+
+// Why a link is broken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BrokenReason {
+    MissingFile,
+    MissingAnchor,
+}
+
+// A link that leads nowhere
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrokenLink {
+    pub source_path: String,
+    pub kind: LinkKind,
+    // Target as in `links::DocumentLink`, without anchor
+    pub target: String,
+    pub anchor: Option<String>,
+    pub reason: BrokenReason,
+    // File the link points to, when only its anchor is missing
+    pub resolved_path: Option<String>,
+    pub line_number: usize,
+    pub line: String,
+}
+
+// A replacement target for a broken link
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkFix {
+    // Target as it is written into the link (`../notes/plan.md#goals`, `Plan#Goals`)
+    pub replacement: String,
+    // File the fixed link points to
+    pub path: String,
+}
+
+// Fixes proposed for a broken link, best first (empty when nothing is close)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkFixSuggestion {
+    pub link: BrokenLink,
+    pub fixes: Vec<LinkFix>,
+}
+
+// A fix the user accepted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AcceptedLinkFix {
+    pub link: BrokenLink,
+    pub replacement: String,
+}
+
+// Levenshtein distance between two strings, by characters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// Headings of a document as (anchor, text)
+fn headings_of(content: &str) -> Vec<(String, String)> {
+    parse_structure(content)
+        .into_iter()
+        .filter_map(|block| match block.kind {
+            BlockKind::Heading { anchor, text, .. } => Some((anchor, text)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Whether `anchor` names one of `headings`, by anchor or by heading text
+fn has_anchor(headings: &[(String, String)], anchor: &str) -> bool {
+    let slug = markdown::slugify_heading(anchor);
+    headings.iter().any(|(id, _)| id == anchor || *id == slug)
+}
+
+// Find the broken links among the cached documents
+pub fn find_broken_links<'a>(
+    root: &Path,
+    documents: impl Iterator<Item = (&'a String, &'a DocumentInfo)> + Clone,
+) -> Vec<BrokenLink> {
+    let targets = LinkTargets::new(documents.clone());
+    let mut headings: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    let mut broken = Vec::new();
+    for (source, info) in documents {
+        for link in &info.links {
+            let resolved = targets.resolve(root, Path::new(source), link);
+            let reason = match &resolved {
+                None if link.kind == LinkKind::Markdown
+                    && links::markdown_link_file(root, Path::new(source), &link.target).is_some_and(|file| file.is_file()) =>
+                {
+                    continue;
+                }
+                None => BrokenReason::MissingFile,
+                Some(path) => {
+                    let Some(anchor) = link.anchor.as_deref().filter(|anchor| !anchor.starts_with('^')) else {
+                        continue;
+                    };
+                    let headings = headings
+                        .entry(path.clone())
+                        .or_insert_with(|| fs::read_to_string(path).map(|c| headings_of(&c)).unwrap_or_default());
+                    if has_anchor(headings, anchor) {
+                        continue;
+                    }
+                    BrokenReason::MissingAnchor
+                }
+            };
+            broken.push(BrokenLink {
+                source_path: source.clone(),
+                kind: link.kind,
+                target: link.target.clone(),
+                anchor: link.anchor.clone(),
+                reason,
+                resolved_path: resolved,
+                line_number: link.line_number,
+                line: link.line.clone(),
+            });
+        }
+    }
+    broken.sort_by(|a, b| a.source_path.cmp(&b.source_path).then(a.line_number.cmp(&b.line_number)));
+    broken
+}
+
+// How a link to `path` (with `anchor`) is written in the file containing `link`
+fn link_text(root: &Path, link: &BrokenLink, path: &Path, anchor: Option<&str>) -> String {
+    let target = match link.kind {
+        LinkKind::Markdown => {
+            let mut target = path.to_path_buf();
+            if Path::new(&link.target).extension().is_none() {
+                target.set_extension("");
+            }
+            let relative = if link.target.starts_with('/') {
+                format!("/{}", relative_link_path(root, &target))
+            } else {
+                relative_link_path(Path::new(&link.source_path).parent().unwrap_or(root), &target)
+            };
+            relative.replace('%', "%25").replace(' ', "%20")
+        }
+        LinkKind::WikiLink => path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default(),
+    };
+    match anchor {
+        Some(anchor) => format!("{}#{}", target, anchor),
+        None => target,
+    }
+}
+
+// The `MAX_SUGGESTIONS` candidates closest to `wanted` (lowercase), as (distance, candidate).
+// Names containing `wanted` or contained in it count as close.
+fn closest<T: Ord>(wanted: &str, candidates: impl Iterator<Item = (String, T)>) -> Vec<(usize, T)> {
+    let limit = (wanted.chars().count() / 3).max(2);
+    let mut ranked: Vec<(usize, T)> = candidates
+        .filter_map(|(name, candidate)| {
+            let name = name.to_lowercase();
+            let distance = edit_distance(wanted, &name);
+            let related = !name.is_empty() && (name.contains(wanted) || wanted.contains(&name));
+            (distance <= limit || related).then_some((distance, candidate))
+        })
+        .collect();
+    ranked.sort();
+    ranked.truncate(MAX_SUGGESTIONS);
+    ranked
+}
+
+// Propose fixes for a broken link: existing files with a name close to the missing one,
+// or headings of the linked document close to the missing anchor
+pub fn suggest_fixes(root: &Path, files: &HashSet<String>, link: &BrokenLink) -> Vec<LinkFix> {
+    match (link.reason, &link.resolved_path, &link.anchor) {
+        (BrokenReason::MissingAnchor, Some(path), Some(anchor)) => {
+            let content = fs::read_to_string(path).unwrap_or_default();
+            let wanted = markdown::slugify_heading(anchor);
+            let headings = headings_of(&content).into_iter().map(|(id, text)| (id.clone(), (id, text)));
+            closest(&wanted, headings)
+                .into_iter()
+                .map(|(_, (id, text))| {
+                    let anchor = if link.kind == LinkKind::WikiLink { text } else { id };
+                    let target = match link.kind {
+                        LinkKind::Markdown => link.target.replace('%', "%25").replace(' ', "%20"),
+                        LinkKind::WikiLink => link.target.clone(),
+                    };
+                    LinkFix {
+                        replacement: format!("{}#{}", target, anchor),
+                        path: path.clone(),
+                    }
+                })
+                .collect()
+        }
+        _ => {
+            let target = link.target.replace('\\', "/");
+            let name = target.rsplit('/').next().unwrap_or(&target);
+            let wanted = Path::new(name).with_extension("").to_string_lossy().to_lowercase();
+            let names = files.iter().filter(|file| **file != link.source_path).map(|file| {
+                let stem = Path::new(file).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+                // Shorter paths win among equally close names
+                (stem, (file.len(), file.clone()))
+            });
+            closest(&wanted, names)
+                .into_iter()
+                .map(|(_, (_, file))| LinkFix {
+                    replacement: link_text(root, link, Path::new(&file), link.anchor.as_deref()),
+                    path: file,
+                })
+                .collect()
+        }
+    }
+}
+
+// Rewrite the accepted fixes of one file. Fails if a link is no longer on its line.
+pub fn apply_fixes_to_content(content: &str, fixes: &[&AcceptedLinkFix]) -> Result<String, String> {
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+    for fix in fixes {
+        let link = &fix.link;
+        let line = lines
+            .get_mut(link.line_number.wrapping_sub(1))
+            .ok_or_else(|| format!("Line {} of {} no longer exists", link.line_number, link.source_path))?;
+        let range = links::find_link_target(line, link.kind, &link.target, link.anchor.as_deref())
+            .ok_or_else(|| format!("The link to {} in {} has changed", link.target, link.source_path))?;
+        line.replace_range(range, &fix.replacement);
+    }
+    Ok(lines.concat())
+}
+
+// Tauri command: List the broken internal links of a workspace
+#[tauri::command]
+pub async fn check_links(root: String) -> Result<Vec<BrokenLink>, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        links::with_refreshed_links(root, |cache| find_broken_links(root, cache.iter()))
+    })
+    .await
+    .map_err(|e| format!("Failed to check links: {}", e))?
+}
+
+// Tauri command: Propose fixes for the broken links of a `check_links` report
+#[tauri::command]
+pub async fn suggest_link_fixes(root: String, report: Vec<BrokenLink>) -> Result<Vec<LinkFixSuggestion>, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        let files = links::with_refreshed_links(root, |cache| LinkTargets::new(cache.iter()).files)?;
+        Ok(report
+            .into_iter()
+            .map(|link| LinkFixSuggestion {
+                fixes: suggest_fixes(root, &files, &link),
+                link,
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("Failed to suggest link fixes: {}", e))?
+}
+
+// Tauri command: Apply accepted link fixes. Returns the files changed.
+#[tauri::command]
+pub async fn apply_link_fixes(fixes: Vec<AcceptedLinkFix>) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut by_file: BTreeMap<&str, Vec<&AcceptedLinkFix>> = BTreeMap::new();
+        for fix in &fixes {
+            by_file.entry(fix.link.source_path.as_str()).or_default().push(fix);
+        }
+        // Everything is checked before the first file is written
+        let mut updated = Vec::new();
+        for (path, fixes) in by_file {
+            path_scope::check_document_write(path)?;
+            let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            updated.push((path.to_string(), apply_fixes_to_content(&content, &fixes)?));
+        }
+        for (path, content) in &updated {
+            storage::write_atomic(Path::new(path), content.as_bytes())?;
+        }
+        let files_changed: Vec<String> = updated.into_iter().map(|(path, _)| path).collect();
+        links::invalidate_links(&files_changed);
+        info!("Fixed {} links in {} files", fixes.len(), files_changed.len());
+        Ok(files_changed)
+    })
+    .await
+    .map_err(|e| format!("Failed to apply link fixes: {}", e))?
+}
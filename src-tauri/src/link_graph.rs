@@ -0,0 +1,145 @@
+//! # Link Graph Module
+//!
+//! This module builds the graph of a workspace for the graph view: one node per document
+//! and one edge per linked pair of documents, from the link index (`links`).
+//!
+//! ## Metrics
+//! - Degrees: links into and out of each document (an edge counts once however many
+//!   times the pair is linked; self-links are left out)
+//! - Cluster hints: the connected component of each document, numbered from the largest
+//!   component down (0 is the largest; a document without links is a component of its
+//!   own), and the folder of the document relative to the workspace
+//!
+//! Links that do not resolve to a document are not part of the graph.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::links::{self, path_key, DocumentInfo, LinkKind, LinkTargets};
+use crate::path_scope;
+
+// A document of the graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub path: String,
+    pub title: String,
+    // Folder relative to the workspace, with '/' separators ("" for the root)
+    pub folder: String,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    // Connected component (0 is the largest)
+    pub component: usize,
+}
+
+// Links from one document to another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    // Markdown if any of the links is a Markdown link
+    pub kind: LinkKind,
+    // Number of links from `source` to `target`
+    pub count: usize,
+}
+
+// Documents and links of a workspace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+// Root of `node` in a union-find forest, compressing the path on the way
+fn find_root(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        parents[node] = parents[parents[node]];
+        node = parents[node];
+    }
+    node
+}
+
+// Build the graph of the cached documents
+pub fn build_link_graph<'a>(
+    root: &Path,
+    documents: impl Iterator<Item = (&'a String, &'a DocumentInfo)> + Clone,
+) -> LinkGraph {
+    let targets = LinkTargets::new(documents.clone());
+    let mut documents: Vec<(String, &DocumentInfo)> = documents.map(|(path, info)| (path_key(Path::new(path)), info)).collect();
+    documents.sort_by(|a, b| a.0.cmp(&b.0));
+    let index: HashMap<&str, usize> = documents.iter().enumerate().map(|(i, (path, _))| (path.as_str(), i)).collect();
+
+    // (source, target) → (kind, count), sorted for a stable order
+    let mut links_between: BTreeMap<(usize, usize), (LinkKind, usize)> = BTreeMap::new();
+    for (source, (path, info)) in documents.iter().enumerate() {
+        for link in &info.links {
+            let Some(target) = targets.resolve(root, Path::new(path), link).and_then(|t| index.get(t.as_str()).copied()) else {
+                continue;
+            };
+            if target == source {
+                continue;
+            }
+            let entry = links_between.entry((source, target)).or_insert((link.kind, 0));
+            if link.kind == LinkKind::Markdown {
+                entry.0 = LinkKind::Markdown;
+            }
+            entry.1 += 1;
+        }
+    }
+
+    let mut in_degree = vec![0; documents.len()];
+    let mut out_degree = vec![0; documents.len()];
+    let mut parents: Vec<usize> = (0..documents.len()).collect();
+    for &(source, target) in links_between.keys() {
+        out_degree[source] += 1;
+        in_degree[target] += 1;
+        let (a, b) = (find_root(&mut parents, source), find_root(&mut parents, target));
+        parents[a.max(b)] = a.min(b);
+    }
+
+    // Components by size, largest first; ties keep the order of their first document
+    let leaders: Vec<usize> = (0..documents.len()).map(|i| find_root(&mut parents, i)).collect();
+    let mut sizes: BTreeMap<usize, usize> = BTreeMap::new();
+    leaders.iter().for_each(|leader| *sizes.entry(*leader).or_default() += 1);
+    let mut ranked: Vec<(usize, usize)> = sizes.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let component: HashMap<usize, usize> = ranked.iter().enumerate().map(|(rank, (leader, _))| (*leader, rank)).collect();
+
+    let nodes = documents
+        .iter()
+        .enumerate()
+        .map(|(i, (path, info))| {
+            let relative = Path::new(path).strip_prefix(root).unwrap_or(Path::new(path));
+            GraphNode {
+                path: path.clone(),
+                title: info.title.clone(),
+                folder: relative.parent().map(|p| p.to_string_lossy().replace('\\', "/")).unwrap_or_default(),
+                in_degree: in_degree[i],
+                out_degree: out_degree[i],
+                component: component[&leaders[i]],
+            }
+        })
+        .collect();
+    let edges = links_between
+        .into_iter()
+        .map(|((source, target), (kind, count))| GraphEdge {
+            source: documents[source].0.clone(),
+            target: documents[target].0.clone(),
+            kind,
+            count,
+        })
+        .collect();
+    LinkGraph { nodes, edges }
+}
+
+// Tauri command: Get the link graph of a workspace
+#[tauri::command]
+pub async fn get_link_graph(root: String) -> Result<LinkGraph, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        links::with_refreshed_links(root, |cache| build_link_graph(root, cache.iter()))
+    })
+    .await
+    .map_err(|e| format!("Failed to build link graph: {}", e))?
+}
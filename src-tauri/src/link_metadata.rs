@@ -0,0 +1,399 @@
+//! # Link Metadata Module
+//!
+//! This module fetches web pages in the backend to read their metadata, so the WebView
+//! does not make cross-origin requests. Pasting a bare URL uses it to turn the URL into
+//! a Markdown link with the page's title, and hovering a link in the preview shows a
+//! tooltip with the page's title, description and image.
+//!
+//! ## Fetching
+//! Only `http` and `https` URLs are fetched, with the page client of the `http` module
+//! (short timeout, at most 5 redirects). At most 512 KiB of a page are read; the
+//! metadata is in the `<head>`, so a cut-off page still has it.
+//!
+//! ## Titles
+//! The OpenGraph title (`og:title`) is preferred over `<title>`, as sites often append
+//! their name to the latter. Entities are decoded and whitespace is collapsed.
+//!
+//! ## Preview Cache
+//! Link previews are cached for a week in `<app data>/link-previews/`, named by the
+//! SHA-256 of the URL: the metadata as JSON, and the page's image (`og:image`) and
+//! favicon as files, so the tooltip loads them from disk. Images over 2 MiB are left
+//! out. Failed fetches are not cached.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use tracing::{debug, warn};
+
+use crate::http;
+use crate::storage;
+
+lazy_static! {
+    static ref META_TAG: Regex = Regex::new(r"(?is)<meta\s[^>]*>").unwrap();
+    static ref LINK_TAG: Regex = Regex::new(r"(?is)<link\s[^>]*>").unwrap();
+    static ref ATTRIBUTE: Regex =
+        Regex::new(r#"(?s)([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap();
+    static ref TITLE: Regex = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+    static ref META_CHARSET: Regex =
+        Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?([\w-]+)"#).unwrap();
+    static ref ENTITY: Regex = Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap();
+}
+
+const MAX_PAGE_SIZE: usize = 512 * 1024;
+const MAX_IMAGE_SIZE: usize = 2 * 1024 * 1024;
+const PREVIEW_DIR: &str = "link-previews";
+const PREVIEW_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// A fetched resource
+struct Fetched {
+    // After redirects
+    url: Url,
+    content_type: String,
+    bytes: Vec<u8>,
+}
+
+// Metadata of a page, as found in its HTML
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub site_name: Option<String>,
+    // Absolute URLs
+    pub image_url: Option<Url>,
+    pub icon_url: Option<Url>,
+}
+
+// Result of `get_link_preview`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkPreview {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub site_name: Option<String>,
+    // Paths of the cached files
+    pub image: Option<String>,
+    pub icon: Option<String>,
+}
+
+// A preview in the cache
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct CachedPreview {
+    // Seconds since the Unix epoch
+    fetched_at: u64,
+    preview: LinkPreview,
+}
+
+// Decode the character references of HTML text
+pub fn decode_entities(text: &str) -> String {
+    ENTITY
+        .replace_all(text, |caps: &regex::Captures| {
+            let entity = &caps[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => {
+                    let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            decoded.map_or_else(|| caps[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+// Decoded text with runs of whitespace collapsed; None if nothing is left
+fn clean_text(text: &str) -> Option<String> {
+    let text = decode_entities(text).split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+// Attributes of a tag, by lowercase name
+pub fn attributes(tag: &str) -> HashMap<String, String> {
+    ATTRIBUTE
+        .captures_iter(tag)
+        .map(|caps| {
+            let value = caps.get(2).or(caps.get(3)).or(caps.get(4)).map_or("", |m| m.as_str());
+            (caps[1].to_ascii_lowercase(), value.to_string())
+        })
+        .collect()
+}
+
+// Content of the first `<meta>` whose `property` or `name` is one of `keys`, in the
+// order of `keys`
+pub fn meta_content(html: &str, keys: &[&str]) -> Option<String> {
+    let tags: Vec<(String, String)> = META_TAG
+        .find_iter(html)
+        .filter_map(|tag| {
+            let mut attributes = attributes(tag.as_str());
+            let key = attributes.remove("property").or_else(|| attributes.remove("name"))?;
+            Some((key.to_ascii_lowercase(), attributes.remove("content")?))
+        })
+        .collect();
+    keys.iter().find_map(|wanted| {
+        tags.iter()
+            .filter(|(key, _)| key == wanted)
+            .find_map(|(_, content)| clean_text(content))
+    })
+}
+
+// Title of a page: the OpenGraph title, else `<title>`
+pub fn page_title(html: &str) -> Option<String> {
+    meta_content(html, &["og:title", "twitter:title"])
+        .or_else(|| TITLE.captures(html).and_then(|caps| clean_text(&caps[1])))
+}
+
+// Icon declared by a `<link rel="icon">` (or `apple-touch-icon`), else `/favicon.ico`
+fn icon_url(html: &str, page_url: &Url) -> Option<Url> {
+    let declared = LINK_TAG.find_iter(html).find_map(|tag| {
+        let attributes = attributes(tag.as_str());
+        let rel = attributes.get("rel")?.to_ascii_lowercase();
+        let is_icon = rel
+            .split_whitespace()
+            .any(|token| token == "icon" || token == "apple-touch-icon");
+        is_icon.then(|| attributes.get("href").cloned()).flatten()
+    });
+    match declared {
+        Some(href) => page_url.join(decode_entities(href.trim()).as_str()).ok(),
+        None => page_url.join("/favicon.ico").ok(),
+    }
+}
+
+// Title, description, image and icon of a page at `page_url`
+pub fn parse_metadata(html: &str, page_url: &Url) -> PageMetadata {
+    PageMetadata {
+        title: page_title(html),
+        description: meta_content(html, &["og:description", "description", "twitter:description"]),
+        site_name: meta_content(html, &["og:site_name"]),
+        image_url: meta_content(html, &["og:image", "og:image:url", "twitter:image"])
+            .and_then(|image| page_url.join(&image).ok())
+            .filter(|url| matches!(url.scheme(), "http" | "https")),
+        icon_url: icon_url(html, page_url).filter(|url| matches!(url.scheme(), "http" | "https")),
+    }
+}
+
+// A Markdown link to `url` with `title` as its text
+pub fn markdown_link(title: &str, url: &str) -> String {
+    let mut text = String::new();
+    for c in title.chars() {
+        if matches!(c, '\\' | '[' | ']') {
+            text.push('\\');
+        }
+        text.push(c);
+    }
+    if url.contains(['(', ')', ' ']) {
+        format!("[{}](<{}>)", text, url)
+    } else {
+        format!("[{}]({})", text, url)
+    }
+}
+
+// Decode a page in the charset of its Content-Type, or of its `<meta charset>`
+fn decode_page(bytes: &[u8], content_type: &str) -> String {
+    let header_charset = content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("charset="))
+        .map(|charset| charset.trim_matches('"').to_string());
+    let charset = header_charset.or_else(|| {
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(4096)]);
+        META_CHARSET.captures(&head).map(|caps| caps[1].to_string())
+    });
+    match charset.and_then(|charset| encoding_rs::Encoding::for_label(charset.as_bytes())) {
+        Some(encoding) => encoding.decode(bytes).0.into_owned(),
+        None => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+// Check that a URL can be fetched
+pub fn parse_web_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        scheme => Err(format!("Unsupported URL scheme: {}", scheme)),
+    }
+}
+
+// Fetch up to `max_size` bytes of a resource
+async fn fetch(url: &Url, max_size: usize) -> Result<Fetched, String> {
+    let mut response = http::page_client()?
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{} returned {}", url, status));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    let mut bytes = Vec::new();
+    while bytes.len() < max_size {
+        match response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", url, e))?
+        {
+            Some(chunk) => bytes.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+    debug!("Fetched {} bytes of {}", bytes.len(), response.url());
+    Ok(Fetched {
+        url: response.url().clone(),
+        content_type,
+        bytes,
+    })
+}
+
+// Fetch the start of an HTML page. Returns the URL after redirects and the HTML.
+pub async fn fetch_page(url: &Url) -> Result<(Url, String), String> {
+    let mut page = fetch(url, MAX_PAGE_SIZE).await?;
+    if !page.content_type.is_empty() && !page.content_type.contains("html") {
+        return Err(format!("{} is not an HTML page ({})", url, page.content_type));
+    }
+    page.bytes.truncate(MAX_PAGE_SIZE);
+    Ok((page.url, decode_page(&page.bytes, &page.content_type)))
+}
+
+// File extension of an image type; None for anything else
+fn image_extension(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    Some(match mime {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        "image/svg+xml" => "svg",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        _ => return None,
+    })
+}
+
+// Download an image into the cache as `<stem>.<ext>`. None if it is not an image, too
+// large or not available.
+async fn cache_image(url: &Url, dir: &Path, stem: &str) -> Option<String> {
+    let image = fetch(url, MAX_IMAGE_SIZE + 1)
+        .await
+        .inspect_err(|e| debug!("No preview image: {}", e))
+        .ok()?;
+    let extension = image_extension(&image.content_type)?;
+    if image.bytes.len() > MAX_IMAGE_SIZE {
+        return None;
+    }
+    let path = dir.join(format!("{}.{}", stem, extension));
+    storage::write_atomic(&path, &image.bytes)
+        .inspect_err(|e| warn!("Failed to cache preview image {}: {}", url, e))
+        .ok()?;
+    Some(path.to_string_lossy().to_string())
+}
+
+// Name of the cache entries of a URL
+fn cache_key(url: &str) -> String {
+    Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Whether a preview fetched at `fetched_at` can still be used at `now`
+pub fn is_fresh(fetched_at: u64, now: u64) -> bool {
+    now >= fetched_at && now - fetched_at < PREVIEW_MAX_AGE.as_secs()
+}
+
+// A fresh preview from the cache, with its files still present
+fn cached_preview(dir: &Path, key: &str) -> Option<LinkPreview> {
+    let path = dir.join(format!("{}.json", key));
+    if !path.exists() {
+        return None;
+    }
+    let cached: CachedPreview = storage::read_json_file(&path);
+    let files_present = [&cached.preview.image, &cached.preview.icon]
+        .into_iter()
+        .flatten()
+        .all(|file| Path::new(file).exists());
+    (is_fresh(cached.fetched_at, now_secs()) && files_present).then_some(cached.preview)
+}
+
+// Fetch a page and its images into the cache directory
+async fn fetch_preview(url: &Url, dir: &Path, key: &str) -> Result<LinkPreview, String> {
+    let (page_url, html) = fetch_page(url).await?;
+    let metadata = parse_metadata(&html, &page_url);
+    let image = match &metadata.image_url {
+        Some(image_url) => cache_image(image_url, dir, &format!("{}-image", key)).await,
+        None => None,
+    };
+    let icon = match &metadata.icon_url {
+        Some(icon_url) => cache_image(icon_url, dir, &format!("{}-icon", key)).await,
+        None => None,
+    };
+    Ok(LinkPreview {
+        url: url.to_string(),
+        title: metadata.title,
+        description: metadata.description,
+        site_name: metadata.site_name,
+        image,
+        icon,
+    })
+}
+
+fn preview_dir() -> Result<PathBuf, String> {
+    storage::app_data_path(PREVIEW_DIR).ok_or_else(|| "App data directory is not available".to_string())
+}
+
+// Tauri command: Turn a URL into a Markdown link titled with the page's title
+#[tauri::command]
+pub async fn fetch_link_title(url: String) -> Result<String, String> {
+    let parsed = parse_web_url(&url)?;
+    let (_, html) = fetch_page(&parsed).await?;
+    let title = page_title(&html).ok_or_else(|| format!("{} has no title", url))?;
+    Ok(markdown_link(&title, url.trim()))
+}
+
+// Tauri command: Get the title, description, image and favicon of a linked page, from
+// the cache if it was fetched in the last week
+#[tauri::command]
+pub async fn get_link_preview(url: String) -> Result<LinkPreview, String> {
+    let parsed = parse_web_url(&url)?;
+    let dir = preview_dir()?;
+    let key = cache_key(parsed.as_str());
+    if let Some(preview) = cached_preview(&dir, &key) {
+        return Ok(preview);
+    }
+
+    let preview = fetch_preview(&parsed, &dir, &key).await?;
+    let cached = CachedPreview {
+        fetched_at: now_secs(),
+        preview: preview.clone(),
+    };
+    if let Err(e) = storage::write_json_file(&dir.join(format!("{}.json", key)), &cached) {
+        warn!("Failed to cache link preview of {}: {}", url, e);
+    }
+    Ok(preview)
+}
@@ -0,0 +1,700 @@
+//! # Links Module
+//!
+//! This module indexes the links between the documents of a workspace and answers
+//! "which notes link to this one" (backlinks).
+//!
+//! ## Link Kinds
+//! - Markdown links: `[text](other.md)`, `[text](<my note.md#heading>)`; targets are
+//!   relative to the linking file, or to the workspace root when they start with '/'
+//! - Reference definitions: `[id]: other.md`
+//! - Wiki-links: `[[Other note]]`, `[[Other note#heading|alias]]` and embeds
+//!   `![[Other note]]`, resolved by file name anywhere in the workspace, then by a loose
+//!   name match (`project-plan.md`), then by note ID (see `zettel`), then by document
+//!   title (see `wikilinks`)
+//!
+//! Images, external URLs and links inside code are ignored. A Markdown link without an
+//! extension also matches the `.md` file of that name.
+//!
+//! ## Index
+//! Links are kept per file in a `workspace::FileCache` and resolved when queried, so a
+//! new file immediately becomes the target of wiki-links that mention it.
+//!
+//! ## Orphans
+//! `find_orphans` lists the documents no other document links to (self-links do not
+//! count), to help prune dead notes.
+//!
+//! ## Moving Files
+//! `rename_file_with_links` renames or moves a file and rewrites the links that point to
+//! it, so the link graph stays intact; `preview_link_updates` is its dry run.
+//! - Markdown links and reference definitions to the file get its new relative path
+//!   (root-relative links stay root-relative); anchors, `<...>` and extensionless links
+//!   are kept as written
+//! - Wiki-links that found the file by name get its new name
+//! - When the file moves to another folder, its own relative links and images are
+//!   rebased on the new folder
+//!
+//! As with `replace`, nothing is renamed if a file to rewrite changed since its links
+//! were planned (hash mismatch), every file is backed up to `replace-backups` first, and
+//! a failed write restores the files already written and moves the file back.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use tracing::{info, warn};
+
+use crate::commands;
+use crate::frontmatter;
+use crate::markdown;
+use crate::path_scope;
+use crate::replace;
+use crate::storage;
+use crate::wikilinks::relative_link_path;
+use crate::workspace::FileCache;
+use crate::zettel;
+
+lazy_static! {
+    static ref MARKDOWN_LINK: Regex =
+        Regex::new(r#"(!?)\[[^\]]*\]\(\s*(<[^>]*>|[^)\s]+)(?:\s+(?:"[^"]*"|'[^']*'))?\s*\)"#).unwrap();
+    static ref REFERENCE_DEFINITION: Regex = Regex::new(r"^ {0,3}\[[^\]]+\]:\s*(<[^>]*>|\S+)").unwrap();
+    static ref WIKI_LINK: Regex = Regex::new(r"!?\[\[([^\]|#]*)(?:#([^\]|]*))?(?:\|[^\]]*)?\]\]").unwrap();
+}
+
+// Kind of a link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkKind {
+    Markdown,
+    WikiLink,
+}
+
+// A link found in a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentLink {
+    pub kind: LinkKind,
+    // Link target as written, without anchor (`other.md`, `Other note`)
+    pub target: String,
+    // Heading the link points to (`heading` of `other.md#heading`)
+    pub anchor: Option<String>,
+    // 1-based
+    pub line_number: usize,
+    pub line: String,
+}
+
+// Data kept per document in the link index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentInfo {
+    pub title: String,
+    // Zettelkasten ID of the note (see `zettel::note_id_of`)
+    pub id: Option<String>,
+    pub links: Vec<DocumentLink>,
+}
+
+// Files a link can point to, with their IDs and titles (for wiki-links)
+pub struct LinkTargets {
+    pub files: HashSet<String>,
+    // Uppercase note ID → files with that ID
+    ids: HashMap<String, Vec<String>>,
+    // Lowercase title → files with that title
+    titles: HashMap<String, Vec<String>>,
+}
+
+// A document linking to the requested one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backlink {
+    pub source_path: String,
+    pub kind: LinkKind,
+    pub line_number: usize,
+    // The linking line, for context
+    pub line: String,
+}
+
+// A link rewritten because a file moved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkUpdate {
+    // File containing the link, at its path after the move
+    pub path: String,
+    pub line_number: usize,
+    pub before: String,
+    pub after: String,
+}
+
+// The new content of a file whose links change
+pub struct FileLinkUpdate {
+    pub path: String,
+    pub content: String,
+    pub updates: Vec<LinkUpdate>,
+    // Where the file is before the move, and the hash of the content the update was
+    // computed from (see `replace::content_hash`)
+    pub source: String,
+    pub hash: String,
+}
+
+static LINK_CACHE: OnceLock<Mutex<FileCache<DocumentInfo>>> = OnceLock::new();
+
+fn link_cache_cell() -> &'static Mutex<FileCache<DocumentInfo>> {
+    LINK_CACHE.get_or_init(|| Mutex::new(FileCache::default()))
+}
+
+// Decode %XX escapes in a link target
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// Local file part of a Markdown link target (None for external URLs and pure anchors)
+fn local_target(raw: &str) -> Option<String> {
+    let target = raw.trim_start_matches('<').trim_end_matches('>');
+    let is_external = target.contains("://") || target.starts_with("mailto:") || target.starts_with("tel:");
+    let path = target.split(['#', '?']).next().unwrap_or("");
+    (!is_external && !path.is_empty()).then(|| percent_decode(path))
+}
+
+// Anchor of a Markdown link target (`other.md#heading` → `heading`)
+fn link_anchor(raw: &str) -> Option<String> {
+    let target = raw.trim_start_matches('<').trim_end_matches('>');
+    let (_, anchor) = target.split_once('#')?;
+    Some(percent_decode(anchor)).filter(|anchor| !anchor.is_empty())
+}
+
+// Anchor of a wiki-link (`[[Other note#Heading]]` → `Heading`)
+fn wikilink_anchor(caps: &regex::Captures) -> Option<String> {
+    caps.get(2).map(|m| m.as_str().trim().to_string()).filter(|anchor| !anchor.is_empty())
+}
+
+// Extract the links of a document
+pub fn extract_links(content: &str) -> Vec<DocumentLink> {
+    let split = frontmatter::split_frontmatter(content);
+    let mut links = Vec::new();
+    for (index, line) in markdown::lines_outside_code(split.body) {
+        let masked = markdown::mask_inline_code(line);
+        let mut push = |kind: LinkKind, target: String, anchor: Option<String>| {
+            links.push(DocumentLink {
+                kind,
+                target,
+                anchor,
+                line_number: split.body_line_offset + index + 1,
+                line: line.trim().to_string(),
+            });
+        };
+
+        for caps in MARKDOWN_LINK.captures_iter(&masked) {
+            // `![alt](image.png)` is an image, not a link
+            if caps[1].is_empty()
+                && let Some(target) = local_target(&caps[2])
+            {
+                push(LinkKind::Markdown, target, link_anchor(&caps[2]));
+            }
+        }
+        if let Some(caps) = REFERENCE_DEFINITION.captures(&masked)
+            && let Some(target) = local_target(&caps[1])
+        {
+            push(LinkKind::Markdown, target, link_anchor(&caps[1]));
+        }
+        for caps in WIKI_LINK.captures_iter(&masked) {
+            let target = caps[1].trim();
+            if !target.is_empty() {
+                push(LinkKind::WikiLink, target.to_string(), wikilink_anchor(&caps));
+            }
+        }
+    }
+    links
+}
+
+// Range of the target of the first link on `line` to `target` and `anchor`, anchor
+// included (`other.md#heading`, `Other note#Heading`; inside `<...>`)
+pub fn find_link_target(line: &str, kind: LinkKind, target: &str, anchor: Option<&str>) -> Option<Range<usize>> {
+    let masked = markdown::mask_inline_code(line);
+    let matches = |found: Option<String>, found_anchor: Option<String>| {
+        found.as_deref() == Some(target) && found_anchor.as_deref() == anchor
+    };
+    match kind {
+        LinkKind::Markdown => {
+            let links = MARKDOWN_LINK
+                .captures_iter(&masked)
+                .filter(|caps| caps[1].is_empty())
+                .filter_map(|caps| caps.get(2))
+                .chain(REFERENCE_DEFINITION.captures(&masked).and_then(|caps| caps.get(1)));
+            for raw in links.collect::<Vec<_>>() {
+                if matches(local_target(raw.as_str()), link_anchor(raw.as_str())) {
+                    let angle = usize::from(raw.as_str().starts_with('<'));
+                    let closing = usize::from(raw.as_str().ends_with('>'));
+                    return Some(raw.start() + angle..raw.end() - closing);
+                }
+            }
+            None
+        }
+        LinkKind::WikiLink => WIKI_LINK.captures_iter(&masked).find_map(|caps| {
+            let name = caps.get(1)?;
+            if !matches(Some(name.as_str().trim().to_string()), wikilink_anchor(&caps)) {
+                return None;
+            }
+            let end = caps.get(2).map_or(name.end(), |heading| heading.end());
+            let text = &line[name.start()..end];
+            let start = name.start() + (text.len() - text.trim_start().len());
+            Some(start..name.start() + text.trim_end().len())
+        }),
+    }
+}
+
+// Extract the title and links of a document
+pub fn extract_document_info(path: &Path, content: &str) -> DocumentInfo {
+    DocumentInfo {
+        title: markdown::document_title(path, content),
+        id: zettel::note_id_of(path, content),
+        links: extract_links(content),
+    }
+}
+
+// Resolve "." and ".." without touching the file system
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+pub fn path_key(path: &Path) -> String {
+    normalize_path(path).to_string_lossy().to_string()
+}
+
+// Resolve a Markdown link target to a workspace file
+pub fn resolve_markdown_link(root: &Path, source: &Path, target: &str, files: &HashSet<String>) -> Option<String> {
+    let base = match target.strip_prefix('/') {
+        Some(rooted) => root.join(rooted),
+        None => source.parent().unwrap_or(root).join(target),
+    };
+    let key = path_key(&base);
+    if files.contains(&key) {
+        return Some(key);
+    }
+    if base.extension().is_none() {
+        let with_md = format!("{}.md", key);
+        if files.contains(&with_md) {
+            return Some(with_md);
+        }
+    }
+    None
+}
+
+// Workspace file a Markdown link target points to (None for external URLs and anchors)
+pub fn markdown_link_file(root: &Path, source: &Path, target: &str) -> Option<PathBuf> {
+    let path = local_target(target)?;
+    Some(normalize_path(&match path.strip_prefix('/') {
+        Some(rooted) => root.join(rooted),
+        None => source.parent().unwrap_or(root).join(path),
+    }))
+}
+
+// Whether a Markdown link to `file` points to `moved` ("notes/plan" matches "notes/plan.md")
+fn is_link_to(file: &Path, moved: &Path) -> bool {
+    file == moved || (file.extension().is_none() && file.with_extension("md") == moved)
+}
+
+// New target of a Markdown link after `old` moved to `new`, for a link in the file at
+// `source` that moves to `new_source`. None if the link stays as it is.
+fn moved_markdown_target(root: &Path, source: &Path, new_source: &Path, raw: &str, old: &Path, new: &Path) -> Option<String> {
+    let file = markdown_link_file(root, source, raw)?;
+    let moves_target = is_link_to(&file, old);
+    let rooted = raw.trim_start_matches('<').starts_with('/');
+    if !moves_target && (rooted || source.parent() == new_source.parent()) {
+        return None;
+    }
+
+    let angle = raw.starts_with('<');
+    let inner = raw.trim_start_matches('<').trim_end_matches('>');
+    let suffix = inner.find(['#', '?']).map_or("", |i| &inner[i..]);
+    let mut target = if moves_target { new.to_path_buf() } else { file.clone() };
+    if moves_target && file.extension().is_none() {
+        target.set_extension("");
+    }
+    let path = if rooted {
+        format!("/{}", relative_link_path(root, &target))
+    } else {
+        relative_link_path(new_source.parent().unwrap_or(root), &target)
+    };
+    if angle {
+        Some(format!("<{}{}>", path, suffix))
+    } else {
+        Some(format!("{}{}", path.replace('%', "%25").replace(' ', "%20"), suffix))
+    }
+}
+
+// New target of a wiki-link that found `old` by name, or None if it still finds the file
+fn moved_wikilink_target(root: &Path, target: &str, old: &Path, new: &Path, files: &HashSet<String>) -> Option<String> {
+    if resolve_wikilink(root, target, files)? != path_key(old) {
+        return None;
+    }
+    let relative = new.strip_prefix(root).unwrap_or(new).with_extension("");
+    let renamed = if target.contains('/') {
+        relative.to_string_lossy().replace('\\', "/")
+    } else {
+        relative.file_name()?.to_string_lossy().into_owned()
+    };
+    let unchanged = renamed.to_lowercase() == target.trim().trim_end_matches(".md").replace('\\', "/").to_lowercase();
+    (!unchanged).then_some(renamed)
+}
+
+// Rewrite the links of `content`, the file at `source` (which moves to `new_source`),
+// after the file `old` moved to `new`. Returns None if no link changes.
+pub fn rewrite_moved_links(
+    root: &Path,
+    content: &str,
+    source: &Path,
+    new_source: &Path,
+    old: &Path,
+    new: &Path,
+    files: &HashSet<String>,
+) -> Option<FileLinkUpdate> {
+    let split = frontmatter::split_frontmatter(content);
+    let header_len = content.len() - split.body.len();
+    let prose: HashSet<usize> = markdown::lines_outside_code(split.body).into_iter().map(|(i, _)| i).collect();
+    let mut output = String::with_capacity(content.len());
+    output.push_str(&content[..header_len]);
+    let mut updates = Vec::new();
+
+    for (index, raw_line) in split.body.split_inclusive('\n').enumerate() {
+        let line = raw_line.trim_end_matches(['\r', '\n']);
+        if !prose.contains(&index) {
+            output.push_str(raw_line);
+            continue;
+        }
+        let masked = markdown::mask_inline_code(line);
+        let mut replacements = Vec::new();
+        for caps in MARKDOWN_LINK.captures_iter(&masked) {
+            let target = caps.get(2).unwrap();
+            if let Some(moved) = moved_markdown_target(root, source, new_source, target.as_str(), old, new) {
+                replacements.push((target.range(), moved));
+            }
+        }
+        if let Some(target) = REFERENCE_DEFINITION.captures(&masked).and_then(|caps| caps.get(1))
+            && let Some(moved) = moved_markdown_target(root, source, new_source, target.as_str(), old, new)
+        {
+            replacements.push((target.range(), moved));
+        }
+        for caps in WIKI_LINK.captures_iter(&masked) {
+            let target = caps.get(1).unwrap();
+            let trimmed = target.as_str().trim();
+            if let Some(moved) = moved_wikilink_target(root, trimmed, old, new, files) {
+                let start = target.start() + target.as_str().find(trimmed).unwrap_or(0);
+                replacements.push((start..start + trimmed.len(), moved));
+            }
+        }
+        if replacements.is_empty() {
+            output.push_str(raw_line);
+            continue;
+        }
+
+        replacements.sort_by_key(|(range, _)| range.start);
+        let mut rewritten = line.to_string();
+        for (range, moved) in replacements.into_iter().rev() {
+            rewritten.replace_range(range, &moved);
+        }
+        updates.push(LinkUpdate {
+            path: new_source.to_string_lossy().into_owned(),
+            line_number: split.body_line_offset + index + 1,
+            before: line.to_string(),
+            after: rewritten.clone(),
+        });
+        output.push_str(&rewritten);
+        output.push_str(&raw_line[line.len()..]);
+    }
+    (!updates.is_empty()).then(|| FileLinkUpdate {
+        path: new_source.to_string_lossy().into_owned(),
+        content: output,
+        updates,
+        source: source.to_string_lossy().into_owned(),
+        hash: replace::content_hash(content),
+    })
+}
+
+// Key for loose name matching: "Project Plan", "project-plan" and "project_plan" match
+fn match_key(text: &str) -> String {
+    markdown::slugify_heading(text)
+        .split(['-', '_'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// Resolve a wiki-link target (a file name without extension, optionally with folders)
+// to a workspace file. The exact name wins over a loose match ("Project Plan" →
+// `project-plan.md`); among several candidates the shortest path wins.
+pub fn resolve_wikilink(root: &Path, target: &str, files: &HashSet<String>) -> Option<String> {
+    let wanted = target.trim().trim_end_matches(".md").replace('\\', "/");
+    let relative_names: Vec<(&String, String)> = files
+        .iter()
+        .map(|file| {
+            let relative = Path::new(file).strip_prefix(root).unwrap_or(Path::new(file));
+            (file, relative.with_extension("").to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+    let shortest = |matches: &dyn Fn(&str) -> bool| {
+        relative_names
+            .iter()
+            .filter(|(_, name)| matches(name))
+            .map(|(file, _)| *file)
+            .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+            .cloned()
+    };
+
+    let exact = wanted.to_lowercase();
+    let found = shortest(&|name| {
+        let name = name.to_lowercase();
+        name == exact || name.ends_with(&format!("/{}", exact))
+    });
+    // Loose matching compares file names only
+    let loose = match_key(&wanted);
+    if found.is_some() || loose.is_empty() || wanted.contains('/') {
+        return found;
+    }
+    shortest(&|name| match_key(name.rsplit('/').next().unwrap_or(name)) == loose)
+}
+
+impl LinkTargets {
+    pub fn new<'a>(documents: impl Iterator<Item = (&'a String, &'a DocumentInfo)>) -> Self {
+        let mut files = HashSet::new();
+        let mut ids: HashMap<String, Vec<String>> = HashMap::new();
+        let mut titles: HashMap<String, Vec<String>> = HashMap::new();
+        for (path, info) in documents {
+            let key = path_key(Path::new(path));
+            if let Some(id) = &info.id {
+                ids.entry(id.to_uppercase()).or_default().push(key.clone());
+            }
+            titles.entry(info.title.to_lowercase()).or_default().push(key.clone());
+            files.insert(key);
+        }
+        Self { files, ids, titles }
+    }
+
+    // Resolve a wiki-link by file name, then by note ID, then by document title
+    pub fn resolve_wikilink(&self, root: &Path, target: &str) -> Option<String> {
+        let lookup = |map: &HashMap<String, Vec<String>>, key: String| map.get(&key).and_then(|paths| paths.iter().min().cloned());
+        resolve_wikilink(root, target, &self.files)
+            .or_else(|| lookup(&self.ids, target.trim().to_uppercase()))
+            .or_else(|| lookup(&self.titles, target.trim().to_lowercase()))
+    }
+
+    // Resolve a link found in `source` to a workspace file
+    pub fn resolve(&self, root: &Path, source: &Path, link: &DocumentLink) -> Option<String> {
+        match link.kind {
+            LinkKind::Markdown => resolve_markdown_link(root, source, &link.target, &self.files),
+            LinkKind::WikiLink => self.resolve_wikilink(root, &link.target),
+        }
+    }
+}
+
+// Find the links to `target` among the cached documents
+pub fn find_backlinks<'a>(
+    root: &Path,
+    documents: impl Iterator<Item = (&'a String, &'a DocumentInfo)> + Clone,
+    target: &Path,
+) -> Vec<Backlink> {
+    let targets = LinkTargets::new(documents.clone());
+    let target = path_key(target);
+    let mut backlinks = Vec::new();
+    for (source, info) in documents {
+        if path_key(Path::new(source)) == target {
+            continue;
+        }
+        for link in &info.links {
+            if targets.resolve(root, Path::new(source), link).as_ref() == Some(&target) {
+                backlinks.push(Backlink {
+                    source_path: source.clone(),
+                    kind: link.kind,
+                    line_number: link.line_number,
+                    line: link.line.clone(),
+                });
+            }
+        }
+    }
+    backlinks.sort_by(|a, b| a.source_path.cmp(&b.source_path).then(a.line_number.cmp(&b.line_number)));
+    backlinks
+}
+
+// The cached documents that no other document links to, sorted by path
+pub fn orphan_documents<'a>(root: &Path, documents: impl Iterator<Item = (&'a String, &'a DocumentInfo)> + Clone) -> Vec<String> {
+    let targets = LinkTargets::new(documents.clone());
+    let mut linked = HashSet::new();
+    for (source, info) in documents.clone() {
+        let source_key = path_key(Path::new(source));
+        for link in &info.links {
+            if let Some(target) = targets.resolve(root, Path::new(source), link)
+                && target != source_key
+            {
+                linked.insert(target);
+            }
+        }
+    }
+    let mut orphans: Vec<String> = documents
+        .map(|(path, _)| path)
+        .filter(|path| !linked.contains(&path_key(Path::new(path))))
+        .cloned()
+        .collect();
+    orphans.sort();
+    orphans
+}
+
+// The files whose links change when `old` moves to `new`: the documents linking to it
+// and the moved file itself
+pub fn plan_link_updates<'a>(
+    root: &Path,
+    documents: impl Iterator<Item = (&'a String, &'a DocumentInfo)> + Clone,
+    old: &Path,
+    new: &Path,
+) -> Result<Vec<FileLinkUpdate>, String> {
+    let targets = LinkTargets::new(documents.clone());
+    let old = normalize_path(old);
+    let new = normalize_path(new);
+    let mut planned = Vec::new();
+    for (source, info) in documents {
+        let source = normalize_path(Path::new(source));
+        let links_to_old = info.links.iter().any(|link| match link.kind {
+            LinkKind::Markdown => markdown_link_file(root, &source, &link.target).is_some_and(|file| is_link_to(&file, &old)),
+            LinkKind::WikiLink => resolve_wikilink(root, &link.target, &targets.files) == Some(path_key(&old)),
+        });
+        if source != old && !links_to_old {
+            continue;
+        }
+        let content = std::fs::read_to_string(&source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        let new_source = if source == old { new.as_path() } else { source.as_path() };
+        if let Some(update) = rewrite_moved_links(root, &content, &source, new_source, &old, &new, &targets.files) {
+            planned.push(update);
+        }
+    }
+    planned.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(planned)
+}
+
+// Drop files from the link cache, so they are read again (after writing them)
+pub fn invalidate_links(paths: &[String]) {
+    if let Ok(mut cache) = link_cache_cell().lock() {
+        paths.iter().for_each(|path| cache.invalidate(path));
+    }
+}
+
+// Run `f` on the link cache after bringing it up to date with the workspace
+pub fn with_refreshed_links<T>(root: &Path, f: impl FnOnce(&FileCache<DocumentInfo>) -> T) -> Result<T, String> {
+    let mut cache = link_cache_cell()
+        .lock()
+        .map_err(|_| "Failed to lock link index".to_string())?;
+    cache.refresh(root, extract_document_info)?;
+    Ok(f(&cache))
+}
+
+// Tauri command: List the documents linking to `path`
+#[tauri::command]
+pub async fn get_backlinks(root: String, path: String) -> Result<Vec<Backlink>, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        with_refreshed_links(root, |cache| find_backlinks(root, cache.iter(), Path::new(&path)))
+    })
+    .await
+    .map_err(|e| format!("Failed to get backlinks: {}", e))?
+}
+
+// Tauri command: List the documents of a workspace that no other document links to
+#[tauri::command]
+pub async fn find_orphans(root: String) -> Result<Vec<String>, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        with_refreshed_links(root, |cache| orphan_documents(root, cache.iter()))
+    })
+    .await
+    .map_err(|e| format!("Failed to find orphans: {}", e))?
+}
+
+// Tauri command: List the link changes that moving `old_path` to `new_path` would make
+// (dry run of `rename_file_with_links`)
+#[tauri::command]
+pub async fn preview_link_updates(root: String, old_path: String, new_path: String) -> Result<Vec<LinkUpdate>, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        let planned = with_refreshed_links(root, |cache| plan_link_updates(root, cache.iter(), Path::new(&old_path), Path::new(&new_path)))??;
+        Ok(planned.into_iter().flat_map(|file| file.updates).collect())
+    })
+    .await
+    .map_err(|e| format!("Failed to preview link updates: {}", e))?
+}
+
+// Tauri command: Rename or move a file inside a workspace and rewrite the links to it.
+// Returns the link changes made.
+#[tauri::command]
+pub async fn rename_file_with_links(root: String, old_path: String, new_path: String) -> Result<Vec<LinkUpdate>, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        let planned = with_refreshed_links(root, |cache| plan_link_updates(root, cache.iter(), Path::new(&old_path), Path::new(&new_path)))??;
+        // Check every file before anything is renamed or written
+        for file in &planned {
+            path_scope::check_document_write(&file.path)?;
+            let current =
+                std::fs::read_to_string(&file.source).map_err(|e| format!("Failed to read {}: {}", file.source, e))?;
+            if replace::content_hash(&current) != file.hash {
+                return Err(format!("File changed while its links were updated: {}", file.source));
+            }
+        }
+        let sources: Vec<PathBuf> = planned.iter().map(|file| PathBuf::from(&file.source)).collect();
+        let backups = if planned.is_empty() {
+            Vec::new()
+        } else {
+            replace::back_up_files(&sources, &replace::new_backup_dir()?)?
+        };
+        commands::rename_path(&old_path, &new_path)?;
+
+        let mut written = Vec::with_capacity(planned.len());
+        let mut result = Ok(());
+        for (file, (source, backup)) in planned.iter().zip(sources.iter().zip(&backups)) {
+            if let Err(e) = storage::write_atomic(Path::new(&file.path), file.content.as_bytes()) {
+                // Move the file back before restoring it, since its backup is of the old path
+                if let Err(e) = std::fs::rename(&new_path, &old_path) {
+                    warn!("Failed to move {} back to {}: {}", new_path, old_path, e);
+                }
+                replace::restore_backups(&written);
+                result = Err(format!("Failed to update the links in {}, the rename was rolled back: {}", file.path, e));
+                break;
+            }
+            written.push((source.clone(), backup.clone()));
+        }
+        let mut touched: Vec<String> = planned.iter().flat_map(|file| [file.path.clone(), file.source.clone()]).collect();
+        touched.extend([old_path.clone(), new_path.clone()]);
+        invalidate_links(&touched);
+        result?;
+
+        let updates: Vec<LinkUpdate> = planned.into_iter().flat_map(|file| file.updates).collect();
+        info!("Moved {} to {}, updating {} links", old_path, new_path, updates.len());
+        Ok(updates)
+    })
+    .await
+    .map_err(|e| format!("Failed to rename file: {}", e))?
+}
@@ -0,0 +1,162 @@
+//! # Locale Module
+//!
+//! This module translates backend-owned UI strings (native menus, tray, context menu,
+//! notifications, error messages).
+//!
+//! ## Catalogs
+//! Translations live in `src-tauri/locales/<locale>.json` as flat `key → text` maps and
+//! are embedded into the binary. The locale codes match the frontend's language setting
+//! (`en`, `ja`, `zh-CN`, `zh-Hant`, `pt-BR`, ...). Missing keys fall back to English, then
+//! to the key itself. `{app}` in a text is replaced with the product name; other
+//! placeholders (`{path}`, `{reason}`) are filled in by `tr_args_in`.
+//!
+//! ## Locale Selection
+//! 1. The locale saved with `set_locale` (stored in the backend settings)
+//! 2. Otherwise the OS locale, mapped to the closest supported locale
+//! 3. Otherwise English
+//!
+//! Changing the locale rebuilds the application menu and the tray menu.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::RwLock;
+
+use tracing::info;
+
+use crate::settings;
+
+pub const DEFAULT_LOCALE: &str = "en";
+const APP_NAME: &str = "Bokuchi";
+
+// Bundled catalogs (locale code, JSON)
+const CATALOG_SOURCES: &[(&str, &str)] = &[
+    ("ar", include_str!("../locales/ar.json")),
+    ("de", include_str!("../locales/de.json")),
+    ("en", include_str!("../locales/en.json")),
+    ("es", include_str!("../locales/es.json")),
+    ("fr", include_str!("../locales/fr.json")),
+    ("hi", include_str!("../locales/hi.json")),
+    ("id", include_str!("../locales/id.json")),
+    ("ja", include_str!("../locales/ja.json")),
+    ("ko", include_str!("../locales/ko.json")),
+    ("pt-BR", include_str!("../locales/pt-BR.json")),
+    ("ru", include_str!("../locales/ru.json")),
+    ("vi", include_str!("../locales/vi.json")),
+    ("zh-CN", include_str!("../locales/zh-CN.json")),
+    ("zh-Hant", include_str!("../locales/zh-Hant.json")),
+];
+
+type Catalog = HashMap<String, String>;
+
+static CATALOGS: OnceLock<HashMap<&'static str, Catalog>> = OnceLock::new();
+static CURRENT_LOCALE: RwLock<&'static str> = RwLock::new(DEFAULT_LOCALE);
+
+fn catalogs() -> &'static HashMap<&'static str, Catalog> {
+    CATALOGS.get_or_init(|| {
+        CATALOG_SOURCES
+            .iter()
+            .map(|(code, json)| {
+                let catalog: Catalog = serde_json::from_str(json)
+                    .unwrap_or_else(|e| panic!("Invalid bundled locale {}: {}", code, e));
+                (*code, catalog)
+            })
+            .collect()
+    })
+}
+
+// Map a language tag ("ja-JP", "zh_TW", "pt", "en-US.UTF-8") to a supported locale
+pub fn resolve_locale(tag: &str) -> Option<&'static str> {
+    let tag = tag.split('.').next().unwrap_or(tag).replace('_', "-");
+    let lower = tag.to_ascii_lowercase();
+    if let Some((code, _)) = CATALOG_SOURCES.iter().find(|(code, _)| code.eq_ignore_ascii_case(&lower)) {
+        return Some(code);
+    }
+    let language = lower.split('-').next().unwrap_or("");
+    match language {
+        "zh" => {
+            let traditional = ["hant", "tw", "hk", "mo"]
+                .iter()
+                .any(|part| lower.split('-').skip(1).any(|p| p == *part));
+            Some(if traditional { "zh-Hant" } else { "zh-CN" })
+        }
+        "pt" => Some("pt-BR"),
+        _ => CATALOG_SOURCES
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(language))
+            .map(|(code, _)| *code),
+    }
+}
+
+// Detect the OS locale
+pub fn detect_system_locale() -> &'static str {
+    sys_locale::get_locale()
+        .and_then(|tag| resolve_locale(&tag))
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+// Pick the locale from the settings or the OS (during setup, and when settings change)
+pub fn select_locale() {
+    let locale = settings::current_settings()
+        .locale
+        .as_deref()
+        .and_then(resolve_locale)
+        .unwrap_or_else(detect_system_locale);
+    if let Ok(mut current) = CURRENT_LOCALE.write() {
+        *current = locale;
+    }
+    info!("Locale: {}", locale);
+}
+
+// Current locale code
+pub fn current_locale() -> &'static str {
+    CURRENT_LOCALE.read().map(|l| *l).unwrap_or(DEFAULT_LOCALE)
+}
+
+// Translate `key` into `locale`
+pub fn tr_in(locale: &str, key: &str) -> String {
+    let catalogs = catalogs();
+    let text = catalogs
+        .get(locale)
+        .and_then(|c| c.get(key))
+        .or_else(|| catalogs.get(DEFAULT_LOCALE).and_then(|c| c.get(key)))
+        .map(String::as_str)
+        .unwrap_or(key);
+    text.replace("{app}", APP_NAME)
+}
+
+// Translate `key` into the current locale
+pub fn tr(key: &str) -> String {
+    tr_in(current_locale(), key)
+}
+
+// Translate `key` into `locale` and replace `{name}` with the value of each argument
+pub fn tr_args_in(locale: &str, key: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(tr_in(locale, key), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+// Tauri command: Get the current locale
+#[tauri::command]
+pub fn get_locale() -> String {
+    current_locale().to_string()
+}
+
+// Tauri command: Switch the locale of backend strings. Accepts any language tag and
+// returns the supported locale it was mapped to.
+#[tauri::command]
+pub fn set_locale(app_handle: tauri::AppHandle, locale: String) -> Result<String, String> {
+    let resolved = resolve_locale(&locale).ok_or_else(|| format!("Unsupported locale: {}", locale))?;
+    if let Ok(mut current) = CURRENT_LOCALE.write() {
+        *current = resolved;
+    }
+
+    let mut new_settings = settings::current_settings();
+    if new_settings.locale.as_deref() != Some(resolved) {
+        new_settings.locale = Some(resolved.to_string());
+        settings::replace_settings(new_settings)?;
+    }
+
+    crate::menu::refresh_localized_menus(&app_handle);
+    info!("Locale set to {}", resolved);
+    Ok(resolved.to_string())
+}
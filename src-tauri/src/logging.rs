@@ -0,0 +1,302 @@
+//! # Logging Module
+//!
+//! This module sets up structured logging with `tracing` so backend (and frontend) logs
+//! survive in packaged builds, where stdout is not visible.
+//!
+//! ## Sinks
+//! - **stdout**: Human-readable output for development
+//! - **Log file**: Daily-rotated `bokuchi.<date>.log` in the app log directory, keeping the
+//!   last `MAX_LOG_FILES` files
+//!
+//! Logging starts at the top of `run()`; the log file is attached in `setup` once the app
+//! log directory is known (events before that only reach stdout).
+//!
+//! ## Frontend Messages
+//! `log_from_frontend` writes messages of the WebView under the `frontend` target. So that
+//! a misbehaving frontend loop cannot fill the disk or flood stdout, each message is cut
+//! to `MAX_FRONTEND_MESSAGE_CHARS` characters, line breaks are escaped and other control
+//! characters removed (a message cannot forge log lines), and at most
+//! `FRONTEND_LOG_BURST` messages per second are written. Dropped messages are counted and
+//! reported once the flood ends.
+//!
+//! ## Commands
+//! - `get_recent_logs`: Last lines of the log file(s), filtered by minimum level
+//! - `set_log_level`: Change the level at runtime (not persisted)
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
+use tracing_appender::rolling::{RollingFileAppender, RollingWriter, Rotation};
+use tracing_subscriber::fmt::writer::{EitherWriter, MakeWriter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::reload;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Registry;
+
+const LOG_FILE_PREFIX: &str = "bokuchi";
+const LOG_FILE_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 7;
+// Default number of lines returned by `get_recent_logs`
+const DEFAULT_LOG_LINES: usize = 200;
+// Target of messages logged by the frontend
+const FRONTEND_TARGET: &str = "frontend";
+// Limits for `log_from_frontend`
+pub const MAX_FRONTEND_MESSAGE_CHARS: usize = 4000;
+pub const FRONTEND_LOG_BURST: u32 = 50;
+const FRONTEND_LOG_WINDOW: Duration = Duration::from_secs(1);
+
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+static LOG_APPENDER: OnceLock<RollingFileAppender> = OnceLock::new();
+static LEVEL_HANDLE: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+static FRONTEND_LIMITER: OnceLock<Mutex<RateLimiter>> = OnceLock::new();
+
+// Writer for the file layer: the rolling appender once attached, a sink before that
+struct LogFileWriter;
+
+impl<'a> MakeWriter<'a> for LogFileWriter {
+    type Writer = EitherWriter<RollingWriter<'a>, std::io::Sink>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        match LOG_APPENDER.get() {
+            Some(appender) => EitherWriter::A(appender.make_writer()),
+            None => EitherWriter::B(std::io::sink()),
+        }
+    }
+}
+
+fn default_level() -> LevelFilter {
+    if cfg!(debug_assertions) {
+        LevelFilter::DEBUG
+    } else {
+        LevelFilter::INFO
+    }
+}
+
+// Parse a level name (trace, debug, info, warn, error, off), case-insensitively
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level.trim()).map_err(|_| format!("Unknown log level: {}", level))
+}
+
+// Install the global subscriber (called once at the top of `run()`)
+pub fn init_logging() {
+    let (filter, handle) = reload::Layer::new(default_level());
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(LogFileWriter),
+        )
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Failed to initialize logging: {}", e);
+        return;
+    }
+    let _ = LEVEL_HANDLE.set(handle);
+}
+
+// Start writing to the log file in the app log directory
+pub fn attach_log_file(app: &tauri::AppHandle) {
+    let dir = match app.path().app_log_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            tracing::warn!("Failed to resolve app log directory: {}", e);
+            return;
+        }
+    };
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir);
+    match appender {
+        Ok(appender) => {
+            let _ = LOG_APPENDER.set(appender);
+            let _ = LOG_DIR.set(dir.clone());
+            tracing::info!("Logging to {:?}", dir);
+        }
+        Err(e) => tracing::warn!("Failed to create log file in {:?}: {}", dir, e),
+    }
+}
+
+// Extract the level of a formatted log line ("<timestamp>  INFO target: message")
+pub fn line_level(line: &str) -> Option<Level> {
+    line.split_whitespace()
+        .take(2)
+        .find_map(|token| Level::from_str(token).ok())
+}
+
+// Keep the last `max_lines` lines at or above `min_level`. Lines without a level (e.g.
+// the continuation of a multi-line message) follow the entry they belong to.
+pub fn filter_log_lines(content: &str, min_level: LevelFilter, max_lines: usize) -> Vec<String> {
+    let mut included = false;
+    let mut result: Vec<String> = Vec::new();
+    for line in content.lines() {
+        if let Some(level) = line_level(line) {
+            included = min_level >= level;
+        }
+        if included {
+            result.push(line.to_string());
+        }
+    }
+    let skip = result.len().saturating_sub(max_lines);
+    result.split_off(skip)
+}
+
+// Make a frontend message safe to log: at most `MAX_FRONTEND_MESSAGE_CHARS` characters,
+// line breaks escaped, other control characters removed
+pub fn sanitize_frontend_message(message: &str) -> String {
+    let mut sanitized = String::with_capacity(message.len().min(MAX_FRONTEND_MESSAGE_CHARS));
+    for (index, c) in message.chars().enumerate() {
+        if index == MAX_FRONTEND_MESSAGE_CHARS {
+            sanitized.push_str("... (truncated)");
+            break;
+        }
+        match c {
+            '\n' => sanitized.push_str("\\n"),
+            '\t' => sanitized.push(' '),
+            c if c.is_control() => {}
+            c => sanitized.push(c),
+        }
+    }
+    sanitized
+}
+
+// Fixed-window rate limiter: `limit` messages per `window`
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    window_start: Instant,
+    count: u32,
+    dropped: u64,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration, now: Instant) -> Self {
+        Self {
+            limit,
+            window,
+            window_start: now,
+            count: 0,
+            dropped: 0,
+        }
+    }
+
+    // Whether a message may be written now. When a new window starts, the number of
+    // messages dropped in the previous one is returned as well (0 if none).
+    pub fn check(&mut self, now: Instant) -> (bool, u64) {
+        let mut reported = 0;
+        if now.duration_since(self.window_start) >= self.window {
+            reported = std::mem::take(&mut self.dropped);
+            self.window_start = now;
+            self.count = 0;
+        }
+        if self.count < self.limit {
+            self.count += 1;
+            (true, reported)
+        } else {
+            self.dropped += 1;
+            (false, reported)
+        }
+    }
+}
+
+// Write a message of the frontend (sanitized and rate-limited)
+pub fn log_frontend_message(message: &str, level: Option<&str>) {
+    let limiter = FRONTEND_LIMITER.get_or_init(|| {
+        Mutex::new(RateLimiter::new(
+            FRONTEND_LOG_BURST,
+            FRONTEND_LOG_WINDOW,
+            Instant::now(),
+        ))
+    });
+    let Ok((allowed, dropped)) = limiter
+        .lock()
+        .map(|mut limiter| limiter.check(Instant::now()))
+    else {
+        return;
+    };
+    if dropped > 0 {
+        tracing::warn!(target: FRONTEND_TARGET, "Dropped {} frontend log messages (rate limit)", dropped);
+    }
+    if !allowed {
+        return;
+    }
+
+    let message = sanitize_frontend_message(message);
+    match level.map(str::to_ascii_lowercase).as_deref() {
+        Some("trace") => tracing::trace!(target: FRONTEND_TARGET, "{}", message),
+        Some("debug") => tracing::debug!(target: FRONTEND_TARGET, "{}", message),
+        Some("warn") => tracing::warn!(target: FRONTEND_TARGET, "{}", message),
+        Some("error") => tracing::error!(target: FRONTEND_TARGET, "{}", message),
+        _ => tracing::info!(target: FRONTEND_TARGET, "{}", message),
+    }
+}
+
+// Log files in `dir`, newest first (the date in the name sorts chronologically)
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with(LOG_FILE_PREFIX) && n.ends_with(LOG_FILE_SUFFIX))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files.reverse();
+    files
+}
+
+// Tauri command: Get the last `lines` log lines at or above `level` (default: all levels)
+#[tauri::command]
+pub fn get_recent_logs(level: Option<String>, lines: Option<usize>) -> Result<Vec<String>, String> {
+    let min_level = match level {
+        Some(level) => parse_level(&level)?,
+        None => LevelFilter::TRACE,
+    };
+    let max_lines = lines.unwrap_or(DEFAULT_LOG_LINES);
+    let Some(dir) = LOG_DIR.get() else {
+        return Ok(Vec::new());
+    };
+
+    // Walk back through older files until enough lines are collected
+    let mut collected: Vec<String> = Vec::new();
+    for file in log_files(dir) {
+        let content = fs::read_to_string(&file).map_err(|e| format!("Failed to read log file: {}", e))?;
+        let mut older = filter_log_lines(&content, min_level, max_lines - collected.len());
+        older.append(&mut collected);
+        collected = older;
+        if collected.len() >= max_lines {
+            break;
+        }
+    }
+    Ok(collected)
+}
+
+// Tauri command: Change the log level at runtime
+#[tauri::command]
+pub fn set_log_level(level: String) -> Result<(), String> {
+    let filter = parse_level(&level)?;
+    let handle = LEVEL_HANDLE
+        .get()
+        .ok_or_else(|| "Logging is not initialized".to_string())?;
+    handle
+        .modify(|current| *current = filter)
+        .map_err(|e| format!("Failed to set log level: {}", e))?;
+    tracing::info!("Log level set to {}", filter);
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! # Markdown Module
+//!
+//! This module holds small line-based helpers for scanning Markdown source, shared by the
+//! workspace indexes (tags, links, search). They deliberately avoid a full parser: the
+//! indexes only need to know which text is prose and which is code.
+//!
+//! ## Heading Slugs
+//! `slugify_heading` and `unique_anchor` are the one GitHub-compatible anchor algorithm of
+//! the app: the outline, deep links, wiki-links, includes and exports all use them, and
+//! the frontend gets the same slugs through `slugify_heading` and `heading_anchors`.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::frontmatter;
+
+lazy_static! {
+    static ref INLINE_CODE: Regex = Regex::new(r"`[^`]*`").unwrap();
+    static ref SLUG_REMOVED: Regex = Regex::new(r"[^\p{L}\p{M}\p{N}\p{Pc} -]").unwrap();
+}
+
+// Lines of a Markdown body outside fenced code blocks, with their 0-based index
+pub fn lines_outside_code(body: &str) -> Vec<(usize, &str)> {
+    let mut fence: Option<&str> = None;
+    let mut lines = Vec::new();
+    for (index, line) in body.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let marker = if trimmed.starts_with("```") {
+            Some("```")
+        } else if trimmed.starts_with("~~~") {
+            Some("~~~")
+        } else {
+            None
+        };
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some(m),
+            (Some(open), Some(m)) if open == m => fence = None,
+            (None, None) => lines.push((index, line)),
+            _ => {}
+        }
+    }
+    lines
+}
+
+// Byte ranges of the inline code spans of a line, backticks included
+pub fn inline_code_ranges(line: &str) -> Vec<Range<usize>> {
+    INLINE_CODE.find_iter(line).map(|m| m.range()).collect()
+}
+
+// Blank out inline code spans, keeping byte offsets of the remaining text valid
+pub fn mask_inline_code(line: &str) -> Cow<'_, str> {
+    INLINE_CODE.replace_all(line, |caps: &regex::Captures| " ".repeat(caps[0].len()))
+}
+
+// Title of a document: the frontmatter `title`, its first level-1 heading, or the file name
+pub fn document_title(path: &Path, content: &str) -> String {
+    let from_frontmatter = frontmatter::parse_frontmatter(content)
+        .and_then(|value| value.get("title").and_then(|t| t.as_str()).map(str::to_string));
+    let split = frontmatter::split_frontmatter(content);
+    from_frontmatter
+        .or_else(|| {
+            lines_outside_code(split.body)
+                .into_iter()
+                .find_map(|(_, line)| line.strip_prefix("# ").map(|t| t.trim().to_string()))
+        })
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+}
+
+// GitHub-compatible slug of a heading ("Hello, World!" → "hello-world"), used for anchors:
+// lowercase, letters, marks, numbers, `_` and `-` kept, spaces replaced by `-`, anything
+// else removed
+pub fn slugify_heading(text: &str) -> String {
+    SLUG_REMOVED.replace_all(&text.trim().to_lowercase(), "").replace(' ', "-")
+}
+
+// Make `slug` unique among the anchors of a document the way GitHub does: repeated slugs
+// get `-1`, `-2`, ... appended. `occurrences` holds the anchors handed out so far.
+pub fn unique_anchor(slug: String, occurrences: &mut HashMap<String, usize>) -> String {
+    let mut anchor = slug.clone();
+    while occurrences.contains_key(&anchor) {
+        let count = occurrences.entry(slug.clone()).or_insert(0);
+        *count += 1;
+        anchor = format!("{}-{}", slug, count);
+    }
+    occurrences.insert(anchor.clone(), 0);
+    anchor
+}
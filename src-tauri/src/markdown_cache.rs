@@ -0,0 +1,87 @@
+//! # Markdown Cache Module
+//!
+//! `process_markdown` runs on every preview refresh, mostly with a document that did not
+//! change since the last call. This module keeps the last `CACHE_CAPACITY` expansions in
+//! an LRU cache, so unchanged documents and repeated previews return without expanding
+//! the variables again.
+//!
+//! ## Cache Key
+//! - SHA-256 of the content (file-level `<!-- @var -->` definitions are part of it)
+//! - SHA-256 of the variable set: the global and workspace variables at the time of the
+//!   call, sorted by name, and the processing options (`ProcessOptions`)
+//!
+//! Changing a global or workspace variable changes the key, so stale results are never
+//! returned.
+//! `clear_markdown_cache` empties the cache.
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, OnceLock};
+
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::variable_processor::ProcessOptions;
+
+// Number of expansions kept
+const CACHE_CAPACITY: usize = 64;
+// Larger documents are expanded every time instead of being kept in memory
+const MAX_CACHED_CONTENT_LEN: usize = 1024 * 1024;
+
+// (content hash, variable-set hash)
+pub type CacheKey = ([u8; 32], [u8; 32]);
+
+static CACHE: OnceLock<Mutex<LruCache<CacheKey, String>>> = OnceLock::new();
+
+fn cache_cell() -> &'static Mutex<LruCache<CacheKey, String>> {
+    CACHE.get_or_init(|| {
+        let capacity = NonZeroUsize::new(CACHE_CAPACITY).unwrap_or(NonZeroUsize::MIN);
+        Mutex::new(LruCache::new(capacity))
+    })
+}
+
+// Cache key of a content expanded with a set of variables (independent of map order)
+// and processing options. None if the content is too large to be cached.
+pub fn cache_key(
+    content: &str,
+    variables: &HashMap<String, String>,
+    options: &ProcessOptions,
+) -> Option<CacheKey> {
+    if content.len() > MAX_CACHED_CONTENT_LEN {
+        return None;
+    }
+    let mut names: Vec<&String> = variables.keys().collect();
+    names.sort();
+    let mut hasher = Sha256::new();
+    for name in names {
+        // Lengths keep "a"+"bc" apart from "ab"+"c"
+        hasher.update((name.len() as u64).to_le_bytes());
+        hasher.update(name.as_bytes());
+        hasher.update((variables[name].len() as u64).to_le_bytes());
+        hasher.update(variables[name].as_bytes());
+    }
+    hasher.update(serde_json::to_vec(options).unwrap_or_default());
+    Some((Sha256::digest(content.as_bytes()).into(), hasher.finalize().into()))
+}
+
+// Cached expansion for a key
+pub fn get(key: &CacheKey) -> Option<String> {
+    cache_cell().lock().ok()?.get(key).cloned()
+}
+
+// Remember an expansion (evicting the least recently used one when full)
+pub fn insert(key: CacheKey, expanded: String) {
+    if let Ok(mut cache) = cache_cell().lock() {
+        cache.put(key, expanded);
+    }
+}
+
+// Tauri command: Clear the Markdown processing cache
+#[tauri::command]
+pub fn clear_markdown_cache() {
+    if let Ok(mut cache) = cache_cell().lock() {
+        debug!("Clearing {} cached Markdown expansions", cache.len());
+        cache.clear();
+    }
+}
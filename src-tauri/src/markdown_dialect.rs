@@ -0,0 +1,85 @@
+//! # Markdown Dialect Module
+//!
+//! This module decides which Markdown extensions the backend's parser (pulldown-cmark)
+//! recognizes, so the outline and document structure, the features built on them
+//! (heading numbering, includes, merging, tasks) and the exports agree on the dialect.
+//!
+//! ## Extensions
+//! Tables, strikethrough, task lists, footnotes, autolinks and heading attributes are
+//! switched in the settings (`markdown_extensions`). YAML frontmatter is always
+//! recognized.
+//!
+//! ## Autolinks
+//! pulldown-cmark only links `<https://...>`. With autolinks enabled, bare URLs starting
+//! with `http://`, `https://` or `www.` in text become links as in GitHub Flavored
+//! Markdown (`link_bare_urls`); trailing punctuation is not part of the link.
+
+use lazy_static::lazy_static;
+use pulldown_cmark::{CowStr, Event, LinkType, Options, Tag, TagEnd};
+use regex::Regex;
+
+use crate::settings::{self, MarkdownExtensions};
+
+lazy_static! {
+    static ref BARE_URL: Regex = Regex::new(r#"\b(?:https?://|www\.)[^\s<]*[^\s<?!.,:;*_~'")\]]"#).unwrap();
+}
+
+// Parser options of an extension set
+pub fn parser_options(extensions: &MarkdownExtensions) -> Options {
+    let mut options = Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+    options.set(Options::ENABLE_TABLES, extensions.tables);
+    options.set(Options::ENABLE_STRIKETHROUGH, extensions.strikethrough);
+    options.set(Options::ENABLE_TASKLISTS, extensions.task_lists);
+    options.set(Options::ENABLE_FOOTNOTES, extensions.footnotes);
+    options.set(Options::ENABLE_HEADING_ATTRIBUTES, extensions.heading_attributes);
+    options
+}
+
+// The extensions of the current settings
+pub fn current_extensions() -> MarkdownExtensions {
+    settings::current_settings().markdown_extensions
+}
+
+// Turn the bare URLs in the text of an event stream into links. Text inside links,
+// images and code blocks is left alone; merge adjacent text events first
+// (`TextMergeStream`), as URLs may be split across them.
+pub fn link_bare_urls<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut output = Vec::new();
+    // Depth of links, images and code blocks around the current event
+    let mut depth = 0usize;
+    for event in events {
+        match &event {
+            Event::Start(Tag::Link { .. } | Tag::Image { .. } | Tag::CodeBlock(_)) => depth += 1,
+            Event::End(TagEnd::Link | TagEnd::Image | TagEnd::CodeBlock) => depth = depth.saturating_sub(1),
+            Event::Text(text) if depth == 0 && BARE_URL.is_match(text) => {
+                let mut last = 0;
+                for url in BARE_URL.find_iter(text) {
+                    if url.start() > last {
+                        output.push(Event::Text(CowStr::from(text[last..url.start()].to_string())));
+                    }
+                    let dest_url = if url.as_str().starts_with("www.") {
+                        format!("http://{}", url.as_str())
+                    } else {
+                        url.as_str().to_string()
+                    };
+                    output.push(Event::Start(Tag::Link {
+                        link_type: LinkType::Autolink,
+                        dest_url: CowStr::from(dest_url),
+                        title: CowStr::from(""),
+                        id: CowStr::from(""),
+                    }));
+                    output.push(Event::Text(CowStr::from(url.as_str().to_string())));
+                    output.push(Event::End(TagEnd::Link));
+                    last = url.end();
+                }
+                if last < text.len() {
+                    output.push(Event::Text(CowStr::from(text[last..].to_string())));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        output.push(event);
+    }
+    output
+}
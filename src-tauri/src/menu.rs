@@ -0,0 +1,383 @@
+//! # Menu Module
+//!
+//! This module builds the native application menu and routes menu clicks to the frontend.
+//!
+//! Every menu (the macOS application menu, the system tray menu and the editor context
+//! menu) funnels its clicks through `handle_menu_event`, which maps the item ID to a
+//! frontend event name and emits it to the focused editor window. The frontend listens
+//! for these `menu-*` events and runs the same handlers it uses for keyboard shortcuts.
+//!
+//! ## Open Recent
+//! The File menu contains an "Open Recent" submenu mirroring the backend MRU list
+//! (`recent_files`). It is rebuilt whenever the list changes; each entry re-emits the
+//! regular `open-file` event, and "Clear Menu" empties the list.
+//!
+//! ## Localization
+//! Labels come from the locale catalogs (`locale::tr`), and submenus are built and found
+//! by ID, so nothing depends on the display language. `refresh_localized_menus` rebuilds
+//! the menus after the locale changed.
+//!
+//! ## Item State
+//! Document-related items (Save, Save As, Save with Variables Applied, Close Tab) are
+//! enabled/disabled by the frontend through `set_menu_state`, based on whether a
+//! document is focused and dirty.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use tauri::menu::{IsMenuItem, MenuItem, MenuItemKind, PredefinedMenuItem, Submenu};
+use tracing::{debug, warn};
+
+use crate::context_menu;
+use crate::document_window;
+use crate::favorites;
+use crate::file_association::handle_open_file_event;
+use crate::locale::tr;
+use crate::recent_files::{self, RecentFile};
+use crate::tray;
+use crate::types::{emit_event, MenuEvent};
+
+// Submenu IDs of the application menu
+#[cfg(target_os = "macos")]
+const APP_SUBMENU_ID: &str = "app_menu";
+#[cfg(target_os = "macos")]
+const FILE_SUBMENU_ID: &str = "file_menu";
+#[cfg(target_os = "macos")]
+const EDIT_SUBMENU_ID: &str = "edit_menu";
+#[cfg(target_os = "macos")]
+const VIEW_SUBMENU_ID: &str = "view_menu";
+
+// ID of the File → Open Recent submenu
+pub const OPEN_RECENT_MENU_ID: &str = "open_recent_menu";
+// Prefix of the per-file entries in the Open Recent submenu ("open_recent_<index>")
+const OPEN_RECENT_ITEM_PREFIX: &str = "open_recent_";
+const CLEAR_RECENT_ITEM_ID: &str = "clear_recent_menu";
+
+// Menu items whose enabled state the frontend controls via `set_menu_state`
+pub const STATEFUL_MENU_ITEMS: &[&str] = &["save", "save_as", "save_with_variables", "close_tab"];
+
+// Custom menu setup (macOS only)
+#[cfg(target_os = "macos")]
+pub fn setup_app_menu(app: &tauri::AppHandle) -> tauri::Result<()> {
+    debug!("Setting up custom menu (locale: {})...", crate::locale::current_locale());
+    let menu = build_app_menu(app)?;
+
+    // アプリメニューとして反映
+    app.set_menu(menu)?;
+    apply_saved_menu_states(app);
+    debug!("Menu set successfully");
+    Ok(())
+}
+
+// Build the application menu in the current locale. Submenus and items are identified by
+// ID, never by their (translated) text.
+#[cfg(target_os = "macos")]
+fn build_app_menu(app: &tauri::AppHandle) -> tauri::Result<tauri::menu::Menu<tauri::Wry>> {
+    use tauri::menu::{AboutMetadata, Menu, HELP_SUBMENU_ID, WINDOW_SUBMENU_ID};
+
+    let package_info = app.package_info();
+    let about_metadata = AboutMetadata {
+        name: Some(package_info.name.clone()),
+        version: Some(package_info.version.to_string()),
+        ..Default::default()
+    };
+
+    let app_menu = Submenu::with_id_and_items(
+        app,
+        APP_SUBMENU_ID,
+        package_info.name.clone(),
+        true,
+        &[
+            &PredefinedMenuItem::about(app, Some(&tr("menu.about")), Some(about_metadata))?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::services(app, Some(&tr("menu.services")))?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::hide(app, Some(&tr("menu.hide")))?,
+            &PredefinedMenuItem::hide_others(app, Some(&tr("menu.hide_others")))?,
+            &PredefinedMenuItem::show_all(app, Some(&tr("menu.show_all")))?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::quit(app, Some(&tr("menu.quit")))?,
+        ],
+    )?;
+
+    // Open Recent (filled from the MRU list)
+    let open_recent = Submenu::with_id(app, OPEN_RECENT_MENU_ID, tr("menu.open_recent"), true)?;
+    fill_open_recent_submenu(app, &open_recent, &recent_files::recent_files())?;
+
+    let file_menu = Submenu::with_id_and_items(
+        app,
+        FILE_SUBMENU_ID,
+        tr("menu.file"),
+        true,
+        &[
+            &PredefinedMenuItem::close_window(app, Some(&tr("menu.close_window")))?,
+            &MenuItem::with_id(app, "new_file", tr("menu.new_file"), true, Some("CmdOrCtrl+N"))?,
+            &MenuItem::with_id(app, "open_file", tr("menu.open_file"), true, Some("CmdOrCtrl+O"))?,
+            &open_recent,
+            &MenuItem::with_id(app, "save", tr("menu.save"), true, Some("CmdOrCtrl+S"))?,
+            &MenuItem::with_id(app, "save_as", tr("menu.save_as"), true, Some("CmdOrCtrl+Shift+S"))?,
+            &MenuItem::with_id(app, "save_with_variables", tr("menu.save_with_variables"), true, None::<&str>)?,
+            // No accelerator: Cmd+W stays with Close Window
+            &MenuItem::with_id(app, "close_tab", tr("menu.close_tab"), true, None::<&str>)?,
+        ],
+    )?;
+
+    let edit_menu = Submenu::with_id_and_items(
+        app,
+        EDIT_SUBMENU_ID,
+        tr("menu.edit"),
+        true,
+        &[
+            &PredefinedMenuItem::undo(app, Some(&tr("menu.undo")))?,
+            &PredefinedMenuItem::redo(app, Some(&tr("menu.redo")))?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::cut(app, Some(&tr("menu.cut")))?,
+            &PredefinedMenuItem::copy(app, Some(&tr("menu.copy")))?,
+            &PredefinedMenuItem::paste(app, Some(&tr("menu.paste")))?,
+            &PredefinedMenuItem::select_all(app, Some(&tr("menu.select_all")))?,
+        ],
+    )?;
+
+    let view_menu = Submenu::with_id_and_items(
+        app,
+        VIEW_SUBMENU_ID,
+        tr("menu.view"),
+        true,
+        &[&PredefinedMenuItem::fullscreen(app, Some(&tr("menu.fullscreen")))?],
+    )?;
+
+    // WINDOW_SUBMENU_ID / HELP_SUBMENU_ID make macOS treat these as the Window and Help menus
+    let window_menu = Submenu::with_id_and_items(
+        app,
+        WINDOW_SUBMENU_ID,
+        tr("menu.window"),
+        true,
+        &[
+            &PredefinedMenuItem::minimize(app, Some(&tr("menu.minimize")))?,
+            &PredefinedMenuItem::maximize(app, Some(&tr("menu.maximize")))?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::close_window(app, Some(&tr("menu.close_window")))?,
+        ],
+    )?;
+
+    let help_menu = Submenu::with_id_and_items(
+        app,
+        HELP_SUBMENU_ID,
+        tr("menu.help"),
+        true,
+        &[&MenuItem::with_id(app, "help", tr("menu.help_item"), true, Some("F1"))?],
+    )?;
+
+    Menu::with_items(app, &[&app_menu, &file_menu, &edit_menu, &view_menu, &window_menu, &help_menu])
+}
+
+// Rebuild every native menu after the locale changed
+pub fn refresh_localized_menus(app: &tauri::AppHandle) {
+    #[cfg(target_os = "macos")]
+    if let Err(e) = setup_app_menu(app) {
+        warn!("Failed to rebuild application menu: {}", e);
+    }
+    tray::refresh_tray_menu(app);
+}
+
+// Find a menu item by ID, searching nested submenus
+pub fn find_menu_item(items: Vec<MenuItemKind<tauri::Wry>>, id: &str) -> Option<MenuItemKind<tauri::Wry>> {
+    for item in items {
+        if item.id() == id {
+            return Some(item);
+        }
+        if let MenuItemKind::Submenu(submenu) = &item
+            && let Some(found) = find_menu_item(submenu.items().unwrap_or_default(), id)
+        {
+            return Some(found);
+        }
+    }
+    None
+}
+
+// Replace the contents of the Open Recent submenu with the given MRU list
+fn fill_open_recent_submenu(
+    app: &tauri::AppHandle,
+    submenu: &Submenu<tauri::Wry>,
+    files: &[RecentFile],
+) -> tauri::Result<()> {
+    while submenu.remove_at(0)?.is_some() {}
+
+    let mut items: Vec<Box<dyn IsMenuItem<tauri::Wry>>> = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        items.push(Box::new(MenuItem::with_id(
+            app,
+            format!("{}{}", OPEN_RECENT_ITEM_PREFIX, index),
+            &file.name,
+            true,
+            None::<&str>,
+        )?));
+    }
+    if !files.is_empty() {
+        items.push(Box::new(PredefinedMenuItem::separator(app)?));
+    }
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        CLEAR_RECENT_ITEM_ID,
+        tr("menu.clear_recent"),
+        !files.is_empty(),
+        None::<&str>,
+    )?));
+
+    let refs: Vec<&dyn IsMenuItem<tauri::Wry>> = items.iter().map(|i| i.as_ref()).collect();
+    submenu.append_items(&refs)
+}
+
+// Rebuild the Open Recent submenu after the MRU list changed.
+// A no-op on platforms without an application menu.
+pub fn rebuild_open_recent_menu(app: &tauri::AppHandle, files: &[RecentFile]) {
+    let Some(menu) = app.menu() else {
+        return;
+    };
+    if let Some(MenuItemKind::Submenu(submenu)) =
+        find_menu_item(menu.items().unwrap_or_default(), OPEN_RECENT_MENU_ID)
+        && let Err(e) = fill_open_recent_submenu(app, &submenu, files)
+    {
+        warn!("Failed to rebuild Open Recent menu: {}", e);
+    }
+}
+
+// Parse the MRU index out of an Open Recent entry ID
+pub fn open_recent_index(id: &str) -> Option<usize> {
+    id.strip_prefix(OPEN_RECENT_ITEM_PREFIX)?.parse().ok()
+}
+
+// Map a menu item ID to the frontend event it triggers
+pub fn menu_event_name(id: &str) -> Option<&'static str> {
+    match id {
+        "new_file" | "tray_new_note" => Some("menu-new-file"),
+        "open_file" => Some("menu-open-file"),
+        "save" => Some("menu-save"),
+        "save_as" => Some("menu-save-as"),
+        "save_with_variables" => Some("menu-save-with-variables"),
+        "help" => Some("menu-help"),
+        "close_tab" => Some("menu-close-tab"),
+        "tray_open_recent" => Some("menu-open-recent"),
+        _ => None,
+    }
+}
+
+// Handle a click on any native menu item
+pub fn handle_menu_event(app: &tauri::AppHandle, id: &str) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    debug!("[{}] Menu event received: {} (thread: {:?})",
+        timestamp, id, std::thread::current().id());
+
+    // Items that act on the backend, the window or the process directly
+    match id {
+        "tray_toggle_window" => {
+            tray::toggle_main_window(app);
+            return;
+        }
+        "tray_quit" => {
+            app.exit(0);
+            return;
+        }
+        CLEAR_RECENT_ITEM_ID => {
+            if let Err(e) = recent_files::clear_recent_files(app.clone()) {
+                warn!("[{}] Failed to clear recent files: {}", timestamp, e);
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    if let Some(action) = context_menu::context_action(id) {
+        context_menu::handle_context_action(app, action);
+        return;
+    }
+
+    if let Some(index) = tray::favorite_index(id) {
+        match favorites::favorites().get(index) {
+            Some(file) => {
+                tray::show_main_window(app);
+                handle_open_file_event(app, file.path.clone());
+            }
+            None => warn!("[{}] Stale Favorites entry: {}", timestamp, id),
+        }
+        return;
+    }
+
+    if let Some(index) = open_recent_index(id) {
+        match recent_files::recent_files().get(index) {
+            Some(file) => handle_open_file_event(app, file.path.clone()),
+            None => warn!("[{}] Stale Open Recent entry: {}", timestamp, id),
+        }
+        return;
+    }
+
+    match menu_event_name(id) {
+        Some(event) => {
+            // Items triggered from the tray must bring the hidden window back first
+            if id.starts_with("tray_") {
+                tray::show_main_window(app);
+            }
+            // Only the window the user is working in handles the command
+            let target = document_window::focused_window_label(app);
+            let result = emit_event(app, Some(target.as_str()), &MenuEvent { event });
+            debug!("[{}] Emitted {} to {}: {:?}", timestamp, event, target, result);
+        }
+        None => {
+            warn!("[{}] Unknown menu item clicked: {}", timestamp, id);
+        }
+    }
+}
+
+// Last enabled state per item from `set_menu_state`, re-applied when the menu is rebuilt
+static MENU_ITEM_STATES: OnceLock<Mutex<HashMap<String, bool>>> = OnceLock::new();
+
+fn menu_item_states() -> &'static Mutex<HashMap<String, bool>> {
+    MENU_ITEM_STATES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Apply enabled states to the current application menu
+fn apply_menu_states(app: &tauri::AppHandle, states: &HashMap<String, bool>) -> Result<(), String> {
+    let Some(menu) = app.menu() else {
+        return Ok(());
+    };
+    let items = menu.items().map_err(|e| format!("Failed to read menu: {}", e))?;
+    for (id, enabled) in states {
+        if let Some(MenuItemKind::MenuItem(item)) = find_menu_item(items.clone(), id) {
+            item.set_enabled(*enabled)
+                .map_err(|e| format!("Failed to update menu item {}: {}", id, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn apply_saved_menu_states(app: &tauri::AppHandle) {
+    let states = menu_item_states().lock().map(|s| s.clone()).unwrap_or_default();
+    if let Err(e) = apply_menu_states(app, &states) {
+        warn!("Failed to restore menu item states: {}", e);
+    }
+}
+
+// Validate a `set_menu_state` request, rejecting IDs the frontend may not toggle
+pub fn validate_menu_states(states: &HashMap<String, bool>) -> Result<(), String> {
+    match states.keys().find(|id| !STATEFUL_MENU_ITEMS.contains(&id.as_str())) {
+        Some(id) => Err(format!("Unknown menu item: {}", id)),
+        None => Ok(()),
+    }
+}
+
+// Tauri command: Enable/disable document-related menu items.
+// `states` maps item IDs (see `STATEFUL_MENU_ITEMS`) to their enabled state. Items
+// missing from the current platform's menu are ignored.
+#[tauri::command]
+pub fn set_menu_state(app_handle: tauri::AppHandle, states: HashMap<String, bool>) -> Result<(), String> {
+    validate_menu_states(&states)?;
+    if let Ok(mut saved) = menu_item_states().lock() {
+        saved.extend(states.iter().map(|(id, enabled)| (id.clone(), *enabled)));
+    }
+    apply_menu_states(&app_handle, &states)
+}
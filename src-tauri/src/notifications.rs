@@ -0,0 +1,100 @@
+//! # Notifications Module
+//!
+//! This module raises OS notifications for things that happen in the background while
+//! the user works in another app:
+//! - A merged document ("book build") was saved (`document_merge`)
+//! - Workspace sync kept a conflict copy (`sync`)
+//! - A pinned file was changed outside the app (`file_watch`)
+//!
+//! Nothing is raised while a window of the app is focused (the app shows these itself),
+//! or when notifications are turned off in the settings.
+//!
+//! ## Focusing the Tab
+//! Desktop notifications have no click callbacks, but clicking one brings the app to the
+//! front. The file of the last notification is kept for `PENDING_FOCUS_TTL`; when a
+//! window of the app is focused within that time, a `focus-tab` event with the file is
+//! emitted so the frontend switches to (or opens) its tab.
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+use tracing::{debug, warn};
+
+use crate::locale::tr;
+use crate::settings;
+use crate::types::{emit_event, FocusTabEvent};
+
+// How long after a notification focusing the app switches to its file
+pub const PENDING_FOCUS_TTL: Duration = Duration::from_secs(120);
+
+// File of the last notification, waiting for the app to be focused
+#[derive(Debug, Clone)]
+pub struct PendingFocus {
+    pub path: String,
+    pub raised_at: Instant,
+}
+
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+static PENDING_FOCUS: Mutex<Option<PendingFocus>> = Mutex::new(None);
+
+// Raise notifications from now on (called once during setup)
+pub fn init_notifications(app_handle: tauri::AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+// The file to focus, if the notification for it is recent enough. Clears it either way.
+pub fn take_pending_focus(pending: &mut Option<PendingFocus>, now: Instant, ttl: Duration) -> Option<String> {
+    pending
+        .take()
+        .filter(|focus| now.saturating_duration_since(focus.raised_at) <= ttl)
+        .map(|focus| focus.path)
+}
+
+// Raise a notification titled with the catalog text `title_key`. `path` is the file
+// whose tab is focused when the app is brought to the front.
+pub fn notify(title_key: &str, body: &str, path: Option<&str>) {
+    let Some(app_handle) = APP_HANDLE.get() else {
+        return;
+    };
+    if settings::current_settings().mute_notifications {
+        return;
+    }
+    let focused = app_handle
+        .webview_windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false));
+    if focused {
+        debug!("Not raising a notification while the app is focused: {}", body);
+        return;
+    }
+
+    if let Ok(mut pending) = PENDING_FOCUS.lock() {
+        *pending = path.map(|path| PendingFocus {
+            path: path.to_string(),
+            raised_at: Instant::now(),
+        });
+    }
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(tr(title_key))
+        .body(body)
+        .show()
+    {
+        warn!("Failed to show notification: {}", e);
+    }
+}
+
+// Switch to the file of a recent notification when a window of the app is focused
+pub fn handle_focused(window: &tauri::Window) {
+    let path = match PENDING_FOCUS.lock() {
+        Ok(mut pending) => take_pending_focus(&mut pending, Instant::now(), PENDING_FOCUS_TTL),
+        Err(_) => None,
+    };
+    if let Some(path) = path {
+        debug!("Focusing the tab of {} after a notification", path);
+        let _ = emit_event(window, None, &FocusTabEvent { path });
+    }
+}
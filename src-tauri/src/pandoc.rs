@@ -0,0 +1,466 @@
+//! # Pandoc Module
+//!
+//! This module bridges to an installed pandoc, making formats Bokuchi does not handle
+//! itself (odt, docx, rst, mediawiki, latex, ...) available for import and export.
+//!
+//! ## Detection
+//! The pandoc configured in the backend settings (`pandoc_path`) is used if set.
+//! Otherwise `pandoc` is looked up in PATH and in the usual install locations, since apps
+//! started from the macOS Dock or the Windows Start menu do not see the shell's PATH.
+//! Pandoc is optional: without it, `pandoc_info` returns None and the frontend hides the
+//! extra formats. A detected pandoc is cached per configured path, so conversions do not
+//! start three processes to find it again; `pandoc_info` always detects it anew (e.g.
+//! after pandoc was installed or updated).
+//!
+//! ## Conversion
+//! `pandoc_convert` passes the input on stdin (or as a file, for binary formats such as
+//! odt and docx) and returns the output, or writes it to a file. Format names are
+//! pandoc's own (`markdown`, `gfm`, `rst`, `odt`, `latex`, ...), with extensions such as
+//! `markdown+smart`.
+//!
+//! pandoc always runs with `--sandbox` (pandoc 2.15 or later is required), so a document
+//! cannot pull local files into the output through images or includes: only the input
+//! and output files are accessed. `resource_path` must lie inside the path scope.
+//!
+//! ## Import
+//! `import_document` converts a Word (docx) or OpenDocument (odt) file to Markdown. pandoc
+//! extracts the embedded images into a temporary folder; they are then moved into the
+//! image folder of the document (`images` by default, as for pasted images), reusing
+//! files with the same content, and the links of the Markdown point there.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use tracing::{info, warn};
+
+use crate::commands::write_image_dedup;
+use crate::path_scope;
+use crate::settings;
+
+lazy_static! {
+    // A format with optional extensions; never starts with "-", so it cannot be read as
+    // an option
+    static ref FORMAT: Regex = Regex::new(r"^[a-z][a-z0-9_]*(?:[+-][a-z][a-z0-9_]*)*$").unwrap();
+}
+
+// An installed pandoc
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PandocInfo {
+    pub path: String,
+    // e.g. "3.1.11"
+    pub version: String,
+    pub input_formats: Vec<String>,
+    pub output_formats: Vec<String>,
+}
+
+// Options of a conversion
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PandocOptions {
+    // `input` is the path of a file to convert instead of the text itself
+    pub input_is_path: bool,
+    // Write the result to this file instead of returning it (needed for binary formats)
+    pub output_path: Option<String>,
+    // Produce a complete document (with header and footer) instead of a fragment
+    pub standalone: bool,
+    pub table_of_contents: bool,
+    // Folder relative images and includes are resolved against
+    pub resource_path: Option<String>,
+    // Folder embedded media are extracted into (relative to `working_dir`); set by
+    // `import_document` only
+    #[serde(skip)]
+    pub extract_media: Option<String>,
+    // Folder pandoc runs in
+    #[serde(skip)]
+    pub working_dir: Option<String>,
+}
+
+// Formats `import_document` reads: file extension → pandoc input format
+const IMPORT_FORMATS: [(&str, &str); 2] = [("docx", "docx"), ("odt", "odt")];
+
+// Default folder below the document for imported images (as for pasted images)
+const IMPORT_IMAGE_SUBDIR: &str = "images";
+
+// Folder of the working folder that pandoc extracts media into
+const EXTRACTED_MEDIA_DIR: &str = "extracted";
+
+// First pandoc version with `--sandbox`
+const MIN_SANDBOX_VERSION: (u32, u32) = (2, 15);
+
+static NEXT_IMPORT: AtomicUsize = AtomicUsize::new(1);
+
+// Detected pandoc by configured path (None: looked up in PATH and the install locations)
+static DETECTED: OnceLock<Mutex<HashMap<Option<String>, PandocInfo>>> = OnceLock::new();
+
+fn detected_cell() -> &'static Mutex<HashMap<Option<String>, PandocInfo>> {
+    DETECTED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// A document converted to Markdown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportedDocument {
+    pub markdown: String,
+    // Images taken from the document, relative to the folder of the Markdown
+    pub images: Vec<String>,
+}
+
+// Candidate pandoc executables, in order of preference
+fn pandoc_candidates(configured: Option<&str>) -> Vec<PathBuf> {
+    if let Some(path) = configured.filter(|path| !path.trim().is_empty()) {
+        return vec![PathBuf::from(path.trim())];
+    }
+    let mut candidates = vec![PathBuf::from("pandoc")];
+    if cfg!(target_os = "macos") {
+        candidates.extend(["/opt/homebrew/bin/pandoc", "/usr/local/bin/pandoc"].map(PathBuf::from));
+    }
+    if cfg!(target_os = "windows") {
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            candidates.push(PathBuf::from(local).join("Pandoc").join("pandoc.exe"));
+        }
+        if let Some(programs) = std::env::var_os("ProgramFiles") {
+            candidates.push(PathBuf::from(programs).join("Pandoc").join("pandoc.exe"));
+        }
+    }
+    candidates
+}
+
+// Version from the output of `pandoc --version` ("pandoc 3.1.11\nFeatures: ...")
+pub fn parse_version(output: &str) -> Option<String> {
+    let first_line = output.lines().next()?;
+    let version = first_line.strip_prefix("pandoc")?.trim();
+    // Windows builds print "pandoc.exe 3.1.11"
+    let version = version.strip_prefix(".exe").unwrap_or(version).trim();
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+// Whether a pandoc version supports `--sandbox`
+pub fn supports_sandbox(version: &str) -> bool {
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().unwrap_or(0));
+    let major = parts.next().unwrap_or(0);
+    let minor = parts.next().unwrap_or(0);
+    (major, minor) >= MIN_SANDBOX_VERSION
+}
+
+// Whether a format name is safe to pass to pandoc
+pub fn is_valid_format(format: &str) -> bool {
+    FORMAT.is_match(format)
+}
+
+fn pandoc_command(program: &str) -> Command {
+    let mut command = Command::new(program);
+    command.stderr(Stdio::piped());
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: no console window flashing up
+        command.creation_flags(0x0800_0000);
+    }
+    command
+}
+
+// Output lines of `pandoc <arg>`, None if it cannot be run
+fn query(program: &str, arg: &str) -> Option<String> {
+    let output = pandoc_command(program).arg(arg).stdin(Stdio::null()).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// The first candidate that runs, with its version and formats
+pub fn detect_pandoc(configured: Option<&str>) -> Option<PandocInfo> {
+    pandoc_candidates(configured).into_iter().find_map(|candidate| {
+        let path = candidate.to_string_lossy().to_string();
+        let version = parse_version(&query(&path, "--version")?)?;
+        let formats = |arg: &str| -> Vec<String> {
+            query(&path, arg)
+                .map(|list| list.lines().map(|line| line.trim().to_string()).filter(|f| !f.is_empty()).collect())
+                .unwrap_or_default()
+        };
+        Some(PandocInfo {
+            input_formats: formats("--list-input-formats"),
+            output_formats: formats("--list-output-formats"),
+            path,
+            version,
+        })
+    })
+}
+
+// Cache key of a configured pandoc path
+fn configured_key(configured: Option<&str>) -> Option<String> {
+    configured.map(str::trim).filter(|path| !path.is_empty()).map(str::to_string)
+}
+
+// Detect pandoc again and remember the result (`pandoc_info`)
+fn refresh_pandoc(configured: Option<&str>) -> Option<PandocInfo> {
+    let detected = detect_pandoc(configured);
+    if let Ok(mut cache) = detected_cell().lock() {
+        match &detected {
+            Some(info) => cache.insert(configured_key(configured), info.clone()),
+            None => cache.remove(&configured_key(configured)),
+        };
+    }
+    detected
+}
+
+// The pandoc to convert with: the cached one, or detected now. Fails when none is
+// installed or it cannot run sandboxed.
+fn sandboxed_pandoc(configured: Option<&str>) -> Result<PandocInfo, String> {
+    let cached = detected_cell()
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&configured_key(configured)).cloned());
+    let pandoc = cached
+        .or_else(|| refresh_pandoc(configured))
+        .ok_or_else(|| "pandoc was not found. Install it or set its path in the settings.".to_string())?;
+    if !supports_sandbox(&pandoc.version) {
+        return Err(format!(
+            "pandoc {} cannot run sandboxed. Install pandoc {}.{} or later.",
+            pandoc.version, MIN_SANDBOX_VERSION.0, MIN_SANDBOX_VERSION.1
+        ));
+    }
+    Ok(pandoc)
+}
+
+// Command line arguments of a conversion (without the input)
+pub fn pandoc_args(from: &str, to: &str, options: &PandocOptions) -> Result<Vec<String>, String> {
+    for format in [from, to] {
+        if !is_valid_format(format) {
+            return Err(format!("Invalid pandoc format: {:?}", format));
+        }
+    }
+    let mut args = vec![
+        "--from".to_string(),
+        from.to_string(),
+        "--to".to_string(),
+        to.to_string(),
+        // Only the input and output files may be accessed
+        "--sandbox".to_string(),
+    ];
+    if options.standalone {
+        args.push("--standalone".to_string());
+    }
+    if options.table_of_contents {
+        args.push("--toc".to_string());
+    }
+    if let Some(dir) = &options.resource_path {
+        args.push(format!("--resource-path={}", dir));
+    }
+    if let Some(dir) = &options.extract_media {
+        args.push(format!("--extract-media={}", dir));
+    }
+    if let Some(output) = &options.output_path {
+        args.push(format!("--output={}", output));
+    }
+    Ok(args)
+}
+
+// Run `program` on `input`. Returns the output, or None if it was written to
+// `options.output_path`.
+pub fn convert(
+    program: &str,
+    input: &str,
+    from: &str,
+    to: &str,
+    options: &PandocOptions,
+) -> Result<Option<String>, String> {
+    let mut command = pandoc_command(program);
+    command
+        .args(pandoc_args(from, to, options)?)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped());
+    if let Some(dir) = &options.working_dir {
+        command.current_dir(dir);
+    }
+    if options.input_is_path {
+        // After "--", so a file name starting with "-" is not read as an option
+        command.arg("--").arg(input);
+    }
+    let mut child = command.spawn().map_err(|e| format!("Failed to start pandoc: {}", e))?;
+
+    // Feed stdin on a thread, so a large document cannot deadlock the pipes
+    let text = if options.input_is_path { Vec::new() } else { input.as_bytes().to_vec() };
+    let mut stdin = child.stdin.take();
+    let writer = std::thread::spawn(move || {
+        if let Some(stdin) = stdin.as_mut() {
+            let _ = stdin.write_all(&text);
+        }
+    });
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run pandoc: {}", e))?;
+    let _ = writer.join();
+
+    let errors = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(format!("pandoc failed ({}): {}", output.status, errors.trim()));
+    }
+    if !errors.trim().is_empty() {
+        warn!("pandoc: {}", errors.trim());
+    }
+    if options.output_path.is_some() {
+        return Ok(None);
+    }
+    String::from_utf8(output.stdout)
+        .map(Some)
+        .map_err(|_| format!("pandoc produced output that is not UTF-8 (write {} to a file instead)", to))
+}
+
+// pandoc input format of a file to import, from its extension
+pub fn import_format(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    IMPORT_FORMATS
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, format)| *format)
+}
+
+// Files below `dir`, recursively
+fn files_below(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            files_below(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+// Move the media pandoc extracted below `working_dir` into `dest_dir/subdir`, pointing the
+// links of `markdown` (relative to `working_dir`) at the moved files. Returns the Markdown
+// and the moved images, relative to `dest_dir`.
+pub fn move_extracted_media(
+    markdown: &str,
+    working_dir: &Path,
+    dest_dir: &Path,
+    subdir: &str,
+) -> Result<(String, Vec<String>), String> {
+    let mut files = Vec::new();
+    files_below(working_dir, &mut files);
+    let mut markdown = markdown.to_string();
+    let mut images = Vec::new();
+    if files.is_empty() {
+        return Ok((markdown, images));
+    }
+    let dir = dest_dir.join(subdir);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create image directory: {} ({:?})", e, e.kind()))?;
+    let subdir = subdir.replace('\\', "/");
+    let subdir = subdir.trim_matches('/');
+    // Longest links first, so "media/image1.png" cannot replace part of "media/image10.png"
+    files.sort_by_key(|file| std::cmp::Reverse(file.as_os_str().len()));
+    for file in files {
+        let bytes = fs::read(&file).map_err(|e| format!("Failed to read extracted image: {}", e))?;
+        let name = write_image_dedup(&dir, &file.to_string_lossy(), &bytes)?;
+        let image = if subdir.is_empty() {
+            name
+        } else {
+            format!("{}/{}", subdir, name)
+        };
+        let link = file
+            .strip_prefix(working_dir)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        markdown = markdown.replace(&link, &image);
+        images.push(image);
+    }
+    images.sort();
+    Ok((markdown, images))
+}
+
+// Tauri command: Get the pandoc in use, or None if none is installed
+#[tauri::command]
+pub async fn pandoc_info() -> Result<Option<PandocInfo>, String> {
+    let configured = settings::current_settings().pandoc_path;
+    tauri::async_runtime::spawn_blocking(move || refresh_pandoc(configured.as_deref()))
+        .await
+        .map_err(|e| format!("Failed to detect pandoc: {}", e))
+}
+
+// Tauri command: Convert a document between formats with pandoc. `input` is the text,
+// or a file path with `options.input_is_path`.
+#[tauri::command]
+pub async fn pandoc_convert(
+    input: String,
+    from: String,
+    to: String,
+    options: Option<PandocOptions>,
+) -> Result<Option<String>, String> {
+    let options = options.unwrap_or_default();
+    if options.input_is_path {
+        path_scope::check_path(&input)?;
+    }
+    if let Some(output) = &options.output_path {
+        path_scope::check_path(output)?;
+    }
+    if let Some(dir) = &options.resource_path {
+        path_scope::check_path(dir)?;
+    }
+    let configured = settings::current_settings().pandoc_path;
+    tauri::async_runtime::spawn_blocking(move || {
+        let pandoc = sandboxed_pandoc(configured.as_deref())?;
+        let converted = convert(&pandoc.path, &input, &from, &to, &options)?;
+        info!("Converted {} to {} with pandoc {}", from, to, pandoc.version);
+        Ok(converted)
+    })
+    .await
+    .map_err(|e| format!("Failed to convert with pandoc: {}", e))?
+}
+
+// Tauri command: Convert a Word (docx) or OpenDocument (odt) file to Markdown with pandoc.
+// Its images are saved into `dest_dir/subdir` (by default the `images` folder next to the
+// file) and linked relative to `dest_dir`.
+#[tauri::command]
+pub async fn import_document(
+    path: String,
+    dest_dir: Option<String>,
+    subdir: Option<String>,
+) -> Result<ImportedDocument, String> {
+    path_scope::check_path(&path)?;
+    let from = import_format(Path::new(&path))
+        .ok_or_else(|| format!("Cannot import {}: only .docx and .odt files can be imported", path))?;
+    // Absolute, since pandoc runs in the working folder
+    let source = fs::canonicalize(&path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let dest_dir = match dest_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => source.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    let subdir = subdir.unwrap_or_else(|| IMPORT_IMAGE_SUBDIR.to_string());
+    path_scope::check_asset_dir(&dest_dir.to_string_lossy(), &subdir)?;
+    let configured = settings::current_settings().pandoc_path;
+    tauri::async_runtime::spawn_blocking(move || {
+        let pandoc = sandboxed_pandoc(configured.as_deref())?;
+        let working_dir = std::env::temp_dir().join(format!(
+            "bokuchi-import-{}-{}",
+            std::process::id(),
+            NEXT_IMPORT.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&working_dir).map_err(|e| format!("Failed to create import folder: {}", e))?;
+        let options = PandocOptions {
+            input_is_path: true,
+            extract_media: Some(EXTRACTED_MEDIA_DIR.to_string()),
+            working_dir: Some(working_dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let result = convert(&pandoc.path, &source.to_string_lossy(), from, "gfm", &options).and_then(|markdown| {
+            move_extracted_media(&markdown.unwrap_or_default(), &working_dir, &dest_dir, &subdir)
+        });
+        let _ = fs::remove_dir_all(&working_dir);
+        let (markdown, images) = result?;
+        info!("Imported {} with {} images using pandoc {}", path, images.len(), pandoc.version);
+        Ok(ImportedDocument { markdown, images })
+    })
+    .await
+    .map_err(|e| format!("Failed to import document: {}", e))?
+}
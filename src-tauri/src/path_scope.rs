@@ -0,0 +1,271 @@
+//! # Path Scope Module
+//!
+//! File commands (`read_file`, `save_file`, ...) receive paths from the WebView. So that a
+//! compromised page (for example script smuggled in through a document) cannot read or
+//! write arbitrary files, every such path must lie inside the scope: the folders and
+//! files the user opened.
+//!
+//! ## What Is In Scope
+//! - Folders and files picked in open/save dialogs (the dialog plugin allows them on the
+//!   fs plugin scope, which is followed here)
+//! - Files and folders dropped onto a window
+//! - Files opened through file association or the command line
+//! - At launch: the paths granted by the three sources above in earlier sessions
+//!   (`scope-grants.json`)
+//!
+//! Folders are in scope with everything below them. Paths are canonicalized before they
+//! are compared, so `..` segments and symlinks cannot lead out of the scope.
+//!
+//! The session, the recent files and the settings are written by the WebView, so they
+//! never put a path into the scope: only the grant store, which is written by the
+//! backend alone, is read at launch. The one exception is the first launch after an
+//! upgrade from a version without the scope (no `scope-grants.json` yet): the workspace
+//! and tabs of the saved session, the recent files and the favorites are granted once,
+//! so that restoring them does not fail until each is picked again.
+//!
+//! ## The fs Plugin
+//! The WebView also reaches files through the fs plugin, so its capability
+//! (`capabilities/default.json`) is kept narrow as well:
+//! - `write_text_file` only reaches paths picked in a dialog (the plugin's runtime scope)
+//! - `read_file` / `read_text_file` are allowed below the home, desktop, documents and
+//!   downloads folders for images, CSS and .md/.txt files only (preview images, Marp
+//!   themes, restored tabs); on macOS and Linux hidden files and folders do not match
+//! - The app directories (settings, grants, plugins, ...) are denied for every fs command
+//!
+//! Preview windows show document HTML, so they get a capability of their own
+//! (`capabilities/preview.json`): events, their own window and reading images, nothing
+//! else.
+//!
+//! ## Checks
+//! - `check_path`: every command that reads or lists a path or workspace root
+//! - `check_document_write`: every command that writes a document (scope plus the
+//!   .md/.txt rule)
+//! - `check_asset_dir`: folders that pasted or dropped images are saved to
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use tauri_plugin_fs::FsExt;
+use tracing::{debug, info, warn};
+
+use crate::error::AppError;
+use crate::favorites;
+use crate::recent_files;
+use crate::session::{self, Session};
+use crate::storage;
+
+const SCOPE_GRANTS_FILE: &str = "scope-grants.json";
+
+// Maximum number of granted paths kept for the next launch (most recent first)
+pub const MAX_SCOPE_GRANTS: usize = 200;
+
+// Allowed folders (recursive) and single files, canonicalized
+#[derive(Debug, Default)]
+struct PathScope {
+    roots: Vec<PathBuf>,
+    files: HashSet<PathBuf>,
+}
+
+static SCOPE: OnceLock<Mutex<PathScope>> = OnceLock::new();
+
+fn scope_cell() -> &'static Mutex<PathScope> {
+    SCOPE.get_or_init(|| Mutex::new(PathScope::default()))
+}
+
+// Canonicalize an absolute path. Paths that do not exist yet (a file about to be
+// created) are resolved through their nearest existing ancestor.
+pub fn resolve_path(path: &Path) -> Result<PathBuf, AppError> {
+    if path.as_os_str().is_empty() {
+        return Err(AppError::InvalidPath("empty path".to_string()));
+    }
+    if !path.is_absolute() {
+        return Err(AppError::InvalidPath(format!("{} is not absolute", path.display())));
+    }
+    if let Ok(resolved) = fs::canonicalize(path) {
+        return Ok(resolved);
+    }
+
+    let mut missing = Vec::new();
+    let mut ancestor = path;
+    loop {
+        match ancestor.components().next_back() {
+            Some(Component::Normal(name)) => missing.push(name.to_os_string()),
+            // ".." or "." below a missing folder cannot be resolved safely
+            _ => return Err(AppError::InvalidPath(format!("cannot resolve {}", path.display()))),
+        }
+        ancestor = ancestor
+            .parent()
+            .ok_or_else(|| AppError::InvalidPath(format!("cannot resolve {}", path.display())))?;
+        if let Ok(resolved) = fs::canonicalize(ancestor) {
+            return Ok(missing.into_iter().rev().fold(resolved, |path, name| path.join(name)));
+        }
+    }
+}
+
+// Put a folder (with everything below it) or a file into the scope
+pub fn allow_path(path: &Path) {
+    let Ok(resolved) = resolve_path(path) else {
+        debug!("Not adding unresolvable path to scope: {:?}", path);
+        return;
+    };
+    if let Ok(mut scope) = scope_cell().lock() {
+        if resolved.is_dir() {
+            if !scope.roots.iter().any(|root| resolved.starts_with(root)) {
+                scope.roots.retain(|root| !root.starts_with(&resolved));
+                scope.roots.push(resolved);
+            }
+        } else {
+            scope.files.insert(resolved);
+        }
+    }
+}
+
+// Put a path the user picked (dialog, file association, drag-and-drop) into the scope
+// and remember it for the next launch
+pub fn grant_path(path: &Path) {
+    allow_path(path);
+    let Ok(resolved) = resolve_path(path) else {
+        return;
+    };
+    let path = resolved.to_string_lossy();
+    let pushed = storage::update_json(SCOPE_GRANTS_FILE, |grants: &mut Vec<String>| {
+        push_grant(grants, &path, MAX_SCOPE_GRANTS)
+    });
+    if let Err(e) = pushed {
+        warn!("Failed to remember granted path {:?}: {}", resolved, e);
+    }
+}
+
+// Move `path` to the top of `grants` (inserting it if new) and enforce the size cap
+pub fn push_grant(grants: &mut Vec<String>, path: &str, max: usize) {
+    grants.retain(|grant| grant != path);
+    grants.insert(0, path.to_string());
+    grants.truncate(max);
+}
+
+fn is_allowed(resolved: &Path) -> bool {
+    scope_cell()
+        .lock()
+        .map(|scope| scope.files.contains(resolved) || scope.roots.iter().any(|root| resolved.starts_with(root)))
+        .unwrap_or(false)
+}
+
+// Check that a path from the frontend lies inside the scope
+pub fn check_path(path: &str) -> Result<(), AppError> {
+    let resolved = resolve_path(Path::new(path))?;
+    if is_allowed(&resolved) {
+        Ok(())
+    } else {
+        Err(AppError::OutsideScope(PathBuf::from(path)))
+    }
+}
+
+// Check that a document may be written: it must lie inside the scope and be a .md/.txt
+// file. Extension-less files are a legitimate case — the folder tree's "all files" mode
+// opens them via `read_file` (which deliberately allows a missing extension, see
+// R-CMD-07), and Ctrl+S on such a tab saves them — so they stay writable. Hidden
+// dotfiles are the exception: `Path::extension()` returns None for names like
+// ".bashrc", which would otherwise bypass the allowlist and let IPC calls write
+// shell/config files. (`read_directory` never lists hidden files, so no in-app flow
+// opens them.)
+pub fn check_document_write(path: &str) -> Result<(), AppError> {
+    check_path(path)?;
+    let path_ref = Path::new(path);
+    match path_ref.extension() {
+        Some(ext) => {
+            let ext_str = ext.to_string_lossy().to_lowercase();
+            if ext_str != "md" && ext_str != "txt" {
+                return Err(AppError::UnsupportedFileType(path_ref.to_path_buf()));
+            }
+        }
+        None => {
+            let is_hidden = path_ref
+                .file_name()
+                .map(|n| n.to_string_lossy().starts_with('.'))
+                .unwrap_or(true);
+            if is_hidden {
+                return Err(AppError::HiddenFile(path_ref.to_path_buf()));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Check a folder that assets (pasted or dropped images) are written to: `dir` must be
+// in the scope or be the folder of an opened file, and `subdir` must stay below it
+pub fn check_asset_dir(dir: &str, subdir: &str) -> Result<(), AppError> {
+    if !Path::new(subdir).components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(AppError::InvalidPath(format!("{} is not a subfolder", subdir)));
+    }
+    let resolved = resolve_path(Path::new(dir))?;
+    let beside_open_file = scope_cell()
+        .lock()
+        .map(|scope| scope.files.iter().any(|file| file.parent() == Some(resolved.as_path())))
+        .unwrap_or(false);
+    if beside_open_file || is_allowed(&resolved) {
+        Ok(())
+    } else {
+        Err(AppError::OutsideScope(PathBuf::from(dir)))
+    }
+}
+
+// Paths the user worked with before the scope existed, most important first: the
+// workspace and tabs of the session, then the recent files and the favorites
+pub fn upgrade_grants(session: &Session, recent: &[String], favorites: &[String], max: usize) -> Vec<String> {
+    let mut grants: Vec<String> = Vec::new();
+    let paths = session
+        .workspace_root
+        .iter()
+        .chain(session.tabs.iter().filter_map(|tab| tab.path.as_ref()))
+        .chain(recent)
+        .chain(favorites);
+    for path in paths {
+        if !path.is_empty() && !grants.contains(path) {
+            grants.push(path.clone());
+        }
+    }
+    grants.truncate(max);
+    grants
+}
+
+// Grant the paths of `upgrade_grants` and create the grant store (first launch after an
+// upgrade only)
+fn migrate_grants() -> Vec<String> {
+    let recent: Vec<String> = recent_files::recent_files().into_iter().map(|file| file.path).collect();
+    let favorites: Vec<String> = favorites::favorites().into_iter().map(|file| file.path).collect();
+    let grants: Vec<String> = upgrade_grants(&session::saved_session(), &recent, &favorites, MAX_SCOPE_GRANTS)
+        .into_iter()
+        .filter_map(|path| resolve_path(Path::new(&path)).ok())
+        .filter(|resolved| resolved.exists())
+        .map(|resolved| resolved.to_string_lossy().to_string())
+        .collect();
+    info!("Granting {} paths from the previous version", grants.len());
+    if let Err(e) = storage::save_json(SCOPE_GRANTS_FILE, &grants) {
+        warn!("Failed to create the grant store: {}", e);
+    }
+    grants
+}
+
+// Put the paths granted in earlier sessions into the scope and follow the paths the
+// dialog plugin allows (called once during setup, after the recent files are loaded)
+pub fn init_path_scope(app_handle: &tauri::AppHandle) {
+    let has_grants = storage::app_data_path(SCOPE_GRANTS_FILE).is_some_and(|path| path.exists());
+    let grants: Vec<String> = if has_grants {
+        storage::load_json(SCOPE_GRANTS_FILE)
+    } else {
+        migrate_grants()
+    };
+    for path in grants {
+        allow_path(Path::new(&path));
+    }
+
+    if let Some(fs_scope) = app_handle.try_fs_scope() {
+        fs_scope.listen(|event| {
+            if let tauri::scope::fs::Event::PathAllowed(path) = event {
+                grant_path(path);
+            }
+        });
+    }
+}
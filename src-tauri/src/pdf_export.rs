@@ -25,6 +25,9 @@ use serde::Deserialize;
 use tauri::async_runtime::Sender;
 use tauri::webview::PageLoadEvent;
 use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tracing::warn;
+
+use crate::path_scope;
 
 /// Page geometry for the exported PDF, in inches. Computed by the frontend
 /// (A4 with margins for normal Markdown; the slide's own pixel size at 96dpi
@@ -52,34 +55,61 @@ pub async fn export_pdf(
     output_path: String,
     page: PdfPageOptions,
 ) -> Result<(), String> {
+    path_scope::check_path(&output_path)?;
+    let window_width = (page.width_inch * CSS_DPI).round().max(200.0);
+    let window_height = (page.height_inch * CSS_DPI).round().max(200.0);
+
+    render_offscreen(
+        &app,
+        EXPORT_WINDOW_LABEL,
+        "PDF Export",
+        html,
+        (window_width, window_height),
+        move |platform_webview, done| print_platform_webview(platform_webview, &output_path, &page, done),
+    )
+    .await
+}
+
+/// Load `html` into a transient off-screen window labelled `label`, sized
+/// `size` (CSS pixels), and once it has loaded hand its native webview to
+/// `run`, which reports the export result through the sender. Also used by
+/// the image export (`image_export`).
+pub(crate) async fn render_offscreen<T, F>(
+    app: &AppHandle,
+    label: &str,
+    title: &str,
+    html: String,
+    size: (f64, f64),
+    run: F,
+) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: Fn(tauri::webview::PlatformWebview, Sender<Result<T, String>>) + Send + Sync + 'static,
+{
     // Load the document via a temp file: it is fully self-contained (inline CSS,
     // data-URI fonts, inline SVG), so a file:// URL renders identically.
-    let tmp_path = std::env::temp_dir().join(format!("bokuchi-pdf-export-{}.html", std::process::id()));
+    let tmp_path = std::env::temp_dir().join(format!("{}-{}.html", label, std::process::id()));
     std::fs::write(&tmp_path, html).map_err(|e| format!("failed to write temp HTML: {e}"))?;
     let file_url = url::Url::from_file_path(&tmp_path).map_err(|_| "invalid temp file path".to_string())?;
 
     // Drop any leftover export window from a previous (possibly failed) run.
-    if let Some(win) = app.get_webview_window(EXPORT_WINDOW_LABEL) {
+    if let Some(win) = app.get_webview_window(label) {
         let _ = win.close();
     }
 
-    let (tx, mut rx) = tauri::async_runtime::channel::<Result<(), String>>(1);
-
-    let window_width = (page.width_inch * CSS_DPI).round().max(200.0);
-    let window_height = (page.height_inch * CSS_DPI).round().max(200.0);
+    let (tx, mut rx) = tauri::async_runtime::channel::<Result<T, String>>(1);
 
-    let output_for_load = output_path.clone();
-    let page_for_load = page.clone();
+    let run = std::sync::Arc::new(run);
     // on_page_load fires for both Started and Finished (and again on any
-    // sub-navigation); only print once.
+    // sub-navigation); only export once.
     let fired = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
 
     // The window is shown off-screen rather than hidden: WKWebView does not lay
     // out (and prints blank) when its window is never displayed. Positioning it
     // far off the visible desktop keeps it invisible to the user.
-    let build_result = WebviewWindowBuilder::new(&app, EXPORT_WINDOW_LABEL, WebviewUrl::External(file_url))
-        .title("PDF Export")
-        .inner_size(window_width, window_height)
+    let build_result = WebviewWindowBuilder::new(app, label, WebviewUrl::External(file_url))
+        .title(title)
+        .inner_size(size.0, size.1)
         .position(12000.0, 12000.0)
         .decorations(false)
         .focused(false)
@@ -94,18 +124,17 @@ pub async fn export_pdf(
             }
             let window = window.clone();
             let tx = tx.clone();
-            let output = output_for_load.clone();
-            let page = page_for_load.clone();
-            // Let layout/fonts settle, then print. We run the wait off the main
+            let run = run.clone();
+            // Let layout/fonts settle, then export. We run the wait off the main
             // thread; `with_webview` re-dispatches the actual native call onto
             // the main thread where it must run.
             std::thread::spawn(move || {
                 std::thread::sleep(std::time::Duration::from_millis(400));
                 let dispatched = window.with_webview(move |platform_webview| {
-                    print_platform_webview(platform_webview, &output, &page, tx);
+                    run(platform_webview, tx);
                 });
                 if let Err(e) = dispatched {
-                    eprintln!("[pdf_export] with_webview dispatch failed: {e}");
+                    warn!("[pdf_export] with_webview dispatch failed: {e}");
                 }
             });
         })
@@ -119,9 +148,9 @@ pub async fn export_pdf(
     let result = rx
         .recv()
         .await
-        .unwrap_or_else(|| Err("PDF export did not report a result".to_string()));
+        .unwrap_or_else(|| Err(format!("{title} did not report a result")));
 
-    if let Some(win) = app.get_webview_window(EXPORT_WINDOW_LABEL) {
+    if let Some(win) = app.get_webview_window(label) {
         let _ = win.close();
     }
     let _ = std::fs::remove_file(&tmp_path);
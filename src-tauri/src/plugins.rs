@@ -0,0 +1,506 @@
+//! # Plugins Module
+//!
+//! This module lets external processors transform document content at defined stages,
+//! so custom syntaxes can be added without changing Bokuchi itself.
+//!
+//! ## Stages
+//! - `pre-variable`: Before `{{variables}}` are expanded (preview and expanded Markdown)
+//! - `post-variable`: After the variables are expanded
+//! - `pre-export`: Before a document is exported; the frontend calls `run_plugins`
+//!
+//! ## Installing
+//! A plugin is a folder in `<app data>/plugins/` with a `plugin.json` manifest:
+//!
+//! ```json
+//! {
+//!   "id": "callouts",
+//!   "name": "Callouts",
+//!   "version": "1.0.0",
+//!   "description": "Turns > [!NOTE] blocks into HTML",
+//!   "kind": "process",
+//!   "command": "bin/callouts",
+//!   "args": ["--markdown"],
+//!   "stages": ["pre-variable", "pre-export"],
+//!   "timeout_ms": 2000
+//! }
+//! ```
+//!
+//! Plugins are disabled until enabled with `enable_plugin`; the enabled IDs are stored in
+//! `plugins.json`. Within a stage, plugins run in the order of their IDs. Enabling a
+//! process plugin shows its command line in a native dialog and asks the user to confirm
+//! it, once per definition (see `approvals`); WASM plugins are sandboxed and need no
+//! confirmation.
+//!
+//! ## Process Plugins
+//! The command (relative to the plugin folder) receives the content on stdin and writes
+//! the transformed content to stdout; a non-zero exit status is a failure. It runs in the
+//! plugin folder with an empty environment (except `PATH`, `BOKUCHI_STAGE` and
+//! `BOKUCHI_PLUGIN_DIR`), is killed after its timeout, and its output is limited in size.
+//!
+//! ## WASM Plugins
+//! `"kind": "wasm"` plugins name a WebAssembly `module` instead of a command. The module
+//! runs in an interpreter (wasmi) and is given no imports at all: no WASI, so it has no
+//! access to files, the network, the clock or the environment. It must export:
+//! - `memory`
+//! - `alloc(len: i32) -> i32`: space for the input
+//! - `process(ptr: i32, len: i32) -> i64`: transforms the UTF-8 input and returns the
+//!   output location as `ptr << 32 | len`
+//!
+//! Every run gets a fresh instance with at most `WASM_MEMORY_LIMIT` of memory. Instead of
+//! a wall-clock timeout, the timeout is turned into a fuel budget (`WASM_FUEL_PER_MS`
+//! instructions per millisecond); a module that runs out of fuel is stopped.
+//!
+//! ## Failures
+//! A failing plugin never breaks the preview: its stage keeps the content it received and
+//! the error is logged. `run_plugins` reports failures to the caller instead.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::approvals;
+use crate::locale;
+use crate::s3::sha256_hex;
+use crate::storage;
+
+const PLUGIN_DIR: &str = "plugins";
+const MANIFEST_FILE: &str = "plugin.json";
+const ENABLED_FILE: &str = "plugins.json";
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_TIMEOUT: Duration = Duration::from_secs(60);
+// Upper bound of the output of a processor
+const MAX_OUTPUT_LEN: u64 = 16 * 1024 * 1024;
+const MAX_STDERR_LEN: u64 = 64 * 1024;
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+// Linear memory a WASM plugin may use
+const WASM_MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+// Fuel (roughly instructions) a WASM plugin gets per millisecond of its timeout
+const WASM_FUEL_PER_MS: u64 = 100_000;
+
+// Point in the processing of a document where plugins run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PluginStage {
+    PreVariable,
+    PostVariable,
+    PreExport,
+}
+
+impl PluginStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PluginStage::PreVariable => "pre-variable",
+            PluginStage::PostVariable => "post-variable",
+            PluginStage::PreExport => "pre-export",
+        }
+    }
+}
+
+// How a plugin is run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginKind {
+    Process,
+    Wasm,
+}
+
+// Contents of `plugin.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub kind: PluginKind,
+    // Executable of a process plugin, relative to the plugin folder
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    // WebAssembly module of a WASM plugin, relative to the plugin folder
+    #[serde(default)]
+    pub module: Option<String>,
+    pub stages: Vec<PluginStage>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+// An installed plugin, as listed by `list_plugins`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginInfo {
+    #[serde(flatten)]
+    pub manifest: PluginManifest,
+    pub dir: String,
+    pub enabled: bool,
+    // Why the plugin cannot run (invalid manifest, missing command or module)
+    pub error: Option<String>,
+}
+
+// Installed plugins, discovered on first use and again by `list_plugins`
+static REGISTRY: OnceLock<Mutex<Option<Vec<PluginInfo>>>> = OnceLock::new();
+static ENABLED: OnceLock<Mutex<BTreeSet<String>>> = OnceLock::new();
+
+fn registry_cell() -> &'static Mutex<Option<Vec<PluginInfo>>> {
+    REGISTRY.get_or_init(|| Mutex::new(None))
+}
+
+fn enabled_cell() -> &'static Mutex<BTreeSet<String>> {
+    ENABLED.get_or_init(|| Mutex::new(storage::load_json(ENABLED_FILE)))
+}
+
+// A path inside the plugin folder (no absolute paths, no "..")
+fn plugin_file(dir: &Path, relative: &str) -> Result<PathBuf, String> {
+    if relative.is_empty() || !Path::new(relative).components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!("{} is not a file inside the plugin folder", relative));
+    }
+    Ok(dir.join(relative))
+}
+
+// Why a plugin cannot run, if it cannot
+fn validate(dir: &Path, manifest: &PluginManifest) -> Option<String> {
+    let valid_id = !manifest.id.is_empty()
+        && manifest
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid_id {
+        return Some(format!("Invalid plugin ID: {:?}", manifest.id));
+    }
+    if manifest.stages.is_empty() {
+        return Some("The plugin runs at no stage".to_string());
+    }
+    let (file, missing) = match manifest.kind {
+        PluginKind::Process => (&manifest.command, "Command"),
+        PluginKind::Wasm => (&manifest.module, "Module"),
+    };
+    match plugin_file(dir, file.as_deref().unwrap_or_default()) {
+        Ok(path) if path.is_file() => None,
+        Ok(path) => Some(format!("{} not found: {}", missing, path.display())),
+        Err(e) => Some(e),
+    }
+}
+
+// Timeout of a plugin run
+fn plugin_timeout(manifest: &PluginManifest) -> Duration {
+    manifest
+        .timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_TIMEOUT)
+        .min(MAX_TIMEOUT)
+}
+
+// Plugins installed in `plugins_dir`, sorted by ID. `enabled` are the enabled IDs.
+pub fn discover(plugins_dir: &Path, enabled: &BTreeSet<String>) -> Vec<PluginInfo> {
+    let Ok(entries) = fs::read_dir(plugins_dir) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<PluginInfo> = Vec::new();
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let manifest_path = dir.join(MANIFEST_FILE);
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let manifest = fs::read_to_string(&manifest_path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<PluginManifest>(&json).map_err(|e| e.to_string()));
+        let manifest = match manifest {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                warn!("Ignoring plugin {:?}: invalid {}: {}", dir, MANIFEST_FILE, e);
+                continue;
+            }
+        };
+        let error = validate(&dir, &manifest);
+        if plugins.iter().any(|plugin| plugin.manifest.id == manifest.id) {
+            warn!("Ignoring plugin {:?}: duplicate ID {}", dir, manifest.id);
+            continue;
+        }
+        plugins.push(PluginInfo {
+            enabled: error.is_none() && enabled.contains(&manifest.id),
+            manifest,
+            dir: dir.to_string_lossy().to_string(),
+            error,
+        });
+    }
+    plugins.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+    plugins
+}
+
+fn refresh_registry() -> Vec<PluginInfo> {
+    let enabled = enabled_cell().lock().map(|enabled| enabled.clone()).unwrap_or_default();
+    let plugins = storage::app_data_path(PLUGIN_DIR)
+        .map(|dir| discover(&dir, &enabled))
+        .unwrap_or_default();
+    if let Ok(mut registry) = registry_cell().lock() {
+        *registry = Some(plugins.clone());
+    }
+    plugins
+}
+
+// Run a process plugin on `content`
+pub fn run_process(plugin: &PluginInfo, stage: PluginStage, content: &str) -> Result<String, String> {
+    let manifest = &plugin.manifest;
+    let dir = Path::new(&plugin.dir);
+    let program = plugin_file(dir, manifest.command.as_deref().unwrap_or_default())?;
+    let timeout = plugin_timeout(manifest);
+
+    let mut command = Command::new(&program);
+    command
+        .args(&manifest.args)
+        .current_dir(dir)
+        .env_clear()
+        .env("BOKUCHI_STAGE", stage.as_str())
+        .env("BOKUCHI_PLUGIN_DIR", dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Needed to start anything (and, on Windows, to load system DLLs)
+    for name in ["PATH", "SYSTEMROOT"] {
+        if let Some(value) = std::env::var_os(name) {
+            command.env(name, value);
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: no console window flashing up
+        command.creation_flags(0x0800_0000);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start plugin {}: {}", manifest.id, e))?;
+
+    // Feed and drain the pipes on threads, so a large document cannot deadlock the pipes
+    let input = content.as_bytes().to_vec();
+    let mut stdin = child.stdin.take();
+    std::thread::spawn(move || {
+        if let Some(stdin) = stdin.as_mut() {
+            let _ = stdin.write_all(&input);
+        }
+    });
+    let read_pipe = |pipe: Option<Box<dyn Read + Send>>, limit: u64| {
+        std::thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if let Some(pipe) = pipe {
+                let _ = pipe.take(limit + 1).read_to_end(&mut bytes);
+            }
+            bytes
+        })
+    };
+    let stdout = read_pipe(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>), MAX_OUTPUT_LEN);
+    let stderr = read_pipe(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>), MAX_STDERR_LEN);
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Plugin {} timed out after {} ms", manifest.id, timeout.as_millis()));
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(format!("Failed to wait for plugin {}: {}", manifest.id, e)),
+        }
+    };
+    let output = stdout.join().unwrap_or_default();
+    let errors = stderr.join().unwrap_or_default();
+    if !status.success() {
+        return Err(format!(
+            "Plugin {} failed ({}): {}",
+            manifest.id,
+            status,
+            String::from_utf8_lossy(&errors).trim()
+        ));
+    }
+    if output.len() as u64 > MAX_OUTPUT_LEN {
+        return Err(format!("Plugin {} produced too much output", manifest.id));
+    }
+    String::from_utf8(output).map_err(|_| format!("Plugin {} produced output that is not UTF-8", manifest.id))
+}
+
+// Run a WASM plugin on `content` in a fresh sandboxed instance
+pub fn run_wasm(plugin: &PluginInfo, content: &str) -> Result<String, String> {
+    let manifest = &plugin.manifest;
+    let fail = |e: &dyn std::fmt::Display| format!("Plugin {} failed: {}", manifest.id, e);
+    let path = plugin_file(Path::new(&plugin.dir), manifest.module.as_deref().unwrap_or_default())?;
+    let wasm = fs::read(&path).map_err(|e| format!("Failed to read plugin {}: {}", manifest.id, e))?;
+
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, &wasm).map_err(|e| fail(&e))?;
+    let mut store: Store<StoreLimits> =
+        Store::new(&engine, StoreLimitsBuilder::new().memory_size(WASM_MEMORY_LIMIT).build());
+    store.limiter(|limits| limits);
+    let fuel = plugin_timeout(manifest).as_millis() as u64 * WASM_FUEL_PER_MS;
+    store.set_fuel(fuel).map_err(|e| fail(&e))?;
+
+    // No host functions: a module importing anything fails to instantiate
+    let instance = Linker::<StoreLimits>::new(&engine)
+        .instantiate(&mut store, &module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| fail(&e))?;
+    let memory = instance
+        .get_memory(&store, "memory")
+        .ok_or_else(|| fail(&"the module exports no memory"))?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&store, "alloc")
+        .map_err(|e| fail(&e))?;
+    let process = instance
+        .get_typed_func::<(i32, i32), i64>(&store, "process")
+        .map_err(|e| fail(&e))?;
+
+    let input = content.as_bytes();
+    let input_len = i32::try_from(input.len()).map_err(|_| fail(&"the document is too large"))?;
+    let input_ptr = alloc.call(&mut store, input_len).map_err(|e| fail(&e))?;
+    memory
+        .write(&mut store, input_ptr as u32 as usize, input)
+        .map_err(|e| fail(&e))?;
+    let location = process
+        .call(&mut store, (input_ptr, input_len))
+        .map_err(|e| fail(&e))? as u64;
+    let (output_ptr, output_len) = ((location >> 32) as usize, (location & 0xffff_ffff) as usize);
+    if output_len as u64 > MAX_OUTPUT_LEN {
+        return Err(format!("Plugin {} produced too much output", manifest.id));
+    }
+    let mut output = vec![0; output_len];
+    memory
+        .read(&store, output_ptr, &mut output)
+        .map_err(|e| fail(&e))?;
+    String::from_utf8(output).map_err(|_| format!("Plugin {} produced output that is not UTF-8", manifest.id))
+}
+
+// Run a plugin of either kind on `content`
+fn run_plugin(plugin: &PluginInfo, stage: PluginStage, content: &str) -> Result<String, String> {
+    match plugin.manifest.kind {
+        PluginKind::Process => run_process(plugin, stage, content),
+        PluginKind::Wasm => run_wasm(plugin, content),
+    }
+}
+
+// Run the enabled plugins of a stage in order. Stops at the first failure.
+fn run_stage(stage: PluginStage, content: String) -> Result<String, String> {
+    let plugins = match registry_cell().lock().ok().and_then(|registry| registry.clone()) {
+        Some(plugins) => plugins,
+        None => refresh_registry(),
+    };
+    plugins
+        .iter()
+        .filter(|plugin| plugin.enabled && plugin.manifest.stages.contains(&stage))
+        .try_fold(content, |content, plugin| run_plugin(plugin, stage, &content))
+}
+
+// Apply the plugins of a stage during Markdown processing. On failure the content is
+// returned unchanged, so a broken plugin cannot break the preview.
+pub fn apply_stage(stage: PluginStage, content: String) -> String {
+    match run_stage(stage, content.clone()) {
+        Ok(transformed) => transformed,
+        Err(e) => {
+            warn!("{} plugins failed: {}", stage.as_str(), e);
+            content
+        }
+    }
+}
+
+fn set_enabled(id: &str, enabled: bool) -> Result<(), String> {
+    let plugins = refresh_registry();
+    let plugin = plugins
+        .iter()
+        .find(|plugin| plugin.manifest.id == id)
+        .ok_or_else(|| format!("Unknown plugin: {}", id))?;
+    if enabled && let Some(error) = &plugin.error {
+        return Err(format!("Plugin {} cannot be enabled: {}", id, error));
+    }
+    {
+        let mut ids = enabled_cell()
+            .lock()
+            .map_err(|_| "Failed to lock plugin settings".to_string())?;
+        let changed = if enabled { ids.insert(id.to_string()) } else { ids.remove(id) };
+        if changed {
+            storage::save_json(ENABLED_FILE, &*ids)?;
+        }
+    }
+    info!("Plugin {} {}", id, if enabled { "enabled" } else { "disabled" });
+    refresh_registry();
+    // Cached previews were processed with the previous plugin set
+    crate::markdown_cache::clear_markdown_cache();
+    Ok(())
+}
+
+// Tauri command: List the installed plugins (scanning the plugin folder again)
+#[tauri::command]
+pub fn list_plugins() -> Vec<PluginInfo> {
+    refresh_registry()
+}
+
+// Fingerprint of what a process plugin runs (ID, command and arguments)
+pub fn plugin_fingerprint(manifest: &PluginManifest) -> String {
+    let definition = serde_json::to_vec(&(&manifest.id, &manifest.command, &manifest.args)).unwrap_or_default();
+    sha256_hex(&definition)
+}
+
+// Ask the user to confirm a process plugin before it is enabled. Blocks on the dialog,
+// so it must not be called on the main thread.
+fn confirm_plugin(app_handle: &tauri::AppHandle, id: &str) -> Result<(), String> {
+    let Some(plugin) = refresh_registry().into_iter().find(|plugin| plugin.manifest.id == id) else {
+        return Err(format!("Unknown plugin: {}", id));
+    };
+    let manifest = &plugin.manifest;
+    let Some(command) = manifest.command.as_deref().filter(|_| manifest.kind == PluginKind::Process) else {
+        return Ok(());
+    };
+    let command = Path::new(&plugin.dir).join(command).to_string_lossy().to_string();
+    let locale = locale::current_locale();
+    let message = locale::tr_args_in(
+        locale,
+        "dialog.enable_plugin_message",
+        &[("id", id), ("command", &approvals::command_line(&command, &manifest.args))],
+    );
+    approvals::confirm_once(
+        app_handle,
+        "plugin",
+        &plugin_fingerprint(manifest),
+        locale::tr_in(locale, "dialog.enable_plugin_title"),
+        message,
+        locale::tr_in(locale, "dialog.enable_plugin_confirm"),
+    )?;
+    Ok(())
+}
+
+// Tauri command: Enable a plugin. A process plugin is confirmed in a native dialog first.
+#[tauri::command]
+pub async fn enable_plugin(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        confirm_plugin(&app_handle, &id)?;
+        set_enabled(&id, true)
+    })
+    .await
+    .map_err(|e| format!("Failed to enable plugin: {}", e))?
+}
+
+// Tauri command: Disable a plugin
+#[tauri::command]
+pub fn disable_plugin(id: String) -> Result<(), String> {
+    set_enabled(&id, false)
+}
+
+// Tauri command: Run the enabled plugins of a stage on some content (e.g. `pre-export`
+// before exporting a document)
+#[tauri::command]
+pub async fn run_plugins(stage: PluginStage, content: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || run_stage(stage, content))
+        .await
+        .map_err(|e| format!("Failed to run plugins: {}", e))?
+}
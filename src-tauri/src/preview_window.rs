@@ -0,0 +1,109 @@
+//! # Preview Window Module
+//!
+//! This module opens a separate window showing only the rendered preview of a document,
+//! so the preview can stay on another monitor while the editor is full-screen.
+//!
+//! ## Window
+//! The window stays on top of other windows. It keeps its decorations (title bar, close
+//! button) until the frontend's preview view (`?view=preview`) can move and close a
+//! frameless window itself. There is one preview window per document; opening the
+//! preview of a document again focuses its window.
+//!
+//! ## Updates
+//! The editor sends the current content with `update_preview` as the user types. The
+//! variables are expanded as for the main preview, and a `preview-updated` event with the
+//! expanded Markdown is sent to the preview window of that document only.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use tracing::info;
+
+use crate::commands;
+use crate::document_window::encode_query_value;
+use crate::types::{emit_event, PreviewUpdatedEvent};
+
+// Label prefix of preview windows ("preview-<n>")
+pub const PREVIEW_WINDOW_PREFIX: &str = "preview-";
+
+static NEXT_PREVIEW_WINDOW: AtomicUsize = AtomicUsize::new(1);
+// Document path → label of its preview window
+static PREVIEW_WINDOWS: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+fn preview_windows_cell() -> &'static Mutex<HashMap<String, String>> {
+    PREVIEW_WINDOWS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Frontend URL of a preview window
+pub fn preview_window_url(path: &str) -> String {
+    format!("index.html?view=preview&file={}", encode_query_value(path))
+}
+
+// Label of the open preview window of a document (windows closed since are forgotten)
+fn open_preview_label(app: &tauri::AppHandle, path: &str) -> Option<String> {
+    let mut windows = preview_windows_cell().lock().ok()?;
+    windows.retain(|_, label| app.get_webview_window(label).is_some());
+    windows.get(path).cloned()
+}
+
+// Tauri command: Open the preview window of a document, or focus it if it is already
+// open. Returns the window label.
+#[tauri::command]
+pub async fn open_preview_window(app_handle: tauri::AppHandle, path: String) -> Result<String, String> {
+    if let Some(label) = open_preview_label(&app_handle, &path)
+        && let Some(window) = app_handle.get_webview_window(&label)
+    {
+        let _ = window.set_focus();
+        return Ok(label);
+    }
+
+    let label = format!(
+        "{}{}",
+        PREVIEW_WINDOW_PREFIX,
+        NEXT_PREVIEW_WINDOW.fetch_add(1, Ordering::SeqCst)
+    );
+    let name = std::path::Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.clone());
+    let window = WebviewWindowBuilder::new(&app_handle, &label, WebviewUrl::App(preview_window_url(&path).into()))
+        .title(format!("{} - Preview", name))
+        .inner_size(700.0, 800.0)
+        .min_inner_size(300.0, 200.0)
+        .always_on_top(true)
+        .build()
+        .map_err(|e| format!("Failed to open preview window: {}", e))?;
+    let _ = window.set_focus();
+    if let Ok(mut windows) = preview_windows_cell().lock() {
+        windows.insert(path.clone(), label.clone());
+    }
+    info!("Opened preview window {} for {}", label, path);
+    Ok(label)
+}
+
+// Tauri command: Send the current content of a document to its preview window, if one is
+// open. Returns whether a preview window received it.
+#[tauri::command]
+pub async fn update_preview(app_handle: tauri::AppHandle, path: String, content: String) -> Result<bool, String> {
+    let Some(label) = open_preview_label(&app_handle, &path) else {
+        return Ok(false);
+    };
+    // Includes and plugins may take a while: expand off the async runtime
+    let expand_path = path.clone();
+    let markdown = tauri::async_runtime::spawn_blocking(move || {
+        commands::expand_markdown_guarded(
+            "update_preview",
+            content,
+            HashMap::new(),
+            Default::default(),
+            Some(expand_path),
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to update preview: {}", e))??;
+    emit_event(&app_handle, Some(&label), &PreviewUpdatedEvent { path, markdown })
+        .map_err(|e| format!("Failed to update preview: {}", e))?;
+    Ok(true)
+}
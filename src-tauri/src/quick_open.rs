@@ -0,0 +1,150 @@
+//! # Quick Open Module
+//!
+//! This module ranks the files of a workspace for the Ctrl+P style file switcher.
+//!
+//! ## Matching
+//! The query matches a path if its characters appear in order (case-insensitive), e.g.
+//! `mtg` matches `meetings/2024.md`. Scores favor:
+//! - Matches inside the file name over matches in the directories
+//! - Consecutive characters and characters at the start of a word
+//! - Shorter paths
+//!
+//! Files in the recent files list (`recent_files`) get a boost by recency, so with an
+//! empty query the switcher lists the most recently opened files first.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::path_scope;
+use crate::recent_files;
+use crate::workspace;
+
+const DEFAULT_LIMIT: usize = 50;
+
+const MATCH_SCORE: i64 = 16;
+const CONSECUTIVE_BONUS: i64 = 24;
+const WORD_START_BONUS: i64 = 20;
+const FILE_NAME_BONUS: i64 = 40;
+// Boost of the most recently opened file; lower ranks get proportionally less
+const RECENCY_BOOST: i64 = 150;
+
+// Ranked quick-open entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickOpenResult {
+    pub path: String,
+    // Path relative to the workspace root, with '/' separators
+    pub relative_path: String,
+    pub name: String,
+    pub score: i64,
+    // Matched character indices in `relative_path` (for highlighting)
+    pub positions: Vec<usize>,
+}
+
+fn is_word_start(chars: &[char], index: usize) -> bool {
+    index == 0
+        || matches!(chars[index - 1], '/' | '\\' | '_' | '-' | '.' | ' ')
+        || (chars[index].is_uppercase() && chars[index - 1].is_lowercase())
+}
+
+fn match_from(query: &str, chars: &[char], start: usize, name_start: usize) -> Option<(i64, Vec<usize>)> {
+    let mut positions: Vec<usize> = Vec::new();
+    let mut score = 0;
+    let mut next = start;
+
+    for query_char in query.chars().filter(|c| !c.is_whitespace()) {
+        let found = (next..chars.len()).find(|&i| chars[i].to_lowercase().eq(query_char.to_lowercase()))?;
+        score += MATCH_SCORE;
+        if positions.last().is_some_and(|last| last + 1 == found) {
+            score += CONSECUTIVE_BONUS;
+        }
+        if is_word_start(chars, found) {
+            score += WORD_START_BONUS;
+        }
+        if found >= name_start {
+            score += FILE_NAME_BONUS;
+        }
+        positions.push(found);
+        next = found + 1;
+    }
+    Some((score, positions))
+}
+
+// Score `candidate` (a '/'-separated path) against `query`. Returns None if the query
+// is not a subsequence of the path.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let name_start = chars.iter().rposition(|c| *c == '/').map(|i| i + 1).unwrap_or(0);
+    // Matching only the file name beats a match spread over the directories
+    let (score, positions) =
+        match_from(query, &chars, name_start, name_start).or_else(|| match_from(query, &chars, 0, name_start))?;
+
+    // Prefer shorter paths among otherwise equal matches
+    Some((score - chars.len() as i64 / 4, positions))
+}
+
+// Boost for a file by its rank in the recent files list (0 = most recent)
+pub fn recency_boost(rank: Option<usize>) -> i64 {
+    match rank {
+        Some(rank) => {
+            let max = recent_files::MAX_RECENT_FILES as i64;
+            RECENCY_BOOST * (max - (rank as i64).min(max)) / max
+        }
+        None => 0,
+    }
+}
+
+// Rank workspace files for a query
+pub fn rank_files(
+    root: &Path,
+    files: &[std::path::PathBuf],
+    query: &str,
+    recent: &HashMap<String, usize>,
+    limit: usize,
+) -> Vec<QuickOpenResult> {
+    let mut results: Vec<QuickOpenResult> = files
+        .iter()
+        .filter_map(|path| {
+            let relative_path = path
+                .strip_prefix(root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let (score, positions) = fuzzy_score(query, &relative_path)?;
+            let path_str = path.to_string_lossy().to_string();
+            let score = score + recency_boost(recent.get(&path_str).copied());
+            Some(QuickOpenResult {
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: path_str,
+                relative_path,
+                score,
+                positions,
+            })
+        })
+        .collect();
+
+    results.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.relative_path.cmp(&b.relative_path)));
+    results.truncate(limit);
+    results
+}
+
+// Tauri command: Fuzzy-find files in a workspace
+#[tauri::command]
+pub async fn quick_open(root: String, query: String, limit: Option<usize>) -> Result<Vec<QuickOpenResult>, String> {
+    path_scope::check_path(&root)?;
+    let recent: HashMap<String, usize> = recent_files::recent_files()
+        .into_iter()
+        .enumerate()
+        .map(|(rank, file)| (file.path, rank))
+        .collect();
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        let files = workspace::walk_workspace(root, false)?;
+        Ok(rank_files(root, &files, &query, &recent, limit.unwrap_or(DEFAULT_LIMIT)))
+    })
+    .await
+    .map_err(|e| format!("Quick open failed: {}", e))?
+}
@@ -0,0 +1,160 @@
+//! # Recent Files Module
+//!
+//! This module keeps the most-recently-used (MRU) file list in the backend so native
+//! surfaces (the File → Open Recent submenu, the tray) can show it without asking the
+//! frontend.
+//!
+//! ## Behavior
+//! - Re-opening a file moves it to the top instead of adding a duplicate
+//! - The list is capped at `MAX_RECENT_FILES` entries
+//! - Every change is persisted to `recent-files.json` and rebuilds the Open Recent submenu;
+//!   a failed write is retried by the periodic and shutdown flushes (`shutdown`)
+//! - A change is applied to the list on disk (under its lock, see `storage`), so files
+//!   opened in a separate process (`--new-instance`) are kept
+//! - Only files inside the path scope can be added (`path_scope`), since opening an entry
+//!   from the menu grants it again
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use crate::menu;
+use crate::path_scope;
+use crate::storage;
+
+const RECENT_FILES_FILE: &str = "recent-files.json";
+
+// Maximum number of entries kept in the MRU list
+pub const MAX_RECENT_FILES: usize = 15;
+
+// Recent file entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentFile {
+    pub path: String,
+    pub name: String,
+    // Milliseconds since the Unix epoch
+    pub last_opened: u64,
+}
+
+static RECENT_FILES: OnceLock<Mutex<Vec<RecentFile>>> = OnceLock::new();
+
+// Set when the in-memory list has changes that are not on disk yet
+static RECENT_FILES_DIRTY: AtomicBool = AtomicBool::new(false);
+
+fn recent_files_cell() -> &'static Mutex<Vec<RecentFile>> {
+    RECENT_FILES.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+// Load the MRU list from disk (called once during setup)
+pub fn load_recent_files() {
+    let loaded: Vec<RecentFile> = storage::load_json(RECENT_FILES_FILE);
+    if let Ok(mut files) = recent_files_cell().lock() {
+        *files = loaded;
+    }
+}
+
+// Get a snapshot of the MRU list, most recent first
+pub fn recent_files() -> Vec<RecentFile> {
+    recent_files_cell()
+        .lock()
+        .map(|files| files.clone())
+        .unwrap_or_default()
+}
+
+// Move `path` to the top of `list` (inserting it if new) and enforce the size cap
+pub fn push_recent(list: &mut Vec<RecentFile>, path: &str, now: u64, max: usize) {
+    list.retain(|f| f.path != path);
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    list.insert(
+        0,
+        RecentFile {
+            path: path.to_string(),
+            name,
+            last_opened: now,
+        },
+    );
+    list.truncate(max);
+}
+
+// Apply a change to the MRU list, persist it and refresh the native menu
+fn update_recent_files<F>(app_handle: &tauri::AppHandle, change: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Vec<RecentFile>),
+{
+    let _lock = storage::lock_app_data_file(RECENT_FILES_FILE)?;
+    let snapshot = {
+        let mut files = recent_files_cell()
+            .lock()
+            .map_err(|_| "Failed to lock recent files".to_string())?;
+        // Start from the list on disk, unless this process has changes it could not write
+        if !RECENT_FILES_DIRTY.load(Ordering::SeqCst) {
+            *files = storage::load_json(RECENT_FILES_FILE);
+        }
+        change(&mut files);
+        files.clone()
+    };
+    menu::rebuild_open_recent_menu(app_handle, &snapshot);
+    RECENT_FILES_DIRTY.store(true, Ordering::SeqCst);
+    write_recent_files().map(|_| ())
+}
+
+// Write the MRU list to disk if it has unsaved changes (e.g. a previous write failed)
+pub fn flush_recent_files() -> Result<bool, String> {
+    if !RECENT_FILES_DIRTY.load(Ordering::SeqCst) {
+        return Ok(false);
+    }
+    let _lock = storage::lock_app_data_file(RECENT_FILES_FILE)?;
+    write_recent_files()
+}
+
+// Write unsaved changes of the MRU list (the caller holds the file's lock)
+fn write_recent_files() -> Result<bool, String> {
+    if !RECENT_FILES_DIRTY.swap(false, Ordering::SeqCst) {
+        return Ok(false);
+    }
+    let snapshot = recent_files();
+    storage::save_json(RECENT_FILES_FILE, &snapshot).inspect_err(|_| {
+        RECENT_FILES_DIRTY.store(true, Ordering::SeqCst);
+    })?;
+    Ok(true)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Tauri command: Record that a file was opened
+#[tauri::command]
+pub fn add_recent_file(app_handle: tauri::AppHandle, path: String) -> Result<(), String> {
+    path_scope::check_path(&path)?;
+    update_recent_files(&app_handle, |files| {
+        push_recent(files, &path, now_millis(), MAX_RECENT_FILES)
+    })
+}
+
+// Tauri command: Get the MRU list
+#[tauri::command]
+pub fn get_recent_files() -> Vec<RecentFile> {
+    recent_files()
+}
+
+// Tauri command: Remove one file from the MRU list
+#[tauri::command]
+pub fn remove_recent_file(app_handle: tauri::AppHandle, path: String) -> Result<(), String> {
+    update_recent_files(&app_handle, |files| files.retain(|f| f.path != path))
+}
+
+// Tauri command: Clear the MRU list
+#[tauri::command]
+pub fn clear_recent_files(app_handle: tauri::AppHandle) -> Result<(), String> {
+    update_recent_files(&app_handle, |files| files.clear())
+}
@@ -0,0 +1,186 @@
+//! # Recovery Module
+//!
+//! This module lists what can be recovered after a crash or a bad bulk edit, so the user
+//! does not have to look through the app data directory:
+//! - Autosave snapshots of unsaved tabs (`autosave`)
+//! - Copies of files made before a find-and-replace changed them (`replace`)
+//!
+//! ## Items
+//! Each item has an ID naming its file inside the app data directory
+//! (`autosave:<file>` or `backup:<run>/<file>`), the time it was taken, the file it came
+//! from (when known) and the first characters of its content.
+//! `restore_recovery_item` saves an item's content to a file the way the editor saves
+//! documents (scope and file type checks, sync and auto-commit).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::{info, warn};
+
+use crate::autosave::{self, AutosaveSnapshot};
+use crate::commands;
+use crate::encryption;
+use crate::replace;
+use crate::storage;
+
+// Characters of content shown as an item's preview
+const PREVIEW_CHARS: usize = 200;
+
+const AUTOSAVE_PREFIX: &str = "autosave:";
+const BACKUP_PREFIX: &str = "backup:";
+
+// Where a recovery item comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryKind {
+    Autosave,
+    Backup,
+}
+
+// Something that can be recovered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryItem {
+    pub id: String,
+    pub kind: RecoveryKind,
+    pub title: String,
+    // File the content came from; None for untitled tabs
+    pub original_path: Option<String>,
+    // Milliseconds since the Unix epoch
+    pub saved_at: u64,
+    pub size: u64,
+    pub preview: String,
+}
+
+fn preview(content: &str) -> String {
+    content.chars().take(PREVIEW_CHARS).collect()
+}
+
+// A plain file name (no separators, `.` or `..`), as used in item IDs
+fn is_plain_name(name: &str) -> bool {
+    Path::new(name).file_name().is_some_and(|n| n == name) && !name.contains(['/', '\\'])
+}
+
+fn autosave_items(dir: &Path) -> Vec<RecoveryItem> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".json") {
+                return None;
+            }
+            let content = fs::read_to_string(entry.path()).ok()?;
+            let snapshot: AutosaveSnapshot = match serde_json::from_str(&content) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    warn!("Skipping unreadable autosave snapshot {:?}: {}", entry.path(), e);
+                    return None;
+                }
+            };
+            // Older versions kept snapshots of encrypted documents: remove their plain text
+            if snapshot.file_path.as_deref().is_some_and(encryption::is_encrypted_path) {
+                let _ = fs::remove_file(entry.path());
+                return None;
+            }
+            Some(RecoveryItem {
+                id: format!("{}{}", AUTOSAVE_PREFIX, name),
+                kind: RecoveryKind::Autosave,
+                title: snapshot.title,
+                original_path: snapshot.file_path,
+                saved_at: snapshot.saved_at,
+                size: snapshot.content.len() as u64,
+                preview: preview(&snapshot.content),
+            })
+        })
+        .collect()
+}
+
+fn backup_items(root: &Path) -> Vec<RecoveryItem> {
+    let Ok(runs) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut items = Vec::new();
+    for run in runs.flatten().filter(|run| run.path().is_dir()) {
+        let run_name = run.file_name().to_string_lossy().to_string();
+        // Runs are named after the time they were made, in milliseconds
+        let saved_at = run_name.parse::<u64>().unwrap_or(0);
+        let manifest: HashMap<String, String> =
+            storage::read_json_file(&run.path().join(replace::BACKUP_MANIFEST));
+        for (backup_name, original) in manifest {
+            let path = run.path().join(&backup_name);
+            if !is_plain_name(&backup_name) || !path.is_file() {
+                continue;
+            }
+            let bytes = fs::read(&path).unwrap_or_default();
+            let title = Path::new(&original)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| backup_name.clone());
+            items.push(RecoveryItem {
+                id: format!("{}{}/{}", BACKUP_PREFIX, run_name, backup_name),
+                kind: RecoveryKind::Backup,
+                title,
+                original_path: Some(original),
+                saved_at,
+                size: bytes.len() as u64,
+                preview: preview(&String::from_utf8_lossy(&bytes)),
+            });
+        }
+    }
+    items
+}
+
+// All recovery items found in the autosave directory and the backup root, newest first
+pub fn collect_recovery_items(autosave_dir: &Path, backup_root: &Path) -> Vec<RecoveryItem> {
+    let mut items = autosave_items(autosave_dir);
+    items.extend(backup_items(backup_root));
+    items.sort_by(|a, b| b.saved_at.cmp(&a.saved_at).then_with(|| a.id.cmp(&b.id)));
+    items
+}
+
+// The content of a recovery item
+pub fn recovery_item_content(autosave_dir: &Path, backup_root: &Path, id: &str) -> Result<String, String> {
+    if let Some(name) = id.strip_prefix(AUTOSAVE_PREFIX).filter(|name| is_plain_name(name)) {
+        let content =
+            fs::read_to_string(autosave_dir.join(name)).map_err(|_| "Recovery item not found".to_string())?;
+        let snapshot: AutosaveSnapshot =
+            serde_json::from_str(&content).map_err(|e| format!("Failed to read autosave snapshot: {}", e))?;
+        return Ok(snapshot.content);
+    }
+    let backup = id.strip_prefix(BACKUP_PREFIX).and_then(|rest| rest.split_once('/'));
+    if let Some((run, name)) = backup.filter(|(run, name)| is_plain_name(run) && is_plain_name(name)) {
+        let bytes =
+            fs::read(backup_root.join(run).join(name)).map_err(|_| "Recovery item not found".to_string())?;
+        return Ok(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    Err(format!("Invalid recovery item: {}", id))
+}
+
+fn recovery_dirs() -> Result<(PathBuf, PathBuf), String> {
+    autosave::autosave_dir()
+        .zip(replace::backup_root())
+        .ok_or_else(|| "App data directory is not available".to_string())
+}
+
+// Tauri command: List the autosave snapshots and find-and-replace backups, newest first
+#[tauri::command]
+pub fn list_recovery_items() -> Result<Vec<RecoveryItem>, String> {
+    // Snapshots still buffered in memory are listed too
+    autosave::flush_pending_snapshots()?;
+    let (autosave_dir, backup_root) = recovery_dirs()?;
+    Ok(collect_recovery_items(&autosave_dir, &backup_root))
+}
+
+// Tauri command: Save the content of a recovery item to `target_path`
+#[tauri::command]
+pub async fn restore_recovery_item(id: String, target_path: String) -> Result<(), String> {
+    let (autosave_dir, backup_root) = recovery_dirs()?;
+    let content = recovery_item_content(&autosave_dir, &backup_root, &id)?;
+    commands::save_file(target_path.clone(), content).await?;
+    info!("Restored {} to {}", id, target_path);
+    Ok(())
+}
@@ -0,0 +1,252 @@
+//! # Remote Files Module
+//!
+//! This module opens and saves documents that live on a server instead of the local disk,
+//! such as notes in a Nextcloud. Each protocol has its own backend module; this module
+//! keeps the configured endpoints and routes the commands to the right backend.
+//!
+//! ## Backends
+//! - `webdav`: WebDAV servers (Nextcloud, ownCloud, Apache mod_dav, ...)
+//! - `sftp`: SSH servers with SFTP (key-based authentication)
+//!
+//! ## Endpoints
+//! Endpoints are stored in `remote-endpoints.json` in the app data directory. Their
+//! passwords (or SSH key passphrases) are kept in the OS keychain (`credentials`), never
+//! in that file.
+//!
+//! ## Paths
+//! Remote paths are relative to the endpoint URL and use '/' separators
+//! (`notes/today.md`); an empty path is the endpoint's root folder.
+//!
+//! ## Conflicts
+//! `remote_read` returns the ETag of the version it read. `remote_save` with that ETag
+//! only writes when the remote file is still that version; otherwise nothing is written
+//! and the result reports a conflict, so the frontend can offer to reload or overwrite
+//! (saving again without an ETag).
+
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::info;
+
+use crate::credentials;
+use crate::sftp::SftpClient;
+use crate::storage;
+use crate::webdav::WebDavClient;
+
+const ENDPOINTS_FILE: &str = "remote-endpoints.json";
+
+// Protocol of a remote endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RemoteKind {
+    #[default]
+    WebDav,
+    Sftp,
+}
+
+// A configured remote location
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteEndpoint {
+    // Assigned when the endpoint is first saved
+    pub id: String,
+    pub name: String,
+    pub kind: RemoteKind,
+    // Base URL (e.g. "https://cloud.example.com/remote.php/dav/files/me/" or
+    // "sftp://example.com/var/www")
+    pub url: String,
+    pub username: Option<String>,
+    // SSH private key file (SFTP); None uses the SSH agent and default keys
+    pub private_key: Option<String>,
+}
+
+// File or folder on a remote endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteEntry {
+    // Relative to the endpoint
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    // As reported by the server (e.g. an HTTP date)
+    pub modified: Option<String>,
+    pub etag: Option<String>,
+}
+
+// Content of a remote document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteDocument {
+    pub path: String,
+    pub content: String,
+    // Version that was read, for conflict detection on save
+    pub etag: Option<String>,
+}
+
+// Result of saving a remote document
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RemoteSaveResult {
+    pub saved: bool,
+    // The remote file changed since it was read; nothing was written
+    pub conflict: bool,
+    // New version after a successful save
+    pub etag: Option<String>,
+}
+
+fn credential_key(endpoint_id: &str) -> String {
+    format!("remote:{}", endpoint_id)
+}
+
+// Normalize a remote path: '/' separators, no leading/trailing '/', no "." or ".."
+pub fn normalize_remote_path(path: &str) -> Result<String, String> {
+    let mut parts = Vec::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return Err(format!("Invalid remote path: {}", path)),
+            part => parts.push(part),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+fn load_endpoints() -> Vec<RemoteEndpoint> {
+    storage::load_json(ENDPOINTS_FILE)
+}
+
+fn find_endpoint(id: &str) -> Result<RemoteEndpoint, String> {
+    load_endpoints()
+        .into_iter()
+        .find(|endpoint| endpoint.id == id)
+        .ok_or_else(|| format!("Unknown remote endpoint: {}", id))
+}
+
+// Backend client of an endpoint
+enum RemoteClient {
+    WebDav(WebDavClient),
+    Sftp(SftpClient),
+}
+
+fn connect(endpoint_id: &str) -> Result<RemoteClient, String> {
+    let endpoint = find_endpoint(endpoint_id)?;
+    let password = credentials::get_secret(&credential_key(&endpoint.id))?;
+    match endpoint.kind {
+        RemoteKind::WebDav => Ok(RemoteClient::WebDav(WebDavClient::new(
+            &endpoint.url,
+            endpoint.username,
+            password,
+        )?)),
+        RemoteKind::Sftp => Ok(RemoteClient::Sftp(SftpClient::new(
+            &endpoint.url,
+            endpoint.username,
+            endpoint.private_key,
+            password,
+        )?)),
+    }
+}
+
+// Run a blocking backend call (SFTP) off the async runtime
+async fn run_blocking<T: Send + 'static>(task: impl FnOnce() -> Result<T, String> + Send + 'static) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| format!("Remote operation failed: {}", e))?
+}
+
+// Tauri command: List the configured remote endpoints
+#[tauri::command]
+pub fn list_remote_endpoints() -> Vec<RemoteEndpoint> {
+    load_endpoints()
+}
+
+// Tauri command: Add or update a remote endpoint. A password replaces the stored one; None
+// keeps it. Returns the endpoint with its id.
+#[tauri::command]
+pub fn save_remote_endpoint(mut endpoint: RemoteEndpoint, password: Option<String>) -> Result<RemoteEndpoint, String> {
+    if endpoint.name.trim().is_empty() {
+        return Err("Endpoint name is empty".to_string());
+    }
+    match endpoint.kind {
+        RemoteKind::WebDav => {
+            WebDavClient::new(&endpoint.url, None, None)?;
+        }
+        RemoteKind::Sftp => {
+            SftpClient::new(&endpoint.url, endpoint.username.clone(), None, None)?;
+        }
+    }
+    if endpoint.id.is_empty() {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        endpoint.id = format!("{:x}", millis);
+    }
+    if let Some(password) = password {
+        credentials::set_secret(&credential_key(&endpoint.id), &password)?;
+    }
+
+    let mut endpoints = load_endpoints();
+    match endpoints.iter_mut().find(|e| e.id == endpoint.id) {
+        Some(existing) => *existing = endpoint.clone(),
+        None => endpoints.push(endpoint.clone()),
+    }
+    storage::save_json(ENDPOINTS_FILE, &endpoints)?;
+    info!("Saved remote endpoint {} ({})", endpoint.name, endpoint.id);
+    Ok(endpoint)
+}
+
+// Tauri command: Remove a remote endpoint and its stored password
+#[tauri::command]
+pub fn delete_remote_endpoint(id: String) -> Result<(), String> {
+    let mut endpoints = load_endpoints();
+    endpoints.retain(|endpoint| endpoint.id != id);
+    storage::save_json(ENDPOINTS_FILE, &endpoints)?;
+    credentials::delete_secret(&credential_key(&id))
+}
+
+// Tauri command: List a folder of a remote endpoint (folders first, then by name)
+#[tauri::command]
+pub async fn remote_list(endpoint_id: String, path: String) -> Result<Vec<RemoteEntry>, String> {
+    let path = normalize_remote_path(&path)?;
+    let mut entries = match connect(&endpoint_id)? {
+        RemoteClient::WebDav(client) => client.list(&path).await?,
+        RemoteClient::Sftp(client) => run_blocking(move || client.list(&path)).await?,
+    };
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    Ok(entries)
+}
+
+// Tauri command: Read a remote document
+#[tauri::command]
+pub async fn remote_read(endpoint_id: String, path: String) -> Result<RemoteDocument, String> {
+    let path = normalize_remote_path(&path)?;
+    match connect(&endpoint_id)? {
+        RemoteClient::WebDav(client) => client.read(&path).await,
+        RemoteClient::Sftp(client) => run_blocking(move || client.read(&path)).await,
+    }
+}
+
+// Tauri command: Save a remote document. With `etag`, the save only succeeds if the remote
+// file is still that version; without it, a new file is created or an existing one is
+// overwritten.
+#[tauri::command]
+pub async fn remote_save(
+    endpoint_id: String,
+    path: String,
+    content: String,
+    etag: Option<String>,
+) -> Result<RemoteSaveResult, String> {
+    let path = normalize_remote_path(&path)?;
+    if path.is_empty() {
+        return Err("Remote path is empty".to_string());
+    }
+    let result = match connect(&endpoint_id)? {
+        RemoteClient::WebDav(client) => client.save(&path, content, etag.as_deref()).await?,
+        RemoteClient::Sftp(client) => {
+            let path = path.clone();
+            run_blocking(move || client.save(&path, &content, etag.as_deref())).await?
+        }
+    };
+    if result.conflict {
+        info!("Remote save of {} skipped: changed on the server", path);
+    }
+    Ok(result)
+}
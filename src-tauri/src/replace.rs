@@ -0,0 +1,314 @@
+//! # Replace Module
+//!
+//! This module implements find-and-replace across the documents of a workspace.
+//!
+//! ## Flow
+//! 1. `replace_in_files` is a dry run: it returns every proposed change (file, line,
+//!    before/after) together with a hash of each file's current content. Files are
+//!    scanned on the `worker_pool`, as a task that can be cancelled (see `tasks`)
+//! 2. The user picks the changes to keep, and `apply_replacements` applies only those
+//!
+//! ## Safety
+//! - A file that changed since the preview (hash mismatch) aborts the whole operation
+//! - Every file is copied to `replace-backups/<timestamp>` in the app data directory
+//!   before it is modified, with a `manifest.json` mapping backups to original paths
+//! - Files are written atomically; if any write fails, the files already written are
+//!   restored from their backups, so either all selected files change or none does
+//!
+//! The backups are shared with other bulk rewrites (`links::rename_file_with_links`).
+
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tracing::{info, warn};
+
+use crate::path_scope;
+use crate::storage;
+use crate::worker_pool;
+use crate::tasks::{self, CancellationToken};
+use crate::workspace;
+
+const BACKUP_DIR: &str = "replace-backups";
+pub const BACKUP_MANIFEST: &str = "manifest.json";
+
+// Replace options (all fields optional from the frontend)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplaceOptions {
+    // Treat the pattern as a regular expression; the replacement may use `$1`, `${name}`
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    // Replace in all files instead of only Markdown and text files
+    pub include_all_files: bool,
+}
+
+// One proposed change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposedChange {
+    // 1-based
+    pub line_number: usize,
+    pub before: String,
+    pub after: String,
+}
+
+// Proposed changes in one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReplacePreview {
+    pub path: String,
+    // Hash of the content the preview was computed from
+    pub hash: String,
+    pub changes: Vec<ProposedChange>,
+}
+
+// Changes selected by the user for one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceSelection {
+    pub path: String,
+    pub hash: String,
+    pub line_numbers: Vec<usize>,
+}
+
+// Result of applying replacements
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceResult {
+    pub files_changed: usize,
+    pub lines_changed: usize,
+    pub backup_dir: String,
+}
+
+// Directory holding a folder of backups for every applied replacement
+pub fn backup_root() -> Option<PathBuf> {
+    storage::app_data_path(BACKUP_DIR)
+}
+
+// New folder of backups in `backup_root`, named after the current time in milliseconds
+pub fn new_backup_dir() -> Result<PathBuf, String> {
+    let stamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    Ok(backup_root()
+        .ok_or_else(|| "App data directory is not available".to_string())?
+        .join(stamp.to_string()))
+}
+
+// Build the regex for a pattern
+pub fn build_pattern(pattern: &str, options: &ReplaceOptions) -> Result<Regex, String> {
+    if pattern.is_empty() {
+        return Err("Search pattern is empty".to_string());
+    }
+    let mut source = if options.regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    if options.whole_word {
+        source = format!(r"\b(?:{})\b", source);
+    }
+    RegexBuilder::new(&source)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn replace_line(regex: &Regex, line: &str, replacement: &str, options: &ReplaceOptions) -> String {
+    if options.regex {
+        regex.replace_all(line, replacement).into_owned()
+    } else {
+        regex.replace_all(line, NoExpand(replacement)).into_owned()
+    }
+}
+
+// Replace in the selected lines (all lines if `lines` is None). Returns the new content
+// and the proposed changes.
+pub fn replace_in_content(
+    content: &str,
+    regex: &Regex,
+    replacement: &str,
+    options: &ReplaceOptions,
+    lines: Option<&HashSet<usize>>,
+) -> (String, Vec<ProposedChange>) {
+    let mut output = String::with_capacity(content.len());
+    let mut changes = Vec::new();
+    for (index, raw_line) in content.split_inclusive('\n').enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.trim_end_matches(['\r', '\n']);
+        let ending = &raw_line[line.len()..];
+        let selected = lines.is_none_or(|l| l.contains(&line_number));
+        if selected && regex.is_match(line) {
+            let after = replace_line(regex, line, replacement, options);
+            if after != line {
+                output.push_str(&after);
+                output.push_str(ending);
+                changes.push(ProposedChange {
+                    line_number,
+                    before: line.to_string(),
+                    after,
+                });
+                continue;
+            }
+        }
+        output.push_str(raw_line);
+    }
+    (output, changes)
+}
+
+// Compute the proposed changes for every file of a workspace
+pub fn preview_replacements(
+    root: &Path,
+    pattern: &str,
+    replacement: &str,
+    options: &ReplaceOptions,
+    cancel: &CancellationToken,
+) -> Result<Vec<FileReplacePreview>, String> {
+    let regex = build_pattern(pattern, options)?;
+    let files = workspace::walk_workspace(root, options.include_all_files)?;
+    let previews = worker_pool::map_parallel(files, |path| {
+        if cancel.is_cancelled() {
+            return None;
+        }
+        // Binary or non-UTF-8 files are not modified
+        let content = fs::read_to_string(&path).ok()?;
+        let (_, changes) = replace_in_content(&content, &regex, replacement, options, None);
+        (!changes.is_empty()).then(|| FileReplacePreview {
+            path: path.to_string_lossy().to_string(),
+            hash: content_hash(&content),
+            changes,
+        })
+    });
+    cancel.check()?;
+    Ok(previews.into_iter().flatten().collect())
+}
+
+// Copy every file to `backup_dir` and write the manifest mapping backups to original
+// paths. Returns the backup of each file.
+pub fn back_up_files(paths: &[PathBuf], backup_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(backup_dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    let mut backups = Vec::with_capacity(paths.len());
+    let mut manifest = BTreeMap::new();
+    for (index, path) in paths.iter().enumerate() {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let backup_name = format!("{}-{}", index, file_name);
+        let backup = backup_dir.join(&backup_name);
+        fs::copy(path, &backup).map_err(|e| format!("Failed to back up {:?}: {}", path, e))?;
+        manifest.insert(backup_name, path.to_string_lossy().to_string());
+        backups.push(backup);
+    }
+    storage::write_json_file(&backup_dir.join(BACKUP_MANIFEST), &manifest)?;
+    Ok(backups)
+}
+
+// Copy backups (second) back over their original files (first)
+pub fn restore_backups(written: &[(PathBuf, PathBuf)]) {
+    for (original, backup) in written {
+        if let Err(e) = fs::copy(backup, original) {
+            warn!("Failed to restore {:?} from {:?}: {}", original, backup, e);
+        }
+    }
+}
+
+// Apply the selected changes, backing up every modified file into `backup_dir`
+pub fn apply_selected_replacements(
+    pattern: &str,
+    replacement: &str,
+    options: &ReplaceOptions,
+    selections: &[ReplaceSelection],
+    backup_dir: &Path,
+) -> Result<ReplaceResult, String> {
+    let regex = build_pattern(pattern, options)?;
+
+    // Compute all new contents first, so nothing is written if any file changed
+    let mut updates = Vec::new();
+    let mut lines_changed = 0;
+    for selection in selections {
+        let content =
+            fs::read_to_string(&selection.path).map_err(|e| format!("Failed to read {}: {}", selection.path, e))?;
+        if content_hash(&content) != selection.hash {
+            return Err(format!("File changed since the preview: {}", selection.path));
+        }
+        let lines: HashSet<usize> = selection.line_numbers.iter().copied().collect();
+        let (new_content, changes) = replace_in_content(&content, &regex, replacement, options, Some(&lines));
+        if !changes.is_empty() {
+            lines_changed += changes.len();
+            updates.push((PathBuf::from(&selection.path), new_content));
+        }
+    }
+
+    // Back up every file before touching any of them
+    let paths: Vec<PathBuf> = updates.iter().map(|(path, _)| path.clone()).collect();
+    let backups = back_up_files(&paths, backup_dir)?;
+
+    let mut written = Vec::with_capacity(updates.len());
+    for ((path, new_content), backup) in updates.iter().zip(&backups) {
+        if let Err(e) = storage::write_atomic(path, new_content.as_bytes()) {
+            restore_backups(&written);
+            return Err(format!("Failed to write {:?}, all changes were rolled back: {}", path, e));
+        }
+        written.push((path.clone(), backup.clone()));
+    }
+
+    Ok(ReplaceResult {
+        files_changed: updates.len(),
+        lines_changed,
+        backup_dir: backup_dir.to_string_lossy().to_string(),
+    })
+}
+
+// Tauri command: Preview a find-and-replace across a workspace (nothing is written)
+#[tauri::command]
+pub async fn replace_in_files(
+    root: String,
+    pattern: String,
+    replacement: String,
+    options: Option<ReplaceOptions>,
+    task_id: Option<String>,
+) -> Result<Vec<FileReplacePreview>, String> {
+    path_scope::check_path(&root)?;
+    let options = options.unwrap_or_default();
+    let task = tasks::register_task(task_id, "replace")?;
+    tauri::async_runtime::spawn_blocking(move || {
+        preview_replacements(Path::new(&root), &pattern, &replacement, &options, &task.token())
+    })
+    .await
+    .map_err(|e| format!("Replace failed: {}", e))?
+}
+
+// Tauri command: Apply the selected changes of a `replace_in_files` preview
+#[tauri::command]
+pub async fn apply_replacements(
+    pattern: String,
+    replacement: String,
+    options: Option<ReplaceOptions>,
+    selections: Vec<ReplaceSelection>,
+) -> Result<ReplaceResult, String> {
+    let options = options.unwrap_or_default();
+    let backup_dir = new_backup_dir()?;
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        // The selections come from the WebView: only documents in the scope may be rewritten
+        for selection in &selections {
+            path_scope::check_document_write(&selection.path)?;
+        }
+        apply_selected_replacements(&pattern, &replacement, &options, &selections, &backup_dir)
+    })
+    .await
+    .map_err(|e| format!("Replace failed: {}", e))??;
+    info!(
+        "Replaced {} lines in {} files (backups in {})",
+        result.lines_changed, result.files_changed, result.backup_dir
+    );
+    Ok(result)
+}
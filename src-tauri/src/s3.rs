@@ -0,0 +1,412 @@
+//! # S3 Module
+//!
+//! This module is a small client for S3-compatible object stores (AWS S3, MinIO,
+//! Cloudflare R2, Backblaze B2, ...), used by the sync engine (`sync`).
+//!
+//! ## Addressing
+//! Without a custom endpoint, AWS virtual-hosted URLs are used
+//! (`https://<bucket>.s3.<region>.amazonaws.com/<key>`). With a custom endpoint, requests
+//! use path-style URLs (`<endpoint>/<bucket>/<key>`), which every S3-compatible store
+//! understands.
+//!
+//! ## Signing
+//! Requests are signed with AWS Signature Version 4. The payload hash is always sent in
+//! `x-amz-content-sha256`; uploads also carry it as `x-amz-meta-sha256` metadata.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::header::{ETAG, LAST_MODIFIED};
+use reqwest::{Method, Response, StatusCode};
+use sha2::{Digest, Sha256};
+
+use crate::http;
+
+const EMPTY_PAYLOAD_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+// An object in a bucket listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub etag: String,
+    pub size: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+// Credentials and location of a bucket
+#[derive(Debug, Clone)]
+pub struct S3Client {
+    // Custom endpoint (e.g. "https://minio.example.com:9000"); None for AWS
+    endpoint: Option<String>,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+// URI-encode as required by Signature Version 4 (everything except unreserved characters)
+pub fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// A request to sign: the values that go into the canonical request
+pub struct SigningRequest<'a> {
+    pub method: &'a str,
+    pub host: &'a str,
+    // URI-encoded path
+    pub path: &'a str,
+    // Decoded query parameters
+    pub query: &'a [(String, String)],
+    // Extra headers to sign (lowercase names)
+    pub headers: &'a [(String, String)],
+    pub payload_sha256: &'a str,
+}
+
+// Compute the Authorization header of a request (Signature Version 4)
+pub fn authorization_header(
+    request: &SigningRequest<'_>,
+    access_key_id: &str,
+    secret_access_key: &str,
+    region: &str,
+    now: DateTime<Utc>,
+) -> String {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let mut query: Vec<(String, String)> = request
+        .query
+        .iter()
+        .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+        .collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut headers: Vec<(String, String)> = vec![
+        ("host".to_string(), request.host.to_string()),
+        ("x-amz-content-sha256".to_string(), request.payload_sha256.to_string()),
+        ("x-amz-date".to_string(), amz_date.clone()),
+    ];
+    headers.extend(
+        request
+            .headers
+            .iter()
+            .map(|(name, value)| (name.to_lowercase(), value.trim().to_string())),
+    );
+    headers.sort();
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method, request.path, canonical_query, canonical_headers, signed_headers, request.payload_sha256
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let key = hmac_sha256(format!("AWS4{}", secret_access_key).as_bytes(), &date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, "s3");
+    let key = hmac_sha256(&key, "aws4_request");
+    let signature = hex_string(&hmac_sha256(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        access_key_id, scope, signed_headers, signature
+    )
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Parse a ListObjectsV2 response: the objects and the continuation token, if truncated
+pub fn parse_list_objects(xml: &str) -> Result<(Vec<ObjectInfo>, Option<String>), String> {
+    // Text is not trimmed: keys may start or end with spaces, also around entities
+    let mut reader = Reader::from_str(xml);
+    let mut objects = Vec::new();
+    let mut current: Option<ObjectInfo> = None;
+    let mut next_token = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event().map_err(|e| format!("Invalid S3 response: {}", e))? {
+            Event::Start(start) => {
+                if start.local_name().as_ref() == b"Contents" {
+                    current = Some(ObjectInfo {
+                        key: String::new(),
+                        etag: String::new(),
+                        size: 0,
+                        last_modified: None,
+                    });
+                }
+                text.clear();
+            }
+            Event::Text(content) => {
+                text.push_str(&content.decode().map_err(|e| format!("Invalid S3 response: {}", e))?);
+            }
+            Event::GeneralRef(reference) => {
+                if let Ok(Some(c)) = reference.resolve_char_ref() {
+                    text.push(c);
+                } else if let Some(resolved) =
+                    quick_xml::escape::resolve_predefined_entity(&String::from_utf8_lossy(&reference))
+                {
+                    text.push_str(resolved);
+                }
+            }
+            Event::End(end) => {
+                let name = end.local_name();
+                match (name.as_ref(), current.as_mut()) {
+                    (b"Contents", _) => objects.extend(current.take()),
+                    (b"Key", Some(object)) => object.key = text.clone(),
+                    (b"ETag", Some(object)) => object.etag = text.clone(),
+                    (b"Size", Some(object)) => object.size = text.parse().unwrap_or(0),
+                    (b"LastModified", Some(object)) => {
+                        object.last_modified = DateTime::parse_from_rfc3339(&text).ok().map(|t| t.with_timezone(&Utc));
+                    }
+                    (b"NextContinuationToken", None) => next_token = Some(text.clone()),
+                    _ => {}
+                }
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok((objects, next_token))
+}
+
+fn header_string(response: &Response, name: reqwest::header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+impl S3Client {
+    pub fn new(
+        endpoint: Option<String>,
+        bucket: String,
+        region: String,
+        access_key_id: String,
+        secret_access_key: String,
+    ) -> Result<Self, String> {
+        if bucket.trim().is_empty() {
+            return Err("No bucket is configured".to_string());
+        }
+        if access_key_id.trim().is_empty() || secret_access_key.is_empty() {
+            return Err("S3 credentials are missing".to_string());
+        }
+        let endpoint = endpoint
+            .map(|e| e.trim().trim_end_matches('/').to_string())
+            .filter(|e| !e.is_empty());
+        if let Some(endpoint) = &endpoint
+            && !(endpoint.starts_with("https://") || endpoint.starts_with("http://"))
+        {
+            return Err("S3 endpoint must start with http:// or https://".to_string());
+        }
+        let region = Some(region.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .unwrap_or_else(|| "us-east-1".to_string());
+        Ok(Self {
+            endpoint,
+            bucket: bucket.trim().to_string(),
+            region,
+            access_key_id: access_key_id.trim().to_string(),
+            secret_access_key,
+        })
+    }
+
+    // Base URL (scheme and host) and URI-encoded path of an object key ("" for the bucket)
+    pub fn object_location(&self, key: &str) -> (String, String) {
+        match &self.endpoint {
+            Some(endpoint) => {
+                let path = format!("/{}/{}", uri_encode(&self.bucket, true), uri_encode(key, false));
+                (endpoint.clone(), path)
+            }
+            None => (
+                format!("https://{}.s3.{}.amazonaws.com", self.bucket, self.region),
+                format!("/{}", uri_encode(key, false)),
+            ),
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(String, String)],
+        headers: &[(String, String)],
+        body: Vec<u8>,
+    ) -> Result<Response, String> {
+        let (base, path) = self.object_location(key);
+        let host = base.split("://").nth(1).unwrap_or(&base).to_string();
+        let payload_sha256 = if body.is_empty() {
+            EMPTY_PAYLOAD_SHA256.to_string()
+        } else {
+            sha256_hex(&body)
+        };
+        let now = Utc::now();
+        let authorization = authorization_header(
+            &SigningRequest {
+                method: method.as_str(),
+                host: &host,
+                path: &path,
+                query,
+                headers,
+                payload_sha256: &payload_sha256,
+            },
+            &self.access_key_id,
+            &self.secret_access_key,
+            &self.region,
+            now,
+        );
+
+        let query_string = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", uri_encode(k, true), uri_encode(v, true)))
+            .collect::<Vec<_>>()
+            .join("&");
+        let url = if query_string.is_empty() {
+            format!("{}{}", base, path)
+        } else {
+            format!("{}{}?{}", base, path, query_string)
+        };
+
+        let mut request = http::client()?
+            .request(method, &url)
+            .header("authorization", authorization)
+            .header("x-amz-content-sha256", payload_sha256)
+            .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string());
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if !body.is_empty() {
+            request = request.body(body);
+        }
+        request.send().await.map_err(|e| format!("S3 request failed: {}", e))
+    }
+
+    async fn error_message(action: &str, response: Response) -> String {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let code = body
+            .split("<Code>")
+            .nth(1)
+            .and_then(|rest| rest.split("</Code>").next())
+            .unwrap_or("");
+        format!("Failed to {}: {} {}", action, status, code).trim().to_string()
+    }
+
+    // List all objects below a prefix
+    pub async fn list_objects(&self, prefix: &str) -> Result<Vec<ObjectInfo>, String> {
+        let mut objects = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("list-type".to_string(), "2".to_string()),
+                ("prefix".to_string(), prefix.to_string()),
+            ];
+            if let Some(token) = &token {
+                query.push(("continuation-token".to_string(), token.clone()));
+            }
+            let response = self.send(Method::GET, "", &query, &[], Vec::new()).await?;
+            if !response.status().is_success() {
+                return Err(Self::error_message("list bucket", response).await);
+            }
+            let body = response
+                .text()
+                .await
+                .map_err(|e| format!("Failed to list bucket: {}", e))?;
+            let (page, next) = parse_list_objects(&body)?;
+            objects.extend(page);
+            match next {
+                Some(next) => token = Some(next),
+                None => break,
+            }
+        }
+        Ok(objects)
+    }
+
+    // ETag and modification time of an object (None if it does not exist)
+    pub async fn head_object(&self, key: &str) -> Result<Option<ObjectInfo>, String> {
+        let response = self.send(Method::HEAD, key, &[], &[], Vec::new()).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(ObjectInfo {
+                key: key.to_string(),
+                etag: header_string(&response, ETAG).unwrap_or_default(),
+                size: response.content_length().unwrap_or(0),
+                last_modified: header_string(&response, LAST_MODIFIED)
+                    .and_then(|value| DateTime::parse_from_rfc2822(&value).ok())
+                    .map(|t| t.with_timezone(&Utc)),
+            })),
+            _ => Err(Self::error_message("read object", response).await),
+        }
+    }
+
+    // Download an object; returns the content and its ETag
+    pub async fn get_object(&self, key: &str) -> Result<(Vec<u8>, String), String> {
+        let response = self.send(Method::GET, key, &[], &[], Vec::new()).await?;
+        if !response.status().is_success() {
+            return Err(Self::error_message("download object", response).await);
+        }
+        let etag = header_string(&response, ETAG).unwrap_or_default();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to download object: {}", e))?;
+        Ok((bytes.to_vec(), etag))
+    }
+
+    // Upload an object; returns its new ETag
+    pub async fn put_object(&self, key: &str, content: Vec<u8>) -> Result<String, String> {
+        let headers = vec![("x-amz-meta-sha256".to_string(), sha256_hex(&content))];
+        let response = self.send(Method::PUT, key, &[], &headers, content).await?;
+        if !response.status().is_success() {
+            return Err(Self::error_message("upload object", response).await);
+        }
+        Ok(header_string(&response, ETAG).unwrap_or_default())
+    }
+
+    // Delete an object (deleting a missing object is not an error)
+    pub async fn delete_object(&self, key: &str) -> Result<(), String> {
+        let response = self.send(Method::DELETE, key, &[], &[], Vec::new()).await?;
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            return Err(Self::error_message("delete object", response).await);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+//! # Screenshot Module
+//!
+//! This module captures a screenshot and saves it into a document's assets folder, so UI
+//! flows can be documented without going through another app.
+//!
+//! ## Modes
+//! - `full`: The whole screen (all monitors where the tool supports it)
+//! - `window`: A window the user picks
+//! - `region`: A rectangle the user drags
+//!
+//! The main window is hidden while capturing, so it does not cover what is captured.
+//!
+//! ## Capture Tools
+//! Screenshots are taken by the tools the OS provides:
+//! - macOS: `screencapture`
+//! - Linux: the first installed of `gnome-screenshot`, `spectacle`, `grim` (with `slurp`
+//!   for regions), `scrot`, `maim` and ImageMagick's `import`
+//! - Windows: PowerShell for the full screen; the Snipping Tool overlay for windows and
+//!   regions, whose result is picked up from the clipboard
+//!
+//! A capture the user cancels (e.g. with Escape) returns an error and saves nothing.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use tauri::Manager;
+use tracing::{debug, info};
+
+use crate::commands::write_image_dedup;
+use crate::path_scope;
+
+// Time for the hidden main window to disappear from the screen
+const HIDE_DELAY: Duration = Duration::from_millis(300);
+#[cfg(target_os = "windows")]
+const SNIP_TIMEOUT: Duration = Duration::from_secs(120);
+#[cfg(target_os = "windows")]
+const SNIP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+// Output path of the PowerShell capture script
+const OUTPUT_PATH_ENV: &str = "BOKUCHI_SCREENSHOT_PATH";
+
+// What to capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMode {
+    Full,
+    Window,
+    Region,
+}
+
+// A command line of a capture tool that writes a PNG file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureCommand {
+    pub program: &'static str,
+    pub args: Vec<String>,
+    pub env: Vec<(&'static str, String)>,
+}
+
+impl CaptureCommand {
+    fn new(program: &'static str, args: &[&str], output: &Path) -> Self {
+        let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        args.push(output.to_string_lossy().to_string());
+        CaptureCommand {
+            program,
+            args,
+            env: Vec::new(),
+        }
+    }
+}
+
+// Capture tools for a mode on this OS, in order of preference. Every command writes the
+// screenshot to `output`. Empty if the mode needs the Snipping Tool (Windows).
+pub fn capture_commands(mode: CaptureMode, output: &Path) -> Vec<CaptureCommand> {
+    if cfg!(target_os = "macos") {
+        let args: &[&str] = match mode {
+            CaptureMode::Full => &["-x"],
+            CaptureMode::Window => &["-x", "-i", "-w"],
+            CaptureMode::Region => &["-x", "-i", "-s"],
+        };
+        return vec![CaptureCommand::new("screencapture", args, output)];
+    }
+    if cfg!(target_os = "windows") {
+        if mode != CaptureMode::Full {
+            return Vec::new();
+        }
+        let script = concat!(
+            "Add-Type -AssemblyName System.Windows.Forms,System.Drawing;",
+            "Add-Type -Name Dpi -Namespace Bokuchi -MemberDefinition ",
+            "'[DllImport(\"user32.dll\")] public static extern bool SetProcessDPIAware();';",
+            "[Bokuchi.Dpi]::SetProcessDPIAware() | Out-Null;",
+            "$b = [System.Windows.Forms.SystemInformation]::VirtualScreen;",
+            "$bmp = New-Object System.Drawing.Bitmap $b.Width, $b.Height;",
+            "$g = [System.Drawing.Graphics]::FromImage($bmp);",
+            "$g.CopyFromScreen($b.Left, $b.Top, 0, 0, $bmp.Size);",
+            "$bmp.Save($env:BOKUCHI_SCREENSHOT_PATH, [System.Drawing.Imaging.ImageFormat]::Png)",
+        );
+        return vec![CaptureCommand {
+            program: "powershell",
+            args: ["-NoProfile", "-NonInteractive", "-Command", script]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            env: vec![(OUTPUT_PATH_ENV, output.to_string_lossy().to_string())],
+        }];
+    }
+
+    let [gnome, spectacle, scrot, maim, import]: [&[&str]; 5] = match mode {
+        CaptureMode::Full => [&["-f"], &["-b", "-n", "-f", "-o"], &[], &[], &["-window", "root"]],
+        CaptureMode::Window => [&["-w", "-f"], &["-b", "-n", "-u", "-o"], &["-s"], &["-s"], &[]],
+        CaptureMode::Region => [&["-a", "-f"], &["-b", "-n", "-r", "-o"], &["-s"], &["-s"], &[]],
+    };
+    let mut commands = vec![
+        CaptureCommand::new("gnome-screenshot", gnome, output),
+        CaptureCommand::new("spectacle", spectacle, output),
+    ];
+    match mode {
+        CaptureMode::Full => commands.push(CaptureCommand::new("grim", &[], output)),
+        // The output path is passed as $1, never pasted into the script
+        CaptureMode::Region => {
+            commands.push(CaptureCommand::new("sh", &["-c", "grim -g \"$(slurp)\" \"$1\"", "sh"], output))
+        }
+        CaptureMode::Window => {}
+    }
+    commands.push(CaptureCommand::new("scrot", scrot, output));
+    commands.push(CaptureCommand::new("maim", maim, output));
+    commands.push(CaptureCommand::new("import", import, output));
+    commands
+}
+
+fn run_capture_command(command: &CaptureCommand) -> std::io::Result<std::process::ExitStatus> {
+    let mut process = Command::new(command.program);
+    process.args(&command.args).envs(command.env.iter().cloned());
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: no console window flashing up
+        process.creation_flags(0x0800_0000);
+    }
+    process.status()
+}
+
+// Capture with the first installed tool. Returns the PNG bytes.
+fn capture_with_tools(mode: CaptureMode) -> Result<Vec<u8>, String> {
+    let output = std::env::temp_dir().join(format!(
+        "bokuchi-screenshot-{}-{}.png",
+        std::process::id(),
+        Local::now().format("%Y%m%d%H%M%S%f")
+    ));
+    for command in capture_commands(mode, &output) {
+        let status = match run_capture_command(&command) {
+            Ok(status) => status,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to run {}: {}", command.program, e)),
+        };
+        debug!("{} exited with {}", command.program, status);
+        let bytes = fs::read(&output).unwrap_or_default();
+        let _ = fs::remove_file(&output);
+        // `sh` exits with 127 when grim or slurp is missing
+        if command.program == "sh" && status.code() == Some(127) {
+            continue;
+        }
+        if bytes.is_empty() {
+            return Err("The screenshot was cancelled".to_string());
+        }
+        return Ok(bytes);
+    }
+    Err("No screenshot tool found (install gnome-screenshot, spectacle, grim, scrot, maim or ImageMagick)".to_string())
+}
+
+// Open the Snipping Tool overlay and wait for its result on the clipboard
+#[cfg(target_os = "windows")]
+fn capture_with_snipping_tool(app_handle: &tauri::AppHandle) -> Result<Vec<u8>, String> {
+    use crate::clipboard_image::{encode_rgba, ImageFormat};
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let read_clipboard = || {
+        app_handle
+            .clipboard()
+            .read_image()
+            .ok()
+            .map(|image| (image.rgba().to_vec(), image.width(), image.height()))
+    };
+    let before = read_clipboard();
+    Command::new("explorer.exe")
+        .arg("ms-screenclip:")
+        .status()
+        .map_err(|e| format!("Failed to open the Snipping Tool: {}", e))?;
+
+    let started = std::time::Instant::now();
+    while started.elapsed() < SNIP_TIMEOUT {
+        std::thread::sleep(SNIP_POLL_INTERVAL);
+        if let Some((rgba, width, height)) = read_clipboard().filter(|image| Some(image) != before.as_ref()) {
+            return encode_rgba(&rgba, width, height, ImageFormat::Png);
+        }
+    }
+    Err("The screenshot was cancelled".to_string())
+}
+
+fn capture(app_handle: &tauri::AppHandle, mode: CaptureMode) -> Result<Vec<u8>, String> {
+    #[cfg(target_os = "windows")]
+    if mode != CaptureMode::Full {
+        return capture_with_snipping_tool(app_handle);
+    }
+    let _ = app_handle;
+    capture_with_tools(mode)
+}
+
+// Markdown image link to a document-relative path
+pub fn markdown_image_link(relative_path: &str) -> String {
+    if relative_path.contains([' ', '(', ')']) {
+        format!("![Screenshot](<{}>)", relative_path)
+    } else {
+        format!("![Screenshot]({})", relative_path)
+    }
+}
+
+// Save PNG bytes as `screenshot-<date>-<time>.png` in `target_dir/subdir`. Returns the
+// path relative to `target_dir`, forward-slashed.
+pub fn save_screenshot(png: &[u8], target_dir: &str, subdir: &str) -> Result<String, String> {
+    let dir = PathBuf::from(target_dir).join(subdir);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create image directory: {} ({:?})", e, e.kind()))?;
+    let filename = format!("screenshot-{}.png", Local::now().format("%Y%m%d-%H%M%S"));
+    let name = write_image_dedup(&dir, &filename, png)?;
+    let subdir = subdir.replace('\\', "/");
+    let subdir = subdir.trim_matches('/');
+    Ok(if subdir.is_empty() {
+        name
+    } else {
+        format!("{}/{}", subdir, name)
+    })
+}
+
+// Tauri command: Capture a screenshot into the document's assets folder (`subdir` of
+// `target_dir`, e.g. "images") and return the Markdown image link
+#[tauri::command]
+pub async fn capture_screenshot(
+    app_handle: tauri::AppHandle,
+    mode: CaptureMode,
+    target_dir: String,
+    subdir: Option<String>,
+) -> Result<String, String> {
+    let subdir = subdir.unwrap_or_default();
+    path_scope::check_asset_dir(&target_dir, &subdir)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let main_window = app_handle
+            .get_webview_window("main")
+            .filter(|window| window.is_visible().unwrap_or(false));
+        if let Some(window) = &main_window {
+            let _ = window.hide();
+            std::thread::sleep(HIDE_DELAY);
+        }
+        let captured = capture(&app_handle, mode);
+        if main_window.is_some() {
+            crate::tray::show_main_window(&app_handle);
+        }
+
+        let path = save_screenshot(&captured?, &target_dir, &subdir)?;
+        info!("Saved {:?} screenshot as {}", mode, path);
+        Ok(markdown_image_link(&path))
+    })
+    .await
+    .map_err(|e| format!("Failed to capture screenshot: {}", e))?
+}
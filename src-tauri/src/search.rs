@@ -0,0 +1,265 @@
+//! # Search Module
+//!
+//! This module implements search across all documents of a workspace, built on the
+//! ripgrep crates (`ignore` for walking, `grep-searcher`/`grep-regex` for matching).
+//!
+//! ## Options
+//! - Literal or regular expression queries
+//! - Case sensitivity and whole-word matching
+//! - Context lines before and after each matching line
+//! - A cap on the number of matching lines; the result is flagged as truncated
+//!
+//! ## Progress
+//! While searching, `search-progress` events are emitted to the calling window every
+//! `PROGRESS_INTERVAL` files and once at the end. The search runs as a task (see
+//! `tasks`): it also reports `task-progress` and stops when it is cancelled.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use grep_matcher::Matcher;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkMatch};
+use tracing::{debug, info};
+
+use crate::path_scope;
+use crate::tasks::{self, CancellationToken};
+use crate::workspace;
+use crate::types::{emit_event, SearchProgressEvent};
+
+// Number of files between two progress events
+const PROGRESS_INTERVAL: usize = 50;
+
+// Search options (all fields optional from the frontend)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    // Treat the query as a regular expression instead of literal text
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    // Number of context lines before and after each match
+    pub context_lines: usize,
+    // Maximum number of matching lines in the result
+    pub max_results: usize,
+    // Search all files instead of only Markdown and text files
+    pub include_all_files: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            context_lines: 1,
+            max_results: 1000,
+            include_all_files: false,
+        }
+    }
+}
+
+// Range of a match within a line, in UTF-16 code units (JavaScript string offsets)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+// A matching line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchMatch {
+    // 1-based
+    pub line_number: u64,
+    pub line: String,
+    pub ranges: Vec<MatchRange>,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+// Matches in one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSearchResult {
+    pub path: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+// Result of a workspace search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub files: Vec<FileSearchResult>,
+    pub total_matches: usize,
+    pub files_searched: usize,
+    // True if the search stopped at `max_results`
+    pub truncated: bool,
+}
+
+// Build the matcher for a query
+pub fn build_matcher(query: &str, options: &SearchOptions) -> Result<RegexMatcher, String> {
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    RegexMatcherBuilder::new()
+        .fixed_strings(!options.regex)
+        .case_insensitive(!options.case_sensitive)
+        .word(options.whole_word)
+        .build(query)
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+fn line_text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end_matches(['\r', '\n']).to_string()
+}
+
+fn utf16_offset(line: &[u8], byte_offset: usize) -> usize {
+    String::from_utf8_lossy(&line[..byte_offset]).encode_utf16().count()
+}
+
+// Collects the matches of one file, attaching context lines to the nearest match
+struct MatchCollector<'a> {
+    matcher: &'a RegexMatcher,
+    remaining: usize,
+    pending_before: Vec<String>,
+    matches: Vec<SearchMatch>,
+}
+
+impl Sink for MatchCollector<'_> {
+    type Error = std::io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        let bytes = mat.bytes();
+        let mut ranges = Vec::new();
+        self.matcher
+            .find_iter(bytes, |m| {
+                ranges.push(MatchRange {
+                    start: utf16_offset(bytes, m.start()),
+                    end: utf16_offset(bytes, m.end()),
+                });
+                true
+            })
+            .map_err(std::io::Error::other)?;
+
+        self.matches.push(SearchMatch {
+            line_number: mat.line_number().unwrap_or(0),
+            line: line_text(bytes),
+            ranges,
+            context_before: std::mem::take(&mut self.pending_before),
+            context_after: Vec::new(),
+        });
+        self.remaining = self.remaining.saturating_sub(1);
+        Ok(self.remaining > 0)
+    }
+
+    fn context(&mut self, _searcher: &Searcher, context: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        let text = line_text(context.bytes());
+        match context.kind() {
+            SinkContextKind::Before => self.pending_before.push(text),
+            SinkContextKind::After => {
+                if let Some(last) = self.matches.last_mut() {
+                    last.context_after.push(text);
+                }
+            }
+            SinkContextKind::Other => {}
+        }
+        Ok(true)
+    }
+
+    fn context_break(&mut self, _searcher: &Searcher) -> Result<bool, Self::Error> {
+        self.pending_before.clear();
+        Ok(true)
+    }
+}
+
+// Search a workspace, reporting progress through `on_progress`
+pub fn search_workspace_with_progress(
+    root: &Path,
+    query: &str,
+    options: &SearchOptions,
+    cancel: &CancellationToken,
+    mut on_progress: impl FnMut(&SearchProgressEvent),
+) -> Result<SearchResults, String> {
+    let matcher = build_matcher(query, options)?;
+    let files = workspace::walk_workspace(root, options.include_all_files)?;
+    let mut searcher = SearcherBuilder::new()
+        .line_number(true)
+        .before_context(options.context_lines)
+        .after_context(options.context_lines)
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .build();
+
+    let mut results = SearchResults {
+        files: Vec::new(),
+        total_matches: 0,
+        files_searched: 0,
+        truncated: false,
+    };
+    let progress = |results: &SearchResults| SearchProgressEvent {
+        root: root.to_string_lossy().to_string(),
+        files_searched: results.files_searched,
+        files_total: files.len(),
+        matches: results.total_matches,
+    };
+
+    for path in &files {
+        cancel.check()?;
+        let mut collector = MatchCollector {
+            matcher: &matcher,
+            remaining: options.max_results.saturating_sub(results.total_matches),
+            pending_before: Vec::new(),
+            matches: Vec::new(),
+        };
+        if let Err(e) = searcher.search_path(&matcher, path, &mut collector) {
+            debug!("Skipping {:?} in search: {}", path, e);
+        }
+        results.files_searched += 1;
+        if !collector.matches.is_empty() {
+            results.total_matches += collector.matches.len();
+            results.files.push(FileSearchResult {
+                path: path.to_string_lossy().to_string(),
+                matches: collector.matches,
+            });
+        }
+
+        if collector.remaining == 0 {
+            results.truncated = true;
+            break;
+        }
+        if results.files_searched.is_multiple_of(PROGRESS_INTERVAL) {
+            on_progress(&progress(&results));
+        }
+    }
+
+    on_progress(&progress(&results));
+    Ok(results)
+}
+
+// Tauri command: Search all documents of a workspace
+#[tauri::command]
+pub async fn search_workspace(
+    window: tauri::Window,
+    root: String,
+    query: String,
+    options: Option<SearchOptions>,
+    task_id: Option<String>,
+) -> Result<SearchResults, String> {
+    path_scope::check_path(&root)?;
+    let options = options.unwrap_or_default();
+    let task = tasks::register_task(task_id, "search")?;
+    let results = tauri::async_runtime::spawn_blocking(move || {
+        search_workspace_with_progress(Path::new(&root), &query, &options, &task.token(), |progress| {
+            let _ = emit_event(&window, Some(window.label()), progress);
+            task.progress(progress.files_searched, progress.files_total);
+        })
+    })
+    .await
+    .map_err(|e| format!("Search failed: {}", e))??;
+
+    info!(
+        "Workspace search: {} matches in {} of {} files{}",
+        results.total_matches,
+        results.files.len(),
+        results.files_searched,
+        if results.truncated { " (truncated)" } else { "" }
+    );
+    Ok(results)
+}
@@ -0,0 +1,457 @@
+//! # Search Index Module
+//!
+//! This module keeps a persistent full-text index of a workspace, so searching folders
+//! with thousands of files does not rescan every file (see `search` for the scanning
+//! search, which stays the default for small workspaces).
+//!
+//! ## Storage
+//! Each workspace has its own tantivy index under `search-index/<hash>` in the app data
+//! directory, next to a `files.json` manifest of the indexed files and their modification
+//! times. `build_index` only re-indexes files that changed since the last build.
+//!
+//! ## Incremental Updates
+//! After `build_index`, a file watcher follows the workspace. Changes are collected for
+//! `WATCH_DEBOUNCE` and then applied in one commit. Paths the build skips (hidden, or
+//! excluded by `.gitignore` / `.ignore`) are skipped there too. Only one workspace is
+//! indexed at a time; building another workspace's index replaces the active one.
+//!
+//! ## Cancellation
+//! `build_index` runs as a task (see `tasks`). When it is cancelled, the files indexed so
+//! far are committed, so the next build continues where it stopped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use notify::RecommendedWatcher;
+use sha2::{Digest, Sha256};
+use tantivy::collector::TopDocs;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, STORED, STRING, TEXT};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+use tracing::{debug, info, warn};
+
+use crate::markdown;
+use crate::path_scope;
+use crate::storage;
+use crate::tasks::{self, CancellationToken};
+use crate::workspace;
+
+const INDEX_DIR: &str = "search-index";
+const MANIFEST_FILE: &str = "files.json";
+const WRITER_MEMORY_BYTES: usize = 50_000_000;
+// Time to collect file changes before they are applied to the index
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+const DEFAULT_QUERY_LIMIT: usize = 50;
+// Number of files between two progress reports while building
+const PROGRESS_INTERVAL: usize = 100;
+
+// A document found in the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexHit {
+    pub path: String,
+    pub title: String,
+    pub score: f32,
+    // Plain-text excerpt around the best matching terms
+    pub snippet: String,
+}
+
+// State of the search index
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexStatus {
+    // Workspace of the active index (None if no index was built)
+    pub root: Option<String>,
+    pub building: bool,
+    pub indexed_files: usize,
+    // Milliseconds since the Unix epoch
+    pub last_updated: Option<u64>,
+}
+
+struct IndexFields {
+    path: Field,
+    title: Field,
+    body: Field,
+}
+
+// Index of one workspace
+pub struct WorkspaceIndex {
+    root: PathBuf,
+    index_dir: PathBuf,
+    index: Index,
+    reader: IndexReader,
+    writer: IndexWriter,
+    fields: IndexFields,
+    // Indexed file path → modification time (milliseconds)
+    manifest: HashMap<String, u64>,
+    last_updated: Option<u64>,
+}
+
+fn build_schema() -> (Schema, IndexFields) {
+    let mut builder = Schema::builder();
+    let fields = IndexFields {
+        path: builder.add_text_field("path", STRING | STORED),
+        title: builder.add_text_field("title", TEXT | STORED),
+        body: builder.add_text_field("body", TEXT | STORED),
+    };
+    (builder.build(), fields)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Directory holding the index of a workspace
+pub fn index_dir_for(base: &Path, root: &str) -> PathBuf {
+    let hash = format!("{:x}", Sha256::digest(crate::workspace_state::workspace_key(root).as_bytes()));
+    base.join(INDEX_DIR).join(&hash[..16])
+}
+
+impl WorkspaceIndex {
+    // Open the index in `index_dir`, creating it if needed
+    pub fn open(root: &Path, index_dir: &Path) -> Result<Self, String> {
+        let (schema, fields) = build_schema();
+        fs::create_dir_all(index_dir).map_err(|e| format!("Failed to create index directory: {}", e))?;
+        let index = match Index::open_in_dir(index_dir) {
+            Ok(index) if index.schema() == schema => index,
+            existing => {
+                // Missing, unreadable or created with another schema: start over
+                if existing.is_ok() {
+                    info!("Search index schema changed; rebuilding {:?}", index_dir);
+                }
+                let _ = fs::remove_dir_all(index_dir);
+                fs::create_dir_all(index_dir).map_err(|e| format!("Failed to create index directory: {}", e))?;
+                Index::create_in_dir(index_dir, schema).map_err(|e| format!("Failed to create search index: {}", e))?
+            }
+        };
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(|e| format!("Failed to open search index: {}", e))?;
+        let writer = index
+            .writer(WRITER_MEMORY_BYTES)
+            .map_err(|e| format!("Failed to open search index for writing: {}", e))?;
+        let manifest = storage::read_json_file(&index_dir.join(MANIFEST_FILE));
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            index_dir: index_dir.to_path_buf(),
+            index,
+            reader,
+            writer,
+            fields,
+            manifest,
+            last_updated: None,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn indexed_files(&self) -> usize {
+        self.manifest.len()
+    }
+
+    fn delete_path(&mut self, path: &str) {
+        self.writer.delete_term(Term::from_field_text(self.fields.path, path));
+        self.manifest.remove(path);
+    }
+
+    fn index_file(&mut self, path: &Path) -> Result<(), String> {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        let content = String::from_utf8_lossy(&bytes);
+        let key = path.to_string_lossy().to_string();
+        self.writer.delete_term(Term::from_field_text(self.fields.path, &key));
+        self.writer
+            .add_document(doc!(
+                self.fields.path => key.clone(),
+                self.fields.title => markdown::document_title(path, &content),
+                self.fields.body => content.to_string(),
+            ))
+            .map_err(|e| format!("Failed to index {:?}: {}", path, e))?;
+        self.manifest.insert(key, workspace::modified_millis(path).unwrap_or(0));
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), String> {
+        self.writer.commit().map_err(|e| format!("Failed to commit search index: {}", e))?;
+        self.reader.reload().map_err(|e| format!("Failed to reload search index: {}", e))?;
+        self.last_updated = Some(now_millis());
+        storage::write_json_file(&self.index_dir.join(MANIFEST_FILE), &self.manifest)
+    }
+
+    // Bring the index up to date with the workspace. Returns the number of changed files.
+    pub fn sync(&mut self, cancel: &CancellationToken, mut on_progress: impl FnMut(usize, usize)) -> Result<usize, String> {
+        let files = workspace::walk_workspace(&self.root, false)?;
+        let mut changed = 0;
+
+        let current: HashMap<String, &PathBuf> = files
+            .iter()
+            .map(|p| (p.to_string_lossy().to_string(), p))
+            .collect();
+        let removed: Vec<String> = self
+            .manifest
+            .keys()
+            .filter(|k| !current.contains_key(*k))
+            .cloned()
+            .collect();
+        for key in removed {
+            self.delete_path(&key);
+            changed += 1;
+        }
+
+        for (done, path) in files.iter().enumerate() {
+            if cancel.is_cancelled() {
+                break;
+            }
+            if done % PROGRESS_INTERVAL == 0 {
+                on_progress(done, files.len());
+            }
+            let key = path.to_string_lossy().to_string();
+            if self.manifest.get(&key).is_some_and(|m| Some(*m) == workspace::modified_millis(path)) {
+                continue;
+            }
+            match self.index_file(path) {
+                Ok(()) => changed += 1,
+                Err(e) => warn!("{}", e),
+            }
+        }
+
+        if changed > 0 || self.last_updated.is_none() {
+            self.commit()?;
+        }
+        cancel.check()?;
+        on_progress(files.len(), files.len());
+        Ok(changed)
+    }
+
+    // Inside the workspace and neither hidden nor ignored (same rules as
+    // `workspace::walk_workspace`, which builds the index)
+    fn is_visible(&self, path: &Path) -> bool {
+        workspace::is_listed(&self.root, path)
+    }
+
+    // Apply changes reported by the file watcher
+    pub fn update_paths(&mut self, paths: &[PathBuf]) -> Result<usize, String> {
+        let mut changed = 0;
+        for path in paths {
+            let key = path.to_string_lossy().to_string();
+            if path.is_dir() {
+                // A directory was created or moved into the workspace
+                if self.is_visible(path) {
+                    for file in workspace::walk_workspace(path, false)? {
+                        if self.index_file(&file).is_ok() {
+                            changed += 1;
+                        }
+                    }
+                }
+            } else if path.is_file() && self.is_visible(path) && workspace::is_document_path(path) {
+                match self.index_file(path) {
+                    Ok(()) => changed += 1,
+                    Err(e) => warn!("{}", e),
+                }
+            } else {
+                // Removed file or directory: drop it and everything below it
+                let prefix = format!("{}{}", key, std::path::MAIN_SEPARATOR);
+                let removed: Vec<String> = self
+                    .manifest
+                    .keys()
+                    .filter(|k| **k == key || k.starts_with(&prefix))
+                    .cloned()
+                    .collect();
+                for removed_key in removed {
+                    self.delete_path(&removed_key);
+                    changed += 1;
+                }
+            }
+        }
+        if changed > 0 {
+            self.commit()?;
+        }
+        Ok(changed)
+    }
+
+    // Search the index
+    pub fn query(&self, text: &str, limit: usize) -> Result<Vec<IndexHit>, String> {
+        let searcher = self.reader.searcher();
+        let mut parser = QueryParser::for_index(&self.index, vec![self.fields.title, self.fields.body]);
+        parser.set_field_boost(self.fields.title, 2.0);
+        // Lenient parsing: user input is not expected to follow the query syntax
+        let (query, _errors) = parser.parse_query_lenient(text);
+
+        let top_docs = searcher
+            .search(&query, &TopDocs::with_limit(limit))
+            .map_err(|e| format!("Search failed: {}", e))?;
+        let snippets = SnippetGenerator::create(&searcher, &*query, self.fields.body)
+            .map_err(|e| format!("Search failed: {}", e))?;
+
+        let mut hits = Vec::with_capacity(top_docs.len());
+        for (score, address) in top_docs {
+            let doc: TantivyDocument = searcher.doc(address).map_err(|e| format!("Search failed: {}", e))?;
+            let text_of = |field: Field| {
+                doc.get_first(field)
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_default()
+            };
+            hits.push(IndexHit {
+                path: text_of(self.fields.path),
+                title: text_of(self.fields.title),
+                score,
+                snippet: snippets.snippet_from_doc(&doc).fragment().trim().to_string(),
+            });
+        }
+        Ok(hits)
+    }
+}
+
+static ACTIVE_INDEX: OnceLock<Mutex<Option<WorkspaceIndex>>> = OnceLock::new();
+// Watcher of the active index's workspace (dropping it stops the update thread)
+static INDEX_WATCHER: OnceLock<Mutex<Option<RecommendedWatcher>>> = OnceLock::new();
+static INDEX_BUILDING: AtomicBool = AtomicBool::new(false);
+
+fn active_index_cell() -> &'static Mutex<Option<WorkspaceIndex>> {
+    ACTIVE_INDEX.get_or_init(|| Mutex::new(None))
+}
+
+fn index_watcher_cell() -> &'static Mutex<Option<RecommendedWatcher>> {
+    INDEX_WATCHER.get_or_init(|| Mutex::new(None))
+}
+
+// Apply debounced watcher events to the active index while it belongs to `root`
+fn watch_workspace(root: &Path) -> Result<RecommendedWatcher, String> {
+    let index_root = root.to_path_buf();
+    workspace::watch_debounced(root, WATCH_DEBOUNCE, move |paths| {
+        let Ok(mut active) = active_index_cell().lock() else {
+            return false;
+        };
+        match active.as_mut() {
+            Some(index) if index.root() == index_root => {
+                match index.update_paths(&paths) {
+                    Ok(changed) => debug!("Search index: {} files updated", changed),
+                    Err(e) => warn!("Failed to update search index: {}", e),
+                }
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+fn current_status() -> IndexStatus {
+    let building = INDEX_BUILDING.load(Ordering::SeqCst);
+    match active_index_cell().lock() {
+        Ok(active) => match active.as_ref() {
+            Some(index) => IndexStatus {
+                root: Some(index.root().to_string_lossy().to_string()),
+                building,
+                indexed_files: index.indexed_files(),
+                last_updated: index.last_updated,
+            },
+            None => IndexStatus {
+                building,
+                ..Default::default()
+            },
+        },
+        Err(_) => IndexStatus {
+            building,
+            ..Default::default()
+        },
+    }
+}
+
+// Build (or bring up to date) the index of a workspace and make it the active one
+fn build_workspace_index(root: &Path, task: &tasks::TaskGuard) -> Result<IndexStatus, String> {
+    let base = storage::app_data_dir().ok_or_else(|| "App data directory is not available".to_string())?;
+    let root_str = root.to_string_lossy().to_string();
+    let index_dir = index_dir_for(&base, &root_str);
+
+    // Stop updating the previous index and release its writer before opening this one
+    if let Ok(mut watcher) = index_watcher_cell().lock() {
+        *watcher = None;
+    }
+    if let Ok(mut active) = active_index_cell().lock()
+        && active.as_ref().is_some_and(|index| index.index_dir == index_dir)
+    {
+        *active = None;
+    }
+
+    let mut index = WorkspaceIndex::open(root, &index_dir)?;
+    let changed = index.sync(&task.token(), |done, total| task.progress(done, total))?;
+    info!(
+        "Search index for {:?}: {} files, {} updated",
+        root,
+        index.indexed_files(),
+        changed
+    );
+
+    let watcher = match watch_workspace(root) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            warn!("{}", e);
+            None
+        }
+    };
+    {
+        let mut active = active_index_cell()
+            .lock()
+            .map_err(|_| "Failed to lock search index".to_string())?;
+        *active = Some(index);
+    }
+    if let Ok(mut current) = index_watcher_cell().lock() {
+        *current = watcher;
+    }
+    Ok(current_status())
+}
+
+// Tauri command: Build or update the search index of a workspace
+#[tauri::command]
+pub async fn build_index(root: String, task_id: Option<String>) -> Result<IndexStatus, String> {
+    path_scope::check_path(&root)?;
+    if INDEX_BUILDING.swap(true, Ordering::SeqCst) {
+        return Err("The search index is already being built".to_string());
+    }
+    let task = match tasks::register_task(task_id, "index") {
+        Ok(task) => task,
+        Err(e) => {
+            INDEX_BUILDING.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+    };
+    let result = tauri::async_runtime::spawn_blocking(move || build_workspace_index(Path::new(&root), &task))
+        .await
+        .map_err(|e| format!("Failed to build search index: {}", e));
+    INDEX_BUILDING.store(false, Ordering::SeqCst);
+    result?
+}
+
+// Tauri command: Search the active index (waits while the watcher commits changes)
+#[tauri::command]
+pub async fn query_index(text: String, limit: Option<usize>) -> Result<Vec<IndexHit>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let active = active_index_cell()
+            .lock()
+            .map_err(|_| "Failed to lock search index".to_string())?;
+        let index = active
+            .as_ref()
+            .ok_or_else(|| "No search index has been built".to_string())?;
+        index.query(&text, limit.unwrap_or(DEFAULT_QUERY_LIMIT))
+    })
+    .await
+    .map_err(|e| format!("Failed to search index: {}", e))?
+}
+
+// Tauri command: Get the state of the search index
+#[tauri::command]
+pub fn index_status() -> IndexStatus {
+    current_status()
+}
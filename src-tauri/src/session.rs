@@ -0,0 +1,129 @@
+//! # Session Module
+//!
+//! This module keeps the editing session (open tabs, active tab, open workspace) in the
+//! backend so it survives a force-quit.
+//!
+//! The frontend reports the session with `update_session` whenever tabs change. Updates
+//! are cheap (memory only); the session is written to `session.json` by the shutdown hook
+//! and the periodic flusher, and only when it actually changed. The files of the tabs
+//! are then watched (`file_watch`), off the main thread and outside the session lock.
+//!
+//! A process started with `--new-instance` detaches its session (`detach_session`): it
+//! starts empty and is never written, as the file belongs to the main process.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use crate::storage;
+
+const SESSION_FILE: &str = "session.json";
+
+// Session tab
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionTab {
+    // None for untitled tabs
+    pub path: Option<String>,
+    pub title: String,
+    pub is_modified: bool,
+}
+
+// Editing session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub tabs: Vec<SessionTab>,
+    pub active_index: Option<usize>,
+    pub workspace_root: Option<String>,
+}
+
+struct SessionState {
+    session: Session,
+    dirty: bool,
+}
+
+static SESSION: OnceLock<Mutex<SessionState>> = OnceLock::new();
+
+// Set in processes whose session is not persisted
+static SESSION_DETACHED: AtomicBool = AtomicBool::new(false);
+
+// Held while the watched tabs are updated, so updates cannot overtake each other
+static WATCH_LOCK: Mutex<()> = Mutex::new(());
+
+fn session_cell() -> &'static Mutex<SessionState> {
+    SESSION.get_or_init(|| {
+        Mutex::new(SessionState {
+            session: Session::default(),
+            dirty: false,
+        })
+    })
+}
+
+// The session last written to disk
+pub fn saved_session() -> Session {
+    storage::load_json(SESSION_FILE)
+}
+
+// Load the last session from disk (called once during setup)
+pub fn load_session() {
+    let loaded = saved_session();
+    if let Ok(mut state) = session_cell().lock() {
+        state.session = loaded;
+        state.dirty = false;
+    }
+}
+
+// Keep the session of this process in memory only (instead of `load_session`)
+pub fn detach_session() {
+    SESSION_DETACHED.store(true, Ordering::SeqCst);
+}
+
+// Write the session to disk if it changed since the last flush
+pub fn flush_session() -> Result<bool, String> {
+    if SESSION_DETACHED.load(Ordering::SeqCst) {
+        return Ok(false);
+    }
+    let mut state = session_cell()
+        .lock()
+        .map_err(|_| "Failed to lock session".to_string())?;
+    if !state.dirty {
+        return Ok(false);
+    }
+    storage::save_json(SESSION_FILE, &state.session)?;
+    state.dirty = false;
+    Ok(true)
+}
+
+// Tauri command: Report the current session
+#[tauri::command]
+pub async fn update_session(session: Session) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        {
+            let mut state = session_cell()
+                .lock()
+                .map_err(|_| "Failed to lock session".to_string())?;
+            if state.session == session {
+                return Ok(());
+            }
+            state.session = session;
+            state.dirty = true;
+        }
+        // Watching hashes newly opened files: follow the latest tabs, without the session lock
+        let _watching = WATCH_LOCK.lock().map_err(|_| "Failed to lock session".to_string())?;
+        crate::file_watch::watch_open_tabs(&get_session().tabs);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Failed to update session: {}", e))?
+}
+
+// Tauri command: Get the last persisted (or reported) session
+#[tauri::command]
+pub fn get_session() -> Session {
+    session_cell()
+        .lock()
+        .map(|state| state.session.clone())
+        .unwrap_or_default()
+}
@@ -0,0 +1,270 @@
+//! # Settings Module
+//!
+//! This module holds the settings the backend itself needs to act on. UI preferences stay
+//! in the frontend store; only options that change backend behavior (for example what
+//! happens when the main window is closed, or the global summon shortcut) live here.
+//!
+//! ## Persistence
+//! Settings are stored as `settings.json` in the app data directory, loaded once during
+//! setup and written back whenever the frontend updates them. Missing fields fall back to
+//! their defaults, so older files keep loading after new options are added.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use crate::storage;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+// What the global summon shortcut does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummonAction {
+    #[default]
+    Focus,
+    QuickCapture,
+}
+
+// Mirroring of a workspace to an S3-compatible bucket (see `sync`). The secret access key
+// is kept in the keychain, not here.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    // Workspace folder to mirror
+    pub workspace: Option<String>,
+    // Endpoint of an S3-compatible store (e.g. "https://minio.example.com"); None uses AWS
+    pub endpoint: Option<String>,
+    pub bucket: String,
+    // Empty uses "us-east-1"
+    pub region: String,
+    // Folder inside the bucket (e.g. "notes")
+    pub prefix: String,
+    pub access_key_id: String,
+}
+
+// Grammar checking with a LanguageTool server (see `grammar`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrammarSettings {
+    pub enabled: bool,
+    // URL of the server (e.g. "http://localhost:8081"); empty uses that default
+    pub server_url: String,
+}
+
+// A user-defined command run on the current file (see `external_tools`). `args` may
+// contain the placeholders `$FILE`, `$DIR` and `$SELECTION`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalTool {
+    pub id: String,
+    pub name: String,
+    // Program to run (a path, or a name looked up in PATH)
+    pub command: String,
+    pub args: Vec<String>,
+    // Working directory (placeholders allowed); None uses the folder of the file
+    pub working_dir: Option<String>,
+}
+
+// Publishing to Confluence (see `confluence`). The API token is kept in the keychain.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfluenceSettings {
+    // URL of the site (e.g. "https://example.atlassian.net/wiki")
+    pub base_url: String,
+    // Account email (Cloud) or username; empty sends the token as a personal access token
+    pub username: String,
+    pub space_key: String,
+    // Page that new pages are created under; None creates them at the top of the space
+    pub parent_page_id: Option<String>,
+}
+
+// Static site generator of the site export (see `site_export`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteGenerator {
+    #[default]
+    Hugo,
+    Jekyll,
+}
+
+// Export of posts to a static site
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StaticSiteSettings {
+    pub generator: SiteGenerator,
+    // Folder posts are written to (e.g. "<site>/content/posts" or "<site>/_posts")
+    pub content_dir: Option<String>,
+}
+
+// Markdown extensions recognized by the backend's parser (see `markdown_dialect`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownExtensions {
+    pub tables: bool,
+    pub strikethrough: bool,
+    pub task_lists: bool,
+    pub footnotes: bool,
+    // Bare URLs (`https://...`, `www.`) become links
+    pub autolinks: bool,
+    // `# Heading {#id .class}`
+    pub heading_attributes: bool,
+}
+
+impl Default for MarkdownExtensions {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            strikethrough: true,
+            task_lists: true,
+            footnotes: true,
+            autolinks: true,
+            heading_attributes: false,
+        }
+    }
+}
+
+// Word-count targets (see `writing_stats`). A document goal wins over the goal of its
+// workspace.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WordGoals {
+    // Target length of a document, by path
+    pub documents: BTreeMap<String, u64>,
+    // Target length of all documents of a workspace together, by workspace folder
+    pub workspaces: BTreeMap<String, u64>,
+}
+
+// Sanitizing of rendered HTML (see `html_sanitizer`). The lists extend the built-in
+// allowlist; scripts, style sheets and event handler attributes are always removed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HtmlSanitizerSettings {
+    pub enabled: bool,
+    // Extra tags (e.g. "iframe")
+    pub allowed_tags: Vec<String>,
+    // Extra attributes allowed on every tag (e.g. "style")
+    pub allowed_attributes: Vec<String>,
+    // Extra URL schemes of links and images (e.g. "obsidian")
+    pub allowed_url_schemes: Vec<String>,
+}
+
+impl Default for HtmlSanitizerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowed_tags: Vec::new(),
+            allowed_attributes: Vec::new(),
+            allowed_url_schemes: Vec::new(),
+        }
+    }
+}
+
+// Backend settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendSettings {
+    // Hide to the system tray instead of quitting when the main window is closed
+    pub keep_running_in_tray: bool,
+    // Global shortcut that summons Bokuchi (e.g. "CmdOrCtrl+Shift+Space"); None disables it
+    pub summon_shortcut: Option<String>,
+    // What the summon shortcut does
+    pub summon_action: SummonAction,
+    // Markdown file that quick capture notes are appended to
+    pub inbox_file: Option<String>,
+    // Locale of backend strings (menus); None follows the OS
+    pub locale: Option<String>,
+    // Commit every saved document that is inside a Git repository
+    pub git_auto_commit: bool,
+    // Message template of automatic commits (see `git::render_commit_message`); None uses the default
+    pub git_auto_commit_message: Option<String>,
+    // Workspace sync
+    pub sync: SyncSettings,
+    // Grammar checking
+    pub grammar: GrammarSettings,
+    // Commands of the "Run Tool" menu
+    pub external_tools: Vec<ExternalTool>,
+    // pandoc executable used for import and export (see `pandoc`); None looks it up
+    pub pandoc_path: Option<String>,
+    // Markers of tasks outside task lists (see `todos`); None uses "TODO:" and "FIXME:"
+    pub todo_markers: Option<Vec<String>>,
+    // Publishing to Confluence
+    pub confluence: ConfluenceSettings,
+    // Static site export
+    pub static_site: StaticSiteSettings,
+    // Raise no OS notifications for background events (see `notifications`)
+    pub mute_notifications: bool,
+    // Markdown dialect of the outline, document structure and exports
+    pub markdown_extensions: MarkdownExtensions,
+    // Curly quotes, dashes and ellipses in the preview and exports (see `typography`)
+    pub smart_typography: bool,
+    // Sanitizing of HTML rendered from Markdown
+    pub html_sanitizer: HtmlSanitizerSettings,
+    // Word-count goals
+    pub word_goals: WordGoals,
+}
+
+static SETTINGS: OnceLock<Mutex<BackendSettings>> = OnceLock::new();
+
+fn settings_cell() -> &'static Mutex<BackendSettings> {
+    SETTINGS.get_or_init(|| Mutex::new(BackendSettings::default()))
+}
+
+// Load settings from disk (called once during setup)
+pub fn load_settings() {
+    let loaded: BackendSettings = storage::load_json(SETTINGS_FILE);
+    if let Ok(mut settings) = settings_cell().lock() {
+        *settings = loaded;
+    }
+}
+
+// Get a snapshot of the current settings
+pub fn current_settings() -> BackendSettings {
+    settings_cell()
+        .lock()
+        .map(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+// Replace the current settings and persist them
+pub fn replace_settings(new_settings: BackendSettings) -> Result<(), String> {
+    if let Ok(mut settings) = settings_cell().lock() {
+        *settings = new_settings.clone();
+    }
+    storage::save_json(SETTINGS_FILE, &new_settings)
+}
+
+// Tauri command: Get backend settings
+#[tauri::command]
+pub fn get_backend_settings() -> BackendSettings {
+    current_settings()
+}
+
+// Tauri command: Update backend settings
+#[tauri::command]
+pub fn set_backend_settings(app_handle: tauri::AppHandle, settings: BackendSettings) -> Result<(), String> {
+    let previous = current_settings();
+    // Register the shortcut first, so one that cannot be registered is never saved
+    if previous.summon_shortcut != settings.summon_shortcut {
+        crate::hotkey::apply_summon_shortcut(&app_handle, Some(&previous), &settings)?;
+    }
+    if let Err(e) = replace_settings(settings.clone()) {
+        if previous.summon_shortcut != settings.summon_shortcut {
+            let _ = crate::hotkey::apply_summon_shortcut(&app_handle, Some(&settings), &previous);
+        }
+        return Err(e);
+    }
+    if previous.locale != settings.locale {
+        crate::locale::select_locale();
+        crate::menu::refresh_localized_menus(&app_handle);
+    }
+    if previous.sync != settings.sync {
+        crate::sync::spawn_sync();
+    }
+    if previous.smart_typography != settings.smart_typography {
+        crate::markdown_cache::clear_markdown_cache();
+    }
+    Ok(())
+}
@@ -0,0 +1,334 @@
+//! # SFTP Module
+//!
+//! This module is the SFTP/SSH backend of the remote-files subsystem (`remote`), for
+//! editing files such as READMEs directly on a server.
+//!
+//! ## Endpoint URL
+//! `sftp://host[:port]/base/folder`; the username comes from the endpoint (or the URL).
+//! Remote paths are relative to the base folder.
+//!
+//! ## Authentication
+//! Key-based: the endpoint's private key file if one is configured (its passphrase is the
+//! password stored in the keychain), otherwise the SSH agent, then the default keys
+//! `~/.ssh/id_ed25519`, `~/.ssh/id_ecdsa` and `~/.ssh/id_rsa`. A stored password is only
+//! used as a last resort.
+//!
+//! The server's host key must match `~/.ssh/known_hosts`. Unknown hosts are rejected
+//! (connect once with `ssh` to trust them) and changed keys are reported as such.
+//!
+//! ## Saving
+//! Uploads go to a temporary file next to the target, which is then renamed over it, so
+//! readers never see a half-written file. Servers that cannot rename over an existing file
+//! get the old file renamed aside (`.name.bokuchi-old`) first; it is put back if the upload
+//! cannot be moved into place, and removed once it is. The version used for conflict
+//! detection is `<mtime>-<size>`.
+
+use ssh2::{CheckResult, FileStat, KnownHostFileKind, RenameFlags, Session, Sftp};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::links::percent_decode;
+use crate::remote::{RemoteDocument, RemoteEntry, RemoteSaveResult};
+
+const DEFAULT_PORT: u16 = 22;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const SESSION_TIMEOUT_MS: u32 = 60_000;
+const DEFAULT_KEYS: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+// Client of one SFTP endpoint
+pub struct SftpClient {
+    host: String,
+    port: u16,
+    // Absolute base folder on the server ("/" or "/home/me/docs")
+    base: String,
+    username: String,
+    private_key: Option<PathBuf>,
+    // Passphrase of the private key, or the account password
+    secret: Option<String>,
+}
+
+fn ssh_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".ssh"))
+}
+
+// Version of a file for conflict detection
+pub fn file_version(stat: &FileStat) -> String {
+    format!("{}-{}", stat.mtime.unwrap_or(0), stat.size.unwrap_or(0))
+}
+
+impl SftpClient {
+    pub fn new(
+        url: &str,
+        username: Option<String>,
+        private_key: Option<String>,
+        secret: Option<String>,
+    ) -> Result<Self, String> {
+        let url = Url::parse(url.trim()).map_err(|e| format!("Invalid SFTP URL: {}", e))?;
+        if url.scheme() != "sftp" {
+            return Err("SFTP URL must start with sftp://".to_string());
+        }
+        let host = url
+            .host_str()
+            .filter(|host| !host.is_empty())
+            .ok_or_else(|| "SFTP URL has no host".to_string())?
+            .to_string();
+        let username = username
+            .filter(|u| !u.is_empty())
+            .or_else(|| Some(percent_decode(url.username())).filter(|u| !u.is_empty()))
+            .ok_or_else(|| "SFTP endpoints need a username".to_string())?;
+        let base = percent_decode(url.path());
+        let base = format!("/{}", base.trim_matches('/'));
+        Ok(Self {
+            host,
+            port: url.port().unwrap_or(DEFAULT_PORT),
+            base,
+            username,
+            private_key: private_key.filter(|k| !k.trim().is_empty()).map(PathBuf::from),
+            secret,
+        })
+    }
+
+    // Absolute server path of a path relative to the endpoint
+    pub fn server_path(&self, path: &str) -> String {
+        match (self.base.as_str(), path) {
+            (base, "") => base.to_string(),
+            ("/", path) => format!("/{}", path),
+            (base, path) => format!("{}/{}", base, path),
+        }
+    }
+
+    fn verify_host_key(&self, session: &Session) -> Result<(), String> {
+        let (key, _) = session
+            .host_key()
+            .ok_or_else(|| "Server sent no host key".to_string())?;
+        let mut known_hosts = session
+            .known_hosts()
+            .map_err(|e| format!("Failed to check host key: {}", e))?;
+        if let Some(file) = ssh_dir().map(|dir| dir.join("known_hosts")).filter(|f| f.is_file()) {
+            known_hosts
+                .read_file(&file, KnownHostFileKind::OpenSSH)
+                .map_err(|e| format!("Failed to read known_hosts: {}", e))?;
+        }
+        match known_hosts.check_port(&self.host, self.port, key) {
+            CheckResult::Match => Ok(()),
+            CheckResult::Mismatch => Err(format!(
+                "The host key of {} has changed. Check the server before trusting it again.",
+                self.host
+            )),
+            CheckResult::NotFound => Err(format!(
+                "{} is not a known host. Connect once with ssh to add it to known_hosts.",
+                self.host
+            )),
+            CheckResult::Failure => Err(format!("Failed to check the host key of {}", self.host)),
+        }
+    }
+
+    fn authenticate(&self, session: &Session) -> Result<(), String> {
+        let user = self.username.as_str();
+        if let Some(key) = &self.private_key {
+            session
+                .userauth_pubkey_file(user, None, key, self.secret.as_deref())
+                .map_err(|e| format!("Key authentication failed: {}", e))?;
+            return Ok(());
+        }
+
+        if let Err(e) = session.userauth_agent(user) {
+            debug!("SSH agent authentication failed: {}", e);
+        }
+        if !session.authenticated()
+            && let Some(dir) = ssh_dir()
+        {
+            for name in DEFAULT_KEYS {
+                let key = dir.join(name);
+                if key.is_file() && session.userauth_pubkey_file(user, None, &key, None).is_ok() {
+                    break;
+                }
+            }
+        }
+        if !session.authenticated()
+            && let Some(password) = &self.secret
+        {
+            session
+                .userauth_password(user, password)
+                .map_err(|e| format!("Password authentication failed: {}", e))?;
+        }
+        if session.authenticated() {
+            Ok(())
+        } else {
+            Err(format!("Authentication as {} failed: no usable key", user))
+        }
+    }
+
+    fn connect(&self) -> Result<Sftp, String> {
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve {}: {}", self.host, e))?
+            .next()
+            .ok_or_else(|| format!("Failed to resolve {}", self.host))?;
+        let tcp = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+            .map_err(|e| format!("Failed to connect to {}: {}", self.host, e))?;
+
+        let mut session = Session::new().map_err(|e| format!("Failed to start SSH session: {}", e))?;
+        session.set_tcp_stream(tcp);
+        session.set_timeout(SESSION_TIMEOUT_MS);
+        session
+            .handshake()
+            .map_err(|e| format!("SSH handshake with {} failed: {}", self.host, e))?;
+        self.verify_host_key(&session)?;
+        self.authenticate(&session)?;
+        session.sftp().map_err(|e| format!("Failed to start SFTP: {}", e))
+    }
+
+    // List the entries of a folder
+    pub fn list(&self, path: &str) -> Result<Vec<RemoteEntry>, String> {
+        let sftp = self.connect()?;
+        let entries = sftp
+            .readdir(Path::new(&self.server_path(path)))
+            .map_err(|e| format!("Failed to list folder: {}", e))?;
+        Ok(entries
+            .into_iter()
+            .filter_map(|(entry_path, stat)| {
+                let name = entry_path.file_name()?.to_string_lossy().to_string();
+                if name.starts_with('.') {
+                    return None;
+                }
+                let relative = if path.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}/{}", path, name)
+                };
+                Some(RemoteEntry {
+                    path: relative,
+                    name,
+                    is_dir: stat.is_dir(),
+                    size: stat.size,
+                    modified: stat.mtime.map(|mtime| mtime.to_string()),
+                    etag: Some(file_version(&stat)),
+                })
+            })
+            .collect())
+    }
+
+    // Read a document
+    pub fn read(&self, path: &str) -> Result<RemoteDocument, String> {
+        let sftp = self.connect()?;
+        let server_path = self.server_path(path);
+        let mut file = sftp
+            .open(Path::new(&server_path))
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let stat = file.stat().map_err(|e| format!("Failed to read file: {}", e))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let content = String::from_utf8(bytes).map_err(|_| "Remote file is not a text file".to_string())?;
+        Ok(RemoteDocument {
+            path: path.to_string(),
+            content,
+            etag: Some(file_version(&stat)),
+        })
+    }
+
+    // Save a document atomically, only if it is still at version `etag` (when given)
+    pub fn save(&self, path: &str, content: &str, etag: Option<&str>) -> Result<RemoteSaveResult, String> {
+        let sftp = self.connect()?;
+        let server_path = self.server_path(path);
+        let target = Path::new(&server_path);
+        let existing = sftp.stat(target).ok();
+        if let Some(etag) = etag
+            && existing.as_ref().map(file_version).as_deref() != Some(etag)
+        {
+            return Ok(RemoteSaveResult {
+                conflict: true,
+                ..Default::default()
+            });
+        }
+
+        let (dir, name) = server_path.rsplit_once('/').unwrap_or(("", &server_path));
+        let temp_path = format!("{}/.{}.bokuchi-upload", dir, name);
+        let temp = Path::new(&temp_path);
+        let upload = (|| -> Result<(), String> {
+            let mut file = sftp.create(temp).map_err(|e| format!("Failed to upload file: {}", e))?;
+            file.write_all(content.as_bytes())
+                .map_err(|e| format!("Failed to upload file: {}", e))?;
+            if let Some(perm) = existing.as_ref().and_then(|stat| stat.perm) {
+                let mode = FileStat {
+                    size: None,
+                    uid: None,
+                    gid: None,
+                    perm: Some(perm & 0o7777),
+                    atime: None,
+                    mtime: None,
+                };
+                let _ = file.setstat(mode);
+            }
+            Ok(())
+        })();
+        if let Err(e) = upload {
+            let _ = sftp.unlink(temp);
+            return Err(e);
+        }
+
+        let flags = Some(RenameFlags::OVERWRITE | RenameFlags::ATOMIC | RenameFlags::NATIVE);
+        if let Err(e) = sftp.rename(temp, target, flags) {
+            // SFTP v3 servers refuse to rename over an existing file
+            debug!("Atomic rename failed ({}), replacing {} instead", e, server_path);
+            replace_with_backup(&sftp, temp, target, &format!("{}/.{}.bokuchi-old", dir, name))?;
+        }
+
+        let etag = sftp.stat(target).ok().map(|stat| file_version(&stat));
+        Ok(RemoteSaveResult {
+            saved: true,
+            conflict: false,
+            etag,
+        })
+    }
+}
+
+// Move the upload `temp` over `target` on a server that cannot rename over a file: the
+// old file is renamed to `backup` first and restored if the upload cannot take its place.
+// Once the old file has been moved aside, the upload is never deleted.
+fn replace_with_backup(sftp: &Sftp, temp: &Path, target: &Path, backup: &str) -> Result<(), String> {
+    let backup = Path::new(backup);
+    let had_target = sftp.stat(target).is_ok();
+    if had_target {
+        let _ = sftp.unlink(backup);
+        if let Err(e) = sftp.rename(target, backup, Some(RenameFlags::empty())) {
+            let _ = sftp.unlink(temp);
+            return Err(format!("Failed to save file: {}", e));
+        }
+    }
+    match sftp.rename(temp, target, Some(RenameFlags::empty())) {
+        Ok(()) => {
+            if had_target && let Err(e) = sftp.unlink(backup) {
+                warn!("Failed to remove {}: {}", backup.display(), e);
+            }
+            Ok(())
+        }
+        Err(e) if had_target => {
+            warn!("Failed to move upload into place: {}", e);
+            if let Err(restore) = sftp.rename(backup, target, Some(RenameFlags::empty())) {
+                warn!("Failed to restore {}: {}", target.display(), restore);
+                return Err(format!(
+                    "Failed to save file: {} (the previous version is at {} and the new one at {})",
+                    e,
+                    backup.display(),
+                    temp.display()
+                ));
+            }
+            let _ = sftp.unlink(temp);
+            Err(format!("Failed to save file: {}", e))
+        }
+        Err(e) => {
+            warn!("Failed to move upload into place: {}", e);
+            let _ = sftp.unlink(temp);
+            Err(format!("Failed to save file: {}", e))
+        }
+    }
+}
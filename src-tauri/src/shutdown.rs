@@ -0,0 +1,58 @@
+//! # Shutdown Module
+//!
+//! This module flushes backend-managed state to disk so quitting the app (including a
+//! force-quit from the Dock, the tray or the OS) never loses it.
+//!
+//! ## Flushed State
+//! - Session (open tabs and workspace) — `session`
+//! - Pending autosave snapshots — `autosave`
+//! - Most-recently-used file list — `recent_files`
+//! - Writing statistics — `writing_stats`
+//!
+//! `flush_backend_state` runs on `RunEvent::ExitRequested` and again on `RunEvent::Exit`
+//! (some quit paths only deliver the latter). Each store only writes when it has
+//! unflushed changes, so running it twice is cheap. A background thread also flushes every
+//! `PERIODIC_FLUSH_INTERVAL` to limit what a crash can lose.
+
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::autosave;
+use crate::recent_files;
+use crate::session;
+use crate::writing_stats;
+
+const PERIODIC_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+// Flush every backend store. Failures are logged and do not stop the other stores
+// from being flushed.
+pub fn flush_backend_state(reason: &str) {
+    match session::flush_session() {
+        Ok(true) => debug!("[{}] Session flushed", reason),
+        Ok(false) => {}
+        Err(e) => warn!("[{}] Failed to flush session: {}", reason, e),
+    }
+    match autosave::flush_pending_snapshots() {
+        Ok(0) => {}
+        Ok(count) => debug!("[{}] Flushed {} autosave snapshot(s)", reason, count),
+        Err(e) => warn!("[{}] Failed to flush autosave snapshots: {}", reason, e),
+    }
+    match recent_files::flush_recent_files() {
+        Ok(true) => debug!("[{}] Recent files flushed", reason),
+        Ok(false) => {}
+        Err(e) => warn!("[{}] Failed to flush recent files: {}", reason, e),
+    }
+    match writing_stats::flush_writing_stats() {
+        Ok(true) => debug!("[{}] Writing stats flushed", reason),
+        Ok(false) => {}
+        Err(e) => warn!("[{}] Failed to flush writing stats: {}", reason, e),
+    }
+}
+
+// Start the background thread that flushes backend state periodically
+pub fn start_periodic_flush() {
+    std::thread::spawn(|| loop {
+        std::thread::sleep(PERIODIC_FLUSH_INTERVAL);
+        flush_backend_state("periodic");
+    });
+}
@@ -0,0 +1,194 @@
+//! # Site Export Module
+//!
+//! This module exports a document as a post of a static site (Hugo or Jekyll): the
+//! processed Markdown is written into the site's content folder with the frontmatter the
+//! generator expects.
+//!
+//! ## Frontmatter
+//! `title`, `date`, `slug` and `tags` are taken from the document's variables
+//! (`<!-- @var title: ... -->`), then from its own frontmatter, and are derived otherwise:
+//! - `title`: The first level-1 heading, or the file name
+//! - `date`: The time of the export
+//! - `slug`: The title as a slug
+//! - `tags`: The document's tags (see `tags`); a `tags` variable is a comma-separated list
+//!
+//! Other frontmatter fields are kept. Jekyll posts also get `layout: post` unless they
+//! name a layout.
+//!
+//! ## Files
+//! - Hugo: `<content dir>/<slug>.md`, dates in RFC 3339 (`2024-10-16T12:30:00+09:00`)
+//! - Jekyll: `<content dir>/<YYYY-MM-DD>-<slug>.md`, dates as `2024-10-16 12:30:00 +0900`
+//!
+//! Exporting again overwrites the post.
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use crate::frontmatter;
+use crate::markdown;
+use crate::path_scope;
+use crate::settings::{self, SiteGenerator};
+use crate::storage;
+use crate::tags;
+use crate::variable_processor::VARIABLE_PROCESSOR;
+
+// A post ready to be written
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitePost {
+    pub file_name: String,
+    pub slug: String,
+    pub content: String,
+}
+
+// Result of `export_to_static_site`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiteExport {
+    pub path: String,
+    pub slug: String,
+}
+
+// A slug usable as a file name ("Hello, World!" → "hello-world")
+fn file_slug(text: &str) -> String {
+    markdown::slugify_heading(text)
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// A frontmatter value as text
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+    .filter(|s| !s.is_empty())
+}
+
+fn format_date(generator: SiteGenerator, time: DateTime<Local>) -> String {
+    match generator {
+        SiteGenerator::Hugo => time.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        SiteGenerator::Jekyll => time.format("%Y-%m-%d %H:%M:%S %z").to_string(),
+    }
+}
+
+// Build the post of a document. `content` is the document as written; `path` is its
+// file, if it has one.
+pub fn build_post(
+    content: &str,
+    path: Option<&Path>,
+    generator: SiteGenerator,
+    now: DateTime<Local>,
+) -> Result<SitePost, String> {
+    let (variables, _) = VARIABLE_PROCESSOR.parse_variables_from_markdown(content);
+    let variables: HashMap<String, String> = variables
+        .into_iter()
+        .filter(|variable| !variable.value.is_empty())
+        .map(|variable| (variable.name, variable.value))
+        .collect();
+    let mut processed = VARIABLE_PROCESSOR.process_variables(content);
+    if content.ends_with('\n') && !processed.ends_with('\n') {
+        processed.push('\n');
+    }
+
+    let mut mapping: Mapping = match frontmatter::parse_frontmatter(&processed) {
+        Some(Value::Mapping(mapping)) => mapping,
+        _ => Mapping::new(),
+    };
+    let existing = |key: &str| mapping.get(key).and_then(value_text);
+
+    let title = variables
+        .get("title")
+        .cloned()
+        .or_else(|| existing("title"))
+        .unwrap_or_else(|| markdown::document_title(path.unwrap_or(Path::new("")), &processed));
+    if title.is_empty() {
+        return Err("The document has no title".to_string());
+    }
+    let date = variables
+        .get("date")
+        .cloned()
+        .or_else(|| existing("date"))
+        .unwrap_or_else(|| format_date(generator, now));
+    let slug = variables
+        .get("slug")
+        .or(existing("slug").as_ref())
+        .map(|slug| file_slug(slug))
+        .filter(|slug| !slug.is_empty())
+        .unwrap_or_else(|| file_slug(&title));
+    if slug.is_empty() {
+        return Err(format!("Cannot make a file name from the title {:?}", title));
+    }
+    let tags: Option<Vec<String>> = match variables.get("tags") {
+        Some(list) => Some(
+            list.split(',')
+                .map(|tag| tag.trim().trim_start_matches('#').to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        ),
+        None if mapping.contains_key("tags") => None,
+        None => Some(tags::extract_tags(&processed)).filter(|tags| !tags.is_empty()),
+    };
+
+    mapping.insert("title".into(), title.into());
+    mapping.insert("date".into(), date.clone().into());
+    mapping.insert("slug".into(), slug.clone().into());
+    if let Some(tags) = tags {
+        let tags = tags.into_iter().map(Value::from).collect::<Vec<_>>();
+        mapping.insert("tags".into(), Value::Sequence(tags));
+    }
+    if generator == SiteGenerator::Jekyll && !mapping.contains_key("layout") {
+        mapping.insert("layout".into(), "post".into());
+    }
+
+    let yaml = serde_yaml::to_string(&mapping).map_err(|e| format!("Failed to write frontmatter: {}", e))?;
+    let body = frontmatter::split_frontmatter(&processed).body.trim_start_matches(['\r', '\n']);
+    let file_name = match generator {
+        SiteGenerator::Hugo => format!("{}.md", slug),
+        SiteGenerator::Jekyll => {
+            let day = date
+                .get(..10)
+                .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+                .unwrap_or_else(|| now.date_naive());
+            format!("{}-{}.md", day.format("%Y-%m-%d"), slug)
+        }
+    };
+    Ok(SitePost {
+        file_name,
+        slug,
+        content: format!("---\n{}---\n\n{}", yaml, body),
+    })
+}
+
+// Tauri command: Export a document as a post into the configured static site content
+// folder. Returns the written file.
+#[tauri::command]
+pub async fn export_to_static_site(content: String, source_path: Option<String>) -> Result<SiteExport, String> {
+    let site = settings::current_settings().static_site;
+    let Some(content_dir) = site.content_dir.filter(|dir| !dir.trim().is_empty()) else {
+        return Err("No static site content folder is configured".to_string());
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let post = build_post(&content, source_path.as_deref().map(Path::new), site.generator, Local::now())?;
+        let dir = PathBuf::from(content_dir.trim());
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create content folder: {}", e))?;
+        let path = dir.join(&post.file_name);
+        // The content folder comes from the settings, which the WebView can change
+        path_scope::check_document_write(&path.to_string_lossy())?;
+        storage::write_atomic(&path, post.content.as_bytes())?;
+        info!("Exported post to {:?}", path);
+        Ok(SiteExport {
+            path: path.to_string_lossy().to_string(),
+            slug: post.slug,
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to export post: {}", e))?
+}
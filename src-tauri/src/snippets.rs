@@ -0,0 +1,255 @@
+//! # Snippets Module
+//!
+//! This module stores the user's snippets and expands them: typing a snippet's trigger
+//! in the editor inserts its body, with the cursor moving through its tab stops.
+//!
+//! ## Snippet Bodies
+//! Bodies use the tab stop syntax of VS Code and TextMate:
+//! - `$1`, `$2`, ...: Tab stops, visited in order
+//! - `${1:default}`: A tab stop with text that is selected when it is reached
+//! - `$0`: Where the cursor ends up
+//! - `\$`, `\}` and `\\`: A literal `$`, `}` and `\`
+//!
+//! `{{variables}}` in the body (and in tab stop defaults) are expanded by the variable
+//! processor, with the context passed to `expand_snippet` (e.g. `selection` or
+//! `filename`) taking precedence over global variables. Unknown variables are left in
+//! place.
+//!
+//! ## Persistence
+//! Snippets are stored as `snippets.json` in the app data directory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::info;
+
+use crate::storage;
+use crate::variable_processor::VARIABLE_PROCESSOR;
+
+const SNIPPETS_FILE: &str = "snippets.json";
+
+// A user snippet
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Snippet {
+    // Assigned when the snippet is first saved
+    pub id: String,
+    // Text that is replaced by the snippet (e.g. "meeting")
+    pub trigger: String,
+    pub description: String,
+    pub body: String,
+}
+
+// A tab stop of an expanded snippet. `start` and `end` are UTF-16 offsets within the
+// text (JavaScript string offsets); they differ when the tab stop has a default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TabStop {
+    pub index: u32,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Result of `expand_snippet`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpandedSnippet {
+    pub text: String,
+    // In the order they are visited: 1, 2, ..., then 0. A tab stop used more than once
+    // appears once per occurrence.
+    pub tab_stops: Vec<TabStop>,
+}
+
+// A piece of a parsed snippet body
+#[derive(Debug, Clone, PartialEq)]
+enum BodyPart {
+    Text(String),
+    TabStop { index: u32, default: String },
+}
+
+// Split a body into text and tab stops. `$` not followed by a tab stop stays text.
+fn parse_body(body: &str) -> Vec<BodyPart> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if matches!(chars.get(i + 1), Some('$' | '}' | '\\')) => {
+                text.push(chars[i + 1]);
+                i += 2;
+            }
+            '$' => match parse_tab_stop(&chars[i + 1..]) {
+                Some((index, default, consumed)) => {
+                    parts.push(BodyPart::Text(std::mem::take(&mut text)));
+                    parts.push(BodyPart::TabStop { index, default });
+                    i += 1 + consumed;
+                }
+                None => {
+                    text.push('$');
+                    i += 1;
+                }
+            },
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    parts.push(BodyPart::Text(text));
+    parts.retain(|part| !matches!(part, BodyPart::Text(text) if text.is_empty()));
+    parts
+}
+
+// Parse `N`, `{N}` or `{N:default}` after a `$`. Returns the index, the default and the
+// number of chars consumed.
+fn parse_tab_stop(chars: &[char]) -> Option<(u32, String, usize)> {
+    let digits = |from: usize| chars[from..].iter().take_while(|c| c.is_ascii_digit()).count();
+    if chars.first().is_some_and(char::is_ascii_digit) {
+        let len = digits(0);
+        let index = chars[..len].iter().collect::<String>().parse().ok()?;
+        return Some((index, String::new(), len));
+    }
+    if chars.first() != Some(&'{') {
+        return None;
+    }
+    let len = digits(1);
+    if len == 0 {
+        return None;
+    }
+    let index = chars[1..1 + len].iter().collect::<String>().parse().ok()?;
+    let mut i = 1 + len;
+    match chars.get(i) {
+        Some('}') => return Some((index, String::new(), i + 1)),
+        Some(':') => i += 1,
+        _ => return None,
+    }
+    // Braces of `{{variables}}` in the default are balanced
+    let mut default = String::new();
+    let mut depth = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if matches!(chars.get(i + 1), Some('$' | '}' | '\\')) => {
+                default.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            '}' if depth == 0 => return Some((index, default, i + 1)),
+            '}' => depth -= 1,
+            '{' => depth += 1,
+            _ => {}
+        }
+        default.push(chars[i]);
+        i += 1;
+    }
+    None
+}
+
+// Substitute the variables of a piece of a body. Line by line, as the variable
+// processor does not keep line endings.
+fn expand_variables(text: &str, context: &HashMap<String, String>) -> String {
+    text.split_inclusive('\n')
+        .map(|line| {
+            let content = line.trim_end_matches(['\r', '\n']);
+            if !content.contains("{{") {
+                return line.to_string();
+            }
+            VARIABLE_PROCESSOR.process_variables_with(content, context) + &line[content.len()..]
+        })
+        .collect()
+}
+
+// Expand a snippet body: tab stops are resolved and `{{variables}}` substituted
+pub fn expand_body(body: &str, context: &HashMap<String, String>) -> ExpandedSnippet {
+    let mut text = String::new();
+    let mut offset = 0;
+    let mut tab_stops = Vec::new();
+    for part in parse_body(body) {
+        match part {
+            BodyPart::Text(part) => {
+                let expanded = expand_variables(&part, context);
+                offset += expanded.encode_utf16().count();
+                text.push_str(&expanded);
+            }
+            BodyPart::TabStop { index, default } => {
+                let expanded = expand_variables(&default, context);
+                let start = offset;
+                offset += expanded.encode_utf16().count();
+                text.push_str(&expanded);
+                tab_stops.push(TabStop {
+                    index,
+                    start,
+                    end: offset,
+                });
+            }
+        }
+    }
+    // Stable: occurrences of the same tab stop keep their order
+    tab_stops.sort_by_key(|stop| if stop.index == 0 { u32::MAX } else { stop.index });
+    ExpandedSnippet { text, tab_stops }
+}
+
+fn load_snippets() -> Vec<Snippet> {
+    storage::load_json(SNIPPETS_FILE)
+}
+
+// Check a snippet before it is saved among `existing`
+pub fn validate_snippet(snippet: &Snippet, existing: &[Snippet]) -> Result<(), String> {
+    let trigger = snippet.trigger.trim();
+    if trigger.is_empty() || trigger.chars().any(char::is_whitespace) {
+        return Err(format!("Invalid snippet trigger: {:?}", snippet.trigger));
+    }
+    if existing
+        .iter()
+        .any(|other| other.trigger == trigger && other.id != snippet.id)
+    {
+        return Err(format!("Another snippet already uses the trigger {:?}", trigger));
+    }
+    Ok(())
+}
+
+// Tauri command: List the user's snippets
+#[tauri::command]
+pub fn list_snippets() -> Vec<Snippet> {
+    load_snippets()
+}
+
+// Tauri command: Add a snippet or update the one with the same ID. Returns the snippet
+// with its ID.
+#[tauri::command]
+pub fn save_snippet(mut snippet: Snippet) -> Result<Snippet, String> {
+    let mut snippets = load_snippets();
+    snippet.trigger = snippet.trigger.trim().to_string();
+    validate_snippet(&snippet, &snippets)?;
+    if snippet.id.is_empty() {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        snippet.id = format!("{:x}", millis);
+    }
+
+    match snippets.iter_mut().find(|s| s.id == snippet.id) {
+        Some(existing) => *existing = snippet.clone(),
+        None => snippets.push(snippet.clone()),
+    }
+    storage::save_json(SNIPPETS_FILE, &snippets)?;
+    info!("Saved snippet {} ({})", snippet.trigger, snippet.id);
+    Ok(snippet)
+}
+
+// Tauri command: Remove a snippet
+#[tauri::command]
+pub fn delete_snippet(id: String) -> Result<(), String> {
+    let mut snippets = load_snippets();
+    snippets.retain(|snippet| snippet.id != id);
+    storage::save_json(SNIPPETS_FILE, &snippets)
+}
+
+// Tauri command: Expand the snippet with a trigger. None if no snippet has it.
+#[tauri::command]
+pub fn expand_snippet(trigger: String, context: Option<HashMap<String, String>>) -> Option<ExpandedSnippet> {
+    let snippet = load_snippets()
+        .into_iter()
+        .find(|snippet| snippet.trigger == trigger.trim())?;
+    Some(expand_body(&snippet.body, &context.unwrap_or_default()))
+}
@@ -0,0 +1,300 @@
+//! # Spell Check Module
+//!
+//! This module checks the prose of a document against Hunspell dictionaries (see the
+//! `hunspell` module) and suggests corrections.
+//!
+//! ## Dictionaries
+//! A language is a pair of `<lang>.aff` / `<lang>.dic` files, looked up in:
+//! - `<app data>/dictionaries/` (where users put additional dictionaries)
+//! - The system dictionary directories (`/usr/share/hunspell` and `/usr/share/myspell` on
+//!   Linux, `~/Library/Spelling` on macOS)
+//!
+//! Languages are named after the files (`en_US`); `en-US` and `en` find them as well.
+//! A dictionary is loaded on first use and kept for the rest of the session.
+//!
+//! ## What Is Checked
+//! Only prose: frontmatter, fenced code, inline code, URLs, link targets, HTML tags,
+//! `{{variables}}` and wiki links are skipped, as are words with digits, all-caps words
+//! (acronyms) and words in scripts without word spacing (Japanese, Chinese, ...).
+//!
+//! ## User Dictionary
+//! Words added with `add_to_dictionary` are stored in `user-dictionary.json` and accepted
+//! in every language.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tracing::{info, warn};
+
+use crate::frontmatter;
+use crate::hunspell::Dictionary;
+use crate::markdown::{self, lines_outside_code};
+use crate::storage;
+
+lazy_static! {
+    static ref SKIPPED: Regex = Regex::new(
+        r"(?x)
+        [a-zA-Z][a-zA-Z0-9+.\-]*://\S+     # URLs
+        | \]\([^)]*\)                      # link targets
+        | \[\[[^\]]*\]\]                   # wiki links
+        | \{\{[^}]*\}\}                    # variables
+        | <!--.*?(?:-->|$)                 # comments
+        | </?[a-zA-Z][^>]*>                # HTML tags
+        | [\w.+\-]+@[\w\-]+(?:\.[\w\-]+)+  # e-mail addresses"
+    )
+    .unwrap();
+    static ref WORD: Regex =
+        Regex::new(r"[\p{L}\p{M}\p{N}]+(?:['’][\p{L}\p{M}\p{N}]+)*").unwrap();
+    static ref UNSPACED_SCRIPT: Regex =
+        Regex::new(r"[\p{Han}\p{Hiragana}\p{Katakana}\p{Hangul}\p{Thai}]").unwrap();
+}
+
+const USER_DICTIONARY_FILE: &str = "user-dictionary.json";
+const DICTIONARY_DIR: &str = "dictionaries";
+// Longest word checked; longer tokens are not words
+const MAX_WORD_LEN: usize = 64;
+const MAX_SUGGESTIONS: usize = 8;
+
+// A misspelled word. `start` and `end` are UTF-16 offsets within the line (JavaScript
+// string offsets).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Misspelling {
+    pub word: String,
+    // 1-based
+    pub line_number: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+static DICTIONARIES: OnceLock<Mutex<HashMap<String, Arc<Dictionary>>>> = OnceLock::new();
+static USER_DICTIONARY: OnceLock<Mutex<BTreeSet<String>>> = OnceLock::new();
+// Language of the last `check_text`, used by `suggest` without a language
+static LAST_LANGUAGE: Mutex<Option<String>> = Mutex::new(None);
+
+fn dictionaries_cell() -> &'static Mutex<HashMap<String, Arc<Dictionary>>> {
+    DICTIONARIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn user_dictionary_cell() -> &'static Mutex<BTreeSet<String>> {
+    USER_DICTIONARY.get_or_init(|| Mutex::new(storage::load_json(USER_DICTIONARY_FILE)))
+}
+
+// Directories searched for dictionaries, in order of precedence
+fn dictionary_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = storage::app_data_path(DICTIONARY_DIR).into_iter().collect();
+    if cfg!(target_os = "linux") {
+        dirs.extend(
+            ["/usr/share/hunspell", "/usr/share/myspell", "/usr/share/myspell/dicts"]
+                .into_iter()
+                .map(PathBuf::from),
+        );
+    }
+    if cfg!(target_os = "macos")
+        && let Some(home) = std::env::var_os("HOME")
+    {
+        dirs.push(PathBuf::from(home).join("Library/Spelling"));
+    }
+    dirs
+}
+
+// Languages with both an .aff and a .dic file in `dirs`, with the directory of each
+pub fn available_languages(dirs: &[PathBuf]) -> Vec<(String, PathBuf)> {
+    let mut languages: Vec<(String, PathBuf)> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "dic") || !path.with_extension("aff").is_file() {
+                continue;
+            }
+            let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+            if !languages.iter().any(|(existing, _)| *existing == name) {
+                languages.push((name, dir.clone()));
+            }
+        }
+    }
+    languages.sort();
+    languages
+}
+
+// The available language a requested one refers to: `en-US` is `en_US`, and `en` (or an
+// unavailable `en_CA`) falls back to another `en` dictionary
+pub fn resolve_language<'a>(requested: &str, available: &'a [(String, PathBuf)]) -> Option<&'a (String, PathBuf)> {
+    let requested = requested.trim().replace('-', "_");
+    let base = requested.split('_').next().unwrap_or_default();
+    available
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&requested))
+        .or_else(|| {
+            available.iter().find(|(name, _)| {
+                name.split('_').next().is_some_and(|b| b.eq_ignore_ascii_case(base))
+            })
+        })
+}
+
+// Load the `<name>.aff` / `<name>.dic` pair in `dir`
+pub fn load_dictionary(dir: &Path, name: &str) -> Result<Dictionary, String> {
+    let read = |ext: &str| {
+        let path = dir.join(format!("{}.{}", name, ext));
+        std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+    Dictionary::from_bytes(&read("aff")?, &read("dic")?)
+}
+
+// Dictionary for a language, loaded on first use
+fn dictionary(lang: &str) -> Result<Arc<Dictionary>, String> {
+    let available = available_languages(&dictionary_dirs());
+    let (name, dir) =
+        resolve_language(lang, &available).ok_or_else(|| format!("No dictionary found for language: {}", lang))?;
+    if let Some(dictionary) = dictionaries_cell().lock().ok().and_then(|cache| cache.get(name).cloned()) {
+        return Ok(dictionary);
+    }
+
+    let dictionary = Arc::new(load_dictionary(dir, name)?);
+    info!("Loaded dictionary {} from {}", name, dir.display());
+    if let Ok(mut cache) = dictionaries_cell().lock() {
+        cache.insert(name.clone(), dictionary.clone());
+    }
+    Ok(dictionary)
+}
+
+// Read the user dictionary and the dictionary files again (after an import)
+pub fn reload_dictionaries() {
+    if let Ok(mut words) = user_dictionary_cell().lock() {
+        *words = storage::load_json(USER_DICTIONARY_FILE);
+    }
+    if let Ok(mut cache) = dictionaries_cell().lock() {
+        cache.clear();
+    }
+}
+
+fn in_user_dictionary(word: &str) -> bool {
+    user_dictionary_cell()
+        .lock()
+        .is_ok_and(|words| words.contains(word) || words.contains(&word.to_lowercase()))
+}
+
+// Blank out text that is not prose, keeping byte offsets of the remaining text valid
+pub fn mask_non_prose(line: &str) -> String {
+    let masked = markdown::mask_inline_code(line);
+    SKIPPED
+        .replace_all(&masked, |caps: &regex::Captures| " ".repeat(caps[0].len()))
+        .into_owned()
+}
+
+fn is_checked_word(word: &str) -> bool {
+    word.chars().count() <= MAX_WORD_LEN
+        && !word.chars().any(|c| c.is_numeric())
+        && !UNSPACED_SCRIPT.is_match(word)
+        // Acronyms ("HTML") and single letters
+        && word.chars().any(char::is_lowercase)
+}
+
+// Words of a document's prose that `is_correct` rejects
+pub fn find_misspellings(content: &str, is_correct: impl Fn(&str) -> bool) -> Vec<Misspelling> {
+    let document = frontmatter::split_frontmatter(content);
+    let mut misspellings = Vec::new();
+    for (index, line) in lines_outside_code(document.body) {
+        let masked = mask_non_prose(line);
+        for word in WORD.find_iter(&masked) {
+            if !is_checked_word(word.as_str()) || is_correct(word.as_str()) {
+                continue;
+            }
+            let start = line[..word.start()].encode_utf16().count();
+            misspellings.push(Misspelling {
+                word: word.as_str().to_string(),
+                line_number: document.body_line_offset + index + 1,
+                start,
+                end: start + word.as_str().encode_utf16().count(),
+            });
+        }
+    }
+    misspellings
+}
+
+fn set_last_language(lang: &str) {
+    if let Ok(mut last) = LAST_LANGUAGE.lock() {
+        *last = Some(lang.to_string());
+    }
+}
+
+// Tauri command: Check the spelling of a document in a language
+#[tauri::command]
+pub async fn check_text(content: String, lang: String) -> Result<Vec<Misspelling>, String> {
+    set_last_language(&lang);
+    tauri::async_runtime::spawn_blocking(move || {
+        let dictionary = dictionary(&lang)?;
+        Ok(find_misspellings(&content, |word| dictionary.check(word) || in_user_dictionary(word)))
+    })
+    .await
+    .map_err(|e| format!("Failed to check spelling: {}", e))?
+}
+
+// Tauri command: Suggest corrections for a word, in the given language or the language
+// of the last check
+#[tauri::command]
+pub async fn suggest(word: String, lang: Option<String>) -> Result<Vec<String>, String> {
+    let lang = lang
+        .or_else(|| LAST_LANGUAGE.lock().ok().and_then(|last| last.clone()))
+        .ok_or_else(|| "No spell check language selected".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || Ok(dictionary(&lang)?.suggest(&word, MAX_SUGGESTIONS)))
+        .await
+        .map_err(|e| format!("Failed to suggest corrections: {}", e))?
+}
+
+// Tauri command: List the languages with an installed dictionary
+#[tauri::command]
+pub fn list_spell_languages() -> Vec<String> {
+    available_languages(&dictionary_dirs())
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect()
+}
+
+// Tauri command: Add a word to the user dictionary
+#[tauri::command]
+pub fn add_to_dictionary(word: String) -> Result<(), String> {
+    let word = word.trim().to_string();
+    if word.is_empty() || word.chars().any(char::is_whitespace) {
+        return Err(format!("Invalid word: {:?}", word));
+    }
+    let mut words = user_dictionary_cell()
+        .lock()
+        .map_err(|_| "Failed to lock user dictionary".to_string())?;
+    if words.insert(word) {
+        storage::save_json(USER_DICTIONARY_FILE, &*words)?;
+    }
+    Ok(())
+}
+
+// Tauri command: Remove a word from the user dictionary
+#[tauri::command]
+pub fn remove_from_dictionary(word: String) -> Result<(), String> {
+    let mut words = user_dictionary_cell()
+        .lock()
+        .map_err(|_| "Failed to lock user dictionary".to_string())?;
+    if words.remove(word.trim()) {
+        storage::save_json(USER_DICTIONARY_FILE, &*words)?;
+    }
+    Ok(())
+}
+
+// Tauri command: Get the words of the user dictionary
+#[tauri::command]
+pub fn get_user_dictionary() -> Vec<String> {
+    user_dictionary_cell()
+        .lock()
+        .map(|words| words.iter().cloned().collect())
+        .unwrap_or_else(|e| {
+            warn!("Failed to lock user dictionary: {}", e);
+            Vec::new()
+        })
+}
@@ -0,0 +1,158 @@
+//! # Storage Module
+//!
+//! This module provides the small amount of persistence the backend owns itself
+//! (as opposed to the frontend's `.app-state.dat` store).
+//!
+//! ## Features
+//! - **App Data Directory**: Resolved once during setup and shared by every subsystem
+//! - **JSON Files**: Load a serde value from a named file, falling back to its default
+//! - **Atomic Writes**: Values are written to a temporary sibling and renamed into place,
+//!   so a crash mid-write never leaves a truncated file behind. A replaced file keeps its
+//!   permissions (e.g. a 0600 journal stays private)
+//! - **Shared Files**: A process started with `--new-instance` shares the app data
+//!   directory with the main process. Files both may change are updated with
+//!   `update_json` (or under `lock_app_data_file`): the change is applied to the current
+//!   content of the file while holding an OS file lock, so neither process overwrites
+//!   what the other wrote
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use tauri::Manager;
+use tracing::warn;
+
+// App data directory, set once in `setup`
+static APP_DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// Resolve and remember the app data directory
+pub fn init_app_data_dir(app_handle: &tauri::AppHandle) {
+    match app_handle.path().app_data_dir() {
+        Ok(dir) => {
+            if let Err(e) = fs::create_dir_all(&dir) {
+                warn!("Failed to create app data directory {:?}: {}", dir, e);
+            }
+            let _ = APP_DATA_DIR.set(dir);
+        }
+        Err(e) => warn!("Failed to resolve app data directory: {}", e),
+    }
+}
+
+// Get the app data directory (None before setup or when it could not be resolved)
+pub fn app_data_dir() -> Option<PathBuf> {
+    APP_DATA_DIR.get().cloned()
+}
+
+// Get the full path of a file inside the app data directory
+pub fn app_data_path(file_name: &str) -> Option<PathBuf> {
+    app_data_dir().map(|dir| dir.join(file_name))
+}
+
+// Load a JSON value from a file in the app data directory.
+// A missing or unreadable file yields the default value.
+pub fn load_json<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    app_data_path(file_name)
+        .map(|path| read_json_file(&path))
+        .unwrap_or_default()
+}
+
+// Save a JSON value to a file in the app data directory
+pub fn save_json<T: Serialize>(file_name: &str, value: &T) -> Result<(), String> {
+    let path = app_data_path(file_name).ok_or_else(|| "App data directory is not available".to_string())?;
+    write_json_file(&path, value)
+}
+
+// Exclusive lock on a file of the app data directory, shared with other Bokuchi
+// processes. Released when dropped (or when the process ends).
+pub struct AppDataLock {
+    _file: fs::File,
+}
+
+// Wait for the lock on a file of the app data directory (`<file_name>.lock`). The lock
+// is not reentrant: a process must not take it twice.
+pub fn lock_app_data_file(file_name: &str) -> Result<AppDataLock, String> {
+    let path = app_data_path(&format!("{}.lock", file_name))
+        .ok_or_else(|| "App data directory is not available".to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open lock file: {}", e))?;
+    file.lock().map_err(|e| format!("Failed to lock {}: {}", file_name, e))?;
+    Ok(AppDataLock { _file: file })
+}
+
+// Apply `change` to the current content of a JSON file in the app data directory and
+// write it back, holding the file's lock. Returns the new value.
+pub fn update_json<T, F>(file_name: &str, change: F) -> Result<T, String>
+where
+    T: Serialize + DeserializeOwned + Default,
+    F: FnOnce(&mut T),
+{
+    let _lock = lock_app_data_file(file_name)?;
+    let mut value: T = load_json(file_name);
+    change(&mut value);
+    save_json(file_name, &value)?;
+    Ok(value)
+}
+
+// Read a JSON value from an arbitrary path, falling back to the default
+pub fn read_json_file<T: DeserializeOwned + Default>(path: &Path) -> T {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring malformed JSON in {:?}: {}", path, e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+// Write a JSON value to an arbitrary path atomically
+pub fn write_json_file<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(value).map_err(|e| format!("Failed to serialize: {}", e))?;
+    write_atomic(path, &json)
+}
+
+// Write a new file with the permissions of the file it replaces. On Unix the file is
+// created with that mode, so the content is never readable with looser permissions.
+fn write_with_permissions(path: &Path, bytes: &[u8], permissions: Option<fs::Permissions>) -> std::io::Result<()> {
+    use std::io::Write;
+    let _ = fs::remove_file(path);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if let Some(permissions) = &permissions {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(permissions.mode() & 0o777);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    Ok(())
+}
+
+// Write bytes to `path` via a temporary sibling file and a rename
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| "Invalid file path".to_string())?;
+    let tmp_path = path.with_file_name(format!(".{}.tmp-{}", file_name, std::process::id()));
+    write_with_permissions(&tmp_path, bytes, fs::metadata(path).ok().map(|m| m.permissions()))
+        .map_err(|e| format!("Failed to write file: {} ({:?})", e, e.kind()))?;
+    fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("Failed to replace file: {} ({:?})", e, e.kind())
+    })
+}
@@ -0,0 +1,563 @@
+//! # Sync Module
+//!
+//! This module mirrors a workspace folder to an S3-compatible bucket, so the same notes
+//! can be used on several machines. Sync is opt-in: it only runs when it is enabled in the
+//! backend settings (`settings::SyncSettings`) and a secret key is stored in the keychain.
+//!
+//! ## When It Runs
+//! - On launch, the whole workspace is synced (this pulls changes made elsewhere)
+//! - When the sync settings change, the whole workspace is synced again
+//! - After `save_file` writes a document inside the workspace, that file is pushed
+//! - `sync_now` syncs the whole workspace on demand
+//!
+//! ## Change Detection
+//! `sync-state.json` records, for every synced file, the SHA-256 of the local content and
+//! the ETag of the object at the last sync. Comparing both sides with that record tells
+//! which side changed (`decide`). Changing the bucket, prefix or workspace starts over
+//! with an empty record.
+//!
+//! ## Conflicts
+//! When both sides changed, the most recently modified version wins (last writer wins).
+//! The other version is kept next to it as `name (conflict <date> <time>).ext`, which is
+//! pushed by the next sync like any new file.
+//! Each conflict raises a notification while the app is in the background.
+//!
+//! ## Events
+//! `sync-status` is emitted with a `SyncStatus` when a sync starts and when it ends.
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use tracing::{debug, info, warn};
+
+use crate::credentials;
+use crate::notifications;
+use crate::remote::normalize_remote_path;
+use crate::s3::{sha256_hex, ObjectInfo, S3Client};
+use crate::settings::{self, SyncSettings};
+use crate::storage;
+use crate::worker_pool;
+use crate::workspace;
+use crate::types::{emit_event, SyncStatusEvent};
+
+const STATE_FILE: &str = "sync-state.json";
+// Keychain key of the S3 secret access key
+const SECRET_KEY: &str = "sync:s3";
+
+// State of a file at the last sync
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    // SHA-256 of the local content
+    pub hash: String,
+    // ETag of the object (without quotes)
+    pub etag: String,
+}
+
+// Persisted sync state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SyncState {
+    // Identifies the configuration the records belong to (see `config_key`)
+    config: String,
+    // Keyed by the path relative to the workspace ('/' separators)
+    files: HashMap<String, SyncRecord>,
+}
+
+// What to do with one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAction {
+    Nothing,
+    Push,
+    Pull,
+    DeleteLocal,
+    DeleteRemote,
+    Conflict,
+    // Gone on both sides: drop the record
+    Forget,
+}
+
+// Sync status reported to the frontend
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub enabled: bool,
+    pub running: bool,
+    // RFC 3339 time of the last successful sync
+    pub last_sync: Option<String>,
+    pub last_error: Option<String>,
+    // Counts of the last sync
+    pub pushed: usize,
+    pub pulled: usize,
+    pub deleted: usize,
+    // Conflict copies created by the last sync (relative paths)
+    pub conflicts: Vec<String>,
+}
+
+// Result of one sync run
+#[derive(Debug, Default)]
+struct SyncReport {
+    pushed: usize,
+    pulled: usize,
+    deleted: usize,
+    conflicts: Vec<String>,
+}
+
+// Local side of a file
+struct LocalFile {
+    hash: String,
+    modified: Option<DateTime<Utc>>,
+}
+
+// Everything a sync run needs
+struct SyncContext {
+    client: S3Client,
+    root: PathBuf,
+    prefix: String,
+    config: String,
+}
+
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+static STATUS: OnceLock<Mutex<SyncStatus>> = OnceLock::new();
+// One sync at a time; a push waits for a running full sync
+static SYNC_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn status_cell() -> &'static Mutex<SyncStatus> {
+    STATUS.get_or_init(|| Mutex::new(SyncStatus::default()))
+}
+
+fn current_status() -> SyncStatus {
+    let mut status = status_cell().lock().map(|s| s.clone()).unwrap_or_default();
+    status.enabled = settings::current_settings().sync.enabled;
+    status
+}
+
+fn update_status(update: impl FnOnce(&mut SyncStatus)) {
+    if let Ok(mut status) = status_cell().lock() {
+        update(&mut status);
+    }
+    if let Some(app_handle) = APP_HANDLE.get() {
+        let _ = emit_event(app_handle, None, &SyncStatusEvent { status: current_status() });
+    }
+}
+
+// Decide what to do with a file from its local hash, its remote ETag and the record of
+// the last sync
+pub fn decide(local: Option<&str>, remote: Option<&str>, record: Option<&SyncRecord>) -> SyncAction {
+    match (local, remote, record) {
+        (None, None, None) => SyncAction::Nothing,
+        (None, None, Some(_)) => SyncAction::Forget,
+        (Some(_), None, None) => SyncAction::Push,
+        (None, Some(_), None) => SyncAction::Pull,
+        // Present on both sides but never synced: compare the contents
+        (Some(_), Some(_), None) => SyncAction::Conflict,
+        // Deleted remotely: follow unless the local file was edited since
+        (Some(hash), None, Some(record)) => {
+            if hash == record.hash {
+                SyncAction::DeleteLocal
+            } else {
+                SyncAction::Push
+            }
+        }
+        // Deleted locally: follow unless the object was replaced since
+        (None, Some(etag), Some(record)) => {
+            if etag == record.etag {
+                SyncAction::DeleteRemote
+            } else {
+                SyncAction::Pull
+            }
+        }
+        (Some(hash), Some(etag), Some(record)) => match (hash != record.hash, etag != record.etag) {
+            (false, false) => SyncAction::Nothing,
+            (true, false) => SyncAction::Push,
+            (false, true) => SyncAction::Pull,
+            (true, true) => SyncAction::Conflict,
+        },
+    }
+}
+
+// Name of the copy that keeps the losing side of a conflict
+pub fn conflict_copy_path(relative: &str, now: DateTime<Local>) -> String {
+    let stamp = now.format("%Y-%m-%d %H%M%S");
+    let (dir, name) = match relative.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), relative),
+    };
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}{} (conflict {}).{}", dir, stem, stamp, ext),
+        _ => format!("{}{} (conflict {})", dir, name, stamp),
+    }
+}
+
+fn normalize_etag(etag: &str) -> String {
+    etag.trim().trim_start_matches("W/").trim_matches('"').to_string()
+}
+
+fn normalize_prefix(prefix: &str) -> String {
+    prefix.trim().trim_matches('/').to_string()
+}
+
+fn object_key(prefix: &str, relative: &str) -> String {
+    if prefix.is_empty() {
+        relative.to_string()
+    } else {
+        format!("{}/{}", prefix, relative)
+    }
+}
+
+// Path of an object relative to the prefix (None for folder markers, hidden files and
+// keys outside of the prefix)
+pub fn relative_key(prefix: &str, key: &str) -> Option<String> {
+    let relative = if prefix.is_empty() {
+        key
+    } else {
+        key.strip_prefix(prefix)?.strip_prefix('/')?
+    };
+    let normalized = normalize_remote_path(relative).ok()?;
+    if normalized != relative || normalized.is_empty() || normalized.split('/').any(|part| part.starts_with('.')) {
+        return None;
+    }
+    Some(normalized)
+}
+
+fn config_key(settings: &SyncSettings) -> String {
+    format!(
+        "{}|{}|{}|{}",
+        settings.endpoint.as_deref().unwrap_or(""),
+        settings.bucket.trim(),
+        normalize_prefix(&settings.prefix),
+        settings.workspace.as_deref().unwrap_or("")
+    )
+}
+
+fn context(settings: &SyncSettings) -> Result<SyncContext, String> {
+    if !settings.enabled {
+        return Err("Sync is not enabled".to_string());
+    }
+    let root = settings
+        .workspace
+        .as_deref()
+        .filter(|w| !w.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| "No sync workspace is configured".to_string())?;
+    if !root.is_dir() {
+        return Err(format!("Sync workspace not found: {}", root.display()));
+    }
+    let secret = credentials::get_secret(SECRET_KEY)?.unwrap_or_default();
+    let client = S3Client::new(
+        settings.endpoint.clone(),
+        settings.bucket.clone(),
+        settings.region.clone(),
+        settings.access_key_id.clone(),
+        secret,
+    )?;
+    Ok(SyncContext {
+        client,
+        root,
+        prefix: normalize_prefix(&settings.prefix),
+        config: config_key(settings),
+    })
+}
+
+fn load_state(config: &str) -> SyncState {
+    let state: SyncState = storage::load_json(STATE_FILE);
+    if state.config == config {
+        state
+    } else {
+        SyncState {
+            config: config.to_string(),
+            files: HashMap::new(),
+        }
+    }
+}
+
+fn local_file(path: &Path) -> Option<LocalFile> {
+    let bytes = fs::read(path).ok()?;
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok().map(DateTime::<Utc>::from);
+    Some(LocalFile {
+        hash: sha256_hex(&bytes),
+        modified,
+    })
+}
+
+fn local_path(root: &Path, relative: &str) -> PathBuf {
+    relative.split('/').fold(root.to_path_buf(), |path, part| path.join(part))
+}
+
+fn write_local(root: &Path, relative: &str, bytes: &[u8]) -> Result<(), String> {
+    let path = local_path(root, relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create folder: {}", e))?;
+    }
+    storage::write_atomic(&path, bytes)
+}
+
+// Hash every file of the workspace (on the worker pool)
+fn scan_workspace(root: &Path) -> Result<HashMap<String, LocalFile>, String> {
+    let paths = workspace::walk_workspace(root, true)?;
+    let files = worker_pool::map_parallel(paths, |path| {
+        let relative = path
+            .strip_prefix(root)
+            .ok()?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        Some((relative, local_file(&path)?))
+    });
+    Ok(files.into_iter().flatten().collect())
+}
+
+async fn upload(ctx: &SyncContext, key: &str, bytes: Vec<u8>) -> Result<String, String> {
+    let etag = ctx.client.put_object(key, bytes).await?;
+    if !etag.is_empty() {
+        return Ok(normalize_etag(&etag));
+    }
+    // Some stores do not return the ETag of an upload
+    let info = ctx.client.head_object(key).await?;
+    Ok(info.map(|info| normalize_etag(&info.etag)).unwrap_or_default())
+}
+
+// Bring one file in sync
+async fn sync_file(
+    ctx: &SyncContext,
+    state: &mut SyncState,
+    relative: &str,
+    local: Option<&LocalFile>,
+    remote: Option<&ObjectInfo>,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    let remote_etag = remote.map(|object| normalize_etag(&object.etag));
+    let action = decide(
+        local.map(|file| file.hash.as_str()),
+        remote_etag.as_deref(),
+        state.files.get(relative),
+    );
+    if action != SyncAction::Nothing {
+        debug!("Sync {}: {:?}", relative, action);
+    }
+    let path = local_path(&ctx.root, relative);
+    let key = object_key(&ctx.prefix, relative);
+
+    match action {
+        SyncAction::Nothing => {}
+        SyncAction::Forget => {
+            state.files.remove(relative);
+        }
+        SyncAction::Push => {
+            let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+            let hash = sha256_hex(&bytes);
+            let etag = upload(ctx, &key, bytes).await?;
+            state.files.insert(relative.to_string(), SyncRecord { hash, etag });
+            report.pushed += 1;
+        }
+        SyncAction::Pull => {
+            let (bytes, etag) = ctx.client.get_object(&key).await?;
+            write_local(&ctx.root, relative, &bytes)?;
+            let record = SyncRecord {
+                hash: sha256_hex(&bytes),
+                etag: normalize_etag(&etag),
+            };
+            state.files.insert(relative.to_string(), record);
+            report.pulled += 1;
+        }
+        SyncAction::DeleteLocal => {
+            fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", relative, e))?;
+            state.files.remove(relative);
+            report.deleted += 1;
+        }
+        SyncAction::DeleteRemote => {
+            ctx.client.delete_object(&key).await?;
+            state.files.remove(relative);
+            report.deleted += 1;
+        }
+        SyncAction::Conflict => {
+            let (remote_bytes, etag) = ctx.client.get_object(&key).await?;
+            let local_bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+            let etag = normalize_etag(&etag);
+            if local_bytes == remote_bytes {
+                let hash = sha256_hex(&local_bytes);
+                state.files.insert(relative.to_string(), SyncRecord { hash, etag });
+                return Ok(());
+            }
+
+            let copy = conflict_copy_path(relative, Local::now());
+            let remote_modified = remote.and_then(|object| object.last_modified);
+            let local_modified = local.and_then(|file| file.modified);
+            let remote_wins = matches!((remote_modified, local_modified), (Some(r), Some(l)) if r > l);
+            if remote_wins {
+                write_local(&ctx.root, &copy, &local_bytes)?;
+                write_local(&ctx.root, relative, &remote_bytes)?;
+                let hash = sha256_hex(&remote_bytes);
+                state.files.insert(relative.to_string(), SyncRecord { hash, etag });
+                report.pulled += 1;
+            } else {
+                write_local(&ctx.root, &copy, &remote_bytes)?;
+                let hash = sha256_hex(&local_bytes);
+                let etag = upload(ctx, &key, local_bytes).await?;
+                state.files.insert(relative.to_string(), SyncRecord { hash, etag });
+                report.pushed += 1;
+            }
+            warn!("Sync conflict on {}; kept the other version as {}", relative, copy);
+            notifications::notify("notification.sync_conflict", &copy, Some(&path.to_string_lossy()));
+            report.conflicts.push(copy);
+        }
+    }
+    Ok(())
+}
+
+// Sync the whole workspace
+async fn sync_workspace(ctx: &SyncContext) -> Result<SyncReport, String> {
+    let root = ctx.root.clone();
+    let local = tauri::async_runtime::spawn_blocking(move || scan_workspace(&root))
+        .await
+        .map_err(|e| format!("Failed to scan workspace: {}", e))??;
+    let list_prefix = if ctx.prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", ctx.prefix)
+    };
+    let remote: HashMap<String, ObjectInfo> = ctx
+        .client
+        .list_objects(&list_prefix)
+        .await?
+        .into_iter()
+        .filter_map(|object| Some((relative_key(&ctx.prefix, &object.key)?, object)))
+        .collect();
+
+    let mut state = load_state(&ctx.config);
+    let paths: BTreeSet<String> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(state.files.keys())
+        .cloned()
+        .collect();
+    let mut report = SyncReport::default();
+    let mut result = Ok(());
+    for relative in &paths {
+        result = sync_file(ctx, &mut state, relative, local.get(relative), remote.get(relative), &mut report).await;
+        if result.is_err() {
+            break;
+        }
+    }
+    // Keep the progress made before a failure
+    storage::save_json(STATE_FILE, &state)?;
+    result.map(|_| report)
+}
+
+// Sync one file of the workspace
+async fn sync_single(ctx: &SyncContext, relative: &str) -> Result<SyncReport, String> {
+    let local = local_file(&local_path(&ctx.root, relative));
+    let remote = ctx.client.head_object(&object_key(&ctx.prefix, relative)).await?;
+    let mut state = load_state(&ctx.config);
+    let mut report = SyncReport::default();
+    let result = sync_file(ctx, &mut state, relative, local.as_ref(), remote.as_ref(), &mut report).await;
+    storage::save_json(STATE_FILE, &state)?;
+    result.map(|_| report)
+}
+
+// Run a sync (the whole workspace, or one file) and report it through the status
+async fn run(relative: Option<String>) -> Result<SyncStatus, String> {
+    let _guard = SYNC_LOCK.lock().await;
+    let ctx = context(&settings::current_settings().sync)?;
+    update_status(|status| status.running = true);
+
+    let result = match &relative {
+        Some(relative) => sync_single(&ctx, relative).await,
+        None => sync_workspace(&ctx).await,
+    };
+    match &result {
+        Ok(report) => {
+            info!(
+                "Sync finished: {} pushed, {} pulled, {} deleted, {} conflicts",
+                report.pushed,
+                report.pulled,
+                report.deleted,
+                report.conflicts.len()
+            );
+            update_status(|status| {
+                status.running = false;
+                status.last_sync = Some(Utc::now().to_rfc3339());
+                status.last_error = None;
+                status.pushed = report.pushed;
+                status.pulled = report.pulled;
+                status.deleted = report.deleted;
+                status.conflicts = report.conflicts.clone();
+            });
+        }
+        Err(e) => {
+            warn!("Sync failed: {}", e);
+            update_status(|status| {
+                status.running = false;
+                status.last_error = Some(e.clone());
+            });
+        }
+    }
+    result.map(|_| current_status())
+}
+
+// Remember the app handle for status events and sync the workspace (called once during
+// setup)
+pub fn spawn_startup_sync(app_handle: tauri::AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+    spawn_sync();
+}
+
+// Sync the workspace in the background when sync is enabled
+pub fn spawn_sync() {
+    if !settings::current_settings().sync.enabled {
+        return;
+    }
+    tauri::async_runtime::spawn(async {
+        let _ = run(None).await;
+    });
+}
+
+// Push a saved file when it lies inside the sync workspace
+pub fn spawn_push(path: &str) {
+    let sync = settings::current_settings().sync;
+    if !sync.enabled {
+        return;
+    }
+    let Some(root) = sync.workspace.as_deref().filter(|w| !w.is_empty()) else {
+        return;
+    };
+    let Ok(relative) = Path::new(path).strip_prefix(root) else {
+        return;
+    };
+    let relative = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    if relative.is_empty() || relative.split('/').any(|part| part.starts_with('.')) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let _ = run(Some(relative)).await;
+    });
+}
+
+// Tauri command: Sync the workspace now
+#[tauri::command]
+pub async fn sync_now() -> Result<SyncStatus, String> {
+    run(None).await
+}
+
+// Tauri command: Get the sync status
+#[tauri::command]
+pub fn get_sync_status() -> SyncStatus {
+    current_status()
+}
+
+// Tauri command: Store the S3 secret access key in the keychain (an empty key removes it)
+#[tauri::command]
+pub fn set_sync_secret_key(secret_key: String) -> Result<(), String> {
+    if secret_key.is_empty() {
+        credentials::delete_secret(SECRET_KEY)
+    } else {
+        credentials::set_secret(SECRET_KEY, &secret_key)
+    }
+}
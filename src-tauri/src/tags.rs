@@ -0,0 +1,303 @@
+//! # Tags Module
+//!
+//! This module indexes the tags used in a workspace.
+//!
+//! ## Tag Sources
+//! - Frontmatter: `tags: [a, b]`, `tags: a, b` or a YAML list (`tag:` works as well)
+//! - Inline: `#tag` tokens in the body, outside code blocks and inline code. Tags may be
+//!   nested with '/' (`#project/bokuchi`); purely numeric tokens (`#123`) are not tags.
+//!
+//! Tags are compared case-insensitively. A query for `project` also matches the nested
+//! tags below it.
+//!
+//! ## Index
+//! Tags are kept per file in a `workspace::FileCache`, which re-reads only changed files
+//! whenever a command is called.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use tracing::info;
+
+use crate::frontmatter;
+use crate::markdown::{self, lines_outside_code};
+use crate::path_scope;
+use crate::storage;
+use crate::workspace::FileCache;
+
+lazy_static! {
+    static ref INLINE_TAG: Regex = Regex::new(r"(?:^|[\s\[,;])#([\p{L}\p{N}_\-/]+)").unwrap();
+    static ref FRONTMATTER_TOKEN: Regex = Regex::new(r#"[^\s,\[\]"']+"#).unwrap();
+}
+
+// Tag with the number of files using it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagInfo {
+    pub name: String,
+    pub count: usize,
+}
+
+// Result of renaming a tag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameTagResult {
+    pub files_changed: Vec<String>,
+}
+
+static TAG_CACHE: OnceLock<Mutex<FileCache<Vec<String>>>> = OnceLock::new();
+
+fn tag_cache_cell() -> &'static Mutex<FileCache<Vec<String>>> {
+    TAG_CACHE.get_or_init(|| Mutex::new(FileCache::default()))
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').trim_end_matches('/').to_string()
+}
+
+fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit() || c == '/')
+}
+
+// Check whether `tag` is `query` or nested below it (case-insensitive)
+pub fn tag_matches(tag: &str, query: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let query = query.to_lowercase();
+    tag == query || tag.starts_with(&format!("{}/", query))
+}
+
+// Byte ranges of the inline tag names (without '#') in a line of body text
+pub fn inline_tag_spans(line: &str) -> Vec<Range<usize>> {
+    let masked = markdown::mask_inline_code(line);
+    INLINE_TAG
+        .captures_iter(&masked)
+        .filter_map(|caps| caps.get(1))
+        .map(|m| {
+            let name = m.as_str().trim_end_matches('/');
+            m.start()..m.start() + name.len()
+        })
+        .filter(|range| is_valid_tag(&line[range.clone()]))
+        .collect()
+}
+
+fn frontmatter_tags(value: &Value) -> Vec<String> {
+    let field = value.get("tags").or_else(|| value.get("tag"));
+    match field {
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect(),
+        Some(Value::String(s)) => s.split([',', ' ']).map(str::to_string).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Extract the tags of a document (deduplicated case-insensitively, in order of appearance)
+pub fn extract_tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut push = |tag: String| {
+        let tag = normalize_tag(&tag);
+        if is_valid_tag(&tag) && !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            tags.push(tag);
+        }
+    };
+
+    if let Some(value) = frontmatter::parse_frontmatter(content) {
+        frontmatter_tags(&value).into_iter().for_each(&mut push);
+    }
+    let body = frontmatter::split_frontmatter(content).body;
+    for (_, line) in lines_outside_code(body) {
+        for span in inline_tag_spans(line) {
+            push(line[span].to_string());
+        }
+    }
+    tags
+}
+
+// Replace `from` (and tags nested below it) with `to` in the given byte ranges of a line
+fn rename_in_spans(line: &str, spans: &[Range<usize>], from: &str, to: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut last = 0;
+    for span in spans {
+        let tag = &line[span.clone()];
+        if tag_matches(tag, from) {
+            output.push_str(&line[last..span.start]);
+            output.push_str(to);
+            output.push_str(&tag[from.len()..]);
+            last = span.end;
+        }
+    }
+    output.push_str(&line[last..]);
+    output
+}
+
+fn rename_in_frontmatter(frontmatter: &str, from: &str, to: &str) -> String {
+    let mut output = String::with_capacity(frontmatter.len());
+    let mut in_tags = false;
+    for raw_line in frontmatter.split_inclusive('\n') {
+        let line = raw_line.trim_end_matches(['\r', '\n']);
+        let ending = &raw_line[line.len()..];
+        let indented = line.starts_with([' ', '\t', '-']);
+
+        let value_start = if let Some(rest) = line.strip_prefix("tags:").or_else(|| line.strip_prefix("tag:")) {
+            in_tags = true;
+            Some(line.len() - rest.len())
+        } else if in_tags && indented {
+            Some(0)
+        } else {
+            in_tags = false;
+            None
+        };
+
+        match value_start {
+            Some(start) => {
+                let spans: Vec<Range<usize>> = FRONTMATTER_TOKEN
+                    .find_iter(&line[start..])
+                    .map(|m| {
+                        // Keep a leading '#' or list marker out of the tag name
+                        let token = m.as_str();
+                        let skip = token.len() - token.trim_start_matches(['#', '-']).len();
+                        start + m.start() + skip..start + m.end()
+                    })
+                    .filter(|span| !span.is_empty())
+                    .collect();
+                output.push_str(&rename_in_spans(line, &spans, from, to));
+            }
+            None => output.push_str(line),
+        }
+        output.push_str(ending);
+    }
+    output
+}
+
+// Rename a tag (and the tags nested below it) in a document. Returns None if unchanged.
+pub fn rename_tag_in_content(content: &str, from: &str, to: &str) -> Option<String> {
+    let split = frontmatter::split_frontmatter(content);
+    let mut output = String::with_capacity(content.len());
+    if let Some(frontmatter) = split.frontmatter {
+        let header_len = content.len() - split.body.len();
+        let head = &content[..header_len];
+        // The frontmatter starts right after the opening "---" line
+        let start = if content.starts_with("---\r\n") { 5 } else { 4 };
+        output.push_str(&head[..start]);
+        output.push_str(&rename_in_frontmatter(frontmatter, from, to));
+        output.push_str(&head[start + frontmatter.len()..]);
+    }
+
+    let code_free: std::collections::HashSet<usize> =
+        lines_outside_code(split.body).into_iter().map(|(i, _)| i).collect();
+    for (index, raw_line) in split.body.split_inclusive('\n').enumerate() {
+        if code_free.contains(&index) {
+            let line = raw_line.trim_end_matches(['\r', '\n']);
+            output.push_str(&rename_in_spans(line, &inline_tag_spans(line), from, to));
+            output.push_str(&raw_line[line.len()..]);
+        } else {
+            output.push_str(raw_line);
+        }
+    }
+    (output != content).then_some(output)
+}
+
+fn with_refreshed_tags<T>(root: &str, f: impl FnOnce(&FileCache<Vec<String>>) -> T) -> Result<T, String> {
+    let mut cache = tag_cache_cell()
+        .lock()
+        .map_err(|_| "Failed to lock tag index".to_string())?;
+    cache.refresh(Path::new(root), |_, content| extract_tags(content))?;
+    Ok(f(&cache))
+}
+
+// Count the files per tag (tags differing only in case are merged)
+pub fn count_tags<'a>(files: impl Iterator<Item = &'a Vec<String>>) -> Vec<TagInfo> {
+    let mut counts: BTreeMap<String, TagInfo> = BTreeMap::new();
+    for tags in files {
+        for tag in tags {
+            counts
+                .entry(tag.to_lowercase())
+                .or_insert_with(|| TagInfo {
+                    name: tag.clone(),
+                    count: 0,
+                })
+                .count += 1;
+        }
+    }
+    let mut tags: Vec<TagInfo> = counts.into_values().collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    tags
+}
+
+// Tauri command: List the tags of a workspace, most used first
+#[tauri::command]
+pub async fn list_tags(root: String) -> Result<Vec<TagInfo>, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || with_refreshed_tags(&root, |cache| count_tags(cache.iter().map(|(_, t)| t))))
+        .await
+        .map_err(|e| format!("Failed to list tags: {}", e))?
+}
+
+// Tauri command: List the files using a tag (or a tag nested below it)
+#[tauri::command]
+pub async fn files_with_tag(root: String, tag: String) -> Result<Vec<String>, String> {
+    path_scope::check_path(&root)?;
+    let tag = normalize_tag(&tag);
+    tauri::async_runtime::spawn_blocking(move || {
+        with_refreshed_tags(&root, |cache| {
+            let mut files: Vec<String> = cache
+                .iter()
+                .filter(|(_, tags)| tags.iter().any(|t| tag_matches(t, &tag)))
+                .map(|(path, _)| path.clone())
+                .collect();
+            files.sort();
+            files
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to list files with tag: {}", e))?
+}
+
+// Tauri command: Rename a tag (and the tags nested below it) in every file of a workspace
+#[tauri::command]
+pub async fn rename_tag(root: String, from: String, to: String) -> Result<RenameTagResult, String> {
+    let from = normalize_tag(&from);
+    let to = normalize_tag(&to);
+    if !is_valid_tag(&from) || !is_valid_tag(&to) || to.contains(char::is_whitespace) {
+        return Err("Invalid tag name".to_string());
+    }
+    path_scope::check_path(&root)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let files = with_refreshed_tags(&root, |cache| {
+            cache
+                .iter()
+                .filter(|(_, tags)| tags.iter().any(|t| tag_matches(t, &from)))
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<String>>()
+        })?;
+
+        let mut files_changed = Vec::new();
+        for path in files {
+            path_scope::check_document_write(&path)?;
+            let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            if let Some(updated) = rename_tag_in_content(&content, &from, &to) {
+                storage::write_atomic(Path::new(&path), updated.as_bytes())?;
+                files_changed.push(path);
+            }
+        }
+
+        if let Ok(mut cache) = tag_cache_cell().lock() {
+            files_changed.iter().for_each(|path| cache.invalidate(path));
+        }
+        info!("Renamed tag {} to {} in {} files", from, to, files_changed.len());
+        Ok(RenameTagResult { files_changed })
+    })
+    .await
+    .map_err(|e| format!("Failed to rename tag: {}", e))?
+}
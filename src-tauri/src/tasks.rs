@@ -0,0 +1,175 @@
+//! # Tasks Module
+//!
+//! Long-running commands (workspace search, replace preview, search index build) are
+//! registered as tasks while they run, so the frontend can follow their progress and
+//! stop them, e.g. when the dialog that started them is closed.
+//!
+//! ## Task IDs
+//! A command that supports tasks takes an optional `task_id`. The frontend picks the ID
+//! (so it can cancel before the command returns); without one, an ID is generated and
+//! announced with the first `task-progress` event.
+//!
+//! ## Cancellation
+//! `cancel_task(id)` sets the task's `CancellationToken`. The work checks the token
+//! between files and stops with `AppError::Cancelled`; nothing is left half-written.
+//!
+//! ## Events
+//! - `task-progress`: `{ id, kind, done, total }`, emitted to all windows when a task
+//!   reports progress
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::error::AppError;
+use crate::types::{emit_event, TaskProgressEvent};
+
+// Flag shared between a task and `cancel_task`
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    // Err(Cancelled) once the task was cancelled, for use with `?` between steps
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            Err(AppError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// A running task, as listed by `list_tasks` and sent with `task-progress`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskInfo {
+    pub id: String,
+    pub kind: String,
+    pub done: usize,
+    pub total: usize,
+}
+
+struct TaskEntry {
+    info: TaskInfo,
+    token: CancellationToken,
+}
+
+static TASKS: OnceLock<Mutex<HashMap<String, TaskEntry>>> = OnceLock::new();
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(1);
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+
+fn tasks_cell() -> &'static Mutex<HashMap<String, TaskEntry>> {
+    TASKS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Registration of a running task; the task is removed from the registry when dropped
+pub struct TaskGuard {
+    id: String,
+    token: CancellationToken,
+}
+
+impl TaskGuard {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    // Record progress and emit `task-progress`
+    pub fn progress(&self, done: usize, total: usize) {
+        let info = match tasks_cell().lock() {
+            Ok(mut tasks) => match tasks.get_mut(&self.id) {
+                Some(entry) => {
+                    entry.info.done = done;
+                    entry.info.total = total;
+                    entry.info.clone()
+                }
+                None => return,
+            },
+            Err(_) => return,
+        };
+        if let Some(app_handle) = APP_HANDLE.get() {
+            let _ = emit_event(app_handle, None, &TaskProgressEvent { task: info });
+        }
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        if let Ok(mut tasks) = tasks_cell().lock() {
+            tasks.remove(&self.id);
+        }
+    }
+}
+
+// Register a task of `kind` under the ID chosen by the frontend, or a generated one
+pub fn register_task(id: Option<String>, kind: &str) -> Result<TaskGuard, String> {
+    let id = id.unwrap_or_else(|| format!("{}-{}", kind, NEXT_TASK_ID.fetch_add(1, Ordering::SeqCst)));
+    let mut tasks = tasks_cell()
+        .lock()
+        .map_err(|_| "Failed to lock task registry".to_string())?;
+    if tasks.contains_key(&id) {
+        return Err(format!("A task with the ID {} is already running", id));
+    }
+    let token = CancellationToken::default();
+    tasks.insert(
+        id.clone(),
+        TaskEntry {
+            info: TaskInfo {
+                id: id.clone(),
+                kind: kind.to_string(),
+                done: 0,
+                total: 0,
+            },
+            token: token.clone(),
+        },
+    );
+    debug!("Task {} ({}) started", id, kind);
+    Ok(TaskGuard { id, token })
+}
+
+// Emit task events from now on (called once during setup)
+pub fn init_tasks(app_handle: tauri::AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+// Tauri command: Cancel a running task. Returns false if no task has this ID (it may
+// have finished already).
+#[tauri::command]
+pub fn cancel_task(id: String) -> bool {
+    let token = tasks_cell()
+        .lock()
+        .ok()
+        .and_then(|tasks| tasks.get(&id).map(|entry| entry.token.clone()));
+    match token {
+        Some(token) => {
+            info!("Cancelling task {}", id);
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+// Tauri command: List the running tasks
+#[tauri::command]
+pub fn list_tasks() -> Vec<TaskInfo> {
+    let mut tasks: Vec<TaskInfo> = tasks_cell()
+        .lock()
+        .map(|tasks| tasks.values().map(|entry| entry.info.clone()).collect())
+        .unwrap_or_default();
+    tasks.sort_by(|a, b| a.id.cmp(&b.id));
+    tasks
+}
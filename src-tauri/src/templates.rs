@@ -0,0 +1,274 @@
+//! # Templates Module
+//!
+//! This module provides the template gallery: Markdown files in `<app data>/templates/`
+//! that new documents are created from.
+//!
+//! ## Variable Declarations
+//! A template declares the values it needs in its frontmatter, under `template`:
+//!
+//! ```text
+//! ---
+//! title: "Weekly report {{project}}"
+//! template:
+//!   name: Weekly report
+//!   description: Status report for the team
+//!   variables:
+//!     - name: project
+//!       label: Project
+//!       required: true
+//!     - name: date
+//!       type: date
+//!       default: today
+//!     - name: status
+//!       type: choice
+//!       options: [green, yellow, red]
+//!       default: green
+//! ---
+//! ```
+//!
+//! Types are `text` (the default), `number`, `date` (`YYYY-MM-DD`; the default `today`
+//! is the current date), `boolean` and `choice`.
+//!
+//! ## Creating a Document
+//! `create_from_template` is called twice: without values it returns the declarations,
+//! so the frontend can prompt the user; with values it returns the new document, with
+//! `{{name}}` replaced by the values and the `template` block removed. A template without
+//! variables is filled in on the first call. Placeholders of other (global or file)
+//! variables are left in place.
+
+use chrono::{Local, NaiveDate};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use tracing::warn;
+
+use crate::frontmatter;
+use crate::storage;
+
+lazy_static! {
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{\{([^}]+)\}\}").unwrap();
+}
+
+const TEMPLATE_DIR: &str = "templates";
+const TEMPLATE_KEY: &str = "template";
+
+// Type of a template variable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateVariableType {
+    #[default]
+    Text,
+    Number,
+    Date,
+    Boolean,
+    Choice,
+}
+
+// A value a template asks for
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemplateVariable {
+    pub name: String,
+    // Shown in the prompt; empty uses the name
+    pub label: String,
+    #[serde(rename = "type")]
+    pub kind: TemplateVariableType,
+    pub default: Option<String>,
+    pub required: bool,
+    // Allowed values of a `choice`
+    pub options: Vec<String>,
+}
+
+// The `template` block of a template's frontmatter
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct TemplateDeclaration {
+    name: Option<String>,
+    description: String,
+    variables: Vec<TemplateVariable>,
+}
+
+// A template of the gallery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateInfo {
+    // File name without `.md`
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub variables: Vec<TemplateVariable>,
+}
+
+// Result of `create_from_template`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TemplateResult {
+    // Values are needed first; call again with them
+    NeedsValues { variables: Vec<TemplateVariable> },
+    Created { content: String },
+}
+
+fn declaration(content: &str) -> Result<TemplateDeclaration, String> {
+    let Some(block) = frontmatter::parse_frontmatter(content).and_then(|value| value.get(TEMPLATE_KEY).cloned())
+    else {
+        return Ok(TemplateDeclaration::default());
+    };
+    let mut declaration: TemplateDeclaration =
+        serde_yaml::from_value(block).map_err(|e| format!("Invalid template declaration: {}", e))?;
+    for variable in &mut declaration.variables {
+        variable.name = variable.name.trim().to_string();
+        if variable.name.is_empty() || variable.name.contains(['{', '}']) {
+            return Err(format!("Invalid template variable name: {:?}", variable.name));
+        }
+        if variable.label.is_empty() {
+            variable.label = variable.name.clone();
+        }
+        if variable.kind == TemplateVariableType::Date && variable.default.as_deref() == Some("today") {
+            variable.default = Some(Local::now().format("%Y-%m-%d").to_string());
+        }
+    }
+    Ok(declaration)
+}
+
+// Read the name, description and variables of a template
+pub fn parse_template(id: &str, content: &str) -> Result<TemplateInfo, String> {
+    let declaration = declaration(content)?;
+    Ok(TemplateInfo {
+        id: id.to_string(),
+        name: declaration.name.unwrap_or_else(|| id.to_string()),
+        description: declaration.description,
+        variables: declaration.variables,
+    })
+}
+
+// Check a value against the type of its variable
+fn validate_value(variable: &TemplateVariable, value: &str) -> Result<(), String> {
+    let valid = match variable.kind {
+        TemplateVariableType::Text => true,
+        TemplateVariableType::Number => value.trim().parse::<f64>().is_ok(),
+        TemplateVariableType::Date => NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").is_ok(),
+        TemplateVariableType::Boolean => matches!(value, "true" | "false"),
+        TemplateVariableType::Choice => variable.options.iter().any(|option| option == value),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid value for {}: {:?}", variable.label, value))
+    }
+}
+
+// The template without its `template` block (and without the frontmatter, if nothing
+// else is in it)
+fn strip_declaration(content: &str) -> Result<String, String> {
+    let document = frontmatter::split_frontmatter(content);
+    let Some(mut mapping) = frontmatter::parse_frontmatter(content).and_then(|value| match value {
+        Value::Mapping(mapping) => Some(mapping),
+        _ => None,
+    }) else {
+        return Ok(content.to_string());
+    };
+    if mapping.remove(TEMPLATE_KEY).is_none() {
+        return Ok(content.to_string());
+    }
+    if mapping.is_empty() {
+        return Ok(document.body.to_string());
+    }
+    let yaml = serde_yaml::to_string(&mapping).map_err(|e| format!("Failed to write frontmatter: {}", e))?;
+    Ok(format!("---\n{}---\n{}", yaml, document.body))
+}
+
+// Fill in a template. `values` without a variable of the template are ignored.
+pub fn fill_template(content: &str, values: &HashMap<String, String>) -> Result<String, String> {
+    let declaration = declaration(content)?;
+    let mut resolved: HashMap<&str, String> = HashMap::new();
+    for variable in &declaration.variables {
+        let value = values
+            .get(&variable.name)
+            .filter(|value| !value.is_empty())
+            .or(variable.default.as_ref());
+        match value {
+            Some(value) => {
+                validate_value(variable, value)?;
+                resolved.insert(&variable.name, value.clone());
+            }
+            None if variable.required => return Err(format!("{} is required", variable.label)),
+            None => {
+                resolved.insert(&variable.name, String::new());
+            }
+        }
+    }
+
+    let content = strip_declaration(content)?;
+    Ok(PLACEHOLDER
+        .replace_all(&content, |caps: &regex::Captures| {
+            resolved
+                .get(caps[1].trim())
+                .cloned()
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned())
+}
+
+// The two steps of creating a document: the declarations without values, the document
+// with them
+pub fn create_document(content: &str, values: Option<&HashMap<String, String>>) -> Result<TemplateResult, String> {
+    let variables = declaration(content)?.variables;
+    match values {
+        None if !variables.is_empty() => Ok(TemplateResult::NeedsValues { variables }),
+        _ => Ok(TemplateResult::Created {
+            content: fill_template(content, values.unwrap_or(&HashMap::new()))?,
+        }),
+    }
+}
+
+// Templates of a folder, sorted by name. Templates with an invalid declaration are
+// skipped.
+pub fn list_templates_in(dir: &Path) -> Vec<TemplateInfo> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut templates: Vec<TemplateInfo> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_string_lossy().to_string();
+            let content = fs::read_to_string(&path).ok()?;
+            parse_template(&id, &content)
+                .inspect_err(|e| warn!("Skipping template {}: {}", path.display(), e))
+                .ok()
+        })
+        .collect();
+    templates.sort_by_key(|template| template.name.to_lowercase());
+    templates
+}
+
+// Content of a template of the gallery
+pub fn read_template(id: &str) -> Result<String, String> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        return Err(format!("Invalid template: {:?}", id));
+    }
+    let path = storage::app_data_path(TEMPLATE_DIR)
+        .ok_or_else(|| "App data directory is not available".to_string())?
+        .join(format!("{}.md", id));
+    fs::read_to_string(&path).map_err(|e| format!("Failed to read template {}: {}", id, e))
+}
+
+// Tauri command: List the templates of the gallery
+#[tauri::command]
+pub fn list_templates() -> Vec<TemplateInfo> {
+    storage::app_data_path(TEMPLATE_DIR)
+        .map(|dir| list_templates_in(&dir))
+        .unwrap_or_default()
+}
+
+// Tauri command: Create a document from a template. Without `values`, returns the
+// variables to prompt for (unless there are none); with them, the new document.
+#[tauri::command]
+pub fn create_from_template(id: String, values: Option<HashMap<String, String>>) -> Result<TemplateResult, String> {
+    create_document(&read_template(&id)?, values.as_ref())
+}
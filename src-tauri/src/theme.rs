@@ -0,0 +1,60 @@
+//! # Theme Module
+//!
+//! This module reports the OS light/dark appearance to the frontend, so the editor and
+//! preview can follow the system without polling `matchMedia` from JS.
+//!
+//! ## Behavior
+//! - `get_system_theme` returns the current appearance (`"light"` or `"dark"`)
+//! - On `WindowEvent::ThemeChanged` a `system-theme-changed` event is emitted to the
+//!   affected window
+//! - `set_window_theme` pins the native window theme (titlebar on Windows, window chrome
+//!   on macOS) to the app theme, or lets it follow the system again with `None`
+
+use tauri::Theme;
+use tracing::debug;
+
+use crate::types::{emit_event, SystemThemeChangedEvent};
+
+// Name of a theme as sent to the frontend
+pub fn theme_name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+// Parse a theme name from the frontend ("system" means follow the OS)
+pub fn parse_theme(name: &str) -> Result<Option<Theme>, String> {
+    match name {
+        "light" => Ok(Some(Theme::Light)),
+        "dark" => Ok(Some(Theme::Dark)),
+        "system" => Ok(None),
+        _ => Err(format!("Unknown theme: {}", name)),
+    }
+}
+
+// Handle `WindowEvent::ThemeChanged`
+pub fn handle_theme_changed(window: &tauri::Window, theme: &Theme) {
+    let event = SystemThemeChangedEvent {
+        theme: theme_name(*theme).to_string(),
+    };
+    let result = emit_event(window, Some(window.label()), &event);
+    debug!("System theme changed to {} ({}): {:?}", theme_name(*theme), window.label(), result);
+}
+
+// Tauri command: Get the current system appearance
+#[tauri::command]
+pub fn get_system_theme(window: tauri::Window) -> Result<String, String> {
+    window
+        .theme()
+        .map(|theme| theme_name(theme).to_string())
+        .map_err(|e| format!("Failed to read system theme: {}", e))
+}
+
+// Tauri command: Set the native window theme ("light", "dark" or "system")
+#[tauri::command]
+pub fn set_window_theme(window: tauri::Window, theme: String) -> Result<(), String> {
+    window
+        .set_theme(parse_theme(&theme)?)
+        .map_err(|e| format!("Failed to set window theme: {}", e))
+}
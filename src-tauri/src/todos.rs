@@ -0,0 +1,199 @@
+//! # Todos Module
+//!
+//! This module collects the open and done tasks of a workspace for the task panel.
+//!
+//! ## Task Sources
+//! - Task list items: `- [ ] text` and `- [x] text` (any bullet, ordered items and items
+//!   inside block quotes included)
+//! - Markers: lines containing one of the configured markers (`TODO:` and `FIXME:` by
+//!   default, see `settings`), also inside HTML comments. Marker tasks are always open.
+//!
+//! Lines inside code blocks, inline code and frontmatter are ignored.
+//!
+//! ## Toggling
+//! `toggle_task` flips the checkbox of the task list item starting at a line, for
+//! clickable checkboxes in the preview. The line must start a task list item according
+//! to the Markdown parser, so nested items and items in block quotes work, while `[ ]`
+//! in code blocks or continuation lines is rejected.
+//!
+//! ## Index
+//! Tasks are kept per file in a `workspace::FileCache`, which re-reads only changed files
+//! whenever a command is called. Changing the markers rebuilds it.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use crate::commands;
+use crate::document_structure::{parse_structure, Block, BlockKind};
+use crate::frontmatter;
+use crate::markdown::{self, lines_outside_code};
+use crate::path_scope;
+use crate::settings;
+use crate::workspace::FileCache;
+
+lazy_static! {
+    static ref TASK_ITEM: Regex =
+        Regex::new(r"^((?:\s*>)*\s*(?:[-*+]|\d{1,9}[.)])\s+\[)([ xX])(\]\s+)(.*)$").unwrap();
+}
+
+const DEFAULT_MARKERS: &[&str] = &["TODO:", "FIXME:"];
+
+// Where a task comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoKind {
+    Checkbox,
+    Marker,
+}
+
+// A task found in a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodoItem {
+    pub path: String,
+    // 1-based
+    pub line: usize,
+    pub text: String,
+    pub checked: bool,
+    pub kind: TodoKind,
+    // The marker of a `Marker` task ("TODO:")
+    pub marker: Option<String>,
+}
+
+// Tasks of each file, with the markers they were collected with
+struct TodoIndex {
+    markers: Vec<String>,
+    files: FileCache<Vec<TodoItem>>,
+}
+
+static TODO_INDEX: OnceLock<Mutex<TodoIndex>> = OnceLock::new();
+
+fn todo_index_cell() -> &'static Mutex<TodoIndex> {
+    TODO_INDEX.get_or_init(|| {
+        Mutex::new(TodoIndex {
+            markers: Vec::new(),
+            files: FileCache::default(),
+        })
+    })
+}
+
+// Markers from the settings, or the defaults
+fn configured_markers() -> Vec<String> {
+    match settings::current_settings().todo_markers {
+        Some(markers) => markers
+            .into_iter()
+            .map(|marker| marker.trim().to_string())
+            .filter(|marker| !marker.is_empty())
+            .collect(),
+        None => DEFAULT_MARKERS.iter().map(|marker| marker.to_string()).collect(),
+    }
+}
+
+// The task of a marker line: the text after the first marker, up to the end of an
+// enclosing HTML comment
+fn marker_task(line: &str, markers: &[String]) -> Option<(String, String)> {
+    let masked = markdown::mask_inline_code(line);
+    let (start, marker) = markers
+        .iter()
+        .filter_map(|marker| masked.find(marker.as_str()).map(|start| (start, marker)))
+        .min_by_key(|(start, _)| *start)?;
+    let rest = &line[start + marker.len()..];
+    let text = rest.split("-->").next().unwrap_or(rest).trim();
+    Some((marker.clone(), text.to_string()))
+}
+
+// Extract the tasks of a document
+pub fn extract_todos(path: &str, content: &str, markers: &[String]) -> Vec<TodoItem> {
+    let split = frontmatter::split_frontmatter(content);
+    let mut todos = Vec::new();
+    for (index, line) in lines_outside_code(split.body) {
+        let (kind, checked, text, marker) = if let Some(caps) = TASK_ITEM.captures(line) {
+            (TodoKind::Checkbox, &caps[2] != " ", caps[4].trim().to_string(), None)
+        } else if let Some((marker, text)) = marker_task(line, markers) {
+            (TodoKind::Marker, false, text, Some(marker))
+        } else {
+            continue;
+        };
+        todos.push(TodoItem {
+            path: path.to_string(),
+            line: split.body_line_offset + index + 1,
+            text,
+            checked,
+            kind,
+            marker,
+        });
+    }
+    todos
+}
+
+// Collect the tasks of every document of a workspace, by path and line
+pub fn collect_workspace_todos(root: &Path) -> Result<Vec<TodoItem>, String> {
+    let markers = configured_markers();
+    let mut index = todo_index_cell()
+        .lock()
+        .map_err(|_| "Failed to lock task index".to_string())?;
+    if index.markers != markers {
+        index.markers = markers.clone();
+        index.files = FileCache::default();
+    }
+    index
+        .files
+        .refresh(root, |path, content| extract_todos(&path.to_string_lossy(), content, &markers))?;
+    let mut todos: Vec<TodoItem> = index.files.iter().flat_map(|(_, todos)| todos.iter().cloned()).collect();
+    todos.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    Ok(todos)
+}
+
+// Whether a task list item starts at a 1-based line
+fn is_task_line(blocks: &[Block], line: usize) -> bool {
+    blocks.iter().any(|block| {
+        let is_task = matches!(block.kind, BlockKind::ListItem { checked: Some(_) }) && block.start_line == line;
+        is_task || (block.start_line <= line && line <= block.end_line && is_task_line(&block.children, line))
+    })
+}
+
+// Flip the checkbox of the task list item at a 1-based line. Returns the edited content.
+pub fn toggle_task_in_content(content: &str, line: usize) -> Result<String, String> {
+    let not_a_task = || format!("Line {} is not a task", line);
+    if line == 0 || !is_task_line(&parse_structure(content), line) {
+        return Err(not_a_task());
+    }
+    let start: usize = content.split_inclusive('\n').take(line - 1).map(str::len).sum();
+    let text = content[start..].lines().next().unwrap_or("");
+    let caps = TASK_ITEM.captures(text).ok_or_else(not_a_task)?;
+    let mark = caps.get(2).ok_or_else(not_a_task)?;
+    let toggled = if mark.as_str() == " " { "x" } else { " " };
+    let mut output = String::with_capacity(content.len());
+    output.push_str(&content[..start + mark.start()]);
+    output.push_str(toggled);
+    output.push_str(&content[start + mark.end()..]);
+    Ok(output)
+}
+
+// Tauri command: List the tasks (task list items and marker lines) of a workspace
+#[tauri::command]
+pub async fn collect_tasks(root: String) -> Result<Vec<TodoItem>, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || collect_workspace_todos(Path::new(&root)))
+        .await
+        .map_err(|e| format!("Failed to collect tasks: {}", e))?
+}
+
+// Tauri command: Flip the checkbox of the task at a 1-based line. With `content`, the
+// edited content is only returned; otherwise the document at `path` is edited and saved.
+#[tauri::command]
+pub async fn toggle_task(path: Option<String>, content: Option<String>, line: usize) -> Result<String, String> {
+    match (content, path) {
+        (Some(content), _) => toggle_task_in_content(&content, line),
+        (None, Some(path)) => {
+            let content = commands::read_file(path.clone()).await?;
+            let toggled = toggle_task_in_content(&content, line)?;
+            commands::save_file(path, toggled.clone()).await?;
+            Ok(toggled)
+        }
+        (None, None) => Err("Either a path or content is required".to_string()),
+    }
+}
@@ -0,0 +1,122 @@
+//! # Tray Module
+//!
+//! This module installs the system tray icon and its quick-action menu.
+//!
+//! ## Menu Items
+//! - **New Note**: Shows the window and opens a new tab (`menu-new-file`)
+//! - **Open Recent…**: Shows the window and opens the recent files dialog (`menu-open-recent`)
+//! - **Favorites**: The pinned files (`favorites`); an entry shows the window and opens it
+//! - **Show/Hide Bokuchi**: Toggles the main window's visibility
+//! - **Quit**: Exits the application, even when "keep running in tray" is enabled
+//!
+//! Clicks are delivered to the global menu handler (`menu::handle_menu_event`), so tray
+//! items share the same emit-based flow as the application menu. A left click on the
+//! icon itself toggles the window. Labels come from the locale catalogs (`tray.*`).
+
+use tauri::menu::{IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::Manager;
+use tracing::{info, warn};
+
+use crate::favorites;
+use crate::locale::tr;
+
+const TRAY_ID: &str = "bokuchi-tray";
+// Prefix of the entries in the Favorites submenu ("tray_favorite_<index>")
+const FAVORITE_ITEM_PREFIX: &str = "tray_favorite_";
+
+// Build the Favorites submenu from the pinned files (disabled when there are none)
+fn build_favorites_submenu(app: &tauri::AppHandle) -> tauri::Result<Submenu<tauri::Wry>> {
+    let files = favorites::favorites();
+    let mut items: Vec<MenuItem<tauri::Wry>> = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        items.push(MenuItem::with_id(
+            app,
+            format!("{}{}", FAVORITE_ITEM_PREFIX, index),
+            &file.name,
+            true,
+            None::<&str>,
+        )?);
+    }
+    let refs: Vec<&dyn IsMenuItem<tauri::Wry>> = items.iter().map(|i| i as &dyn IsMenuItem<tauri::Wry>).collect();
+    Submenu::with_items(app, tr("tray.favorites"), !files.is_empty(), &refs)
+}
+
+// Parse the favorites index out of a Favorites entry ID
+pub fn favorite_index(id: &str) -> Option<usize> {
+    id.strip_prefix(FAVORITE_ITEM_PREFIX)?.parse().ok()
+}
+
+// Build the tray menu in the current locale
+fn build_tray_menu(app: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let new_note = MenuItem::with_id(app, "tray_new_note", tr("tray.new_note"), true, None::<&str>)?;
+    let open_recent = MenuItem::with_id(app, "tray_open_recent", tr("tray.open_recent"), true, None::<&str>)?;
+    let favorites = build_favorites_submenu(app)?;
+    let toggle = MenuItem::with_id(app, "tray_toggle_window", tr("tray.toggle_window"), true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit = MenuItem::with_id(app, "tray_quit", tr("tray.quit"), true, None::<&str>)?;
+    Menu::with_items(app, &[&new_note, &open_recent, &favorites, &toggle, &separator, &quit])
+}
+
+// Create the tray icon and its menu
+pub fn setup_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
+    let menu = build_tray_menu(app)?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Bokuchi")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                toggle_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    info!("Tray icon created");
+    Ok(())
+}
+
+// Rebuild the tray menu (after the locale or the favorites changed)
+pub fn refresh_tray_menu(app: &tauri::AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(e) = build_tray_menu(app).and_then(|menu| tray.set_menu(Some(menu))) {
+        warn!("Failed to rebuild tray menu: {}", e);
+    }
+}
+
+// Whether the tray icon exists (it may fail to initialize, e.g. without a status notifier host)
+pub fn has_tray(app: &tauri::AppHandle) -> bool {
+    app.tray_by_id(TRAY_ID).is_some()
+}
+
+// Show, restore and focus the main window
+pub fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(main_window) = app.get_webview_window("main") {
+        let _ = main_window.show();
+        let _ = main_window.unminimize();
+        let _ = main_window.set_focus();
+    }
+}
+
+// Hide the main window if it is visible, otherwise show it
+pub fn toggle_main_window(app: &tauri::AppHandle) {
+    if let Some(main_window) = app.get_webview_window("main") {
+        let visible = main_window.is_visible().unwrap_or(false);
+        let minimized = main_window.is_minimized().unwrap_or(false);
+        if visible && !minimized {
+            let _ = main_window.hide();
+        } else {
+            show_main_window(app);
+        }
+    }
+}
@@ -6,16 +6,32 @@
 //! - `Variable`: Represents a key-value pair for variable substitution in Markdown
 //! - `VariableSet`: Container for multiple variables, used for YAML serialization
 //! - `FileHashInfo`: Contains file metadata including hash, modification time, and size
-//! - `OpenFileEvent`: Event payload for file association handling
+//! - `DirEntry`: Entry of the folder tree
+//!
+//! ## Events
+//! Every event the backend emits has a payload struct here, implementing `EventPayload`
+//! with the event's name. `emit_event` sends the payload's fields together with
+//! `version` (`EVENT_SCHEMA_VERSION`), so the frontend can tell payload layouts apart;
+//! the version is raised whenever a payload changes incompatibly. Payloads that are also
+//! returned by commands (e.g. `SyncStatus`) are flattened into their event struct.
 //!
 //! ## Global State
-//! - `PENDING_FILE_PATHS`: Buffers file paths received before frontend is ready
-//! - `FRONTEND_READY`: Tracks whether the frontend is initialized and ready to receive events
+//! - `PENDING_FILE_PATHS`: Buffers file paths received before frontend is ready, together
+//!   with the flag that tracks whether the frontend is initialized and ready to receive
+//!   events (one lock, so a path cannot slip between the check and the flag update)
 
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
 use std::sync::OnceLock;
 
+use tauri::Emitter;
+
+use crate::external_tools::ToolStream;
+use crate::sync::SyncStatus;
+use crate::tasks::TaskInfo;
+use crate::updater::UpdateInfo;
+use crate::writing_stats::GoalProgress;
+
 // Variable definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Variable {
@@ -37,12 +53,210 @@ pub struct FileHashInfo {
     pub file_size: u64,
 }
 
+// Version of the event payload layout, sent with every event
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+// Payload of a backend event
+pub trait EventPayload: Serialize {
+    // Name the event is emitted under
+    fn event_name(&self) -> &'static str;
+}
+
+macro_rules! event_payloads {
+    ($($payload:ty => $name:literal),* $(,)?) => {
+        $(impl EventPayload for $payload {
+            fn event_name(&self) -> &'static str {
+                $name
+            }
+        })*
+    };
+}
+
+event_payloads! {
+    OpenFileEvent => "open-file",
+    FocusTabEvent => "focus-tab",
+    FileReloadedEvent => "file-reloaded",
+    FileStateChangedEvent => "file-state-changed",
+    EditorContextActionEvent => "editor-context-action",
+    ToolOutputEvent => "tool-output",
+    GitStatusChangedEvent => "git-status-changed",
+    SearchProgressEvent => "search-progress",
+    SyncStatusEvent => "sync-status",
+    TaskProgressEvent => "task-progress",
+    SystemThemeChangedEvent => "system-theme-changed",
+    UpdateAvailableEvent => "update-available",
+    UpdateProgressEvent => "update-progress",
+    UpdateInstalledEvent => "update-installed",
+    ConfirmCloseEvent => "confirm-close",
+    GoalProgressEvent => "goal-progress",
+    PreviewUpdatedEvent => "preview-updated",
+}
+
+// What is sent for an event: the payload's fields and the schema version
+#[derive(Serialize)]
+struct VersionedPayload<'a, P: EventPayload> {
+    version: u32,
+    #[serde(flatten)]
+    payload: &'a P,
+}
+
+// The JSON sent for an event payload
+pub fn event_json<P: EventPayload>(payload: &P) -> serde_json::Value {
+    let versioned = VersionedPayload {
+        version: EVENT_SCHEMA_VERSION,
+        payload,
+    };
+    serde_json::to_value(versioned).unwrap_or(serde_json::Value::Null)
+}
+
+// Emit an event to the window `target`, or to all windows
+pub fn emit_event<R: tauri::Runtime, P: EventPayload>(
+    emitter: &impl Emitter<R>,
+    target: Option<&str>,
+    payload: &P,
+) -> tauri::Result<()> {
+    let json = event_json(payload);
+    match target {
+        Some(label) => emitter.emit_to(label, payload.event_name(), json),
+        None => emitter.emit(payload.event_name(), json),
+    }
+}
+
 // File open event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenFileEvent {
     pub file_path: String,
 }
 
+// Event sent when the app was brought to the front by a notification about a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusTabEvent {
+    pub path: String,
+}
+
+// Event sent when a clean tab's file was changed on disk and read again. A proposal: the
+// frontend applies it only if the tab still has no unsaved edits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReloadedEvent {
+    pub path: String,
+    pub content: String,
+    pub hash: FileHashInfo,
+}
+
+// Event sent when a subscribed file changed on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStateChangedEvent {
+    pub path: String,
+    // False when the file was deleted or moved away
+    pub exists: bool,
+    pub hash: Option<FileHashInfo>,
+    // Whether the content differs, not only the modification time
+    pub content_changed: bool,
+}
+
+// Menu item event (`menu-new-file`, `menu-save`, ...); only the event name differs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MenuEvent {
+    #[serde(skip)]
+    pub event: &'static str,
+}
+
+impl EventPayload for MenuEvent {
+    fn event_name(&self) -> &'static str {
+        self.event
+    }
+}
+
+// Editor context menu action, sent to the window that opened the menu
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorContextActionEvent {
+    pub action: String,
+}
+
+// Line of output of an external tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolOutputEvent {
+    pub task_id: String,
+    pub stream: ToolStream,
+    pub line: String,
+}
+
+// The Git status of a repository may have changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStatusChangedEvent {
+    pub repository: String,
+}
+
+// Progress of a workspace search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchProgressEvent {
+    pub root: String,
+    pub files_searched: usize,
+    pub files_total: usize,
+    pub matches: usize,
+}
+
+// Workspace sync started or ended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncStatusEvent {
+    #[serde(flatten)]
+    pub status: SyncStatus,
+}
+
+// Progress of a running task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskProgressEvent {
+    #[serde(flatten)]
+    pub task: TaskInfo,
+}
+
+// The system appearance changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemThemeChangedEvent {
+    pub theme: String,
+}
+
+// The startup check found an update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateAvailableEvent {
+    #[serde(flatten)]
+    pub info: UpdateInfo,
+}
+
+// Download progress of an update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateProgressEvent {
+    pub downloaded: u64,
+    pub content_length: Option<u64>,
+}
+
+// An update was installed; the app restarts next
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateInstalledEvent {
+    #[serde(flatten)]
+    pub info: UpdateInfo,
+}
+
+// A window is about to close; the frontend confirms unsaved changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfirmCloseEvent {
+    pub window_label: String,
+}
+
+// New content for the preview window of a document (variables expanded)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewUpdatedEvent {
+    pub path: String,
+    pub markdown: String,
+}
+
+// Progress towards a word-count goal, after a revision of a document was recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgressEvent {
+    #[serde(flatten)]
+    pub progress: GoalProgress,
+}
+
 // Directory entry for folder tree
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DirEntry {
@@ -51,8 +265,35 @@ pub struct DirEntry {
     pub is_directory: bool,
 }
 
-// Global state for buffering file paths received before frontend is ready
-pub static PENDING_FILE_PATHS: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+// Most file paths kept while the frontend is not ready
+pub const MAX_PENDING_FILE_PATHS: usize = 100;
+
+// File paths received before the frontend is ready, in arrival order
+#[derive(Debug, Default)]
+pub struct PendingFiles {
+    pub paths: Vec<String>,
+    pub frontend_ready: bool,
+}
 
-// Check if frontend is ready
-pub static FRONTEND_READY: OnceLock<Mutex<bool>> = OnceLock::new();
\ No newline at end of file
+impl PendingFiles {
+    // Buffer a path. Paths the OS delivers again are kept once, at their first position;
+    // beyond `MAX_PENDING_FILE_PATHS` new paths are dropped. Returns false if dropped.
+    pub fn push(&mut self, path: String) -> bool {
+        if self.paths.contains(&path) {
+            return true;
+        }
+        if self.paths.len() >= MAX_PENDING_FILE_PATHS {
+            return false;
+        }
+        self.paths.push(path);
+        true
+    }
+
+    // Take all buffered paths
+    pub fn take(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.paths)
+    }
+}
+
+// Global state for buffering file paths received before frontend is ready
+pub static PENDING_FILE_PATHS: OnceLock<Mutex<PendingFiles>> = OnceLock::new();
\ No newline at end of file
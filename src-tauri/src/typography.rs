@@ -0,0 +1,92 @@
+//! # Typography Module
+//!
+//! This module applies smart typography to processed Markdown, as shown in the preview and
+//! exported: straight quotes become curly quotes, `--` and `---` become en and em dashes,
+//! and `...` becomes an ellipsis. The source document is never changed.
+//!
+//! ## Enabling
+//! The `smart_typography` setting turns it on for all documents; a document overrides the
+//! setting with `smart_typography: true` or `false` in its frontmatter.
+//!
+//! ## What Is Left Alone
+//! The frontmatter, fenced code blocks, inline code, link and image destinations, HTML
+//! tags and comments, and lines made only of dashes, pipes and colons (thematic breaks,
+//! setext underlines, table delimiter rows).
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashSet;
+
+use crate::frontmatter;
+use crate::markdown::lines_outside_code;
+
+// Frontmatter key that overrides the setting
+const FRONTMATTER_KEY: &str = "smart_typography";
+// Characters after which a quote opens
+const OPENING_CONTEXT: &str = "([{“‘—–-/";
+
+lazy_static! {
+    // Spans of a line left alone: inline code, link destinations, HTML tags and comments
+    static ref PROTECTED: Regex = Regex::new(r"`[^`]*`|\]\([^)]*\)|<[^>\s]+[^>]*>").unwrap();
+    static ref DELIMITER_LINE: Regex = Regex::new(r"^[\s|:+-]*-[\s|:+-]*$").unwrap();
+}
+
+// Whether smart typography applies to a document: its frontmatter decides, otherwise
+// `default` (the setting)
+pub fn is_enabled(content: &str, default: bool) -> bool {
+    frontmatter::parse_frontmatter(content)
+        .and_then(|value| value.get(FRONTMATTER_KEY).and_then(|v| v.as_bool()))
+        .unwrap_or(default)
+}
+
+// Dashes, ellipses and curly quotes in `text`; `previous` is the character before it
+fn smarten(text: &str, mut previous: Option<char>) -> String {
+    let text = text.replace("---", "—").replace("--", "–").replace("...", "…");
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        let opening = previous.is_none_or(|p| p.is_whitespace() || OPENING_CONTEXT.contains(p));
+        let c = match c {
+            '"' if opening => '“',
+            '"' => '”',
+            '\'' if opening => '‘',
+            '\'' => '’',
+            c => c,
+        };
+        output.push(c);
+        previous = Some(c);
+    }
+    output
+}
+
+// Smart typography of a line, outside its protected spans
+pub fn smarten_line(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut start = 0;
+    for span in PROTECTED.find_iter(line) {
+        output.push_str(&smarten(&line[start..span.start()], line[..start].chars().last()));
+        output.push_str(span.as_str());
+        start = span.end();
+    }
+    output.push_str(&smarten(&line[start..], line[..start].chars().last()));
+    output
+}
+
+// Smart typography of a Markdown document, outside its frontmatter and code
+pub fn smarten_markdown(content: &str) -> String {
+    let body = frontmatter::split_frontmatter(content).body;
+    let head = &content[..content.len() - body.len()];
+    let prose: HashSet<usize> = lines_outside_code(body).into_iter().map(|(index, _)| index).collect();
+    let body = body
+        .split('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            if prose.contains(&index) && !DELIMITER_LINE.is_match(line) {
+                smarten_line(line)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("{}{}", head, body)
+}
@@ -0,0 +1,251 @@
+//! # Updater Module
+//!
+//! This module drives the Tauri updater from the backend so every distribution (including
+//! AppImage and portable builds) can upgrade from inside the app.
+//!
+//! ## Flow
+//! 1. On startup (release builds only) a background check runs; if an update is found an
+//!    `update-available` event carrying the version and release notes is emitted
+//! 2. The frontend can also call `check_for_updates` at any time
+//! 3. `install_update` downloads the pending update, emitting `update-progress` events,
+//!    then emits `update-installed` and restarts the app
+//!
+//! The endpoint and signing key are configured under `plugins.updater` in `tauri.conf.json`.
+//!
+//! ## Manual Check
+//! `check_for_updates_manual` asks the GitHub releases API for the latest release instead
+//! of the updater endpoint. It installs nothing; it returns the version, changelog and a
+//! download link for this platform, so builds that cannot update themselves (or an
+//! updater endpoint that is down) still learn about new versions.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tracing::{debug, info, warn};
+
+use crate::http;
+use crate::shutdown;
+use crate::types::{emit_event, UpdateAvailableEvent, UpdateInstalledEvent, UpdateProgressEvent};
+
+// Update information sent to the frontend
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    pub available: bool,
+    pub current_version: String,
+    pub version: Option<String>,
+    // Release notes (Markdown)
+    pub notes: Option<String>,
+    pub date: Option<String>,
+    // Where the new version can be downloaded
+    pub download_url: Option<String>,
+}
+
+// Latest release, as returned by the GitHub releases API
+const RELEASES_API: &str = "https://api.github.com/repos/Bokuchi-Editor/bokuchi/releases/latest";
+
+// Installer suffixes of this platform, preferred first
+#[cfg(target_os = "linux")]
+const ASSET_SUFFIXES: &[&str] = &[".AppImage", ".deb", ".rpm"];
+#[cfg(target_os = "windows")]
+const ASSET_SUFFIXES: &[&str] = &["-setup.exe", ".msi"];
+#[cfg(target_os = "macos")]
+const ASSET_SUFFIXES: &[&str] = &[".dmg"];
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+const ASSET_SUFFIXES: &[&str] = &[];
+
+// Release of the GitHub releases API (only the fields used here)
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubRelease {
+    pub tag_name: String,
+    pub html_url: String,
+    pub body: Option<String>,
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+// Numeric parts of a version ("v1.2.3-beta.1" -> [1, 2, 3]) and whether it is a pre-release
+fn parse_version(version: &str) -> Option<(Vec<u64>, bool)> {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    let (release, pre) = match version.split_once('-') {
+        Some((release, _)) => (release, true),
+        None => (version, false),
+    };
+    let parts = release.split('.').map(|part| part.parse().ok()).collect::<Option<Vec<u64>>>()?;
+    Some((parts, pre))
+}
+
+// Whether `latest` is a newer version than `current`. A pre-release is older than the
+// release of the same version.
+pub fn is_newer_version(latest: &str, current: &str) -> bool {
+    let (Some((latest, latest_pre)), Some((current, current_pre))) = (parse_version(latest), parse_version(current))
+    else {
+        return false;
+    };
+    let len = latest.len().max(current.len());
+    let pad = |parts: Vec<u64>| parts.into_iter().chain(std::iter::repeat(0)).take(len).collect::<Vec<_>>();
+    match pad(latest).cmp(&pad(current)) {
+        std::cmp::Ordering::Equal => current_pre && !latest_pre,
+        ordering => ordering.is_gt(),
+    }
+}
+
+// Update information for a release from the GitHub releases API. The download link is
+// the installer for this platform, or the release page.
+pub fn release_update_info(release: &GithubRelease, current_version: &str, suffixes: &[&str]) -> UpdateInfo {
+    if !is_newer_version(&release.tag_name, current_version) {
+        return UpdateInfo {
+            available: false,
+            current_version: current_version.to_string(),
+            ..Default::default()
+        };
+    }
+    let installer = suffixes.iter().find_map(|suffix| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name.ends_with(suffix))
+            .map(|asset| asset.browser_download_url.clone())
+    });
+    UpdateInfo {
+        available: true,
+        current_version: current_version.to_string(),
+        version: Some(release.tag_name.trim_start_matches(['v', 'V']).to_string()),
+        notes: release.body.clone(),
+        date: release.published_at.clone(),
+        download_url: Some(installer.unwrap_or_else(|| release.html_url.clone())),
+    }
+}
+
+// Update found by the last check, kept for `install_update`
+static PENDING_UPDATE: OnceLock<Mutex<Option<Update>>> = OnceLock::new();
+
+fn pending_update_cell() -> &'static Mutex<Option<Update>> {
+    PENDING_UPDATE.get_or_init(|| Mutex::new(None))
+}
+
+fn update_info(update: &Update) -> UpdateInfo {
+    UpdateInfo {
+        available: true,
+        current_version: update.current_version.clone(),
+        version: Some(update.version.clone()),
+        notes: update.body.clone(),
+        date: update.date.map(|d| d.to_string()),
+        download_url: Some(update.download_url.to_string()),
+    }
+}
+
+// Check the update endpoint and remember the result
+async fn check(app_handle: &tauri::AppHandle) -> Result<UpdateInfo, String> {
+    let updater = app_handle
+        .updater()
+        .map_err(|e| format!("Failed to initialize updater: {}", e))?;
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    let info = match &update {
+        Some(update) => update_info(update),
+        None => UpdateInfo {
+            available: false,
+            current_version: app_handle.package_info().version.to_string(),
+            ..Default::default()
+        },
+    };
+    if let Ok(mut pending) = pending_update_cell().lock() {
+        *pending = update;
+    }
+    Ok(info)
+}
+
+// Run the startup update check in the background (skipped in debug builds)
+pub fn spawn_startup_check(app_handle: tauri::AppHandle) {
+    if cfg!(debug_assertions) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        match check(&app_handle).await {
+            Ok(info) if info.available => {
+                info!("Update available: {:?}", info.version);
+                let result = emit_event(&app_handle, None, &UpdateAvailableEvent { info });
+                debug!("Emitted update-available: {:?}", result);
+            }
+            Ok(_) => info!("No update available"),
+            Err(e) => warn!("Startup update check failed: {}", e),
+        }
+    });
+}
+
+// Tauri command: Check for updates
+#[tauri::command]
+pub async fn check_for_updates(app_handle: tauri::AppHandle) -> Result<UpdateInfo, String> {
+    check(&app_handle).await
+}
+
+// Tauri command: Check the GitHub releases for a newer version without installing it
+#[tauri::command]
+pub async fn check_for_updates_manual(app_handle: tauri::AppHandle) -> Result<UpdateInfo, String> {
+    let response = http::client()?
+        .get(RELEASES_API)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read the release information: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("Failed to check for updates: GitHub returned {}", status));
+    }
+    let release: GithubRelease =
+        serde_json::from_str(&text).map_err(|e| format!("Invalid release information: {}", e))?;
+
+    let current_version = app_handle.package_info().version.to_string();
+    let info = release_update_info(&release, &current_version, ASSET_SUFFIXES);
+    info!("Latest release is {} (current {})", release.tag_name, current_version);
+    Ok(info)
+}
+
+// Tauri command: Download and install the update found by the last check, then restart
+#[tauri::command]
+pub async fn install_update(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let update = pending_update_cell()
+        .lock()
+        .map_err(|_| "Failed to lock pending update".to_string())?
+        .clone()
+        .ok_or_else(|| "No update available. Call check_for_updates first.".to_string())?;
+
+    let mut downloaded: u64 = 0;
+    update
+        .download_and_install(
+            |chunk_length, content_length| {
+                downloaded += chunk_length as u64;
+                let event = UpdateProgressEvent {
+                    downloaded,
+                    content_length,
+                };
+                let _ = emit_event(&app_handle, None, &event);
+            },
+            || info!("Update download finished"),
+        )
+        .await
+        .map_err(|e| format!("Failed to install update: {}", e))?;
+
+    let _ = emit_event(&app_handle, None, &UpdateInstalledEvent { info: update_info(&update) });
+    info!("Update {} installed, restarting", update.version);
+
+    // `restart` does not go through `RunEvent::Exit`, so flush explicitly
+    shutdown::flush_backend_state("update");
+    app_handle.restart()
+}
@@ -0,0 +1,145 @@
+//! # Variable History Module
+//!
+//! This module keeps the previous values of global and workspace variables, so a value
+//! that was overwritten by mistake can be brought back. The history is stored in
+//! `variable_history.json` in the app data directory.
+//!
+//! ## Versions
+//! Each time a variable gets a different value (or a workspace variable is removed), the
+//! value it had is kept as a new version. Versions are numbered per variable from 1 and
+//! keep their number when old versions are dropped (beyond `MAX_VERSIONS`).
+//! Reverting to a version sets that value again, so the value it replaces becomes a
+//! version too and the revert can itself be undone.
+//!
+//! Only explicit changes are recorded (`set_global_variable`, `revert_variable` and the
+//! workspace variable commands). The globals the editor sends along with every preview
+//! update are set without a version, so typing a value does not push the real versions
+//! out of the history.
+//!
+//! ## Scopes
+//! Global variables and the variables of each workspace (see `workspace_variables`) have
+//! separate histories. Commands take the workspace root for workspace variables and no
+//! root for global ones.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::{info, warn};
+
+use crate::storage;
+use crate::variable_processor::VARIABLE_PROCESSOR;
+use crate::workspace_state;
+use crate::workspace_variables;
+
+const HISTORY_FILE: &str = "variable_history.json";
+const GLOBAL_SCOPE: &str = "global";
+// Versions kept for each variable
+pub const MAX_VERSIONS: usize = 50;
+
+// A previous value of a variable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariableVersion {
+    pub version: u32,
+    pub value: String,
+    // Milliseconds since the Unix epoch
+    pub replaced_at: u64,
+}
+
+// Versions by scope ("global" or the workspace key) and variable name, oldest first
+type History = HashMap<String, HashMap<String, Vec<VariableVersion>>>;
+
+static HISTORY: OnceLock<Mutex<History>> = OnceLock::new();
+
+fn history_cell() -> &'static Mutex<History> {
+    HISTORY.get_or_init(|| Mutex::new(storage::load_json(HISTORY_FILE)))
+}
+
+fn scope_key(root: Option<&str>) -> String {
+    root.map(workspace_state::workspace_key)
+        .unwrap_or_else(|| GLOBAL_SCOPE.to_string())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Add a replaced value to the versions of a variable, unless it is the latest version
+// already, and drop the oldest versions beyond `MAX_VERSIONS`
+pub fn push_version(versions: &mut Vec<VariableVersion>, value: String, replaced_at: u64) {
+    if versions.last().is_some_and(|last| last.value == value) {
+        return;
+    }
+    let version = versions.last().map_or(1, |last| last.version + 1);
+    versions.push(VariableVersion { version, value, replaced_at });
+    if versions.len() > MAX_VERSIONS {
+        versions.drain(..versions.len() - MAX_VERSIONS);
+    }
+}
+
+// Keep the value a variable had before it was changed or removed. The version is added
+// to the history on disk (under its lock), which then replaces the one in memory, so
+// versions recorded by a separate process (`--new-instance`) are kept.
+pub fn record(root: Option<&str>, name: &str, old_value: &str) -> Result<(), String> {
+    let mut history = history_cell()
+        .lock()
+        .map_err(|_| "Failed to lock variable history".to_string())?;
+    *history = storage::update_json(HISTORY_FILE, |stored: &mut History| {
+        let versions = stored
+            .entry(scope_key(root))
+            .or_default()
+            .entry(name.to_string())
+            .or_default();
+        push_version(versions, old_value.to_string(), now_millis());
+    })?;
+    Ok(())
+}
+
+// Set a global variable, keeping the value it replaces in the history
+pub fn set_global_variable(name: String, value: String) {
+    if let Some(old_value) = VARIABLE_PROCESSOR.get_global_variable(&name)
+        && old_value != value
+        && let Err(e) = record(None, &name, &old_value)
+    {
+        warn!("Failed to record the previous value of {}: {}", name, e);
+    }
+    VARIABLE_PROCESSOR.set_global_variable(name, value);
+}
+
+// Tauri command: Get the previous values of a global variable, or of a variable of the
+// workspace at `root`, newest first
+#[tauri::command]
+pub fn get_variable_history(name: String, root: Option<String>) -> Result<Vec<VariableVersion>, String> {
+    let history = history_cell()
+        .lock()
+        .map_err(|_| "Failed to lock variable history".to_string())?;
+    let mut versions = history
+        .get(&scope_key(root.as_deref()))
+        .and_then(|variables| variables.get(&name))
+        .cloned()
+        .unwrap_or_default();
+    versions.reverse();
+    Ok(versions)
+}
+
+// Tauri command: Set a global variable, or a variable of the workspace at `root`, back to
+// one of its previous values. Returns the value.
+#[tauri::command]
+pub fn revert_variable(name: String, version: u32, root: Option<String>) -> Result<String, String> {
+    let value = get_variable_history(name.clone(), root.clone())?
+        .into_iter()
+        .find(|v| v.version == version)
+        .map(|v| v.value)
+        .ok_or_else(|| format!("Version {} of {} not found", version, name))?;
+    match root {
+        Some(root) => workspace_variables::set_workspace_variable(root, name.clone(), value.clone())?,
+        None => set_global_variable(name.clone(), value.clone()),
+    }
+    info!("Reverted variable {} to version {}", name, version);
+    Ok(value)
+}
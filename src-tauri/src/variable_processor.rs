@@ -6,34 +6,207 @@
 //! - **Variable Definition**: Parse variables from Markdown comments (`<!-- @var name: value -->`)
 //! - **Variable Substitution**: Replace `{{variable}}` placeholders with actual values
 //! - **Global Variable Management**: Store and retrieve global variables across the application
+//! - **Workspace Variables**: A layer of variables of the open workspace above the global
+//!   ones (see `workspace_variables`)
 //! - **YAML Import/Export**: Load variables from YAML files and export current variables
+//! - **Required Variables**: A document declares the variables it needs
+//!   (`<!-- @require client_name, due_date -->`), so the frontend can ask for the missing
+//!   ones before showing a half-filled preview
 //!
 //! ## Usage
 //! The `VARIABLE_PROCESSOR` is a global singleton instance that can be used throughout the application
 //! to process Markdown content with variable substitution.
 //!
+//! ## Code
+//! Placeholders inside fenced code blocks and inline code spans are left as they are, so
+//! documentation about templating keeps its `{{examples}}`. `ProcessOptions` can force
+//! substitution there too.
+//!
+//! ## Definitions in the Output
+//! `ProcessOptions::var_comments` decides what happens to the `<!-- @var -->` definitions:
+//! they are stripped (default), kept as they are (so a processed template stays a
+//! template), or moved into a `variables` mapping of the frontmatter.
+//!
+//! ## Substitution Map
+//! `process_variables_with_map` also reports each substitution: the variable, where its
+//! value came from (and the line of its definition, for file variables), and the UTF-16
+//! ranges of the placeholder in the source and of the value in the output, so the preview
+//! can highlight substituted text and jump to the definition.
+//!
 //! ## Variable Priority
 //! 1. File-level variables (defined in `<!-- @var -->` comments)
-//! 2. Global variables (set via `set_global_variable`)
+//! 2. Context variables passed to `process_variables_with` (e.g. by snippet expansion)
+//! 3. Workspace variables (set via `set_workspace_variables`)
+//! 4. Global variables (set via `set_global_variable`)
 
 use anyhow::Result;
 use regex::Regex;
-use serde_yaml;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
 use std::collections::HashMap;
 use std::sync::Mutex;
 use lazy_static::lazy_static;
 
+use crate::frontmatter;
+use crate::markdown::{inline_code_ranges, lines_outside_code};
 use crate::types::{Variable, VariableSet};
 
+// Frontmatter key the definitions are moved to by `VarComments::Frontmatter`
+const FRONTMATTER_KEY: &str = "variables";
+
+// What happens to the `<!-- @var -->` definitions of a document when it is processed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VarComments {
+    #[default]
+    Strip,
+    Keep,
+    Frontmatter,
+}
+
+// Options of variable processing
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessOptions {
+    // Substitute placeholders inside fenced code blocks and inline code too
+    pub substitute_in_code: bool,
+    pub var_comments: VarComments,
+}
+
+// Where the value of a substituted variable came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableOrigin {
+    File,
+    Context,
+    Workspace,
+    Global,
+}
+
+// A placeholder replaced by the value of its variable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Substitution {
+    pub name: String,
+    pub origin: VariableOrigin,
+    // 1-based line of the `<!-- @var -->` definition, for file variables
+    pub definition_line: Option<usize>,
+    // Range of the placeholder in the source content
+    pub source_start: usize,
+    pub source_end: usize,
+    // Range of the value in the processed content
+    pub output_start: usize,
+    pub output_end: usize,
+}
+
+// Processed content with the substitutions made
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessedContent {
+    pub content: String,
+    pub substitutions: Vec<Substitution>,
+}
+
+// Kind of a line of a document, for the definitions
+enum LineKind {
+    // `<!-- @var name: value -->`; None if it has no `name:`
+    Definition(Option<Variable>),
+    // `<!-- @include: filename -->`, left over after `includes` resolved the others
+    Include,
+    // `<!-- @require name, name -->`
+    Require(Vec<String>),
+    Text,
+}
+
+fn classify_line(line: &str) -> LineKind {
+    const VAR_PREFIX: &str = "<!-- @var ";
+    const VAR_SUFFIX: &str = " -->";
+    const REQUIRE_PREFIX: &str = "<!-- @require ";
+
+    let trimmed = line.trim();
+
+    // Check for variable definition pattern. The length guard prevents
+    // a panic for inputs like `<!-- @var -->`, where the trailing space
+    // of the prefix and the leading space of the suffix are the same
+    // character — stripping the prefix would leave a string shorter
+    // than the suffix, so the previous `.unwrap()` chain crashed.
+    if trimmed.starts_with(VAR_PREFIX)
+        && trimmed.ends_with(VAR_SUFFIX)
+        && trimmed.len() >= VAR_PREFIX.len() + VAR_SUFFIX.len()
+    {
+        // <!-- @var name: value --> format
+        let var_content = &trimmed[VAR_PREFIX.len()..trimmed.len() - VAR_SUFFIX.len()];
+
+        LineKind::Definition(var_content.find(':').map(|colon_index| Variable {
+            name: var_content[..colon_index].trim().to_string(),
+            value: var_content[colon_index + 1..].trim().to_string(),
+        }))
+    } else if trimmed.starts_with("<!-- @include:") && trimmed.ends_with(" -->") {
+        LineKind::Include
+    } else if trimmed.starts_with(REQUIRE_PREFIX)
+        && trimmed.ends_with(VAR_SUFFIX)
+        && trimmed.len() >= REQUIRE_PREFIX.len() + VAR_SUFFIX.len()
+    {
+        let names = &trimmed[REQUIRE_PREFIX.len()..trimmed.len() - VAR_SUFFIX.len()];
+        LineKind::Require(
+            names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    } else {
+        LineKind::Text
+    }
+}
+
+// Convert byte offsets into `text`, in ascending order, into UTF-16 offsets
+fn to_utf16_offsets<'a>(text: &str, offsets: impl Iterator<Item = &'a mut usize>) {
+    let (mut byte, mut utf16) = (0, 0);
+    for offset in offsets {
+        utf16 += text[byte..*offset].encode_utf16().count();
+        byte = *offset;
+        *offset = utf16;
+    }
+}
+
+// Put variables into the `variables` mapping of a document's frontmatter, creating the
+// frontmatter if needed. Content whose frontmatter is not a YAML mapping is returned as is.
+fn definitions_to_frontmatter(content: &str, variables: &[Variable]) -> String {
+    if variables.is_empty() {
+        return content.to_string();
+    }
+    let document = frontmatter::split_frontmatter(content);
+    let mut mapping = match (document.frontmatter, frontmatter::parse_frontmatter(content)) {
+        (None, _) => Mapping::new(),
+        (Some(_), Some(Value::Mapping(mapping))) => mapping,
+        (Some(_), _) => return content.to_string(),
+    };
+    let mut definitions = match mapping.remove(FRONTMATTER_KEY) {
+        Some(Value::Mapping(existing)) => existing,
+        _ => Mapping::new(),
+    };
+    for v in variables {
+        definitions.insert(Value::String(v.name.clone()), Value::String(v.value.clone()));
+    }
+    mapping.insert(Value::String(FRONTMATTER_KEY.to_string()), Value::Mapping(definitions));
+    match serde_yaml::to_string(&mapping) {
+        Ok(yaml) => format!("---\n{}---\n{}", yaml, document.body),
+        Err(_) => content.to_string(),
+    }
+}
+
 // Variable processor
 pub struct VariableProcessor {
     global_variables: Mutex<HashMap<String, String>>,
+    // Variables of the open workspace, overriding global ones
+    workspace_variables: Mutex<HashMap<String, String>>,
 }
 
 impl VariableProcessor {
     pub fn new() -> Self {
         Self {
             global_variables: Mutex::new(HashMap::new()),
+            workspace_variables: Mutex::new(HashMap::new()),
         }
     }
 
@@ -55,77 +228,251 @@ impl VariableProcessor {
         vars.clone()
     }
 
+    // Replace the variables of the workspace layer
+    pub fn set_workspace_variables(&self, variables: HashMap<String, String>) {
+        let mut vars = self.workspace_variables.lock().unwrap();
+        *vars = variables;
+    }
+
+    // Get all variables of the workspace layer
+    pub fn get_workspace_variables(&self) -> HashMap<String, String> {
+        let vars = self.workspace_variables.lock().unwrap();
+        vars.clone()
+    }
+
+    // Get a variable of the workspace layer, or else a global variable
+    pub fn get_variable(&self, name: &str) -> Option<String> {
+        let workspace = self.workspace_variables.lock().unwrap().get(name).cloned();
+        workspace.or_else(|| self.get_global_variable(name))
+    }
+
+    // Get all global variables, overridden by the workspace layer
+    pub fn get_all_variables(&self) -> HashMap<String, String> {
+        let mut vars = self.get_all_global_variables();
+        vars.extend(self.get_workspace_variables());
+        vars
+    }
+
     // Extract variable definitions from Markdown
     pub fn parse_variables_from_markdown(&self, content: &str) -> (Vec<Variable>, String) {
         let mut variables = Vec::new();
-        let lines: Vec<&str> = content.lines().collect();
         let mut processed_lines = Vec::new();
 
-        const VAR_PREFIX: &str = "<!-- @var ";
-        const VAR_SUFFIX: &str = " -->";
-
-        for line in lines {
-            let trimmed = line.trim();
+        for line in content.lines() {
+            match classify_line(line) {
+                LineKind::Definition(variable) => variables.extend(variable),
+                // Skipped
+                LineKind::Include | LineKind::Require(_) => {}
+                LineKind::Text => processed_lines.push(line),
+            }
+        }
 
-            // Check for variable definition pattern. The length guard prevents
-            // a panic for inputs like `<!-- @var -->`, where the trailing space
-            // of the prefix and the leading space of the suffix are the same
-            // character — stripping the prefix would leave a string shorter
-            // than the suffix, so the previous `.unwrap()` chain crashed.
-            if trimmed.starts_with(VAR_PREFIX)
-                && trimmed.ends_with(VAR_SUFFIX)
-                && trimmed.len() >= VAR_PREFIX.len() + VAR_SUFFIX.len()
-            {
-                // <!-- @var name: value --> format
-                let var_content = &trimmed[VAR_PREFIX.len()..trimmed.len() - VAR_SUFFIX.len()];
+        (variables, processed_lines.join("\n"))
+    }
 
-                if let Some(colon_index) = var_content.find(':') {
-                    let name = var_content[..colon_index].trim().to_string();
-                    let value = var_content[colon_index + 1..].trim().to_string();
-                    variables.push(Variable { name, value });
+    // Variables declared with `<!-- @require -->`, in order of first declaration
+    pub fn required_variables(&self, content: &str) -> Vec<String> {
+        let mut required: Vec<String> = Vec::new();
+        for line in content.lines() {
+            if let LineKind::Require(names) = classify_line(line) {
+                for name in names {
+                    if !required.contains(&name) {
+                        required.push(name);
+                    }
                 }
-            } else if trimmed.starts_with("<!-- @include:") && trimmed.ends_with(" -->") {
-                // <!-- @include: filename --> format (future implementation)
-                // Currently skipped
-            } else {
-                processed_lines.push(line);
             }
         }
+        required
+    }
 
-        (variables, processed_lines.join("\n"))
+    // Required variables that have no value (or an empty one) in the document, `context`,
+    // the workspace or the global variables
+    pub fn unmet_requirements(&self, content: &str, context: &HashMap<String, String>) -> Vec<String> {
+        let (file_variables, _) = self.parse_variables_from_markdown(content);
+        self.required_variables(content)
+            .into_iter()
+            .filter(|name| {
+                let value = file_variables
+                    .iter()
+                    .find(|v| &v.name == name)
+                    .map(|v| v.value.clone())
+                    .or_else(|| context.get(name).cloned())
+                    .or_else(|| self.get_variable(name));
+                value.is_none_or(|value| value.trim().is_empty())
+            })
+            .collect()
     }
 
     // Expand variables in Markdown content
     pub fn process_variables(&self, content: &str) -> String {
-        // Extract variable definitions from file
-        let (file_variables, processed_content) = self.parse_variables_from_markdown(content);
+        self.process_variables_with(content, &HashMap::new())
+    }
+
+    // Expand variables, with `context` variables (e.g. of a snippet expansion) taking
+    // precedence over global ones
+    pub fn process_variables_with(&self, content: &str, context: &HashMap<String, String>) -> String {
+        self.process_variables_with_options(content, context, &ProcessOptions::default())
+    }
+
+    // Expand variables as `process_variables_with` does, with processing options
+    pub fn process_variables_with_options(
+        &self,
+        content: &str,
+        context: &HashMap<String, String>,
+        options: &ProcessOptions,
+    ) -> String {
+        self.expand_variables(content, context, options).0
+    }
+
+    // Expand variables as `process_variables_with_options` does and report where each
+    // substitution was made, with UTF-16 ranges
+    pub fn process_variables_with_map(
+        &self,
+        content: &str,
+        context: &HashMap<String, String>,
+        options: &ProcessOptions,
+    ) -> ProcessedContent {
+        let (processed, mut substitutions) = self.expand_variables(content, context, options);
+        to_utf16_offsets(content, substitutions.iter_mut().flat_map(|s| [&mut s.source_start, &mut s.source_end]));
+        to_utf16_offsets(&processed, substitutions.iter_mut().flat_map(|s| [&mut s.output_start, &mut s.output_end]));
+        ProcessedContent { content: processed, substitutions }
+    }
+
+    // Expand variables, returning the substitutions with byte ranges
+    fn expand_variables(
+        &self,
+        content: &str,
+        context: &HashMap<String, String>,
+        options: &ProcessOptions,
+    ) -> (String, Vec<Substitution>) {
+        let keep_definitions = options.var_comments == VarComments::Keep;
+
+        // Extract variable definitions from file, with the lines they are defined on, and
+        // the lines that remain in the output
+        let mut file_variables = Vec::new();
+        let mut definition_lines = HashMap::new();
+        let mut kept_lines = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            match classify_line(line) {
+                LineKind::Definition(variable) => {
+                    if let Some(v) = variable {
+                        definition_lines.insert(v.name.clone(), index + 1);
+                        file_variables.push(v);
+                    }
+                    if keep_definitions {
+                        kept_lines.push((index, line));
+                    }
+                }
+                LineKind::Require(_) if keep_definitions => kept_lines.push((index, line)),
+                LineKind::Include | LineKind::Require(_) => {}
+                LineKind::Text => kept_lines.push((index, line)),
+            }
+        }
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(offset, _)| offset + 1))
+            .collect();
 
         // Convert file variables to map
         let mut file_var_map = HashMap::new();
-        for v in file_variables {
-            file_var_map.insert(v.name, v.value);
+        for v in &file_variables {
+            file_var_map.insert(v.name.clone(), v.value.clone());
         }
 
         // Regular expression for variable expansion
         let re = Regex::new(r"\{\{([^}]+)\}\}").unwrap();
 
-        // Expand variables
-        let result = re.replace_all(&processed_content, |caps: &regex::Captures| {
-            let var_name = caps.get(1).unwrap().as_str().trim();
+        // Expand variables of `text` (found at `source_start` of the content) into `output`
+        let expand = |text: &str, source_start: usize, output: &mut String, substitutions: &mut Vec<Substitution>| {
+            let mut last = 0;
+            for caps in re.captures_iter(text) {
+                let placeholder = caps.get(0).unwrap();
+                let var_name = caps.get(1).unwrap().as_str().trim();
 
-            // Prioritize file variables, then global variables
-            if let Some(value) = file_var_map.get(var_name) {
-                return value.clone();
-            }
-            if let Some(value) = self.get_global_variable(var_name) {
-                return value;
+                // Prioritize file variables, then context variables, then workspace and
+                // global variables
+                let found = if let Some(value) = file_var_map.get(var_name) {
+                    Some((value.clone(), VariableOrigin::File))
+                } else if let Some(value) = context.get(var_name) {
+                    Some((value.clone(), VariableOrigin::Context))
+                } else if let Some(value) = self.workspace_variables.lock().unwrap().get(var_name) {
+                    Some((value.clone(), VariableOrigin::Workspace))
+                } else {
+                    self.get_global_variable(var_name).map(|value| (value, VariableOrigin::Global))
+                };
+
+                // Keep the original string if variable not found
+                let Some((value, origin)) = found else {
+                    continue;
+                };
+                output.push_str(&text[last..placeholder.start()]);
+                let output_start = output.len();
+                output.push_str(&value);
+                substitutions.push(Substitution {
+                    name: var_name.to_string(),
+                    origin,
+                    definition_line: match origin {
+                        VariableOrigin::File => definition_lines.get(var_name).copied(),
+                        _ => None,
+                    },
+                    source_start: source_start + placeholder.start(),
+                    source_end: source_start + placeholder.end(),
+                    output_start,
+                    output_end: output.len(),
+                });
+                last = placeholder.end();
             }
+            output.push_str(&text[last..]);
+        };
 
-            // Return original string if variable not found
-            caps[0].to_string()
-        });
+        // Only prose is expanded unless substitution in code is forced: lines outside
+        // fences, between inline code spans. Kept definitions are never expanded.
+        let processed_content = kept_lines.iter().map(|(_, line)| *line).collect::<Vec<_>>().join("\n");
+        let prose: Vec<usize> = lines_outside_code(&processed_content)
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect();
+        let mut expanded = String::with_capacity(processed_content.len());
+        let mut substitutions = Vec::new();
+        for (index, (source_index, line)) in kept_lines.iter().enumerate() {
+            if index > 0 {
+                expanded.push('\n');
+            }
+            let source_start = line_starts[*source_index];
+            if (keep_definitions && matches!(classify_line(line), LineKind::Definition(_)))
+                || (!options.substitute_in_code && prose.binary_search(&index).is_err())
+            {
+                expanded.push_str(line);
+                continue;
+            }
+            if options.substitute_in_code {
+                expand(line, source_start, &mut expanded, &mut substitutions);
+                continue;
+            }
+            let mut start = 0;
+            for code in inline_code_ranges(line) {
+                expand(&line[start..code.start], source_start + start, &mut expanded, &mut substitutions);
+                expanded.push_str(&line[code.clone()]);
+                start = code.end;
+            }
+            expand(&line[start..], source_start + start, &mut expanded, &mut substitutions);
+        }
 
-        result.to_string()
+        if options.var_comments != VarComments::Frontmatter {
+            return (expanded, substitutions);
+        }
+        // The frontmatter is rewritten: substitutions in it are dropped, the ones in the
+        // body move with it
+        let result = definitions_to_frontmatter(&expanded, &file_variables);
+        if result != expanded {
+            let body_len = frontmatter::split_frontmatter(&expanded).body.len();
+            let (old_body, new_body) = (expanded.len() - body_len, result.len() - body_len);
+            substitutions.retain(|s| s.output_start >= old_body);
+            for s in &mut substitutions {
+                s.output_start = s.output_start - old_body + new_body;
+                s.output_end = s.output_end - old_body + new_body;
+            }
+        }
+        (result, substitutions)
     }
 
     // Load variables from YAML file
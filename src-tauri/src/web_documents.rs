@@ -0,0 +1,108 @@
+//! # Web Documents Module
+//!
+//! This module opens Markdown files published on the web ("Open from URL…"), such as
+//! raw GitHub files or pages of an internal wiki. They are opened as read-only buffers;
+//! saving one means saving a local copy.
+//!
+//! ## Fetching
+//! Only `http` and `https` URLs are accepted. Files over 10 MiB are rejected. Links to
+//! the GitHub and GitLab file viewers (`.../blob/...`) are fetched from their raw form,
+//! as the viewer is an HTML page.
+//!
+//! ## Caching
+//! Documents are kept in the `documents` folder of the HTTP cache (see `http_cache`).
+//! Once the lifetime the server gave them has passed, they are revalidated with their
+//! ETag, so reopening an unchanged file transfers nothing; a file opened before is still
+//! available offline.
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use tracing::info;
+
+use crate::http_cache;
+use crate::link_metadata;
+
+const CACHE_NAMESPACE: &str = "documents";
+const MAX_DOCUMENT_SIZE: usize = 10 * 1024 * 1024;
+
+// Result of `read_url`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebDocument {
+    // The URL the content was fetched from
+    pub url: String,
+    pub content: String,
+    pub etag: Option<String>,
+    // Always true: web documents cannot be saved back
+    pub read_only: bool,
+    // The cached copy was used (still fresh, not modified, or the server could not be
+    // reached)
+    pub from_cache: bool,
+    // The server could not be reached, so the content may be outdated
+    pub stale: bool,
+}
+
+// The raw file behind a link to the GitHub or GitLab file viewer; other URLs unchanged
+pub fn raw_url(url: &Url) -> Url {
+    let segments: Vec<&str> = url.path_segments().map(|s| s.collect()).unwrap_or_default();
+    let raw = match url.host_str() {
+        Some("github.com") if segments.len() > 4 && segments[2] == "blob" => {
+            let path = [&segments[..2], &segments[3..]].concat().join("/");
+            Url::parse(&format!("https://raw.githubusercontent.com/{}", path)).ok()
+        }
+        Some(_) => match segments.iter().position(|s| *s == "-") {
+            Some(dash) if segments.get(dash + 1) == Some(&"blob") => {
+                let mut raw = url.clone();
+                let mut path = segments.clone();
+                path[dash + 1] = "raw";
+                raw.set_path(&path.join("/"));
+                Some(raw)
+            }
+            _ => None,
+        },
+        None => None,
+    };
+    raw.unwrap_or_else(|| url.clone())
+}
+
+// Decode a downloaded document as text
+pub fn decode_document(bytes: &[u8], content_type: &str, url: &Url) -> Result<String, String> {
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    if mime == "text/html" || mime == "application/xhtml+xml" {
+        return Err(format!("{} is a web page, not a Markdown file", url));
+    }
+    let charset = content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("charset="))
+        .map(|charset| charset.trim_matches('"'))
+        .and_then(|charset| encoding_rs::Encoding::for_label(charset.as_bytes()))
+        .filter(|encoding| *encoding != encoding_rs::UTF_8);
+    let text = match charset {
+        Some(encoding) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+        None => String::from_utf8(bytes.to_vec()).map_err(|_| format!("{} is not a text file", url))?,
+    };
+    Ok(text.strip_prefix('\u{feff}').map(str::to_string).unwrap_or(text))
+}
+
+// Tauri command: Fetch a Markdown file from the web to open it as a read-only document
+#[tauri::command]
+pub async fn read_url(url: String) -> Result<WebDocument, String> {
+    let parsed = raw_url(&link_metadata::parse_web_url(&url)?);
+    // Any type: `decode_document` rejects what is not text
+    let response = http_cache::fetch(CACHE_NAMESPACE, &parsed, MAX_DOCUMENT_SIZE, |_| true).await?;
+    let content = decode_document(&response.bytes, &response.entry.content_type, &parsed)?;
+    info!(
+        "Read {} ({} bytes{})",
+        parsed,
+        response.bytes.len(),
+        if response.stale { ", offline copy" } else { "" }
+    );
+    Ok(WebDocument {
+        url: parsed.to_string(),
+        content,
+        etag: response.entry.etag,
+        read_only: true,
+        from_cache: response.from_cache,
+        stale: response.stale,
+    })
+}
@@ -0,0 +1,290 @@
+//! # WebDAV Module
+//!
+//! This module is the WebDAV backend of the remote-files subsystem (`remote`).
+//!
+//! ## Requests
+//! - List: `PROPFIND` with `Depth: 1`, parsing the `multistatus` response
+//! - Read: `GET`, keeping the `ETag` response header
+//! - Save: `PUT` with `If-Match: <etag>` when the version that was read is known. A
+//!   `412 Precondition Failed` answer means the file changed on the server.
+//!
+//! Requests use HTTP basic authentication when the endpoint has a username. Servers
+//! that do not return an ETag for `PUT` are asked with a `HEAD` request afterwards.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MATCH};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use url::Url;
+
+use crate::http;
+use crate::links::percent_decode;
+use crate::remote::{RemoteDocument, RemoteEntry, RemoteSaveResult};
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:resourcetype/>
+    <d:getcontentlength/>
+    <d:getlastmodified/>
+    <d:getetag/>
+  </d:prop>
+</d:propfind>"#;
+
+// A `response` element of a PROPFIND answer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropfindEntry {
+    pub href: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    pub modified: Option<String>,
+    pub etag: Option<String>,
+}
+
+// Parse a WebDAV `multistatus` document (namespace prefixes are ignored)
+pub fn parse_multistatus(xml: &str) -> Result<Vec<PropfindEntry>, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut entries = Vec::new();
+    let mut current: Option<PropfindEntry> = None;
+    let mut element = String::new();
+    let mut text = String::new();
+    let mut in_resourcetype = false;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("Invalid WebDAV response: {}", e))?;
+        match event {
+            Event::Start(start) | Event::Empty(start) => {
+                let name = String::from_utf8_lossy(start.local_name().as_ref()).to_string();
+                match name.as_str() {
+                    "response" => current = Some(PropfindEntry::default()),
+                    "resourcetype" => in_resourcetype = true,
+                    "collection" if in_resourcetype => {
+                        if let Some(entry) = current.as_mut() {
+                            entry.is_dir = true;
+                        }
+                    }
+                    _ => {}
+                }
+                element = name;
+                text.clear();
+            }
+            Event::Text(content) => {
+                text.push_str(&content.decode().map_err(|e| format!("Invalid WebDAV response: {}", e))?);
+            }
+            Event::GeneralRef(reference) => {
+                if let Ok(Some(c)) = reference.resolve_char_ref() {
+                    text.push(c);
+                } else if let Some(resolved) =
+                    quick_xml::escape::resolve_predefined_entity(&String::from_utf8_lossy(&reference))
+                {
+                    text.push_str(resolved);
+                }
+            }
+            Event::End(end) => {
+                let name = String::from_utf8_lossy(end.local_name().as_ref()).to_string();
+                if let Some(entry) = current.as_mut()
+                    && name == element
+                {
+                    let value = text.trim().to_string();
+                    match name.as_str() {
+                        "href" => entry.href = value,
+                        "getcontentlength" => entry.size = value.parse().ok(),
+                        "getlastmodified" if !value.is_empty() => entry.modified = Some(value),
+                        "getetag" if !value.is_empty() => entry.etag = Some(value),
+                        _ => {}
+                    }
+                }
+                match name.as_str() {
+                    "response" => entries.extend(current.take()),
+                    "resourcetype" => in_resourcetype = false,
+                    _ => {}
+                }
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+// Client of one WebDAV endpoint
+pub struct WebDavClient {
+    base: Url,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl WebDavClient {
+    pub fn new(url: &str, username: Option<String>, password: Option<String>) -> Result<Self, String> {
+        let mut base = Url::parse(url.trim()).map_err(|e| format!("Invalid WebDAV URL: {}", e))?;
+        if !matches!(base.scheme(), "http" | "https") {
+            return Err("WebDAV URL must start with http:// or https://".to_string());
+        }
+        // The base is a folder: make relative joins stay below it
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        Ok(Self {
+            base,
+            username: username.filter(|u| !u.is_empty()),
+            password,
+        })
+    }
+
+    // URL of a path relative to the endpoint (folders end with '/')
+    pub fn resource_url(&self, path: &str, is_dir: bool) -> Url {
+        let mut url = self.base.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty();
+            segments.extend(path.split('/').filter(|part| !part.is_empty()));
+            if is_dir {
+                segments.push("");
+            }
+        }
+        url
+    }
+
+    // Path of an href relative to the endpoint (None if it lies outside of it)
+    pub fn relative_path(&self, href: &str) -> Option<String> {
+        let url = self.base.join(href).ok()?;
+        let base_path = percent_decode(self.base.path());
+        let path = percent_decode(url.path());
+        path.strip_prefix(&base_path)
+            .map(|relative| relative.trim_matches('/').to_string())
+    }
+
+    fn request(&self, method: Method, url: Url) -> Result<RequestBuilder, String> {
+        let request = http::client()?.request(method, url);
+        Ok(match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_deref()),
+            None => request,
+        })
+    }
+
+    async fn send(request: RequestBuilder) -> Result<Response, String> {
+        request
+            .send()
+            .await
+            .map_err(|e| format!("WebDAV request failed: {}", e))
+    }
+
+    fn etag_of(response: &Response) -> Option<String> {
+        response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    }
+
+    fn status_error(action: &str, status: StatusCode) -> String {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                format!("Failed to {}: access denied ({})", action, status)
+            }
+            StatusCode::NOT_FOUND => format!("Failed to {}: not found", action),
+            _ => format!("Failed to {}: server returned {}", action, status),
+        }
+    }
+
+    // List the entries of a folder
+    pub async fn list(&self, path: &str) -> Result<Vec<RemoteEntry>, String> {
+        let method = Method::from_bytes(b"PROPFIND").map_err(|e| e.to_string())?;
+        let request = self
+            .request(method, self.resource_url(path, true))?
+            .header("Depth", "1")
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY);
+        let response = Self::send(request).await?;
+        if response.status() != StatusCode::MULTI_STATUS {
+            return Err(Self::status_error("list folder", response.status()));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to list folder: {}", e))?;
+
+        let mut entries = Vec::new();
+        for entry in parse_multistatus(&body)? {
+            let Some(relative) = self.relative_path(&entry.href) else {
+                continue;
+            };
+            // The listed folder itself
+            if relative == path {
+                continue;
+            }
+            let name = relative.rsplit('/').next().unwrap_or(&relative).to_string();
+            // Hidden entries are skipped, like in the local folder tree
+            if name.is_empty() || name.starts_with('.') {
+                continue;
+            }
+            entries.push(RemoteEntry {
+                path: relative,
+                name,
+                is_dir: entry.is_dir,
+                size: entry.size,
+                modified: entry.modified,
+                etag: entry.etag,
+            });
+        }
+        Ok(entries)
+    }
+
+    // Read a document
+    pub async fn read(&self, path: &str) -> Result<RemoteDocument, String> {
+        let response = Self::send(self.request(Method::GET, self.resource_url(path, false))?).await?;
+        if !response.status().is_success() {
+            return Err(Self::status_error("read file", response.status()));
+        }
+        let etag = Self::etag_of(&response);
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let content = String::from_utf8(bytes.to_vec()).map_err(|_| "Remote file is not a text file".to_string())?;
+        Ok(RemoteDocument {
+            path: path.to_string(),
+            content,
+            etag,
+        })
+    }
+
+    // Save a document, only if it is still at version `etag` (when given)
+    pub async fn save(&self, path: &str, content: String, etag: Option<&str>) -> Result<RemoteSaveResult, String> {
+        let url = self.resource_url(path, false);
+        let mut request = self
+            .request(Method::PUT, url.clone())?
+            .header(CONTENT_TYPE, "text/markdown; charset=utf-8")
+            .body(content);
+        if let Some(etag) = etag {
+            request = request.header(IF_MATCH, etag);
+        }
+        let response = Self::send(request).await?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => {
+                return Ok(RemoteSaveResult {
+                    conflict: true,
+                    ..Default::default()
+                });
+            }
+            status if !status.is_success() => return Err(Self::status_error("save file", status)),
+            _ => {}
+        }
+
+        let etag = match Self::etag_of(&response) {
+            Some(etag) => Some(etag),
+            None => {
+                let head = Self::send(self.request(Method::HEAD, url)?).await?;
+                Self::etag_of(&head)
+            }
+        };
+        Ok(RemoteSaveResult {
+            saved: true,
+            conflict: false,
+            etag,
+        })
+    }
+}
@@ -0,0 +1,243 @@
+//! # Wiki-Links Module
+//!
+//! This module implements `[[Note Title]]` links on top of the link index (`links`).
+//!
+//! ## Syntax
+//! - `[[Target]]`, `[[Target#Heading]]`, `[[Target|shown text]]`
+//! - `![[Target]]` embeds the target (e.g. an image)
+//!
+//! ## Resolution
+//! A target resolves to a workspace file by name (`[[notes/Plan]]` → `notes/Plan.md`),
+//! then by a loose name match (`[[Project Plan]]` → `project-plan.md`), then by note ID
+//! (`[[202410161230]]` → `202410161230 Plan.md`), then by document title (frontmatter
+//! `title` or first heading). See `links::LinkTargets`.
+//!
+//! ## Flows
+//! - Click-to-open: `resolve_wikilink` returns the file, or where it would be created
+//! - Create if missing: `create_wikilink_note` creates that file with a heading
+//! - Export: `convert_wikilinks_for_export` turns wiki-links into standard Markdown links
+//!   relative to the exported document; unresolved ones become plain text
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use tracing::info;
+
+use crate::links::{self, LinkTargets};
+use crate::markdown;
+use crate::path_scope;
+
+lazy_static! {
+    static ref WIKI_LINK: Regex = Regex::new(r"(!?)\[\[([^\]|#]*)(?:#([^\]|]*))?(?:\|([^\]]*))?\]\]").unwrap();
+}
+
+// Parsed wiki-link
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WikiLink {
+    pub target: String,
+    pub heading: Option<String>,
+    pub alias: Option<String>,
+    pub embed: bool,
+}
+
+// Result of resolving a wiki-link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WikiLinkResolution {
+    pub link: WikiLink,
+    // Resolved file (None if no document matches)
+    pub path: Option<String>,
+    // Anchor of the heading within the file (e.g. "next-steps")
+    pub anchor: Option<String>,
+    // Where the note is created if it does not exist
+    pub suggested_path: String,
+}
+
+fn non_empty(value: Option<regex::Match<'_>>) -> Option<String> {
+    value.map(|m| m.as_str().trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn wikilink_from_captures(caps: &Captures<'_>) -> WikiLink {
+    WikiLink {
+        target: caps[2].trim().to_string(),
+        heading: non_empty(caps.get(3)),
+        alias: non_empty(caps.get(4)),
+        embed: !caps[1].is_empty(),
+    }
+}
+
+// Parse a wiki-link, with or without the surrounding brackets
+pub fn parse_wikilink(text: &str) -> Option<WikiLink> {
+    let text = text.trim();
+    let bracketed = if text.contains("[[") {
+        text.to_string()
+    } else {
+        format!("[[{}]]", text)
+    };
+    WIKI_LINK
+        .captures(&bracketed)
+        .map(|caps| wikilink_from_captures(&caps))
+        .filter(|link| !link.target.is_empty())
+}
+
+// Make a wiki-link target usable as a file name (folders are kept)
+fn sanitize_target(target: &str) -> String {
+    target
+        .split('/')
+        .map(|part| {
+            part.chars()
+                .map(|c| if matches!(c, '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '-' } else { c })
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .filter(|part| !part.is_empty() && part != "." && part != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Path of the note to create for an unresolved target: next to the linking document, or
+// relative to the workspace root when the target names folders
+pub fn suggested_note_path(root: &Path, source: Option<&Path>, target: &str) -> PathBuf {
+    let name = sanitize_target(target);
+    let base = match source.and_then(Path::parent) {
+        Some(dir) if !name.contains('/') && dir.starts_with(root) => dir.to_path_buf(),
+        _ => root.to_path_buf(),
+    };
+    let path = base.join(&name);
+    if crate::workspace::is_document_path(&path) {
+        path
+    } else {
+        base.join(format!("{}.md", name))
+    }
+}
+
+// Path of `to` relative to the directory `from_dir`, with '/' separators
+pub fn relative_link_path(from_dir: &Path, to: &Path) -> String {
+    let from_dir = links::normalize_path(from_dir);
+    let to = links::normalize_path(to);
+    let from: Vec<Component> = from_dir.components().collect();
+    let to_components: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to_components).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        to_components[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
+
+// Resolve a wiki-link against the link targets of a workspace
+pub fn resolve(root: &Path, source: Option<&Path>, link: WikiLink, targets: &LinkTargets) -> WikiLinkResolution {
+    WikiLinkResolution {
+        path: targets.resolve_wikilink(root, &link.target),
+        anchor: link.heading.as_deref().map(markdown::slugify_heading),
+        suggested_path: suggested_note_path(root, source, &link.target)
+            .to_string_lossy()
+            .to_string(),
+        link,
+    }
+}
+
+// Replace the wiki-links of a document with standard Markdown links
+pub fn convert_wikilinks(content: &str, root: &Path, source: &Path, targets: &LinkTargets) -> String {
+    let source_dir = source.parent().unwrap_or(root);
+    let code_free: std::collections::HashSet<usize> = markdown::lines_outside_code(content)
+        .into_iter()
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut output = String::with_capacity(content.len());
+    for (index, raw_line) in content.split_inclusive('\n').enumerate() {
+        if !code_free.contains(&index) || !raw_line.contains("[[") {
+            output.push_str(raw_line);
+            continue;
+        }
+        let converted = WIKI_LINK.replace_all(raw_line, |caps: &Captures<'_>| {
+            let link = wikilink_from_captures(caps);
+            let text = link.alias.clone().unwrap_or_else(|| link.target.clone());
+            let Some(path) = targets.resolve_wikilink(root, &link.target) else {
+                return text;
+            };
+            let mut href = relative_link_path(source_dir, Path::new(&path));
+            if let Some(heading) = &link.heading {
+                href = format!("{}#{}", href, markdown::slugify_heading(heading));
+            }
+            if href.contains(' ') {
+                href = format!("<{}>", href);
+            }
+            let prefix = if link.embed { "!" } else { "" };
+            format!("{}[{}]({})", prefix, text, href)
+        });
+        output.push_str(&converted);
+    }
+    output
+}
+
+fn link_targets(root: &Path) -> Result<LinkTargets, String> {
+    links::with_refreshed_links(root, |cache| LinkTargets::new(cache.iter()))
+}
+
+// Tauri command: Resolve a wiki-link clicked in `source_path`
+#[tauri::command]
+pub async fn resolve_wikilink(
+    root: String,
+    link: String,
+    source_path: Option<String>,
+) -> Result<WikiLinkResolution, String> {
+    let parsed = parse_wikilink(&link).ok_or_else(|| format!("Invalid wiki-link: {}", link))?;
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        let targets = link_targets(root)?;
+        Ok(resolve(root, source_path.as_deref().map(Path::new), parsed, &targets))
+    })
+    .await
+    .map_err(|e| format!("Failed to resolve wiki-link: {}", e))?
+}
+
+// Tauri command: Create the note a wiki-link points to (returns the existing file if the
+// link already resolves)
+#[tauri::command]
+pub async fn create_wikilink_note(root: String, link: String, source_path: Option<String>) -> Result<String, String> {
+    let parsed = parse_wikilink(&link).ok_or_else(|| format!("Invalid wiki-link: {}", link))?;
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        let targets = link_targets(root)?;
+        let resolution = resolve(root, source_path.as_deref().map(Path::new), parsed, &targets);
+        if let Some(path) = resolution.path {
+            return Ok(path);
+        }
+
+        let path = PathBuf::from(&resolution.suggested_path);
+        path_scope::check_document_write(&resolution.suggested_path)?;
+        if !path.exists() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+            }
+            let title = resolution.link.target.rsplit('/').next().unwrap_or(&resolution.link.target);
+            fs::write(&path, format!("# {}\n", title)).map_err(|e| format!("Failed to create note: {}", e))?;
+            info!("Created note {:?} for wiki-link [[{}]]", path, resolution.link.target);
+        }
+        Ok(resolution.suggested_path)
+    })
+    .await
+    .map_err(|e| format!("Failed to create note: {}", e))?
+}
+
+// Tauri command: Convert the wiki-links of a document to Markdown links for export
+#[tauri::command]
+pub async fn convert_wikilinks_for_export(root: String, source_path: String, content: String) -> Result<String, String> {
+    path_scope::check_path(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(&root);
+        let targets = link_targets(root)?;
+        Ok(convert_wikilinks(&content, root, Path::new(&source_path), &targets))
+    })
+    .await
+    .map_err(|e| format!("Failed to convert wiki-links: {}", e))?
+}
@@ -0,0 +1,101 @@
+//! # Window Close Module
+//!
+//! This module decides what happens when the user closes an editor window (the main
+//! window or a `document-*` window).
+//!
+//! ## Close Flow
+//! 1. Main window only: if "keep running in tray" is enabled (and a tray icon exists),
+//!    the window is hidden instead of closed — nothing is lost, so no confirmation is
+//!    needed
+//! 2. Otherwise, if the window's frontend has armed the close guard, the close is
+//!    prevented and a `confirm-close` event is sent to that window; the frontend asks
+//!    about unsaved tabs and then calls `force_close_window` (or does nothing to cancel)
+//! 3. Without an armed guard the window closes normally
+//!
+//! The guard is opt-in and armed per window, so a frontend that does not listen for
+//! `confirm-close` can never end up with a window that refuses to close.
+
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+
+use tauri::Manager;
+use tracing::{debug, info};
+
+use crate::document_window::DOCUMENT_WINDOW_PREFIX;
+use crate::settings;
+use crate::tray;
+use crate::types::{emit_event, ConfirmCloseEvent};
+
+// Labels of the windows whose frontend handles `confirm-close`
+static GUARDED_WINDOWS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn guarded_windows_cell() -> &'static Mutex<HashSet<String>> {
+    GUARDED_WINDOWS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+// Whether closing a window may lose unsaved tabs
+pub fn is_editor_window(label: &str) -> bool {
+    label == "main" || label.starts_with(DOCUMENT_WINDOW_PREFIX)
+}
+
+fn is_guarded(label: &str) -> bool {
+    guarded_windows_cell()
+        .lock()
+        .map(|labels| labels.contains(label))
+        .unwrap_or(false)
+}
+
+// Handle `WindowEvent::CloseRequested` for any window
+pub fn handle_close_requested(window: &tauri::Window, api: &tauri::CloseRequestApi) {
+    let label = window.label();
+    debug!("Window close requested: {}", label);
+    if !is_editor_window(label) {
+        return;
+    }
+
+    // Keep running in the tray: hide the main window instead of closing it
+    if label == "main" && settings::current_settings().keep_running_in_tray && tray::has_tray(window.app_handle())
+    {
+        api.prevent_close();
+        let _ = window.hide();
+        info!("Main window hidden to tray");
+        return;
+    }
+
+    if is_guarded(label) {
+        api.prevent_close();
+        let event = ConfirmCloseEvent {
+            window_label: label.to_string(),
+        };
+        let result = emit_event(window.app_handle(), Some(label), &event);
+        info!("Close of {} deferred to frontend confirmation: {:?}", label, result);
+    }
+}
+
+// Tauri command: Arm or disarm the close guard of the calling window. The frontend of
+// each window enables it once its `confirm-close` listener is registered.
+#[tauri::command]
+pub fn set_close_guard(window: tauri::Window, enabled: bool) {
+    if let Ok(mut labels) = guarded_windows_cell().lock() {
+        if enabled {
+            labels.insert(window.label().to_string());
+        } else {
+            labels.remove(window.label());
+        }
+    }
+    info!("Close guard of {} {}", window.label(), if enabled { "enabled" } else { "disabled" });
+}
+
+// Tauri command: Close a window without asking again (after the user confirmed)
+#[tauri::command]
+pub fn force_close_window(app_handle: tauri::AppHandle, window_label: Option<String>) -> Result<(), String> {
+    let label = window_label.unwrap_or_else(|| "main".to_string());
+    let window = app_handle
+        .get_webview_window(&label)
+        .ok_or_else(|| format!("Window not found: {}", label))?;
+    if let Ok(mut labels) = guarded_windows_cell().lock() {
+        labels.remove(&label);
+    }
+    // `destroy` skips `CloseRequested`, so the guard does not intercept it again
+    window.destroy().map_err(|e| format!("Failed to close window: {}", e))
+}
@@ -0,0 +1,107 @@
+//! # Worker Pool Module
+//!
+//! Workspace-wide operations (hashing every file for sync and change detection, parsing
+//! tags and links, replace previews) do the same work for many files. This module runs
+//! that work on a shared rayon pool instead of one file after another.
+//!
+//! ## Bounded Concurrency
+//! The pool has at most `MAX_WORKERS` threads (fewer on small machines), so a large
+//! workspace cannot saturate every core or open hundreds of files at once while the
+//! editor is in use. It is created on first use and shared by all subsystems.
+//!
+//! ## Results
+//! - `map_parallel`: Apply a function to every item; results keep the order of the items
+//! - `map_paths`: Like `map_parallel` for files, collecting failures as `BatchError`s
+//!   next to the successful results instead of stopping at the first error
+
+use std::sync::OnceLock;
+
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+// Upper bound of worker threads
+const MAX_WORKERS: usize = 8;
+
+static POOL: OnceLock<Option<ThreadPool>> = OnceLock::new();
+
+// Result of one file of a batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItem<T> {
+    pub path: String,
+    pub value: T,
+}
+
+// Failure of one file of a batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchError {
+    pub path: String,
+    pub error: String,
+}
+
+// Aggregated result of a batch: successes and failures, in the order of the input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult<T> {
+    pub items: Vec<BatchItem<T>>,
+    pub errors: Vec<BatchError>,
+}
+
+// Number of worker threads for this machine
+pub fn worker_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .clamp(1, MAX_WORKERS)
+}
+
+fn pool() -> Option<&'static ThreadPool> {
+    POOL.get_or_init(|| {
+        ThreadPoolBuilder::new()
+            .num_threads(worker_count())
+            .thread_name(|index| format!("bokuchi-worker-{}", index))
+            .build()
+            .map_err(|e| warn!("Failed to start worker pool, running serially: {}", e))
+            .ok()
+    })
+    .as_ref()
+}
+
+// Apply `f` to every item on the worker pool. The results keep the order of `items`.
+pub fn map_parallel<I, T, F>(items: Vec<I>, f: F) -> Vec<T>
+where
+    I: Send,
+    T: Send,
+    F: Fn(I) -> T + Sync + Send,
+{
+    match pool() {
+        Some(pool) if items.len() > 1 => pool.install(|| items.into_par_iter().map(f).collect()),
+        _ => items.into_iter().map(f).collect(),
+    }
+}
+
+// Apply `f` to every path on the worker pool, collecting results and errors
+pub fn map_paths<T, F>(paths: Vec<String>, f: F) -> BatchResult<T>
+where
+    T: Send,
+    F: Fn(&str) -> Result<T, String> + Sync + Send,
+{
+    let outcomes = map_parallel(paths, |path| {
+        let outcome = f(&path);
+        (path, outcome)
+    });
+    let mut result = BatchResult {
+        items: Vec::new(),
+        errors: Vec::new(),
+    };
+    for (path, outcome) in outcomes {
+        match outcome {
+            Ok(value) => result.items.push(BatchItem { path, value }),
+            Err(error) => result.errors.push(BatchError { path, error }),
+        }
+    }
+    result
+}
@@ -0,0 +1,215 @@
+//! # Workspace Module
+//!
+//! This module lists the documents of a workspace (the folder opened in the sidebar) for
+//! features that work across files, such as workspace search.
+//!
+//! ## Filtering
+//! The same rules as the folder tree (`read_directory`) apply:
+//! - Hidden files and directories (starting with '.') are skipped
+//! - Only Markdown and text files (`DOCUMENT_EXTENSIONS`) are listed, unless all files
+//!   are requested
+//!
+//! In addition, `.gitignore` and `.ignore` files are respected, so build output and
+//! dependencies inside a Git repository are not searched.
+//!
+//! ## File Cache
+//! `FileCache` keeps data parsed from each document (tags, links, ...) and re-parses only
+//! the files whose modification time changed, so workspace-wide indexes stay cheap to
+//! refresh on every request. Changed files are read and parsed on the `worker_pool`.
+//!
+//! ## Watching
+//! `watch_debounced` follows a folder with a file watcher and reports the changed paths
+//! once a burst of events has settled (used by the search index and Git status).
+//! `watch_dir_debounced` does the same for the files directly in a folder (used for the
+//! files open in tabs).
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use ignore::WalkBuilder;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::debug;
+
+use crate::worker_pool;
+
+// File extensions shown in the folder tree and searched by default
+pub const DOCUMENT_EXTENSIONS: &[&str] = &["md", "txt"];
+
+// Check whether a path is a Markdown or text document
+pub fn is_document_path(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| DOCUMENT_EXTENSIONS.contains(&ext.as_str()))
+}
+
+// List the files of a workspace, sorted by path
+pub fn walk_workspace(root: &Path, all_files: bool) -> Result<Vec<PathBuf>, String> {
+    if !root.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let mut files = Vec::new();
+    for entry in WalkBuilder::new(root).hidden(true).follow_links(false).build() {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(e) => {
+                debug!("Skipping workspace entry: {}", e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if all_files || is_document_path(entry.path()) {
+            files.push(entry.into_path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// Whether `walk_workspace(root, ..)` would reach `path` (a file or folder below `root`):
+// it is not hidden and not excluded by an ignore file. Only the folders on the way to
+// `path` are read.
+pub fn is_listed(root: &Path, path: &Path) -> bool {
+    if !path.starts_with(root) {
+        return false;
+    }
+    let target = path.to_path_buf();
+    WalkBuilder::new(root)
+        .hidden(true)
+        .follow_links(false)
+        .filter_entry(move |entry| target.starts_with(entry.path()))
+        .build()
+        .flatten()
+        .any(|entry| entry.path() == path)
+}
+
+// Modification time of a file in milliseconds since the Unix epoch
+pub fn modified_millis(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    modified
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+// Per-file data derived from the documents of one workspace, re-parsed only for files
+// whose modification time changed since the last refresh
+pub struct FileCache<T> {
+    root: Option<PathBuf>,
+    entries: HashMap<String, (u64, T)>,
+}
+
+impl<T> Default for FileCache<T> {
+    fn default() -> Self {
+        Self {
+            root: None,
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Send> FileCache<T> {
+    // Bring the cache up to date with the documents of `root` (changed files are parsed
+    // on the worker pool)
+    pub fn refresh(&mut self, root: &Path, parse: impl Fn(&Path, &str) -> T + Sync + Send) -> Result<(), String> {
+        if self.root.as_deref() != Some(root) {
+            self.root = Some(root.to_path_buf());
+            self.entries.clear();
+        }
+        let files = walk_workspace(root, false)?;
+        let current: HashSet<String> = files.iter().map(|p| p.to_string_lossy().to_string()).collect();
+        self.entries.retain(|path, _| current.contains(path));
+
+        let changed: Vec<(PathBuf, u64)> = files
+            .into_iter()
+            .map(|path| {
+                let modified = modified_millis(&path).unwrap_or(0);
+                (path, modified)
+            })
+            .filter(|(path, modified)| {
+                self.entries
+                    .get(path.to_string_lossy().as_ref())
+                    .is_none_or(|(m, _)| m != modified)
+            })
+            .collect();
+        let parsed = worker_pool::map_parallel(changed, |(path, modified)| {
+            // Binary or non-UTF-8 files have no content to parse
+            let content = std::fs::read_to_string(&path).unwrap_or_default();
+            let data = parse(&path, &content);
+            (path.to_string_lossy().to_string(), (modified, data))
+        });
+        self.entries.extend(parsed);
+        Ok(())
+    }
+
+    // Forget cached data, e.g. after files were rewritten within the same millisecond
+    pub fn invalidate(&mut self, path: &str) {
+        self.entries.remove(path);
+    }
+
+    // Cached entries (path, data)
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &T)> + Clone {
+        self.entries.iter().map(|(path, (_, data))| (path, data))
+    }
+}
+
+// Watch a folder recursively and call `on_change` with the paths changed in each burst of
+// events, once no event arrived for `debounce`. The thread stops when the watcher is
+// dropped or `on_change` returns false.
+pub fn watch_debounced(
+    root: &Path,
+    debounce: Duration,
+    on_change: impl FnMut(Vec<PathBuf>) -> bool + Send + 'static,
+) -> Result<RecommendedWatcher, String> {
+    spawn_watcher(root, RecursiveMode::Recursive, debounce, on_change)
+}
+
+// Like `watch_debounced`, for the entries directly in `dir`
+pub fn watch_dir_debounced(
+    dir: &Path,
+    debounce: Duration,
+    on_change: impl FnMut(Vec<PathBuf>) -> bool + Send + 'static,
+) -> Result<RecommendedWatcher, String> {
+    spawn_watcher(dir, RecursiveMode::NonRecursive, debounce, on_change)
+}
+
+fn spawn_watcher(
+    root: &Path,
+    mode: RecursiveMode,
+    debounce: Duration,
+    mut on_change: impl FnMut(Vec<PathBuf>) -> bool + Send + 'static,
+) -> Result<RecommendedWatcher, String> {
+    let (sender, events) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(sender).map_err(|e| format!("Failed to watch {:?}: {}", root, e))?;
+    watcher
+        .watch(root, mode)
+        .map_err(|e| format!("Failed to watch {:?}: {}", root, e))?;
+
+    let root = root.to_path_buf();
+    std::thread::spawn(move || {
+        while let Ok(first) = events.recv() {
+            let mut batch = vec![first];
+            while let Ok(event) = events.recv_timeout(debounce) {
+                batch.push(event);
+            }
+
+            let mut paths: Vec<PathBuf> = batch
+                .into_iter()
+                .filter_map(|event| event.ok())
+                .filter(|event| !matches!(event.kind, EventKind::Access(_)))
+                .flat_map(|event| event.paths)
+                .collect();
+            paths.sort();
+            paths.dedup();
+            if !paths.is_empty() && !on_change(paths) {
+                break;
+            }
+        }
+        debug!("Watcher for {:?} stopped", root);
+    });
+    Ok(watcher)
+}
@@ -0,0 +1,162 @@
+//! # Workspace Window State Module
+//!
+//! This module remembers window geometry, sidebar visibility and zoom per workspace
+//! (folder), on top of the global window-state plugin.
+//!
+//! ## Flow
+//! - When a workspace is closed or the window is about to close, the frontend calls
+//!   `save_workspace_window_state`; the backend reads the window geometry itself, the
+//!   frontend passes only what the backend cannot see (sidebar visibility, zoom)
+//! - When a folder is opened, `restore_workspace_window_state` moves/resizes the window,
+//!   applies the zoom, and returns the saved state so the frontend can restore the sidebar
+//!
+//! States are keyed by the canonical workspace path and stored in
+//! `workspace-window-state.json`. Only the `MAX_WORKSPACES` most recently used workspaces
+//! are kept.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use tauri::{LogicalPosition, LogicalSize};
+use tracing::debug;
+
+use crate::storage;
+
+const WORKSPACE_STATE_FILE: &str = "workspace-window-state.json";
+
+// Maximum number of workspaces whose state is kept
+pub const MAX_WORKSPACES: usize = 50;
+
+// Saved window state of one workspace
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceWindowState {
+    // Logical pixels
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub maximized: bool,
+    pub sidebar_visible: Option<bool>,
+    pub zoom: Option<f64>,
+    // Milliseconds since the Unix epoch
+    pub last_used: u64,
+}
+
+static WORKSPACE_STATES: OnceLock<Mutex<HashMap<String, WorkspaceWindowState>>> = OnceLock::new();
+
+fn states_cell() -> &'static Mutex<HashMap<String, WorkspaceWindowState>> {
+    WORKSPACE_STATES.get_or_init(|| Mutex::new(storage::load_json(WORKSPACE_STATE_FILE)))
+}
+
+// Key of a workspace: its canonical path, so "~/notes" and "~/notes/" match
+pub fn workspace_key(workspace_path: &str) -> String {
+    let path = Path::new(workspace_path);
+    path.canonicalize()
+        .unwrap_or_else(|_| path.components().collect())
+        .to_string_lossy()
+        .to_string()
+}
+
+// Insert a state and drop the least recently used workspaces beyond `max`
+pub fn remember_state(
+    states: &mut HashMap<String, WorkspaceWindowState>,
+    key: String,
+    state: WorkspaceWindowState,
+    max: usize,
+) {
+    states.insert(key, state);
+    while states.len() > max {
+        let oldest = states
+            .iter()
+            .min_by_key(|(_, s)| s.last_used)
+            .map(|(k, _)| k.clone());
+        match oldest {
+            Some(oldest) => states.remove(&oldest),
+            None => break,
+        };
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Tauri command: Save the calling window's state for a workspace
+#[tauri::command]
+pub fn save_workspace_window_state(
+    window: tauri::WebviewWindow,
+    workspace_path: String,
+    sidebar_visible: Option<bool>,
+    zoom: Option<f64>,
+) -> Result<(), String> {
+    let scale = window.scale_factor().map_err(|e| format!("Failed to read window scale: {}", e))?;
+    let position: LogicalPosition<f64> = window
+        .outer_position()
+        .map_err(|e| format!("Failed to read window position: {}", e))?
+        .to_logical(scale);
+    let size: LogicalSize<f64> = window
+        .inner_size()
+        .map_err(|e| format!("Failed to read window size: {}", e))?
+        .to_logical(scale);
+
+    let state = WorkspaceWindowState {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: window.is_maximized().unwrap_or(false),
+        sidebar_visible,
+        zoom,
+        last_used: now_millis(),
+    };
+
+    let snapshot = {
+        let mut states = states_cell()
+            .lock()
+            .map_err(|_| "Failed to lock workspace states".to_string())?;
+        remember_state(&mut states, workspace_key(&workspace_path), state, MAX_WORKSPACES);
+        states.clone()
+    };
+    storage::save_json(WORKSPACE_STATE_FILE, &snapshot)
+}
+
+// Tauri command: Restore the calling window's state for a workspace. Returns the saved
+// state (None if the workspace has none) so the frontend can restore the sidebar.
+#[tauri::command]
+pub fn restore_workspace_window_state(
+    window: tauri::WebviewWindow,
+    workspace_path: String,
+) -> Result<Option<WorkspaceWindowState>, String> {
+    let state = {
+        let states = states_cell()
+            .lock()
+            .map_err(|_| "Failed to lock workspace states".to_string())?;
+        states.get(&workspace_key(&workspace_path)).cloned()
+    };
+    let Some(state) = state else {
+        return Ok(None);
+    };
+
+    if state.maximized {
+        let _ = window.maximize();
+    } else {
+        let _ = window.unmaximize();
+        if state.width > 0.0 && state.height > 0.0 {
+            let _ = window.set_size(LogicalSize::new(state.width, state.height));
+        }
+        let _ = window.set_position(LogicalPosition::new(state.x, state.y));
+    }
+    if let Some(zoom) = state.zoom {
+        let _ = window.set_zoom(zoom);
+    }
+    debug!("Restored window state for workspace {}", workspace_path);
+    Ok(Some(state))
+}
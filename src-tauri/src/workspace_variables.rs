@@ -0,0 +1,148 @@
+//! # Workspace Variables Module
+//!
+//! This module keeps the variables of a workspace (the folder opened in the sidebar) in
+//! `.bokuchi/variables.yaml` inside the workspace, so they can be committed together with
+//! the documents.
+//!
+//! ## File Format
+//! The same format as the variable import and export (`variables:`, a list of `name` and
+//! `value`), sorted by name so that changes make small diffs.
+//!
+//! ## Layering
+//! The variables of the loaded workspace override the global variables and are
+//! overridden by the variables a document defines (see `variable_processor`).
+//! `load_workspace_variables` is called when a workspace is opened, and without a
+//! workspace when it is closed. `set_workspace_variable` and `remove_workspace_variable`
+//! edit the file and, for the loaded workspace, the layer as well. The values they
+//! replace are kept in the `variable_history`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tracing::{info, warn};
+
+use crate::path_scope;
+use crate::storage;
+use crate::types::{Variable, VariableSet};
+use crate::variable_history;
+use crate::variable_processor::VARIABLE_PROCESSOR;
+
+// Folder of the workspace's own settings
+pub const WORKSPACE_CONFIG_DIR: &str = ".bokuchi";
+pub const WORKSPACE_VARIABLES_FILE: &str = "variables.yaml";
+
+// Root of the workspace whose variables are loaded
+static LOADED_WORKSPACE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// Path of the variables file of a workspace
+pub fn variables_path(root: &Path) -> PathBuf {
+    root.join(WORKSPACE_CONFIG_DIR).join(WORKSPACE_VARIABLES_FILE)
+}
+
+// Read the variables of a workspace; none if it has no variables file
+pub fn read_workspace_variables(root: &Path) -> Result<HashMap<String, String>, String> {
+    let path = variables_path(root);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    if content.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    let var_set: VariableSet =
+        serde_yaml::from_str(&content).map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+    Ok(var_set.variables.into_iter().map(|v| (v.name, v.value)).collect())
+}
+
+// Write the variables of a workspace, sorted by name
+pub fn write_workspace_variables(root: &Path, variables: &HashMap<String, String>) -> Result<(), String> {
+    let sorted: BTreeMap<&String, &String> = variables.iter().collect();
+    let var_set = VariableSet {
+        variables: sorted
+            .into_iter()
+            .map(|(name, value)| Variable { name: name.clone(), value: value.clone() })
+            .collect(),
+    };
+    let yaml = serde_yaml::to_string(&var_set).map_err(|e| format!("Failed to serialize variables: {}", e))?;
+    let path = variables_path(root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    storage::write_atomic(&path, yaml.as_bytes())
+}
+
+fn is_loaded(root: &Path) -> bool {
+    LOADED_WORKSPACE.lock().map(|loaded| loaded.as_deref() == Some(root)).unwrap_or(false)
+}
+
+// Read, change and write the variables of a workspace, keeping the values that changed
+// in the history
+fn edit_workspace_variables(root: &str, edit: impl FnOnce(&mut HashMap<String, String>)) -> Result<(), String> {
+    path_scope::check_path(root)?;
+    let root_path = Path::new(root);
+    let previous = read_workspace_variables(root_path)?;
+    let mut variables = previous.clone();
+    edit(&mut variables);
+    write_workspace_variables(root_path, &variables)?;
+    for (name, old_value) in &previous {
+        if variables.get(name) != Some(old_value)
+            && let Err(e) = variable_history::record(Some(root), name, old_value)
+        {
+            warn!("Failed to record the previous value of {}: {}", name, e);
+        }
+    }
+    if is_loaded(root_path) {
+        VARIABLE_PROCESSOR.set_workspace_variables(variables);
+    }
+    Ok(())
+}
+
+// Tauri command: Load the variables of the workspace at `root` as the workspace layer
+// (or clear the layer without a workspace). Returns the loaded variables.
+#[tauri::command]
+pub fn load_workspace_variables(root: Option<String>) -> Result<HashMap<String, String>, String> {
+    let variables = match &root {
+        Some(root) => {
+            path_scope::check_path(root)?;
+            read_workspace_variables(Path::new(root))?
+        }
+        None => HashMap::new(),
+    };
+    if let Ok(mut loaded) = LOADED_WORKSPACE.lock() {
+        *loaded = root.as_ref().map(PathBuf::from);
+    }
+    VARIABLE_PROCESSOR.set_workspace_variables(variables.clone());
+    info!("Loaded {} workspace variables from {:?}", variables.len(), root);
+    Ok(variables)
+}
+
+// Tauri command: Get the variables stored in the workspace at `root`
+#[tauri::command]
+pub fn get_workspace_variables(root: String) -> Result<HashMap<String, String>, String> {
+    path_scope::check_path(&root)?;
+    read_workspace_variables(Path::new(&root))
+}
+
+// Tauri command: Set a variable of the workspace at `root`
+#[tauri::command]
+pub fn set_workspace_variable(root: String, name: String, value: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Variable name is empty".to_string());
+    }
+    edit_workspace_variables(&root, |variables| {
+        variables.insert(name, value);
+    })
+}
+
+// Tauri command: Remove a variable of the workspace at `root`
+#[tauri::command]
+pub fn remove_workspace_variable(root: String, name: String) -> Result<(), String> {
+    edit_workspace_variables(&root, |variables| {
+        variables.remove(name.trim());
+    })
+}
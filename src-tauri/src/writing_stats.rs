@@ -0,0 +1,492 @@
+//! # Writing Stats Module
+//!
+//! This module keeps a per-day history of how much was written in each document, for the
+//! progress view (daily goals and writing streaks).
+//!
+//! ## Recording
+//! Every save (`save_file`) and every autosave snapshot of a saved document is compared
+//! with the previous version of that document, and the words added and removed are added
+//! to the current day. The previous version is the last recorded one, or the file on disk
+//! the first time a document is seen in a session.
+//!
+//! Words are compared as counts, not in order: moving a paragraph adds and removes
+//! nothing, rewriting a word counts one removed and one added. In scripts without word
+//! spacing (Japanese, Chinese) every character counts as a word. Frontmatter is not
+//! counted.
+//!
+//! ## Goals
+//! A document or a workspace can have a word-count target (`word_goals` in the settings).
+//! Every recorded revision of a document with a goal emits a `goal-progress` event, and
+//! `get_goal_progress` returns the progress for the status bar. The length of a
+//! workspace is the sum of the lengths of its documents.
+//!
+//! Counting a workspace walks its folder, so it never runs on the save path: a save in a
+//! workspace with a goal only queues a recount, which runs in the background once
+//! `RECOUNT_DELAY` has passed. Saves within that time (e.g. autosaves) share one recount.
+//! Only files changed since the last count are read again (see `FileCache`).
+//!
+//! ## Persistence
+//! The history is stored as `writing-stats.json` in the app data directory. Changes are
+//! written by the periodic and shutdown flushes (see `shutdown`). A flush replays the
+//! revisions recorded since the last one on the file (under its lock, see `storage`), so
+//! a separate process (`--new-instance`) recording revisions too loses none of its own.
+
+use chrono::{Duration, Local, NaiveDate};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration as StdDuration;
+
+use tracing::warn;
+
+use crate::frontmatter;
+use crate::path_scope;
+use crate::settings::{self, WordGoals};
+use crate::storage;
+use crate::types::{emit_event, GoalProgressEvent};
+use crate::workspace::FileCache;
+
+lazy_static! {
+    static ref WORD: Regex = Regex::new(
+        r"[\p{Han}\p{Hiragana}\p{Katakana}]|[\p{L}\p{M}\p{N}&&[^\p{Han}\p{Hiragana}\p{Katakana}]]+(?:['’][\p{L}\p{M}\p{N}]+)*"
+    )
+    .unwrap();
+}
+
+const STATS_FILE: &str = "writing-stats.json";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+// Time a workspace recount waits for further saves
+const RECOUNT_DELAY: StdDuration = StdDuration::from_secs(1);
+
+// Writing in one document on one day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DayStats {
+    pub added: u64,
+    pub removed: u64,
+    // Length of the document after the last change of the day
+    pub words: u64,
+}
+
+// Days of a document, keyed by date (YYYY-MM-DD)
+pub type DocumentHistory = BTreeMap<String, DayStats>;
+
+// One day of `get_writing_stats`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayEntry {
+    pub date: String,
+    pub added: u64,
+    pub removed: u64,
+    // Length of the document at the end of the day (None when several documents are
+    // summed up)
+    pub words: Option<u64>,
+}
+
+// Result of `get_writing_stats`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WritingStats {
+    // Days with writing in the range, oldest first
+    pub days: Vec<DayEntry>,
+    pub total_added: u64,
+    pub total_removed: u64,
+    // Consecutive days with words added, up to today (or yesterday, if nothing was
+    // written yet today)
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+// Range of `get_writing_stats`; open ends include all days
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsRange {
+    // YYYY-MM-DD, inclusive
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+// What a word-count goal is set on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalScope {
+    Document,
+    Workspace,
+}
+
+// Progress towards a word-count goal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub scope: GoalScope,
+    // The document, or the workspace folder
+    pub path: String,
+    pub target: u64,
+    pub words: u64,
+    // Share of the target written, in percent (may exceed 100)
+    pub percent: u32,
+    pub reached: bool,
+}
+
+static HISTORY: OnceLock<Mutex<HashMap<String, DocumentHistory>>> = OnceLock::new();
+// Word counts of the last recorded version of each document (this session only)
+static BASELINES: OnceLock<Mutex<HashMap<String, HashMap<String, u64>>>> = OnceLock::new();
+// Revisions recorded since the last flush, oldest first
+static UNFLUSHED: Mutex<Vec<RecordedChange>> = Mutex::new(Vec::new());
+
+// A recorded revision: words added and removed on a day, and the new length
+#[derive(Debug, Clone)]
+struct RecordedChange {
+    path: String,
+    date: String,
+    added: u64,
+    removed: u64,
+    words: u64,
+}
+
+impl RecordedChange {
+    fn apply_to(&self, history: &mut HashMap<String, DocumentHistory>) {
+        apply_change(
+            history.entry(self.path.clone()).or_default(),
+            &self.date,
+            self.added,
+            self.removed,
+            self.words,
+        );
+    }
+}
+
+// Length of each document of the workspace last asked about
+static WORKSPACE_WORDS: OnceLock<Mutex<FileCache<u64>>> = OnceLock::new();
+// Workspaces waiting for a recount, with the last document saved in each and its length
+static PENDING_RECOUNTS: OnceLock<Mutex<HashMap<String, (String, u64)>>> = OnceLock::new();
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+
+fn history_cell() -> &'static Mutex<HashMap<String, DocumentHistory>> {
+    HISTORY.get_or_init(|| Mutex::new(storage::load_json(STATS_FILE)))
+}
+
+fn baselines_cell() -> &'static Mutex<HashMap<String, HashMap<String, u64>>> {
+    BASELINES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// How often each word occurs in a document (frontmatter excluded)
+pub fn word_counts(content: &str) -> HashMap<String, u64> {
+    let mut counts = HashMap::new();
+    for word in WORD.find_iter(frontmatter::split_frontmatter(content).body) {
+        *counts.entry(word.as_str().to_lowercase()).or_insert(0) += 1;
+    }
+    counts
+}
+
+// Length of a document in words (frontmatter excluded)
+pub fn word_count(content: &str) -> u64 {
+    WORD.find_iter(frontmatter::split_frontmatter(content).body).count() as u64
+}
+
+// Words added and removed between two versions
+pub fn word_delta(old: &HashMap<String, u64>, new: &HashMap<String, u64>) -> (u64, u64) {
+    let added = new
+        .iter()
+        .map(|(word, count)| count.saturating_sub(old.get(word).copied().unwrap_or(0)))
+        .sum();
+    let removed = old
+        .iter()
+        .map(|(word, count)| count.saturating_sub(new.get(word).copied().unwrap_or(0)))
+        .sum();
+    (added, removed)
+}
+
+// Add a change to the day of a document's history
+pub fn apply_change(history: &mut DocumentHistory, date: &str, added: u64, removed: u64, words: u64) {
+    let day = history.entry(date.to_string()).or_default();
+    day.added += added;
+    day.removed += removed;
+    day.words = words;
+}
+
+// Record a new version of a document. Called before the file is written, so the first
+// version of a session is compared with the file on disk.
+pub fn record_revision(path: &str, content: &str) {
+    let new = word_counts(content);
+    let Ok(mut baselines) = baselines_cell().lock() else {
+        return;
+    };
+    let old = baselines.remove(path).unwrap_or_else(|| {
+        std::fs::read_to_string(path)
+            .map(|previous| word_counts(&previous))
+            .unwrap_or_default()
+    });
+    let (added, removed) = word_delta(&old, &new);
+    let words = new.values().sum();
+    baselines.insert(path.to_string(), new);
+    drop(baselines);
+    if added == 0 && removed == 0 {
+        return;
+    }
+    emit_goal_progress(path, words);
+
+    let today = Local::now().format(DATE_FORMAT).to_string();
+    let change = RecordedChange {
+        path: path.to_string(),
+        date: today,
+        added,
+        removed,
+        words,
+    };
+    if let Ok(mut history) = history_cell().lock() {
+        change.apply_to(&mut history);
+        if let Ok(mut unflushed) = UNFLUSHED.lock() {
+            unflushed.push(change);
+        }
+    }
+}
+
+// The goal that applies to a document: its own, or that of the innermost workspace
+// containing it. Returns (scope, document or workspace folder, target).
+pub fn goal_for(goals: &WordGoals, path: &str) -> Option<(GoalScope, String, u64)> {
+    if let Some(target) = goals.documents.get(path) {
+        return Some((GoalScope::Document, path.to_string(), *target));
+    }
+    goals
+        .workspaces
+        .iter()
+        .filter(|(root, _)| Path::new(path).starts_with(root))
+        .max_by_key(|(root, _)| root.len())
+        .map(|(root, target)| (GoalScope::Workspace, root.clone(), *target))
+}
+
+pub fn goal_progress(scope: GoalScope, path: String, target: u64, words: u64) -> GoalProgress {
+    let percent = match target {
+        0 => 100,
+        _ => (words.saturating_mul(100) / target).min(u32::MAX as u64) as u32,
+    };
+    GoalProgress {
+        scope,
+        path,
+        target,
+        words,
+        percent,
+        reached: words >= target,
+    }
+}
+
+// Length of a workspace; `current` replaces the length on disk of a document being saved.
+// The cache is taken out while the folder is walked, so the lock is only held briefly.
+fn workspace_word_count(root: &Path, current: Option<(&str, u64)>) -> Result<u64, String> {
+    let cell = WORKSPACE_WORDS.get_or_init(|| Mutex::new(FileCache::default()));
+    let lock_error = || "Failed to lock workspace word counts".to_string();
+    let mut cache = std::mem::take(&mut *cell.lock().map_err(|_| lock_error())?);
+    let refreshed = cache.refresh(root, |_, content| word_count(content));
+    let words = cache
+        .iter()
+        .map(|(path, words)| match current {
+            Some((current, words)) if Path::new(path) == Path::new(current) => words,
+            _ => *words,
+        })
+        .sum();
+    *cell.lock().map_err(|_| lock_error())? = cache;
+    refreshed.map(|_| words)
+}
+
+// Progress of the goal that applies to a document of `words` words (None without goal)
+fn progress_for(path: &str, words: u64) -> Result<Option<GoalProgress>, String> {
+    let Some((scope, goal_path, target)) = goal_for(&settings::current_settings().word_goals, path) else {
+        return Ok(None);
+    };
+    let words = match scope {
+        GoalScope::Document => words,
+        GoalScope::Workspace => workspace_word_count(Path::new(&goal_path), Some((path, words)))?,
+    };
+    Ok(Some(goal_progress(scope, goal_path, target, words)))
+}
+
+fn emit_progress(app_handle: &tauri::AppHandle, progress: GoalProgress) {
+    let _ = emit_event(app_handle, None, &GoalProgressEvent { progress });
+}
+
+// Emit the progress of the goal of a saved document. A document goal is emitted right
+// away; a workspace goal is recounted in the background (see `queue_recount`).
+fn emit_goal_progress(path: &str, words: u64) {
+    let Some(app_handle) = APP_HANDLE.get() else {
+        return;
+    };
+    match goal_for(&settings::current_settings().word_goals, path) {
+        Some((GoalScope::Document, goal_path, target)) => {
+            emit_progress(app_handle, goal_progress(GoalScope::Document, goal_path, target, words));
+        }
+        Some((GoalScope::Workspace, root, _)) => queue_recount(app_handle, root, path, words),
+        None => {}
+    }
+}
+
+// Recount a workspace after `RECOUNT_DELAY`. A workspace that is already queued only
+// takes the newer document length.
+fn queue_recount(app_handle: &tauri::AppHandle, root: String, path: &str, words: u64) {
+    let Ok(mut pending) = PENDING_RECOUNTS.get_or_init(|| Mutex::new(HashMap::new())).lock() else {
+        return;
+    };
+    if pending.insert(root.clone(), (path.to_string(), words)).is_some() {
+        return;
+    }
+    drop(pending);
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        std::thread::sleep(RECOUNT_DELAY);
+        let Some((path, words)) = PENDING_RECOUNTS
+            .get()
+            .and_then(|pending| pending.lock().ok()?.remove(&root))
+        else {
+            return;
+        };
+        match progress_for(&path, words) {
+            Ok(Some(progress)) => emit_progress(&app_handle, progress),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to compute goal progress of {}: {}", path, e),
+        }
+    });
+}
+
+// Emit goal progress events from now on (called once during setup)
+pub fn init_writing_stats(app_handle: tauri::AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+// Write the revisions recorded since the last flush to disk, and take over the
+// revisions other processes wrote meanwhile
+pub fn flush_writing_stats() -> Result<bool, String> {
+    let changes = {
+        let mut unflushed = UNFLUSHED
+            .lock()
+            .map_err(|_| "Failed to lock writing stats".to_string())?;
+        std::mem::take(&mut *unflushed)
+    };
+    if changes.is_empty() {
+        return Ok(false);
+    }
+    let merged = storage::update_json(STATS_FILE, |history: &mut HashMap<String, DocumentHistory>| {
+        for change in &changes {
+            change.apply_to(history);
+        }
+    });
+    let merged = match merged {
+        Ok(merged) => merged,
+        Err(e) => {
+            // Keep the revisions for the next flush, before any recorded meanwhile
+            if let Ok(mut unflushed) = UNFLUSHED.lock() {
+                unflushed.splice(0..0, changes);
+            }
+            return Err(e);
+        }
+    };
+    let mut history = history_cell()
+        .lock()
+        .map_err(|_| "Failed to lock writing stats".to_string())?;
+    *history = merged;
+    // Revisions recorded during the write are on disk with the next flush
+    if let Ok(unflushed) = UNFLUSHED.lock() {
+        for change in unflushed.iter() {
+            change.apply_to(&mut history);
+        }
+    }
+    Ok(true)
+}
+
+// Streaks of consecutive days with words added: (current, longest)
+fn streaks(days: &BTreeMap<String, (u64, u64)>, today: NaiveDate) -> (u32, u32) {
+    let writing_days: Vec<NaiveDate> = days
+        .iter()
+        .filter(|(_, (added, _))| *added > 0)
+        .filter_map(|(date, _)| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+        .collect();
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for date in &writing_days {
+        run = match previous {
+            Some(previous) if *date - previous == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*date);
+    }
+    let current = match writing_days.last() {
+        Some(last) if *last == today || *last == today - Duration::days(1) => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+// Sum up the histories of one or more documents within a range. Streaks are counted over
+// all days, not only those in the range.
+pub fn summarize(histories: &[&DocumentHistory], range: &StatsRange, today: NaiveDate) -> WritingStats {
+    let mut days: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for history in histories {
+        for (date, day) in history.iter() {
+            let entry = days.entry(date.clone()).or_default();
+            entry.0 += day.added;
+            entry.1 += day.removed;
+        }
+    }
+    let (current_streak, longest_streak) = streaks(&days, today);
+
+    let in_range = |date: &str| {
+        range.from.as_deref().is_none_or(|from| date >= from) && range.to.as_deref().is_none_or(|to| date <= to)
+    };
+    let single = (histories.len() == 1).then(|| histories[0]);
+    let days: Vec<DayEntry> = days
+        .into_iter()
+        .filter(|(date, _)| in_range(date))
+        .map(|(date, (added, removed))| DayEntry {
+            words: single.and_then(|history| history.get(&date)).map(|day| day.words),
+            date,
+            added,
+            removed,
+        })
+        .collect();
+    WritingStats {
+        total_added: days.iter().map(|day| day.added).sum(),
+        total_removed: days.iter().map(|day| day.removed).sum(),
+        days,
+        current_streak,
+        longest_streak,
+    }
+}
+
+// Tauri command: Get the writing history of a document, or of all documents without a
+// path
+#[tauri::command]
+pub fn get_writing_stats(path: Option<String>, range: Option<StatsRange>) -> Result<WritingStats, String> {
+    let history = history_cell()
+        .lock()
+        .map_err(|_| "Failed to lock writing stats".to_string())?;
+    let histories: Vec<&DocumentHistory> = match &path {
+        Some(path) => history.get(path).into_iter().collect(),
+        None => history.values().collect(),
+    };
+    Ok(summarize(&histories, &range.unwrap_or_default(), Local::now().date_naive()))
+}
+
+// Tauri command: Get the progress towards the word-count goal of a document (its own
+// goal, or that of its workspace). None if no goal applies.
+#[tauri::command]
+pub async fn get_goal_progress(path: String) -> Result<Option<GoalProgress>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        // The last recorded version, or the file on disk
+        let recorded = baselines_cell()
+            .lock()
+            .ok()
+            .and_then(|baselines| baselines.get(&path).map(|counts| counts.values().sum()));
+        let words = match recorded {
+            Some(words) => words,
+            None => {
+                path_scope::check_path(&path)?;
+                std::fs::read_to_string(&path).map(|content| word_count(&content)).unwrap_or(0)
+            }
+        };
+        progress_for(&path, words)
+    })
+    .await
+    .map_err(|e| format!("Failed to compute goal progress: {}", e))?
+}
@@ -0,0 +1,222 @@
+//! # Zettel Module
+//!
+//! This module supports Zettelkasten-style notes: every note gets a unique ID that is
+//! part of its file name (`202410161230 Reading list.md`), so it can be linked as
+//! `[[202410161230]]` no matter how the note is renamed later.
+//!
+//! ## ID Formats
+//! - `timestamp`: Local date and time to the minute (`202410161230`), the classic format
+//! - `timestamp_seconds`: To the second (`20241016123045`)
+//! - `ulid`: A ULID (`01JAA6R2Z3XQ8M4N5P6Q7R8S9T`), unique without coordination, e.g.
+//!   across synced devices
+//!
+//! New notes never reuse an ID of the folder they are created in: a taken timestamp is
+//! moved forward to the next free minute (or second).
+//!
+//! ## Linking
+//! A note's ID is its frontmatter `id`, or the ID its file name starts with. The wiki-link
+//! resolver (`links::LinkTargets`) looks targets up by ID after file names, so
+//! `[[202410161230]]` finds `202410161230 Reading list.md`.
+
+use chrono::{DateTime, Duration, Local};
+use lazy_static::lazy_static;
+use regex::Regex;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use crate::frontmatter;
+use crate::path_scope;
+use crate::templates;
+
+lazy_static! {
+    static ref LEADING_ID: Regex = Regex::new(r"^(\d{12}|\d{14}|[0-9A-HJKMNP-TV-Za-hjkmnp-tv-z]{26})(?:$|[\s_-])").unwrap();
+    static ref BUILTIN_PLACEHOLDER: Regex = Regex::new(r"\{\{\s*(id|title)\s*\}\}").unwrap();
+}
+
+// Crockford's Base32, as used by ULIDs
+const ULID_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+// Format of generated note IDs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteIdFormat {
+    #[default]
+    Timestamp,
+    TimestampSeconds,
+    Ulid,
+}
+
+// Result of `new_zettel`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZettelNote {
+    pub id: String,
+    pub path: String,
+}
+
+// A ULID for a time in milliseconds and 80 random bits
+pub fn encode_ulid(millis: u64, random: [u8; 10]) -> String {
+    let mut value = (millis as u128 & 0xFFFF_FFFF_FFFF) << 80;
+    for (i, byte) in random.iter().enumerate() {
+        value |= (*byte as u128) << (72 - 8 * i);
+    }
+    // 26 characters of 5 bits; the first one holds the top 3 bits
+    (0..26)
+        .map(|i| ULID_ALPHABET[((value >> (125 - 5 * i)) & 0x1F) as usize] as char)
+        .collect()
+}
+
+// A note ID for a point in time
+pub fn note_id_at(format: NoteIdFormat, time: DateTime<Local>) -> Result<String, String> {
+    Ok(match format {
+        NoteIdFormat::Timestamp => time.format("%Y%m%d%H%M").to_string(),
+        NoteIdFormat::TimestampSeconds => time.format("%Y%m%d%H%M%S").to_string(),
+        NoteIdFormat::Ulid => {
+            let mut random = [0u8; 10];
+            SystemRandom::new()
+                .fill(&mut random)
+                .map_err(|_| "Failed to generate random bytes".to_string())?;
+            encode_ulid(time.timestamp_millis().max(0) as u64, random)
+        }
+    })
+}
+
+// A note ID that is not in `taken`, starting at `time`
+pub fn unique_note_id(format: NoteIdFormat, time: DateTime<Local>, taken: &HashSet<String>) -> Result<String, String> {
+    let step = match format {
+        NoteIdFormat::Timestamp => Duration::minutes(1),
+        _ => Duration::seconds(1),
+    };
+    let mut time = time;
+    loop {
+        let id = note_id_at(format, time)?;
+        if !taken.contains(&id.to_uppercase()) {
+            return Ok(id);
+        }
+        time += step;
+    }
+}
+
+// ID of a note: its frontmatter `id`, else the ID its file name starts with
+pub fn note_id_of(path: &Path, content: &str) -> Option<String> {
+    let from_frontmatter = frontmatter::parse_frontmatter(content).and_then(|value| match value.get("id") {
+        Some(Value::String(id)) => Some(id.trim().to_string()),
+        Some(Value::Number(id)) => Some(id.to_string()),
+        _ => None,
+    });
+    from_frontmatter.filter(|id| !id.is_empty()).or_else(|| {
+        let stem = path.file_stem()?.to_string_lossy();
+        LEADING_ID.captures(&stem).map(|caps| caps[1].to_string())
+    })
+}
+
+// IDs of the notes in a folder (uppercase, as ULIDs are case-insensitive)
+fn ids_in_dir(dir: &Path) -> HashSet<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return HashSet::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let stem = entry.path().file_stem()?.to_string_lossy().to_string();
+            LEADING_ID.captures(&stem).map(|caps| caps[1].to_uppercase())
+        })
+        .collect()
+}
+
+// File name of a new note: the ID, then the title without characters file systems reject
+pub fn zettel_file_name(id: &str, title: &str) -> String {
+    let title: String = title
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '-' } else { c })
+        .collect();
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let title = title.trim_matches('.');
+    if title.is_empty() {
+        format!("{}.md", id)
+    } else {
+        format!("{} {}.md", id, title)
+    }
+}
+
+// Content of a new note: the template filled with `values`, `{{id}}` and `{{title}}`, or
+// a heading without a template
+pub fn zettel_content(
+    id: &str,
+    title: &str,
+    template: Option<&str>,
+    values: &HashMap<String, String>,
+) -> Result<String, String> {
+    let Some(template) = template else {
+        let heading = if title.is_empty() { id } else { title };
+        return Ok(format!("# {}\n", heading));
+    };
+    let mut values = values.clone();
+    values.insert("id".to_string(), id.to_string());
+    values.insert("title".to_string(), title.to_string());
+    let filled = templates::fill_template(template, &values)?;
+    Ok(BUILTIN_PLACEHOLDER
+        .replace_all(&filled, |caps: &regex::Captures| values[&caps[1]].clone())
+        .into_owned())
+}
+
+// Create a note in `dir` with a new ID
+pub fn create_zettel(
+    dir: &Path,
+    title: &str,
+    format: NoteIdFormat,
+    template: Option<&str>,
+    values: &HashMap<String, String>,
+) -> Result<ZettelNote, String> {
+    let id = unique_note_id(format, Local::now(), &ids_in_dir(dir))?;
+    let content = zettel_content(&id, title, template, values)?;
+    let path: PathBuf = dir.join(zettel_file_name(&id, title));
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, content.as_bytes()))
+        .map_err(|e| format!("Failed to create note: {}", e))?;
+    info!("Created zettel {:?}", path);
+    Ok(ZettelNote {
+        id,
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+// Tauri command: Generate a note ID (for inserting it into an existing note)
+#[tauri::command]
+pub fn generate_note_id(format: Option<NoteIdFormat>) -> Result<String, String> {
+    note_id_at(format.unwrap_or_default(), Local::now())
+}
+
+// Tauri command: Create a note named with a new ID in `dir`, from a template of the
+// gallery (filled with `values`) or with a heading
+#[tauri::command]
+pub async fn new_zettel(
+    dir: String,
+    title: Option<String>,
+    format: Option<NoteIdFormat>,
+    template: Option<String>,
+    values: Option<HashMap<String, String>>,
+) -> Result<ZettelNote, String> {
+    path_scope::check_path(&dir)?;
+    let template = template.map(|id| templates::read_template(&id)).transpose()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        create_zettel(
+            Path::new(&dir),
+            title.as_deref().unwrap_or("").trim(),
+            format.unwrap_or_default(),
+            template.as_deref(),
+            &values.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to create note: {}", e))?
+}